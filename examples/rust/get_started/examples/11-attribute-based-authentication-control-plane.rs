@@ -135,6 +135,7 @@ async fn start_node(ctx: Context, project_information_path: &str, token: OneTime
             node.context(),
             route![secure_channel_address.clone(), DefaultAddress::CREDENTIALS_SERVICE],
             credential,
+            None,
         )
         .await?;
 
@@ -131,6 +131,7 @@ async fn start_node(ctx: Context, project_information_path: &str, token: OneTime
             node.context(),
             route![secure_channel_address.clone(), DefaultAddress::CREDENTIALS_SERVICE],
             credential.clone(),
+            None,
         )
         .await?;
 
@@ -153,6 +154,7 @@ async fn start_node(ctx: Context, project_information_path: &str, token: OneTime
             route![secure_channel_to_control.clone(), "credential_exchange"],
             &[project.authority_identifier()],
             credential,
+            None,
         )
         .await?;
     println!("credential exchange done");
@@ -26,7 +26,7 @@ pub use eval::eval;
 pub use expr::Expr;
 pub use policy::PolicyAccessControl;
 pub use storage::*;
-pub use types::{Action, Resource, Subject};
+pub use types::{Action, Resource, Subject, WILDCARD};
 
 #[cfg(feature = "std")]
 pub use parser::parse;
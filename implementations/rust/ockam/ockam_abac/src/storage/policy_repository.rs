@@ -1,23 +1,137 @@
-use crate::{Action, Expr, Resource};
+use crate::expr::str;
+use crate::{eval, Action, Env, Expr, Resource};
 use ockam_core::async_trait;
 use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::format;
+use ockam_core::compat::string::String;
 use ockam_core::compat::vec::Vec;
-use ockam_core::Result;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_identity::TimestampInSeconds;
+
+/// Attribute names and their values, used to evaluate a policy expression
+pub type AttributeMap = BTreeMap<String, String>;
 
 /// This repository stores policies.
 /// A policy is an expression which can be evaluated against an environment (a list of attribute
 /// names and values) in order to determine if a given action can be performed on a given resource.
 #[async_trait]
 pub trait PoliciesRepository: Send + Sync + 'static {
-    /// Return the policy associated to a given resource and action
+    /// Return the policy associated to a given resource and action.
+    /// When there is no policy for that exact resource and action, the policy set for all the
+    /// actions on the resource is returned, then the policy set for the action on all resources,
+    /// then the policy set for all actions on all resources. See [`Resource::all`] and [`Action::all`]
     async fn get_policy(&self, r: &Resource, a: &Action) -> Result<Option<Expr>>;
 
-    /// Set a policy for a given resource and action
-    async fn set_policy(&self, r: &Resource, a: &Action, c: &Expr) -> Result<()>;
+    /// Return the policy associated to a given resource and action, inherited from the parents
+    /// of the resource when it has no policy of its own.
+    ///
+    /// The parents of a resource are given by its dot-separated segments: `tcp-outlet.db.primary`
+    /// inherits from `tcp-outlet.db`, then from `tcp-outlet`. Each resource is checked, from
+    /// the most specific to the least specific, for a policy for the action then for all the
+    /// actions. The policies set for all resources are returned last, like with [`Self::get_policy`]
+    async fn get_policy_inherited(&self, r: &Resource, a: &Action) -> Result<Option<Expr>>;
 
-    /// Delete the policy associated to a given resource and action
+    /// Set a policy for a given resource and action.
+    /// The resource and the action can be wildcards, see [`Resource::all`] and [`Action::all`]
+    ///
+    /// When an expiration time is given, the policy is considered as absent by all the
+    /// other methods of this repository once that time has passed
+    async fn set_policy(
+        &self,
+        r: &Resource,
+        a: &Action,
+        c: &Expr,
+        expires_at: Option<TimestampInSeconds>,
+    ) -> Result<()>;
+
+    /// Delete the policy associated to a given resource and action.
+    /// The policy is kept in the history of deleted policies
     async fn delete_policy(&self, r: &Resource, a: &Action) -> Result<()>;
 
+    /// Delete the policy associated to a given resource and action
+    /// without keeping it in the history of deleted policies
+    async fn hard_delete_policy(&self, r: &Resource, a: &Action) -> Result<()>;
+
+    /// Return the list of all the deleted policies, ordered by deletion time
+    async fn get_deleted_policies(&self) -> Result<Vec<DeletedPolicy>>;
+
     /// Return the list of all the policies associated to a given resource
     async fn get_policies_by_resource(&self, r: &Resource) -> Result<Vec<(Action, Expr)>>;
+
+    /// Return the list of all the policies associated to some resources, in a single query.
+    /// The policies are grouped by resource and sorted by action
+    async fn get_policies_by_resources(
+        &self,
+        resources: &[Resource],
+    ) -> Result<Vec<(Resource, Action, Expr)>>;
+
+    /// Return the list of the policies associated to a given resource, for some specific actions
+    async fn get_policies_by_resource_and_actions(
+        &self,
+        r: &Resource,
+        actions: &[Action],
+    ) -> Result<Vec<(Action, Expr)>>;
+
+    /// Return the sorted list of all the resources having at least one policy
+    async fn list_resources(&self) -> Result<Vec<Resource>>;
+
+    /// Return the sorted list of all the actions having at least one policy
+    async fn list_actions(&self) -> Result<Vec<Action>>;
+
+    /// Delete all the policies which have expired and return their number.
+    /// The expired policies are not kept in the history of deleted policies
+    async fn purge_expired_policies(&self) -> Result<u64>;
+}
+
+/// A policy which has been deleted with [`PoliciesRepository::delete_policy`]
+#[derive(Debug, Clone)]
+pub struct DeletedPolicy {
+    pub resource: Resource,
+    pub action: Action,
+    pub expression: Expr,
+    pub deleted_at: TimestampInSeconds,
+}
+
+/// Evaluate a policy expression against some attributes, without storing the policy.
+///
+/// This can be used to check the outcome of a policy before setting it with a PoliciesRepository.
+/// Each attribute is bound as a string value to its name in the evaluation environment.
+/// An error is returned if the expression can not be evaluated or does not evaluate to a boolean.
+pub fn evaluate_policy(expr: &Expr, attributes: &AttributeMap) -> Result<bool> {
+    let mut environment = Env::new();
+    for (name, value) in attributes {
+        environment.put(name.as_str(), str(value.as_str()));
+    }
+    match eval(expr, &environment)? {
+        Expr::Bool(b) => Ok(b),
+        other => Err(Error::new(
+            Origin::Application,
+            Kind::Invalid,
+            format!("the policy evaluated to {other} instead of a boolean"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::expr::{eq, ident};
+    use ockam_core::compat::string::ToString;
+
+    #[test]
+    fn test_evaluate_policy() -> Result<()> {
+        let expr = eq([ident("name"), str("me")]);
+
+        let attributes = AttributeMap::from([("name".to_string(), "me".to_string())]);
+        assert!(evaluate_policy(&expr, &attributes)?);
+
+        let attributes = AttributeMap::from([("name".to_string(), "you".to_string())]);
+        assert!(!evaluate_policy(&expr, &attributes)?);
+
+        // an attribute which is missing cannot be evaluated
+        assert!(evaluate_policy(&expr, &AttributeMap::new()).is_err());
+        Ok(())
+    }
 }
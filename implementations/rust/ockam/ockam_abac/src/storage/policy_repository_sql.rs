@@ -1,24 +1,77 @@
 use sqlx::*;
-use tracing::debug;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
 
 use ockam_core::async_trait;
+use ockam_core::compat::collections::BTreeSet;
+use ockam_core::compat::format;
 use ockam_core::compat::sync::Arc;
-use ockam_core::compat::vec::Vec;
-use ockam_core::Result;
+use ockam_core::compat::vec::{vec, Vec};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_identity::utils::now;
+use ockam_identity::TimestampInSeconds;
 use ockam_node::database::{FromSqlxError, SqlxDatabase, SqlxType, ToSqlxType, ToVoid};
 
-use crate::{Action, Expr, PoliciesRepository, Resource};
+use crate::{Action, DeletedPolicy, Expr, PoliciesRepository, Resource, WILDCARD};
 
 #[derive(Clone)]
 pub struct PolicySqlxDatabase {
     database: Arc<SqlxDatabase>,
+    changes: broadcast::Sender<PolicyChange>,
+}
+
+/// Columns expected in the policy table
+const POLICY_COLUMNS: &[&str] = &["resource", "action", "expression", "expires_at"];
+
+/// Condition selecting the policies which have not expired, given the current time
+const NOT_EXPIRED: &str = "(expires_at IS NULL OR expires_at > ?)";
+
+/// Version of the format used to store the policy expressions.
+/// The stored expressions are prefixed with this version so that they can still be decoded
+/// if the expression language evolves
+const EXPRESSION_FORMAT_VERSION: u8 = 1;
+
+/// Name of the data migration normalizing the resource and action names of the stored policies
+const NORMALIZE_POLICIES_MIGRATION: &str = "normalize_policies";
+
+/// Number of changes kept for the subscribers which are lagging behind
+const CHANGES_CAPACITY: usize = 64;
+
+/// Change made to a policy with a [`PolicySqlxDatabase`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyChange {
+    pub resource: Resource,
+    pub action: Action,
+    pub kind: PolicyChangeKind,
+}
+
+/// Kind of change made to a policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyChangeKind {
+    Set,
+    Deleted,
 }
 
 impl PolicySqlxDatabase {
     /// Create a new database for policies keys
     pub fn new(database: Arc<SqlxDatabase>) -> Self {
+        Self::new_with_changes(database, Self::create_changes_sender())
+    }
+
+    /// Create a new database for policies keys, notifying its changes with a given sender.
+    /// The repositories created with clones of the same sender notify the same subscribers
+    pub fn new_with_changes(
+        database: Arc<SqlxDatabase>,
+        changes: broadcast::Sender<PolicyChange>,
+    ) -> Self {
         debug!("create a repository for policies");
-        Self { database }
+        Self { database, changes }
+    }
+
+    /// Create a sender for the changes made to the policies, to be shared by several repositories
+    pub fn create_changes_sender() -> broadcast::Sender<PolicyChange> {
+        broadcast::channel(CHANGES_CAPACITY).0
     }
 
     /// Create a new in-memory database for policies
@@ -27,14 +80,159 @@ impl PolicySqlxDatabase {
             SqlxDatabase::in_memory("policies").await?,
         )))
     }
+
+    /// Return an error if the schema of the policy table needs to be migrated.
+    /// This check is meant to be done once, when the database is opened
+    pub async fn check_schema(database: &SqlxDatabase) -> Result<()> {
+        database.check_columns("policy", POLICY_COLUMNS).await
+    }
+
+    /// Store the policies by their normalized resource and action names,
+    /// see [`Resource::normalized`] and [`Action::normalized`].
+    ///
+    /// This is done in Rust since the SQLite `lower` function only supports ASCII characters.
+    /// The migration is recorded in the `data_migration` table so that it is only applied once.
+    /// When several policies end up with the same names, the policy which was already
+    /// normalized, or else the first one, is kept. The others are moved to the `policy_history`
+    /// table and a warning is logged
+    pub async fn normalize_policies(database: &SqlxDatabase) -> Result<()> {
+        let mut transaction = database.begin().await.into_core()?;
+        // the migration is recorded in the same transaction as the changes.
+        // If it was already recorded, the transaction is rolled back when dropped
+        let query1 = query("INSERT OR IGNORE INTO data_migration (name, applied_at) VALUES (?, ?)")
+            .bind(NORMALIZE_POLICIES_MIGRATION.to_sql())
+            .bind(now()?.to_sql());
+        let recorded = query1.execute(&mut *transaction).await.into_core()?;
+        if recorded.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        let query2 = query_as("SELECT resource, action FROM policy ORDER BY rowid");
+        let names: Vec<(String, String)> = query2.fetch_all(&mut *transaction).await.into_core()?;
+        let normalize = |(resource, action): &(String, String)| {
+            (
+                Resource::from(resource.as_str())
+                    .normalized()
+                    .as_str()
+                    .to_string(),
+                Action::from(action.as_str())
+                    .normalized()
+                    .as_str()
+                    .to_string(),
+            )
+        };
+        let mut normalized: BTreeSet<(String, String)> = names
+            .iter()
+            .filter(|names| normalize(names) == **names)
+            .cloned()
+            .collect();
+
+        for names in names.iter().filter(|names| normalize(names) != **names) {
+            let (resource, action) = names;
+            let (normalized_resource, normalized_action) = normalize(names);
+            if normalized.contains(&(normalized_resource.clone(), normalized_action.clone())) {
+                warn!(
+                    "the policy for the resource '{resource}' and the action '{action}' is removed: \
+                     a policy already exists for the resource '{normalized_resource}' and the action '{normalized_action}'"
+                );
+                let query3 = query(
+                    "INSERT INTO policy_history SELECT resource, action, expression, ? FROM policy WHERE resource = ? and action = ?",
+                )
+                .bind(now()?.to_sql())
+                .bind(resource.to_sql())
+                .bind(action.to_sql());
+                query3.execute(&mut *transaction).await.void()?;
+
+                let query4 = query("DELETE FROM policy WHERE resource = ? and action = ?")
+                    .bind(resource.to_sql())
+                    .bind(action.to_sql());
+                query4.execute(&mut *transaction).await.void()?;
+            } else {
+                let query3 = query(
+                    "UPDATE policy SET resource = ?, action = ? WHERE resource = ? and action = ?",
+                )
+                .bind(normalized_resource.to_sql())
+                .bind(normalized_action.to_sql())
+                .bind(resource.to_sql())
+                .bind(action.to_sql());
+                query3.execute(&mut *transaction).await.void()?;
+                normalized.insert((normalized_resource, normalized_action));
+            }
+        }
+        transaction.commit().await.void()
+    }
+
+    /// Return a receiver for the changes made to the policies with this repository, or one of
+    /// its clones, once they have been stored.
+    ///
+    /// A receiver lagging behind by more than a fixed number of changes misses the oldest ones
+    /// and gets a `RecvError::Lagged` error instead
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<PolicyChange> {
+        self.changes.subscribe()
+    }
+
+    /// Notify the subscribers of a change, if there are any
+    fn notify_change(&self, resource: &Resource, action: &Action, kind: PolicyChangeKind) {
+        let _ = self.changes.send(PolicyChange {
+            resource: resource.normalized(),
+            action: action.normalized(),
+            kind,
+        });
+    }
 }
 
 #[async_trait]
 impl PoliciesRepository for PolicySqlxDatabase {
     async fn get_policy(&self, resource: &Resource, action: &Action) -> Result<Option<Expr>> {
-        let query = query_as("SELECT * FROM policy WHERE resource=$1 and action=$2")
+        // the exact resource and action take precedence over the wildcards
+        let sql = format!(
+            "SELECT * FROM policy WHERE resource IN (?, ?) and action IN (?, ?) and {NOT_EXPIRED} \
+             ORDER BY resource = ?, action = ? LIMIT 1"
+        );
+        let query = query_as(&sql)
             .bind(resource.to_sql())
-            .bind(action.to_sql());
+            .bind(WILDCARD.to_sql())
+            .bind(action.to_sql())
+            .bind(WILDCARD.to_sql())
+            .bind(now()?.to_sql())
+            .bind(WILDCARD.to_sql())
+            .bind(WILDCARD.to_sql());
+        let row: Option<PolicyRow> = query
+            .fetch_optional(&self.database.pool)
+            .await
+            .into_core()?;
+        Ok(row.map(|r| r.expression()).transpose()?)
+    }
+
+    async fn get_policy_inherited(
+        &self,
+        resource: &Resource,
+        action: &Action,
+    ) -> Result<Option<Expr>> {
+        // the resource and its parents, from the most specific to the least specific
+        let name = resource.normalized();
+        let segments: Vec<&str> = name.as_str().split('.').collect();
+        let resources: Vec<Resource> = (1..=segments.len())
+            .rev()
+            .map(|n| Resource::from(segments[..n].join(".")))
+            .collect();
+
+        let placeholders = vec!["?"; resources.len()].join(", ");
+        let sql = format!(
+            "SELECT * FROM policy WHERE resource IN ({placeholders}, ?) and action IN (?, ?) \
+             and {NOT_EXPIRED} ORDER BY resource = ?, length(resource) DESC, action = ? LIMIT 1"
+        );
+        let mut query = query_as(&sql);
+        for resource in resources.iter() {
+            query = query.bind(resource.to_sql());
+        }
+        let query = query
+            .bind(WILDCARD.to_sql())
+            .bind(action.to_sql())
+            .bind(WILDCARD.to_sql())
+            .bind(now()?.to_sql())
+            .bind(WILDCARD.to_sql())
+            .bind(WILDCARD.to_sql());
         let row: Option<PolicyRow> = query
             .fetch_optional(&self.database.pool)
             .await
@@ -47,41 +245,195 @@ impl PoliciesRepository for PolicySqlxDatabase {
         resource: &Resource,
         action: &Action,
         expression: &Expr,
+        expires_at: Option<TimestampInSeconds>,
     ) -> Result<()> {
-        let query = query("INSERT OR REPLACE INTO policy VALUES (?, ?, ?)")
-            .bind(resource.to_sql())
-            .bind(action.to_sql())
-            .bind(minicbor::to_vec(expression)?.to_sql());
-        query.execute(&self.database.pool).await.void()
+        let query = query(
+            "INSERT OR REPLACE INTO policy (resource, action, expression, expires_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(resource.to_sql())
+        .bind(action.to_sql())
+        .bind(encode_expression(expression)?.to_sql())
+        .bind(expires_at.map(|t| t.to_sql()));
+        query.execute(&self.database.pool).await.void()?;
+        self.notify_change(resource, action, PolicyChangeKind::Set);
+        Ok(())
     }
 
     async fn delete_policy(&self, resource: &Resource, action: &Action) -> Result<()> {
+        let mut transaction = self.database.begin().await.into_core()?;
+        // keep the policy in the history of deleted policies
+        let query1 = query(
+            "INSERT INTO policy_history SELECT resource, action, expression, ? FROM policy WHERE resource = ? and action = ?",
+        )
+        .bind(now()?.to_sql())
+        .bind(resource.to_sql())
+        .bind(action.to_sql());
+        query1.execute(&mut *transaction).await.void()?;
+
+        let query2 = query("DELETE FROM policy WHERE resource = ? and action = ?")
+            .bind(resource.to_sql())
+            .bind(action.to_sql());
+        query2.execute(&mut *transaction).await.void()?;
+        transaction.commit().await.void()?;
+        self.notify_change(resource, action, PolicyChangeKind::Deleted);
+        Ok(())
+    }
+
+    async fn hard_delete_policy(&self, resource: &Resource, action: &Action) -> Result<()> {
         let query = query("DELETE FROM policy WHERE resource = ? and action = ?")
             .bind(resource.to_sql())
             .bind(action.to_sql());
-        query.execute(&self.database.pool).await.void()
+        query.execute(&self.database.pool).await.void()?;
+        self.notify_change(resource, action, PolicyChangeKind::Deleted);
+        Ok(())
+    }
+
+    async fn get_deleted_policies(&self) -> Result<Vec<DeletedPolicy>> {
+        let query = query_as("SELECT * FROM policy_history ORDER BY deleted_at, rowid");
+        let rows: Vec<DeletedPolicyRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        rows.into_iter().map(|r| r.deleted_policy()).collect()
     }
 
     async fn get_policies_by_resource(&self, resource: &Resource) -> Result<Vec<(Action, Expr)>> {
-        let query = query_as("SELECT * FROM policy where resource = $1").bind(resource.to_sql());
+        let sql = format!("SELECT * FROM policy where resource = ? and {NOT_EXPIRED}");
+        let query = query_as(&sql).bind(resource.to_sql()).bind(now()?.to_sql());
         let row: Vec<PolicyRow> = query.fetch_all(&self.database.pool).await.into_core()?;
         row.into_iter()
             .map(|r| r.expression().map(|e| (r.action(), e)))
             .collect::<Result<Vec<(Action, Expr)>>>()
     }
+
+    async fn get_policies_by_resources(
+        &self,
+        resources: &[Resource],
+    ) -> Result<Vec<(Resource, Action, Expr)>> {
+        if resources.is_empty() {
+            return Ok(vec![]);
+        }
+        let placeholders = vec!["?"; resources.len()].join(", ");
+        let sql = format!(
+            "SELECT * FROM policy where resource IN ({placeholders}) and {NOT_EXPIRED} ORDER BY resource, action"
+        );
+        let mut query = query_as(&sql);
+        for resource in resources {
+            query = query.bind(resource.to_sql());
+        }
+        let query = query.bind(now()?.to_sql());
+        let row: Vec<PolicyRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        row.into_iter()
+            .map(|r| r.expression().map(|e| (r.resource(), r.action(), e)))
+            .collect::<Result<Vec<(Resource, Action, Expr)>>>()
+    }
+
+    async fn get_policies_by_resource_and_actions(
+        &self,
+        resource: &Resource,
+        actions: &[Action],
+    ) -> Result<Vec<(Action, Expr)>> {
+        if actions.is_empty() {
+            return Ok(vec![]);
+        }
+        let placeholders = vec!["?"; actions.len()].join(", ");
+        let sql = format!(
+            "SELECT * FROM policy where resource = ? and action IN ({placeholders}) and {NOT_EXPIRED}"
+        );
+        let mut query = query_as(&sql).bind(resource.to_sql());
+        for action in actions {
+            query = query.bind(action.to_sql());
+        }
+        let query = query.bind(now()?.to_sql());
+        let row: Vec<PolicyRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        row.into_iter()
+            .map(|r| r.expression().map(|e| (r.action(), e)))
+            .collect::<Result<Vec<(Action, Expr)>>>()
+    }
+
+    async fn list_resources(&self) -> Result<Vec<Resource>> {
+        let sql =
+            format!("SELECT DISTINCT resource FROM policy WHERE {NOT_EXPIRED} ORDER BY resource");
+        let query = query_scalar(&sql).bind(now()?.to_sql());
+        let rows: Vec<String> = query.fetch_all(&self.database.pool).await.into_core()?;
+        Ok(rows.into_iter().map(Resource::from).collect())
+    }
+
+    async fn list_actions(&self) -> Result<Vec<Action>> {
+        let sql = format!("SELECT DISTINCT action FROM policy WHERE {NOT_EXPIRED} ORDER BY action");
+        let query = query_scalar(&sql).bind(now()?.to_sql());
+        let rows: Vec<String> = query.fetch_all(&self.database.pool).await.into_core()?;
+        Ok(rows.into_iter().map(Action::from).collect())
+    }
+
+    async fn purge_expired_policies(&self) -> Result<u64> {
+        let now = now()?;
+        let mut transaction = self.database.begin().await.into_core()?;
+        let query1 = query_as(
+            "SELECT resource, action FROM policy WHERE expires_at IS NOT NULL and expires_at <= ?",
+        )
+        .bind(now.to_sql());
+        let expired: Vec<(String, String)> =
+            query1.fetch_all(&mut *transaction).await.into_core()?;
+
+        let query2 = query("DELETE FROM policy WHERE expires_at IS NOT NULL and expires_at <= ?")
+            .bind(now.to_sql());
+        query2.execute(&mut *transaction).await.void()?;
+        transaction.commit().await.void()?;
+
+        for (resource, action) in expired.iter() {
+            self.notify_change(
+                &Resource::from(resource.clone()),
+                &Action::from(action.clone()),
+                PolicyChangeKind::Deleted,
+            );
+        }
+        Ok(expired.len() as u64)
+    }
 }
 
 // Database serialization / deserialization
 
+/// Resources and actions are stored, and looked up, by their normalized name
+/// so that `Outlet` and `outlet` denote the same policy
 impl ToSqlxType for Resource {
     fn to_sql(&self) -> SqlxType {
-        SqlxType::Text(self.as_str().to_string())
+        SqlxType::Text(self.normalized().as_str().to_string())
     }
 }
 
 impl ToSqlxType for Action {
     fn to_sql(&self) -> SqlxType {
-        SqlxType::Text(self.as_str().to_string())
+        SqlxType::Text(self.normalized().as_str().to_string())
+    }
+}
+
+/// Encode an expression prefixed with the current format version
+fn encode_expression(expression: &Expr) -> Result<Vec<u8>> {
+    let mut encoded = vec![EXPRESSION_FORMAT_VERSION];
+    encoded.extend(minicbor::to_vec(expression)?);
+    Ok(encoded)
+}
+
+/// Decode an expression according to its format version.
+///
+/// The expressions stored before the format was versioned are not prefixed. They start with
+/// a CBOR array header, which can not be mistaken for a version number.
+fn decode_expression(encoded: &[u8]) -> Result<Expr> {
+    match encoded.first() {
+        Some(header) if (0x80..=0x9f).contains(header) => Ok(minicbor::decode(encoded)?),
+        Some(&EXPRESSION_FORMAT_VERSION) => Ok(minicbor::decode(&encoded[1..])?),
+        Some(version) => Err(Error::new(
+            Origin::Application,
+            Kind::Unsupported,
+            format!(
+                "the policy expression was stored with the format version {version}, \
+                 but only the versions up to {EXPRESSION_FORMAT_VERSION} are supported. \
+                 The policy must be set again with this version of Ockam"
+            ),
+        )),
+        None => Err(Error::new(
+            Origin::Application,
+            Kind::Invalid,
+            "the stored policy expression is empty",
+        )),
     }
 }
 
@@ -94,7 +446,6 @@ pub(crate) struct PolicyRow {
 }
 
 impl PolicyRow {
-    #[allow(dead_code)]
     pub(crate) fn resource(&self) -> Resource {
         Resource::from(self.resource.clone())
     }
@@ -104,7 +455,27 @@ impl PolicyRow {
     }
 
     pub(crate) fn expression(&self) -> Result<Expr> {
-        Ok(minicbor::decode(self.expression.as_slice())?)
+        decode_expression(self.expression.as_slice())
+    }
+}
+
+/// Low-level representation of a row in the policy_history table
+#[derive(FromRow)]
+struct DeletedPolicyRow {
+    resource: String,
+    action: String,
+    expression: Vec<u8>,
+    deleted_at: i64,
+}
+
+impl DeletedPolicyRow {
+    fn deleted_policy(&self) -> Result<DeletedPolicy> {
+        Ok(DeletedPolicy {
+            resource: Resource::from(self.resource.clone()),
+            action: Action::from(self.action.clone()),
+            expression: decode_expression(self.expression.as_slice())?,
+            deleted_at: TimestampInSeconds(self.deleted_at as u64),
+        })
     }
 }
 
@@ -122,7 +493,7 @@ mod test {
         let r = Resource::from("outlet");
         let a = Action::from("create");
         let e = eq([ident("name"), str("me")]);
-        repository.set_policy(&r, &a, &e).await?;
+        repository.set_policy(&r, &a, &e, None).await?;
         assert!(repository.get_policy(&r, &a).await?.unwrap().equals(&e)?);
 
         // we can retrieve all the policies associated to a given resource
@@ -130,7 +501,7 @@ mod test {
         assert_eq!(policies.len(), 1);
 
         let a = Action::from("delete");
-        repository.set_policy(&r, &a, &e).await?;
+        repository.set_policy(&r, &a, &e, None).await?;
         let policies = repository.get_policies_by_resource(&r).await?;
         assert_eq!(policies.len(), 2);
 
@@ -144,6 +515,547 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_policy_inherited() -> Result<()> {
+        let repository = create_repository().await?;
+        let leaf = Resource::from("tcp-outlet.db.primary");
+        let a = Action::from("handle_message");
+
+        // no policy is found up the chain
+        repository
+            .set_policy(
+                &Resource::from("tcp-inlet"),
+                &a,
+                &eq([ident("name"), str("inlet")]),
+                None,
+            )
+            .await?;
+        repository
+            .set_policy(
+                &Resource::from("tcp-outlet.db.primary.replica"),
+                &a,
+                &eq([ident("name"), str("replica")]),
+                None,
+            )
+            .await?;
+        assert!(repository.get_policy_inherited(&leaf, &a).await?.is_none());
+
+        // the policy of the closest parent is inherited
+        let root = eq([ident("name"), str("root")]);
+        repository
+            .set_policy(&Resource::from("tcp-outlet"), &a, &root, None)
+            .await?;
+        assert!(repository
+            .get_policy_inherited(&leaf, &a)
+            .await?
+            .unwrap()
+            .equals(&root)?);
+
+        let parent = eq([ident("name"), str("parent")]);
+        repository
+            .set_policy(
+                &Resource::from("tcp-outlet.db"),
+                &Action::all(),
+                &parent,
+                None,
+            )
+            .await?;
+        assert!(repository
+            .get_policy_inherited(&leaf, &a)
+            .await?
+            .unwrap()
+            .equals(&parent)?);
+
+        // the policy of the leaf takes precedence
+        let own = eq([ident("name"), str("own")]);
+        repository.set_policy(&leaf, &a, &own, None).await?;
+        assert!(repository
+            .get_policy_inherited(&leaf, &a)
+            .await?
+            .unwrap()
+            .equals(&own)?);
+
+        // the policies set for all resources come last
+        let all = eq([ident("name"), str("all")]);
+        repository
+            .set_policy(&Resource::all(), &a, &all, None)
+            .await?;
+        assert!(repository
+            .get_policy_inherited(&leaf, &a)
+            .await?
+            .unwrap()
+            .equals(&own)?);
+        let other = Resource::from("kafka-consumer.topic");
+        assert!(repository
+            .get_policy_inherited(&other, &a)
+            .await?
+            .unwrap()
+            .equals(&all)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_normalized_resources_and_actions() -> Result<()> {
+        let repository = create_repository().await?;
+
+        // the resource and action names are case-insensitive and whitespace-tolerant
+        let e1 = eq([ident("name"), str("me")]);
+        repository
+            .set_policy(
+                &Resource::from("Outlet"),
+                &Action::from(" Handle_Message "),
+                &e1,
+                None,
+            )
+            .await?;
+        let r = Resource::from("outlet");
+        let a = Action::from("handle_message");
+        assert!(repository.get_policy(&r, &a).await?.unwrap().equals(&e1)?);
+
+        // setting the policy again with another case replaces it
+        let e2 = eq([ident("name"), str("you")]);
+        repository.set_policy(&r, &a, &e2, None).await?;
+        let policies = repository.get_policies_by_resource(&r).await?;
+        assert_eq!(policies.len(), 1);
+        assert!(repository
+            .get_policy(&Resource::from("OUTLET"), &a)
+            .await?
+            .unwrap()
+            .equals(&e2)?);
+        assert_eq!(repository.list_resources().await?, vec![r.clone()]);
+
+        // the policy can be deleted with another case
+        repository
+            .delete_policy(&Resource::from(" Outlet"), &Action::from("HANDLE_MESSAGE"))
+            .await?;
+        assert!(repository.get_policy(&r, &a).await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_policies_by_resource_and_actions() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let r = Resource::from("outlet");
+        let e = eq([ident("name"), str("me")]);
+        for a in ["create", "delete", "update"] {
+            repository
+                .set_policy(&r, &Action::from(a), &e, None)
+                .await?;
+        }
+
+        // only the policies for the requested actions are returned
+        let actions = [Action::from("create"), Action::from("update")];
+        let policies = repository
+            .get_policies_by_resource_and_actions(&r, &actions)
+            .await?;
+        let mut returned: Vec<Action> = policies.into_iter().map(|(a, _)| a).collect();
+        returned.sort();
+        assert_eq!(returned, actions.to_vec());
+
+        // no actions, no policies
+        let policies = repository
+            .get_policies_by_resource_and_actions(&r, &[])
+            .await?;
+        assert!(policies.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_policies_by_resources() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let e1 = eq([ident("name"), str("me")]);
+        let e2 = eq([ident("name"), str("you")]);
+        for (r, a, e) in [
+            ("outlet", "create", &e1),
+            ("outlet", "delete", &e2),
+            ("inlet", "create", &e2),
+            ("relay", "create", &e1),
+            ("relay", "update", &e1),
+            ("other", "create", &e1),
+        ] {
+            repository
+                .set_policy(&Resource::from(r), &Action::from(a), e, None)
+                .await?;
+        }
+
+        // all the policies of the requested resources are returned, grouped by resource
+        let resources = [
+            Resource::from("relay"),
+            Resource::from("outlet"),
+            Resource::from("inlet"),
+        ];
+        let policies = repository.get_policies_by_resources(&resources).await?;
+        let expected = [
+            ("inlet", "create", &e2),
+            ("outlet", "create", &e1),
+            ("outlet", "delete", &e2),
+            ("relay", "create", &e1),
+            ("relay", "update", &e1),
+        ];
+        assert_eq!(policies.len(), expected.len());
+        for ((r, a, e), (expected_r, expected_a, expected_e)) in policies.iter().zip(expected) {
+            assert_eq!(r, &Resource::from(expected_r));
+            assert_eq!(a, &Action::from(expected_a));
+            assert!(e.equals(expected_e)?);
+        }
+
+        // no resources, no policies
+        let policies = repository.get_policies_by_resources(&[]).await?;
+        assert!(policies.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_resources_and_actions() -> Result<()> {
+        let repository = create_repository().await?;
+        assert!(repository.list_resources().await?.is_empty());
+        assert!(repository.list_actions().await?.is_empty());
+
+        let e = eq([ident("name"), str("me")]);
+        for (r, a) in [
+            ("outlet", "update"),
+            ("outlet", "create"),
+            ("inlet", "delete"),
+            ("inlet", "create"),
+        ] {
+            repository
+                .set_policy(&Resource::from(r), &Action::from(a), &e, None)
+                .await?;
+        }
+
+        // each resource and action is listed once, in order
+        assert_eq!(
+            repository.list_resources().await?,
+            vec![Resource::from("inlet"), Resource::from("outlet")]
+        );
+        assert_eq!(
+            repository.list_actions().await?,
+            vec![
+                Action::from("create"),
+                Action::from("delete"),
+                Action::from("update")
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_policy() -> Result<()> {
+        let repository = create_repository().await?;
+        assert!(repository.get_deleted_policies().await?.is_empty());
+
+        let r = Resource::from("outlet");
+        let a = Action::from("create");
+        let e = eq([ident("name"), str("me")]);
+        repository.set_policy(&r, &a, &e, None).await?;
+
+        // a deleted policy is not returned anymore
+        repository.delete_policy(&r, &a).await?;
+        assert!(repository.get_policy(&r, &a).await?.is_none());
+        assert!(repository.get_policies_by_resource(&r).await?.is_empty());
+        assert!(repository.list_resources().await?.is_empty());
+
+        // but it is kept in the history of deleted policies
+        let deleted = repository.get_deleted_policies().await?;
+        assert_eq!(deleted.len(), 1);
+        let policy = deleted.first().unwrap();
+        assert_eq!(policy.resource, r);
+        assert_eq!(policy.action, a);
+        assert!(policy.expression.equals(&e)?);
+        assert!(policy.deleted_at.0 > 0);
+
+        // deleting a missing policy does not add anything to the history
+        repository.delete_policy(&r, &a).await?;
+        assert_eq!(repository.get_deleted_policies().await?.len(), 1);
+
+        // a policy can be removed without being kept in the history
+        let a = Action::from("delete");
+        repository.set_policy(&r, &a, &e, None).await?;
+        repository.hard_delete_policy(&r, &a).await?;
+        assert!(repository.get_policy(&r, &a).await?.is_none());
+        assert_eq!(repository.get_deleted_policies().await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_policies() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let r = Resource::from("outlet");
+        let a1 = Action::from("create");
+        let a2 = Action::from("delete");
+        let e = eq([ident("name"), str("me")]);
+        let now = now()?;
+        repository
+            .set_policy(&r, &a1, &e, Some(TimestampInSeconds(now.0 + 3600)))
+            .await?;
+        repository
+            .set_policy(&r, &a2, &e, Some(TimestampInSeconds(now.0 - 1)))
+            .await?;
+
+        // a policy past its expiration time is not returned
+        assert!(repository.get_policy(&r, &a1).await?.is_some());
+        assert!(repository.get_policy(&r, &a2).await?.is_none());
+        let policies = repository.get_policies_by_resource(&r).await?;
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].0, a1);
+        assert_eq!(repository.list_actions().await?, vec![a1.clone()]);
+
+        // the expired policies can be purged, without being kept in the history
+        assert_eq!(repository.purge_expired_policies().await?, 1);
+        assert_eq!(repository.purge_expired_policies().await?, 0);
+        assert!(repository.get_deleted_policies().await?.is_empty());
+        assert!(repository.get_policy(&r, &a1).await?.is_some());
+
+        // setting the policy again without an expiration time makes it permanent
+        repository
+            .set_policy(&r, &a2, &e, Some(TimestampInSeconds(now.0 - 1)))
+            .await?;
+        repository.set_policy(&r, &a2, &e, None).await?;
+        assert!(repository.get_policy(&r, &a2).await?.is_some());
+        assert_eq!(repository.purge_expired_policies().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exact_action_takes_precedence_over_wildcard_action() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let r = Resource::from("outlet");
+        let a = Action::from("create");
+        let exact = eq([ident("name"), str("me")]);
+        let wildcard = eq([ident("name"), str("you")]);
+        repository
+            .set_policy(&r, &Action::all(), &wildcard, None)
+            .await?;
+        repository.set_policy(&r, &a, &exact, None).await?;
+
+        assert!(repository
+            .get_policy(&r, &a)
+            .await?
+            .unwrap()
+            .equals(&exact)?);
+
+        // the wildcard policy is used again once the exact one is deleted
+        repository.delete_policy(&r, &a).await?;
+        assert!(repository
+            .get_policy(&r, &a)
+            .await?
+            .unwrap()
+            .equals(&wildcard)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_action_fallback() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let r = Resource::from("outlet");
+        let e = eq([ident("name"), str("me")]);
+        repository.set_policy(&r, &Action::all(), &e, None).await?;
+
+        // the wildcard policy applies to any action on the resource
+        for a in ["create", "delete"] {
+            let policy = repository.get_policy(&r, &Action::from(a)).await?;
+            assert!(policy.unwrap().equals(&e)?);
+        }
+
+        // but not to the actions on other resources
+        let policy = repository
+            .get_policy(&Resource::from("inlet"), &Action::from("create"))
+            .await?;
+        assert!(policy.is_none());
+
+        // the wildcard policy is stored as such
+        let policies = repository.get_policies_by_resource(&r).await?;
+        assert_eq!(policies.len(), 1);
+        assert!(policies.first().unwrap().0.is_all());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_resource_and_action() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let r = Resource::from("outlet");
+        let a = Action::from("create");
+        let all = eq([ident("name"), str("all")]);
+        let all_resources = eq([ident("name"), str("all resources")]);
+        let all_actions = eq([ident("name"), str("all actions")]);
+
+        // a policy for all actions on all resources applies everywhere
+        repository
+            .set_policy(&Resource::all(), &Action::all(), &all, None)
+            .await?;
+        assert!(repository.get_policy(&r, &a).await?.unwrap().equals(&all)?);
+
+        // a policy for an action on all resources is more specific
+        repository
+            .set_policy(&Resource::all(), &a, &all_resources, None)
+            .await?;
+        assert!(repository
+            .get_policy(&r, &a)
+            .await?
+            .unwrap()
+            .equals(&all_resources)?);
+        assert!(repository
+            .get_policy(&r, &Action::from("delete"))
+            .await?
+            .unwrap()
+            .equals(&all)?);
+
+        // a policy for all actions on a resource is more specific than a policy on all resources
+        repository
+            .set_policy(&r, &Action::all(), &all_actions, None)
+            .await?;
+        assert!(repository
+            .get_policy(&r, &a)
+            .await?
+            .unwrap()
+            .equals(&all_actions)?);
+        assert!(repository
+            .get_policy(&Resource::from("inlet"), &a)
+            .await?
+            .unwrap()
+            .equals(&all_resources)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_policies_survive_a_dump_to_a_file() -> Result<()> {
+        let database = SqlxDatabase::in_memory("policies").await?;
+        let repository = PolicySqlxDatabase::new(database.clone());
+        let r = Resource::from("outlet");
+        let a = Action::from("handle_message");
+        let e = eq([ident("name"), str("me")]);
+        repository.set_policy(&r, &a, &e, None).await?;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("policies.sqlite3");
+        database.dump_to_file(&path).await?;
+
+        let repository = PolicySqlxDatabase::new(SqlxDatabase::load_from_file(&path).await?);
+        assert!(repository.get_policy(&r, &a).await?.unwrap().equals(&e)?);
+        assert_eq!(repository.list_resources().await?, vec![r]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expression_format_version() -> Result<()> {
+        let database = SqlxDatabase::in_memory("policies").await?;
+        let repository = PolicySqlxDatabase::new(database.clone());
+        let r = Resource::from("outlet");
+        let a = Action::from("create");
+        let e = eq([ident("name"), str("me")]);
+        let update = |expression: Vec<u8>| {
+            query("UPDATE policy SET expression = ?")
+                .bind(expression.to_sql())
+                .execute(&database.pool)
+        };
+
+        // the stored expression is prefixed with its format version
+        repository.set_policy(&r, &a, &e, None).await?;
+        let stored: Vec<u8> = query_scalar("SELECT expression FROM policy")
+            .fetch_one(&database.pool)
+            .await
+            .into_core()?;
+        assert_eq!(stored[0], EXPRESSION_FORMAT_VERSION);
+
+        // an expression stored without a version can still be read
+        update(minicbor::to_vec(&e)?).await.void()?;
+        assert!(repository.get_policy(&r, &a).await?.unwrap().equals(&e)?);
+
+        // an expression stored with a more recent version is rejected with a descriptive error
+        let mut bumped = vec![EXPRESSION_FORMAT_VERSION + 1];
+        bumped.extend(minicbor::to_vec(&e)?);
+        update(bumped).await.void()?;
+        let error = repository.get_policy(&r, &a).await.unwrap_err();
+        assert_eq!(error.code().kind, Kind::Unsupported);
+        assert!(error.to_string().contains("format version 2"), "{error}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_changes() -> Result<()> {
+        let repository = PolicySqlxDatabase::create().await?;
+        let mut changes = repository.subscribe_changes();
+
+        let r = Resource::from("outlet");
+        let a = Action::from("create");
+        let e = eq([ident("name"), str("me")]);
+        repository.set_policy(&r, &a, &e, None).await?;
+        repository.delete_policy(&r, &a).await?;
+
+        // each change is received once it has been stored
+        for kind in [PolicyChangeKind::Set, PolicyChangeKind::Deleted] {
+            let change = changes.try_recv().unwrap();
+            assert_eq!(
+                change,
+                PolicyChange {
+                    resource: r.clone(),
+                    action: a.clone(),
+                    kind,
+                }
+            );
+        }
+        assert!(changes.try_recv().is_err());
+        Ok(())
+    }
+
+    /// This test checks that the policies names are normalized, including non-ASCII characters,
+    /// that the policies colliding with an existing policy are moved to the history
+    /// and that the normalization is only done once
+    #[tokio::test]
+    async fn test_normalize_policies() -> Result<()> {
+        let database = SqlxDatabase::in_memory("policies").await?;
+        let insert = |resource: &'static str, action: &'static str, expression: &'static str| {
+            query("INSERT INTO policy (resource, action, expression) VALUES (?, ?, ?)")
+                .bind(resource.to_sql())
+                .bind(action.to_sql())
+                .bind(expression.as_bytes().to_vec().to_sql())
+                .execute(&database.pool)
+        };
+        insert("outlet", "handle_message", "1").await.void()?;
+        insert(" Outlet", "HANDLE_MESSAGE", "2").await.void()?;
+        insert("ÉCOLE", "Handle_Message ", "3").await.void()?;
+        PolicySqlxDatabase::normalize_policies(&database).await?;
+
+        let policies: Vec<(String, String, Vec<u8>)> =
+            query_as("SELECT resource, action, expression FROM policy ORDER BY resource")
+                .fetch_all(&database.pool)
+                .await
+                .into_core()?;
+        assert_eq!(
+            policies,
+            vec![
+                ("outlet".into(), "handle_message".into(), b"1".to_vec()),
+                ("école".into(), "handle_message".into(), b"3".to_vec()),
+            ]
+        );
+
+        let history: Vec<(String, String, Vec<u8>)> =
+            query_as("SELECT resource, action, expression FROM policy_history")
+                .fetch_all(&database.pool)
+                .await
+                .into_core()?;
+        assert_eq!(
+            history,
+            vec![(" Outlet".into(), "HANDLE_MESSAGE".into(), b"2".to_vec())]
+        );
+
+        // the policies are not normalized again
+        insert("Inlet", "handle_message", "4").await.void()?;
+        PolicySqlxDatabase::normalize_policies(&database).await?;
+        let resources: Vec<String> = query_scalar("SELECT resource FROM policy ORDER BY resource")
+            .fetch_all(&database.pool)
+            .await
+            .into_core()?;
+        assert_eq!(resources, vec!["Inlet", "outlet", "école"]);
+        Ok(())
+    }
+
     /// HELPERS
     async fn create_repository() -> Result<Arc<dyn PoliciesRepository>> {
         Ok(PolicySqlxDatabase::create().await?)
@@ -35,6 +35,12 @@ macro_rules! define {
             pub fn as_str(&self) -> &str {
                 &self.0
             }
+
+            /// Return the lowercase name, without surrounding whitespace,
+            /// so that names differing only by their case denote the same value
+            pub fn normalized(&self) -> Self {
+                Self::from(self.as_str().trim().to_lowercase())
+            }
         }
 
         impl From<&str> for $t {
@@ -83,3 +89,82 @@ macro_rules! define {
 define!(Subject);
 define!(Resource);
 define!(Action);
+
+/// Name of the resource, or of the action, of a policy applying to all resources, or all actions
+pub const WILDCARD: &str = "*";
+
+/// Well-known resources of a node.
+/// Any other name can still be used with [`Resource::new`] or [`Resource::from`]
+impl Resource {
+    pub const TCP_INLET: Resource = Resource::assert_inline("tcp-inlet");
+    pub const TCP_OUTLET: Resource = Resource::assert_inline("tcp-outlet");
+    pub const RELAY: Resource = Resource::assert_inline("relay");
+    pub const KAFKA_CONSUMER: Resource = Resource::assert_inline("kafka-consumer");
+    pub const KAFKA_PRODUCER: Resource = Resource::assert_inline("kafka-producer");
+}
+
+impl Resource {
+    /// Return the resource used to set a policy for all the resources
+    pub const fn all() -> Self {
+        Self::assert_inline(WILDCARD)
+    }
+
+    /// Return true if this resource stands for all the resources
+    pub fn is_all(&self) -> bool {
+        self.as_str() == WILDCARD
+    }
+}
+
+/// Well-known actions on the resources of a node.
+/// Any other name can still be used with [`Action::new`] or [`Action::from`]
+impl Action {
+    pub const CREATE: Action = Action::assert_inline("create");
+    pub const DELETE: Action = Action::assert_inline("delete");
+    pub const LIST: Action = Action::assert_inline("list");
+    pub const HANDLE_MESSAGE: Action = Action::assert_inline("handle_message");
+    pub const CONNECT_TO_TARGET: Action = Action::assert_inline("connect_to_target");
+}
+
+impl Action {
+    /// Return the action used to set a policy for all the actions on a resource
+    pub const fn all() -> Self {
+        Self::assert_inline(WILDCARD)
+    }
+
+    /// Return true if this action stands for all the actions
+    pub fn is_all(&self) -> bool {
+        self.as_str() == WILDCARD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Resource};
+
+    #[test]
+    fn test_well_known_resources_and_actions() {
+        let resources = [
+            (Resource::TCP_INLET, "tcp-inlet"),
+            (Resource::TCP_OUTLET, "tcp-outlet"),
+            (Resource::RELAY, "relay"),
+            (Resource::KAFKA_CONSUMER, "kafka-consumer"),
+            (Resource::KAFKA_PRODUCER, "kafka-producer"),
+        ];
+        for (resource, name) in resources {
+            assert_eq!(resource.as_str(), name);
+            assert_eq!(resource, Resource::from(name));
+        }
+
+        let actions = [
+            (Action::CREATE, "create"),
+            (Action::DELETE, "delete"),
+            (Action::LIST, "list"),
+            (Action::HANDLE_MESSAGE, "handle_message"),
+            (Action::CONNECT_TO_TARGET, "connect_to_target"),
+        ];
+        for (action, name) in actions {
+            assert_eq!(action.as_str(), name);
+            assert_eq!(action, Action::from(name));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::net::{SocketAddr, TcpListener};
+use std::net::{IpAddr, SocketAddr, TcpListener};
 use std::str::FromStr;
 
 use ockam_core::Result;
@@ -59,3 +59,101 @@ pub fn get_free_address() -> Result<SocketAddr, ApiError> {
         .map_err(ParseError::from)?;
     Ok(res)
 }
+
+/// Lookup of the IP addresses assigned to the network interfaces of the host
+pub trait InterfaceLookup {
+    /// Return the current IP addresses of a network interface
+    fn addresses(&self, interface: &str) -> Result<Vec<IpAddr>>;
+}
+
+/// Lookup of the network interfaces using the operating system
+pub struct SystemInterfaceLookup;
+
+impl InterfaceLookup for SystemInterfaceLookup {
+    #[cfg(unix)]
+    fn addresses(&self, interface: &str) -> Result<Vec<IpAddr>> {
+        use std::net::{SocketAddrV4, SocketAddrV6};
+
+        let interfaces = nix::ifaddrs::getifaddrs()
+            .map_err(|e| ApiError::core(format!("cannot list the network interfaces: {e}")))?;
+        Ok(interfaces
+            .filter(|i| i.interface_name == interface)
+            .filter_map(|i| i.address)
+            .filter_map(|address| {
+                if let Some(a) = address.as_sockaddr_in() {
+                    Some(IpAddr::V4(*SocketAddrV4::from(*a).ip()))
+                } else {
+                    address
+                        .as_sockaddr_in6()
+                        .map(|a| IpAddr::V6(*SocketAddrV6::from(*a).ip()))
+                }
+            })
+            .collect())
+    }
+
+    #[cfg(not(unix))]
+    fn addresses(&self, interface: &str) -> Result<Vec<IpAddr>> {
+        Err(ApiError::core(format!(
+            "cannot find the address of the network interface {interface}: \
+            listing the network interfaces is not supported on this platform"
+        )))
+    }
+}
+
+/// Return the socket address to listen at on a given network interface.
+/// IPv4 addresses are preferred over IPv6 addresses
+pub fn interface_socket_address(
+    lookup: &dyn InterfaceLookup,
+    interface: &str,
+    port: u16,
+) -> Result<SocketAddr> {
+    let addresses = lookup.addresses(interface)?;
+    addresses
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or_else(|| addresses.first())
+        .map(|ip| SocketAddr::new(*ip, port))
+        .ok_or_else(|| {
+            ApiError::core(format!(
+                "the network interface {interface} does not exist or has no IP address"
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    struct StubInterfaceLookup(HashMap<String, Vec<IpAddr>>);
+
+    impl InterfaceLookup for StubInterfaceLookup {
+        fn addresses(&self, interface: &str) -> Result<Vec<IpAddr>> {
+            Ok(self.0.get(interface).cloned().unwrap_or_default())
+        }
+    }
+
+    #[test]
+    fn test_interface_socket_address() {
+        let ipv4 = IpAddr::V4(Ipv4Addr::new(172, 17, 0, 2));
+        let ipv6 = IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 2));
+        let lookup = StubInterfaceLookup(HashMap::from([
+            ("eth0".to_string(), vec![ipv6, ipv4]),
+            ("eth1".to_string(), vec![ipv6]),
+            ("eth2".to_string(), vec![]),
+        ]));
+
+        // IPv4 addresses are preferred
+        let result = interface_socket_address(&lookup, "eth0", 4000).unwrap();
+        assert_eq!(result, SocketAddr::new(ipv4, 4000));
+
+        let result = interface_socket_address(&lookup, "eth1", 4000).unwrap();
+        assert_eq!(result, SocketAddr::new(ipv6, 4000));
+
+        // interfaces without address or unknown interfaces can't be used
+        assert!(interface_socket_address(&lookup, "eth2", 4000).is_err());
+        assert!(interface_socket_address(&lookup, "eth3", 4000).is_err());
+    }
+}
@@ -10,7 +10,7 @@ use ockam::identity::{
     TrustEveryonePolicy,
 };
 use ockam_abac::expr::{and, eq, ident, str};
-use ockam_abac::{AbacAccessControl, Env};
+use ockam_abac::{AbacAccessControl, Action, Env};
 use ockam_core::compat::sync::Arc;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControlId;
@@ -24,7 +24,6 @@ use crate::authority_node::authority::EnrollerCheck::{AnyMember, EnrollerOnly};
 use crate::authority_node::Configuration;
 use crate::bootstrapped_identities_store::BootstrapedIdentityAttributesStore;
 use crate::echoer::Echoer;
-use crate::nodes::service::actions;
 use crate::nodes::service::default_address::DefaultAddress;
 
 /// This struct represents an Authority, which is an
@@ -356,7 +355,7 @@ impl Authority {
 
         let mut env = Env::new();
         env.put("resource.id", str(address.as_str()));
-        env.put("action.id", str(actions::HANDLE_MESSAGE.as_str()));
+        env.put("action.id", str(Action::HANDLE_MESSAGE.as_str()));
         env.put(
             "resource.trust_context_id",
             str(configuration.project_identifier.clone()),
@@ -1,15 +1,17 @@
 use std::path::{Path, PathBuf};
 
 use rand::random;
+use tokio::sync::broadcast;
 
 use cli_state::error::Result;
 use ockam::SqlxDatabase;
+use ockam_abac::{PolicyChange, PolicySqlxDatabase};
 use ockam_core::compat::sync::Arc;
 use ockam_core::env::get_env_with_default;
 use ockam_node::Executor;
 
 use crate::cli_state;
-use crate::cli_state::CliStateError;
+use crate::cli_state::{CliStateError, UsersSqlxDatabase};
 
 /// The CliState struct manages all the data persisted locally.
 ///
@@ -28,6 +30,12 @@ use crate::cli_state::CliStateError;
 pub struct CliState {
     dir: PathBuf,
     database: Arc<SqlxDatabase>,
+    /// Shared by all the policies repositories so that a change made with one repository
+    /// is notified to the subscribers of any of them
+    policy_changes: broadcast::Sender<PolicyChange>,
+    /// If true, the personal information of the users is encrypted at rest.
+    /// This is set with the `OCKAM_ENCRYPTED_USERS` environment variable
+    encrypted_users: bool,
 }
 
 impl CliState {
@@ -43,6 +51,14 @@ impl CliState {
     pub fn database_path(&self) -> PathBuf {
         Self::make_database_path(&self.dir)
     }
+
+    /// Encrypt the personal information of the users at rest, or not.
+    /// The users stored before the encryption is enabled are not encrypted and can not be read
+    /// while it is enabled
+    pub fn with_encrypted_users(mut self, encrypted_users: bool) -> Self {
+        self.encrypted_users = encrypted_users;
+        self
+    }
 }
 
 /// These functions allow to create and reset the local state
@@ -123,7 +139,16 @@ impl CliState {
         std::fs::create_dir_all(&dir)?;
         let database = Arc::new(SqlxDatabase::create(Self::make_database_path(&dir)).await?);
         debug!("Opened the database with options {:?}", database);
-        let state = Self { dir, database };
+        // fail early with a clear error if the schema expected by the repositories is missing
+        UsersSqlxDatabase::check_schema(&database).await?;
+        PolicySqlxDatabase::check_schema(&database).await?;
+        PolicySqlxDatabase::normalize_policies(&database).await?;
+        let state = Self {
+            dir,
+            database,
+            policy_changes: PolicySqlxDatabase::create_changes_sender(),
+            encrypted_users: get_env_with_default("OCKAM_ENCRYPTED_USERS", false)?,
+        };
         Ok(state)
     }
 
@@ -131,6 +156,14 @@ impl CliState {
         self.database.clone()
     }
 
+    pub(super) fn policy_changes(&self) -> broadcast::Sender<PolicyChange> {
+        self.policy_changes.clone()
+    }
+
+    pub(super) fn encrypted_users(&self) -> bool {
+        self.encrypted_users
+    }
+
     pub(super) fn make_database_path(root_path: &Path) -> PathBuf {
         root_path.join("database.sqlite3")
     }
@@ -173,3 +206,28 @@ impl CliState {
 pub fn random_name() -> String {
     petname::petname(2, "-").unwrap_or(hex::encode(random::<[u8; 4]>()))
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlx::query;
+
+    use ockam_node::database::ToVoid;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_create_checks_the_database_schema() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = CliState::make_database_path(dir.path());
+        let database = SqlxDatabase::create(&path).await?;
+        query("ALTER TABLE policy DROP COLUMN expires_at")
+            .execute(&database.pool)
+            .await
+            .void()?;
+        database.pool.close().await;
+
+        let error = CliState::create(dir.path().into()).await.unwrap_err();
+        assert!(error.to_string().contains("`expires_at`"), "{error}");
+        Ok(())
+    }
+}
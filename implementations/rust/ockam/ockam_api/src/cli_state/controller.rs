@@ -0,0 +1,43 @@
+use ockam::identity::Identifier;
+use ockam_multiaddr::MultiAddr;
+
+use crate::cli_state::CliState;
+
+use super::Result;
+
+/// The address and identifier of the controller to use instead of the default
+/// Orchestrator controller, for example a staging or a self-hosted Orchestrator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControllerConfig {
+    pub multiaddr: MultiAddr,
+    pub identifier: Option<Identifier>,
+}
+
+impl ControllerConfig {
+    pub fn new(multiaddr: MultiAddr, identifier: Option<Identifier>) -> Self {
+        Self {
+            multiaddr,
+            identifier,
+        }
+    }
+}
+
+impl CliState {
+    /// Store the controller to use for the next requests to the Orchestrator
+    pub async fn set_controller_config(&self, controller_config: &ControllerConfig) -> Result<()> {
+        Ok(self
+            .controller_repository()
+            .await?
+            .set_controller_config(controller_config)
+            .await?)
+    }
+
+    /// Return the controller overriding the default Orchestrator controller, if any
+    pub async fn get_controller_config(&self) -> Result<Option<ControllerConfig>> {
+        Ok(self
+            .controller_repository()
+            .await?
+            .get_controller_config()
+            .await?)
+    }
+}
@@ -1,5 +1,9 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
 use ockam::identity::models::ChangeHistory;
 use ockam::identity::{Identifier, Identity};
+use ockam_abac::{Action, Expr, Resource};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::Error;
 use ockam_vault::{HandleToSecret, SigningSecretKeyHandle};
@@ -135,6 +139,30 @@ impl CliState {
         }
     }
 
+    /// Return a named identity given either its identifier or its name,
+    /// or the default named identity if no value is given.
+    ///
+    /// The value is first parsed as an identifier. If it is not a valid identifier, or if no
+    /// identity has that identifier, then it is used as a name.
+    pub async fn get_named_identity_by_identifier_or_name(
+        &self,
+        identifier_or_name: &Option<String>,
+    ) -> Result<NamedIdentity> {
+        if let Some(value) = identifier_or_name {
+            if let Ok(identifier) = Identifier::from_str(value) {
+                if let Some(named_identity) = self
+                    .identities_repository()
+                    .await?
+                    .get_named_identity_by_identifier(&identifier)
+                    .await?
+                {
+                    return Ok(named_identity);
+                }
+            }
+        }
+        self.get_named_identity_or_default(identifier_or_name).await
+    }
+
     /// Return the identifier of a named identity
     pub async fn get_identifier_by_name(&self, name: &str) -> Result<Identifier> {
         Ok(self.get_named_identity(name).await?.identifier())
@@ -292,12 +320,30 @@ impl CliState {
     /// Delete an identity by name:
     ///
     ///  - check that the identity is not used by a node first
+    ///  - check that the identity is not referenced by a policy or a trust context, unless `force` is true
     ///  - then remove the the name association to the identity
     ///  - and remove the identity change history
     ///
-    pub async fn delete_identity_by_name(&self, name: &str) -> Result<()> {
+    pub async fn delete_identity_by_name(&self, name: &str, force: bool) -> Result<()> {
         let nodes = self.get_nodes_by_identity_name(name).await?;
         if nodes.is_empty() {
+            if !force {
+                let references = self.get_identity_references(name).await?;
+                if !references.is_empty() {
+                    let references: Vec<String> =
+                        references.iter().map(|r| r.to_string()).collect();
+                    return Err(Error::new(
+                        Origin::Api,
+                        Kind::Conflict,
+                        format!(
+                            "The identity named {name} cannot be deleted because it is referenced by: {}. Its deletion must be forced to leave these references dangling",
+                            references.join(", ")
+                        ),
+                    )
+                    .into());
+                }
+            }
+
             if let Some(identifier) = self
                 .identities_repository()
                 .await?
@@ -325,8 +371,82 @@ impl CliState {
     }
 }
 
+/// The following methods allow to rotate the keys of a named identity
+impl CliState {
+    /// Replace the identity with the given name by a new identity, with new keys, stored in the
+    /// same vault. The name is kept so that references to the identity by name still work.
+    /// If the old identity was the default one, the new identity becomes the default one.
+    ///
+    /// If the new identity cannot be created, the old identity is restored
+    pub async fn recreate_identity_by_name(
+        &self,
+        name: &str,
+        force: bool,
+    ) -> Result<NamedIdentity> {
+        let old = self.get_named_identity(name).await?;
+        let is_default = old.is_default();
+        let change_history = self.get_change_history(&old.identifier()).await?;
+        // another identity becomes the default one when the old identity is deleted
+        self.delete_identity_by_name(name, force).await?;
+
+        match self
+            .create_identity_with_name_and_vault(name, &old.vault_name())
+            .await
+        {
+            Ok(identity) if is_default && !identity.is_default() => {
+                self.set_as_default_identity(name).await?;
+                self.get_named_identity(name).await
+            }
+            Ok(identity) => Ok(identity),
+            Err(e) => {
+                self.change_history_repository()
+                    .await?
+                    .store_change_history(&old.identifier(), change_history)
+                    .await?;
+                let repository = self.identities_repository().await?;
+                repository
+                    .store_named_identity(&old.identifier(), name, &old.vault_name())
+                    .await?;
+                if is_default {
+                    repository.set_as_default(name).await?;
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
 /// Support methods
 impl CliState {
+    /// Return the policies and trust contexts referencing the identity with the given name.
+    /// A policy references an identity when one of its string values is the identity identifier.
+    /// A trust context references an identity when that identity is its authority
+    pub async fn get_identity_references(&self, name: &str) -> Result<Vec<IdentityReference>> {
+        let identifier = self.get_identifier_by_name(name).await?;
+        let mut references = vec![];
+
+        let policies = self.policies_repository().await?;
+        for resource in policies.list_resources().await? {
+            for (action, expression) in policies.get_policies_by_resource(&resource).await? {
+                if expression_contains_str(&expression, &identifier.to_string()) {
+                    references.push(IdentityReference::Policy {
+                        resource: resource.clone(),
+                        action,
+                    });
+                }
+            }
+        }
+
+        for trust_context in self.get_trust_contexts().await? {
+            if trust_context.authority_identifier().await? == Some(identifier.clone()) {
+                references.push(IdentityReference::TrustContext {
+                    name: trust_context.name(),
+                });
+            }
+        }
+        Ok(references)
+    }
+
     /// Once a identity has been created, store it.
     /// If there is no previous default identity we set it as the default identity
     async fn store_named_identity(
@@ -379,6 +499,42 @@ impl CliState {
     }
 }
 
+/// Return true if a string value of the expression is equal to `value`
+fn expression_contains_str(expression: &Expr, value: &str) -> bool {
+    let mut expressions = vec![expression];
+    while let Some(expression) = expressions.pop() {
+        match expression {
+            Expr::Str(s) if s == value => return true,
+            Expr::Seq(xs) | Expr::List(xs) => expressions.extend(xs),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Policy or trust context referencing an identity
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IdentityReference {
+    /// The policy of a resource and action
+    Policy { resource: Resource, action: Action },
+    /// A trust context using the identity as its authority
+    TrustContext { name: String },
+}
+
+impl Display for IdentityReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdentityReference::Policy { resource, action } => {
+                write!(
+                    f,
+                    "the policy of resource '{resource}' and action '{action}'"
+                )
+            }
+            IdentityReference::TrustContext { name } => write!(f, "the trust context '{name}'"),
+        }
+    }
+}
+
 /// A named identity associates a name with a persisted identity.
 /// This is a convenience for users since they can refer to an identity by the name "alice"
 /// instead of the identifier "I1234561234561234561234561234561234561234"
@@ -434,6 +590,8 @@ impl NamedIdentity {
 
 #[cfg(test)]
 mod tests {
+    use ockam_abac::expr::{eq, ident, str};
+
     use super::*;
 
     #[tokio::test]
@@ -513,7 +671,7 @@ mod tests {
         assert!(result.is_ok());
 
         // now if we delete the identity there is no more persisted data
-        cli.delete_identity_by_name(&identity.name()).await?;
+        cli.delete_identity_by_name(&identity.name(), false).await?;
         let result = cli.get_change_history(&identity.identifier()).await;
         assert!(result.is_err());
 
@@ -522,4 +680,116 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_delete_identity_referenced_by_a_policy() -> Result<()> {
+        let cli = CliState::test().await?;
+        let identity = cli.create_identity_with_name("name").await?;
+
+        // a policy only allows that identity to access a resource
+        let resource = Resource::from("outlet");
+        let action = Action::from("handle_message");
+        let expression = eq([
+            ident("subject.identifier"),
+            str(identity.identifier().to_string()),
+        ]);
+        cli.set_policy(&resource, &action, &expression).await?;
+
+        let references = cli.get_identity_references(&identity.name()).await?;
+        assert_eq!(
+            references,
+            vec![IdentityReference::Policy {
+                resource: resource.clone(),
+                action: action.clone()
+            }]
+        );
+
+        // the identity cannot be deleted unless the deletion is forced
+        let result = cli.delete_identity_by_name(&identity.name(), false).await;
+        assert!(result.is_err());
+        assert!(cli.get_named_identity(&identity.name()).await.is_ok());
+
+        cli.delete_identity_by_name(&identity.name(), true).await?;
+        assert!(cli.get_named_identity(&identity.name()).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recreate_identity() -> Result<()> {
+        let cli = CliState::test().await?;
+        let alice = cli.create_identity_with_name("alice").await?;
+        let bob = cli.create_identity_with_name("bob").await?;
+
+        // the recreated identity keeps its name and its vault but gets a new identifier
+        let recreated = cli.recreate_identity_by_name("alice", false).await?;
+        assert_eq!(recreated.name(), "alice");
+        assert_eq!(recreated.vault_name(), alice.vault_name());
+        assert_ne!(recreated.identifier(), alice.identifier());
+        assert_eq!(
+            cli.get_identifier_by_name("alice").await?,
+            recreated.identifier()
+        );
+
+        // the old identity is not persisted anymore
+        assert!(cli.get_change_history(&alice.identifier()).await.is_err());
+
+        // the recreated identity is still the default one, even though bob was made the default
+        // identity when the old identity was deleted
+        assert!(recreated.is_default());
+        assert_eq!(cli.get_default_identity_name().await?, "alice");
+        assert!(!cli.get_named_identity("bob").await?.is_default());
+
+        // a non-default identity stays non-default
+        let recreated = cli.recreate_identity_by_name("bob", false).await?;
+        assert_ne!(recreated.identifier(), bob.identifier());
+        assert!(!recreated.is_default());
+        assert_eq!(cli.get_named_identities().await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_identity_by_identifier_or_name() -> Result<()> {
+        let cli = CliState::test().await?;
+        let alice = cli.create_identity_with_name("alice").await?;
+        let bob = cli.create_identity_with_name("bob").await?;
+
+        // an identity can be found by identifier
+        let identity = cli
+            .get_named_identity_by_identifier_or_name(&Some(alice.identifier().to_string()))
+            .await?;
+        assert_eq!(identity, alice);
+        cli.delete_identity_by_name(&identity.name(), false).await?;
+        assert!(cli.get_named_identity("alice").await.is_err());
+
+        // or by name
+        let identity = cli
+            .get_named_identity_by_identifier_or_name(&Some("bob".to_string()))
+            .await?;
+        assert_eq!(identity, bob);
+        cli.delete_identity_by_name(&identity.name(), false).await?;
+        assert!(cli.get_named_identity("bob").await.is_err());
+
+        // when a name is also a valid identifier, the identifier takes precedence
+        let charlie = cli.create_identity_with_name("charlie").await?;
+        let ambiguous = cli
+            .create_identity_with_name(&charlie.identifier().to_string())
+            .await?;
+        let identity = cli
+            .get_named_identity_by_identifier_or_name(&Some(charlie.identifier().to_string()))
+            .await?;
+        assert_eq!(identity, charlie);
+
+        // once the identity with that identifier is deleted, the value is used as a name
+        cli.delete_identity_by_name(&identity.name(), false).await?;
+        let identity = cli
+            .get_named_identity_by_identifier_or_name(&Some(charlie.identifier().to_string()))
+            .await?;
+        assert_eq!(identity.name(), ambiguous.name());
+        cli.delete_identity_by_name(&identity.name(), false).await?;
+        assert!(cli.get_named_identities().await?.is_empty());
+
+        Ok(())
+    }
 }
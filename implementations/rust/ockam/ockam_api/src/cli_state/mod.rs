@@ -1,4 +1,5 @@
 pub use cli_state::*;
+pub use controller::*;
 pub use credentials::*;
 pub use enrollments::*;
 pub use error::*;
@@ -16,6 +17,7 @@ pub use vaults::*;
 
 #[allow(clippy::module_inception)]
 pub mod cli_state;
+pub mod controller;
 pub mod credentials;
 pub mod enrollments;
 pub mod error;
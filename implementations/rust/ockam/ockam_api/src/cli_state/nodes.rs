@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
 use nix::errno::Errno;
 use sysinfo::{Pid, ProcessExt, ProcessStatus, System, SystemExt};
@@ -203,6 +204,33 @@ impl CliState {
             .set_node_pid(node_name, pid)
             .await?)
     }
+
+    /// Store how long before its expiration the credential of a node is refreshed,
+    /// so that the credential is still refreshed when the node is restarted
+    pub async fn set_node_credential_refresh_skew(
+        &self,
+        node_name: &str,
+        skew: Duration,
+    ) -> Result<()> {
+        Ok(self
+            .nodes_repository()
+            .await?
+            .set_node_credential_refresh_skew(node_name, skew)
+            .await?)
+    }
+
+    /// Return how long before its expiration the credential of a node is refreshed,
+    /// if that node refreshes its credential
+    pub async fn get_node_credential_refresh_skew(
+        &self,
+        node_name: &str,
+    ) -> Result<Option<Duration>> {
+        Ok(self
+            .nodes_repository()
+            .await?
+            .get_node_credential_refresh_skew(node_name)
+            .await?)
+    }
 }
 
 /// The following methods return nodes data
@@ -1,17 +1,27 @@
 use crate::cli_state::CliState;
 use crate::cli_state::Result;
-use ockam_abac::{Action, Env, Expr, PolicyAccessControl, Resource};
+use crate::nodes::service::target_authorization::PolicyTargetAuthorization;
+use ockam_abac::{Action, Env, Expr, PolicyAccessControl, PolicyChange, Resource};
+use tokio::sync::broadcast;
 
 impl CliState {
     pub async fn get_policy(&self, r: &Resource, a: &Action) -> Result<Option<Expr>> {
         Ok(self.policies_repository().await?.get_policy(r, a).await?)
     }
 
+    pub async fn get_policy_inherited(&self, r: &Resource, a: &Action) -> Result<Option<Expr>> {
+        Ok(self
+            .policies_repository()
+            .await?
+            .get_policy_inherited(r, a)
+            .await?)
+    }
+
     pub async fn set_policy(&self, r: &Resource, a: &Action, c: &Expr) -> Result<()> {
         Ok(self
             .policies_repository()
             .await?
-            .set_policy(r, a, c)
+            .set_policy(r, a, c, None)
             .await?)
     }
 
@@ -31,6 +41,23 @@ impl CliState {
             .await?)
     }
 
+    pub async fn get_policies_by_resources(
+        &self,
+        resources: &[Resource],
+    ) -> Result<Vec<(Resource, Action, Expr)>> {
+        Ok(self
+            .policies_repository()
+            .await?
+            .get_policies_by_resources(resources)
+            .await?)
+    }
+
+    /// Return a receiver for the changes made to the policies with this CliState,
+    /// or with any of its clones, once they have been stored
+    pub fn subscribe_policy_changes(&self) -> broadcast::Receiver<PolicyChange> {
+        self.policy_changes().subscribe()
+    }
+
     pub async fn make_policy_access_control(
         &self,
         r: &Resource,
@@ -51,4 +78,47 @@ impl CliState {
             env,
         ))
     }
+
+    pub async fn make_target_authorization(
+        &self,
+        r: &Resource,
+        a: &Action,
+    ) -> Result<PolicyTargetAuthorization> {
+        Ok(PolicyTargetAuthorization::new(
+            self.policies_repository().await?,
+            r.clone(),
+            a.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_abac::PolicyChangeKind;
+
+    #[tokio::test]
+    async fn test_policy_changes_are_notified_across_repositories() -> Result<()> {
+        let cli = CliState::test().await?;
+        let mut changes = cli.subscribe_policy_changes();
+
+        // each write goes through a distinct repository
+        let resource = Resource::from("outlet");
+        let action = Action::from("handle_message");
+        cli.policies_repository()
+            .await?
+            .set_policy(&resource, &action, &Expr::Bool(true), None)
+            .await?;
+        cli.policies_repository()
+            .await?
+            .delete_policy(&resource, &action)
+            .await?;
+
+        for kind in [PolicyChangeKind::Set, PolicyChangeKind::Deleted] {
+            let change = changes.try_recv().unwrap();
+            assert_eq!(change.resource, resource);
+            assert_eq!(change.kind, kind);
+        }
+        Ok(())
+    }
 }
@@ -140,6 +140,42 @@ impl CliState {
         }
     }
 
+    /// Return the name of the project to use when a command refers to a project without naming it.
+    ///
+    /// The project given explicitly takes precedence, then the default project and finally
+    /// the last project which was successfully used by a command, if any
+    pub async fn get_project_name_or_default(
+        &self,
+        project_name: &Option<String>,
+    ) -> Result<Option<String>> {
+        if let Some(project_name) = project_name {
+            return Ok(Some(project_name.clone()));
+        }
+        let repository = self.projects_repository().await?;
+        if let Some(project) = repository.get_default_project().await? {
+            return Ok(Some(project.name));
+        }
+        Ok(repository.get_last_used_project_name().await?)
+    }
+
+    /// Return the name of the last project which was successfully used by a command, if any
+    pub async fn get_last_used_project_name(&self) -> Result<Option<String>> {
+        Ok(self
+            .projects_repository()
+            .await?
+            .get_last_used_project_name()
+            .await?)
+    }
+
+    /// Remember the name of a project which was successfully used by a command
+    pub async fn set_last_used_project_name(&self, project_name: &str) -> Result<()> {
+        Ok(self
+            .projects_repository()
+            .await?
+            .set_last_used_project_name(project_name)
+            .await?)
+    }
+
     pub async fn get_projects(&self) -> Result<Vec<Project>> {
         Ok(self.projects_repository().await?.get_projects().await?)
     }
@@ -175,4 +211,38 @@ mod tests {
         .await?;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_get_project_name_or_default() -> Result<()> {
+        let cli = CliState::test().await?;
+
+        // there is no project to use
+        let result = cli.get_project_name_or_default(&None).await?;
+        assert_eq!(result, None);
+
+        // the last used project is used when there is no default project
+        cli.set_last_used_project_name("last").await?;
+        let result = cli.get_project_name_or_default(&None).await?;
+        assert_eq!(result, Some("last".to_string()));
+
+        // the default project takes precedence over the last used project
+        cli.import_project(
+            "project_id",
+            "default",
+            &None,
+            &MultiAddr::from_string("/project/default").unwrap(),
+            &None,
+            &None,
+        )
+        .await?;
+        let result = cli.get_project_name_or_default(&None).await?;
+        assert_eq!(result, Some("default".to_string()));
+
+        // an explicit project takes precedence over both
+        let result = cli
+            .get_project_name_or_default(&Some("explicit".to_string()))
+            .await?;
+        assert_eq!(result, Some("explicit".to_string()));
+        Ok(())
+    }
 }
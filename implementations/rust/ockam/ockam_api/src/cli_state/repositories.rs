@@ -1,7 +1,7 @@
 use ockam::identity::storage::{PurposeKeysRepository, PurposeKeysSqlxDatabase};
 use ockam::identity::{
     ChangeHistoryRepository, ChangeHistorySqlxDatabase, IdentityAttributesRepository,
-    IdentityAttributesSqlxDatabase,
+    IdentityAttributesSqlxDatabase, Vault,
 };
 use ockam_abac::{PoliciesRepository, PolicySqlxDatabase};
 use ockam_core::compat::sync::Arc;
@@ -53,7 +53,10 @@ impl CliState {
     }
 
     pub(super) async fn policies_repository(&self) -> Result<Arc<dyn PoliciesRepository>> {
-        Ok(Arc::new(PolicySqlxDatabase::new(self.database())))
+        Ok(Arc::new(PolicySqlxDatabase::new_with_changes(
+            self.database(),
+            self.policy_changes(),
+        )))
     }
 
     pub(super) async fn projects_repository(&self) -> Result<Arc<dyn ProjectsRepository>> {
@@ -65,9 +68,21 @@ impl CliState {
     }
 
     pub(super) async fn users_repository(&self) -> Result<Arc<dyn UsersRepository>> {
+        if self.encrypted_users() {
+            // the encryption key is stored with the other secrets of the database
+            let vault = Vault::create_with_database(self.database());
+            return Ok(Arc::new(
+                EncryptedUsersRepository::create(self.database(), vault.secure_channel_vault)
+                    .await?,
+            ));
+        }
         Ok(Arc::new(UsersSqlxDatabase::new(self.database())))
     }
 
+    pub(super) async fn controller_repository(&self) -> Result<Arc<dyn ControllerRepository>> {
+        Ok(Arc::new(ControllerSqlxDatabase::new(self.database())))
+    }
+
     pub(super) async fn credentials_repository(&self) -> Result<Arc<dyn CredentialsRepository>> {
         Ok(Arc::new(CredentialsSqlxDatabase::new(self.database())))
     }
@@ -0,0 +1,15 @@
+use ockam_core::async_trait;
+use ockam_core::Result;
+
+use crate::cli_state::controller::ControllerConfig;
+
+/// This trait stores the address and identifier of the controller
+/// when the default Orchestrator controller is overridden
+#[async_trait]
+pub trait ControllerRepository: Send + Sync + 'static {
+    /// Store the controller configuration, replacing any previous one
+    async fn set_controller_config(&self, controller_config: &ControllerConfig) -> Result<()>;
+
+    /// Return the controller configuration if one has been stored
+    async fn get_controller_config(&self) -> Result<Option<ControllerConfig>>;
+}
@@ -0,0 +1,121 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use sqlx::*;
+
+use ockam::identity::Identifier;
+use ockam_core::async_trait;
+use ockam_core::Result;
+use ockam_multiaddr::MultiAddr;
+use ockam_node::database::{FromSqlxError, SqlxDatabase, ToSqlxType, ToVoid};
+
+use crate::cli_state::controller::ControllerConfig;
+
+use super::ControllerRepository;
+
+#[derive(Clone)]
+pub struct ControllerSqlxDatabase {
+    database: Arc<SqlxDatabase>,
+}
+
+impl ControllerSqlxDatabase {
+    /// Create a new database
+    pub fn new(database: Arc<SqlxDatabase>) -> Self {
+        debug!("create a repository for the controller");
+        Self { database }
+    }
+
+    /// Create a new in-memory database
+    pub async fn create() -> Result<Arc<Self>> {
+        Ok(Arc::new(Self::new(
+            SqlxDatabase::in_memory("controller").await?,
+        )))
+    }
+}
+
+#[async_trait]
+impl ControllerRepository for ControllerSqlxDatabase {
+    async fn set_controller_config(&self, controller_config: &ControllerConfig) -> Result<()> {
+        let mut transaction = self.database.begin().await.into_core()?;
+        // only one controller is kept
+        let query1 = query("DELETE FROM controller");
+        query1.execute(&mut *transaction).await.void()?;
+
+        let query2 = query("INSERT INTO controller VALUES (?, ?)")
+            .bind(controller_config.multiaddr.to_string().to_sql())
+            .bind(controller_config.identifier.as_ref().map(|i| i.to_sql()));
+        query2.execute(&mut *transaction).await.void()?;
+        transaction.commit().await.void()
+    }
+
+    async fn get_controller_config(&self) -> Result<Option<ControllerConfig>> {
+        let query = query_as("SELECT multiaddr, identifier FROM controller");
+        let row: Option<ControllerRow> = query
+            .fetch_optional(&self.database.pool)
+            .await
+            .into_core()?;
+        row.map(|r| r.controller_config()).transpose()
+    }
+}
+
+// Database serialization / deserialization
+
+/// Low-level representation of a row in the controller table
+#[derive(sqlx::FromRow)]
+struct ControllerRow {
+    multiaddr: String,
+    identifier: Option<String>,
+}
+
+impl ControllerRow {
+    fn controller_config(&self) -> Result<ControllerConfig> {
+        Ok(ControllerConfig {
+            multiaddr: MultiAddr::from_str(&self.multiaddr)?,
+            identifier: self
+                .identifier
+                .as_ref()
+                .map(|i| Identifier::from_str(i))
+                .transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_repository() -> Result<()> {
+        let repository = create_repository().await?;
+
+        // no controller has been stored yet
+        let result = repository.get_controller_config().await?;
+        assert_eq!(result, None);
+
+        // only the last controller is kept
+        let controller1 = ControllerConfig {
+            multiaddr: MultiAddr::from_str("/dnsaddr/localhost/tcp/4000/service/api").unwrap(),
+            identifier: None,
+        };
+        let controller2 = ControllerConfig {
+            multiaddr: MultiAddr::from_str("/dnsaddr/localhost/tcp/5000/service/api").unwrap(),
+            identifier: Some(
+                Identifier::from_str(
+                    "I84502ce0d9a0a91bae29026b84e19be69fb4203a6bdd1424c85a43c812772a00",
+                )
+                .unwrap(),
+            ),
+        };
+        repository.set_controller_config(&controller1).await?;
+        repository.set_controller_config(&controller2).await?;
+
+        let result = repository.get_controller_config().await?;
+        assert_eq!(result, Some(controller2));
+        Ok(())
+    }
+
+    /// HELPERS
+    async fn create_repository() -> Result<Arc<dyn ControllerRepository>> {
+        Ok(ControllerSqlxDatabase::create().await?)
+    }
+}
@@ -1,3 +1,5 @@
+pub use controller_repository::*;
+pub use controller_repository_sql::*;
 pub use credentials_repository::*;
 pub use credentials_repository_sql::*;
 pub use enrollments_repository::*;
@@ -13,10 +15,13 @@ pub use spaces_repository_sql::*;
 pub use trust_contexts_repository::*;
 pub use trust_contexts_repository_sql::*;
 pub use users_repository::*;
+pub use users_repository_encrypted::*;
 pub use users_repository_sql::*;
 pub use vaults_repository::*;
 pub use vaults_repository_sql::*;
 
+mod controller_repository;
+mod controller_repository_sql;
 mod credentials_repository;
 mod credentials_repository_sql;
 mod enrollments_repository;
@@ -32,6 +37,7 @@ mod spaces_repository_sql;
 mod trust_contexts_repository;
 mod trust_contexts_repository_sql;
 mod users_repository;
+mod users_repository_encrypted;
 mod users_repository_sql;
 mod vaults_repository;
 mod vaults_repository_sql;
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use ockam::identity::Identifier;
 use ockam_core::async_trait;
 use ockam_core::Result;
@@ -10,6 +12,7 @@ use crate::config::lookup::InternetAddress;
 ///  - a node has a unique name
 ///  - a node is always associated to an identifier
 ///  - a node can be associated to a (single) project
+///  - a node can refresh its credential some time before it expires
 ///  - when a node is running we can persist its process id and its TCP listener address
 ///  - one of the nodes is always set as the default node
 ///  - a node can be set as an authority node. The purpose of this flag is to be able to display
@@ -61,4 +64,12 @@ pub trait NodesRepository: Send + Sync + 'static {
 
     /// Return the name of the project associated to a node
     async fn get_node_project_name(&self, node_name: &str) -> Result<Option<String>>;
+
+    /// Store how long before its expiration the credential of a node is refreshed
+    async fn set_node_credential_refresh_skew(&self, node_name: &str, skew: Duration)
+        -> Result<()>;
+
+    /// Return how long before its expiration the credential of a node is refreshed,
+    /// if the node refreshes its credential
+    async fn get_node_credential_refresh_skew(&self, node_name: &str) -> Result<Option<Duration>>;
 }
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use sqlx::sqlite::SqliteRow;
 use sqlx::*;
@@ -105,8 +106,14 @@ impl NodesRepository for NodesSqlxDatabase {
     }
 
     async fn delete_node(&self, node_name: &str) -> Result<()> {
-        let query = query("DELETE FROM node WHERE name=?").bind(node_name.to_sql());
-        query.execute(&self.database.pool).await.void()
+        let mut transaction = self.database.begin().await.into_core()?;
+        let query1 = query("DELETE FROM node WHERE name=?").bind(node_name.to_sql());
+        query1.execute(&mut *transaction).await.void()?;
+
+        let query2 =
+            query("DELETE FROM node_credential_refresh WHERE node_name=?").bind(node_name.to_sql());
+        query2.execute(&mut *transaction).await.void()?;
+        transaction.commit().await.void()
     }
 
     async fn set_tcp_listener_address(&self, node_name: &str, address: &str) -> Result<()> {
@@ -159,6 +166,28 @@ impl NodesRepository for NodesSqlxDatabase {
         let project_name: Option<String> = row.map(|r| r.get(0));
         Ok(project_name)
     }
+
+    async fn set_node_credential_refresh_skew(
+        &self,
+        node_name: &str,
+        skew: Duration,
+    ) -> Result<()> {
+        let query = query("INSERT OR REPLACE INTO node_credential_refresh VALUES (?1, ?2)")
+            .bind(node_name.to_sql())
+            .bind(skew.as_secs().to_sql());
+        Ok(query.execute(&self.database.pool).await.void()?)
+    }
+
+    async fn get_node_credential_refresh_skew(&self, node_name: &str) -> Result<Option<Duration>> {
+        let query = query("SELECT skew FROM node_credential_refresh WHERE node_name = ?")
+            .bind(node_name.to_sql());
+        let row: Option<SqliteRow> = query
+            .fetch_optional(&self.database.pool)
+            .await
+            .into_core()?;
+        let skew: Option<i64> = row.map(|r| r.get(0));
+        Ok(skew.map(|s| Duration::from_secs(s as u64)))
+    }
 }
 
 // Database serialization / deserialization
@@ -298,6 +327,35 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_node_credential_refresh_skew() -> Result<()> {
+        let repository = create_repository().await?;
+
+        // by default a node doesn't refresh its credential
+        let result = repository
+            .get_node_credential_refresh_skew("node_name")
+            .await?;
+        assert_eq!(result, None);
+
+        // the credential refresh skew of a node can be stored
+        repository
+            .set_node_credential_refresh_skew("node_name", Duration::from_secs(300))
+            .await?;
+        let result = repository
+            .get_node_credential_refresh_skew("node_name")
+            .await?;
+        assert_eq!(result, Some(Duration::from_secs(300)));
+
+        // it is removed with the node
+        repository.delete_node("node_name").await?;
+        let result = repository
+            .get_node_credential_refresh_skew("node_name")
+            .await?;
+        assert_eq!(result, None);
+
+        Ok(())
+    }
+
     /// HELPERS
     async fn create_repository() -> Result<Arc<dyn NodesRepository>> {
         Ok(NodesSqlxDatabase::create().await?)
@@ -33,4 +33,10 @@ pub trait ProjectsRepository: Send + Sync + 'static {
     /// Delete a project
     /// Return true if the project could be deleted
     async fn delete_project(&self, project_id: &str) -> Result<()>;
+
+    /// Store the name of the last project successfully used by a command
+    async fn set_last_used_project_name(&self, project_name: &str) -> Result<()>;
+
+    /// Return the name of the last project successfully used by a command, if any
+    async fn get_last_used_project_name(&self) -> Result<Option<String>>;
 }
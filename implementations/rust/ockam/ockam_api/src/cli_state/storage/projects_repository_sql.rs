@@ -257,6 +257,26 @@ impl ProjectsRepository for ProjectsSqlxDatabase {
         transaction.commit().await.void()?;
         Ok(())
     }
+
+    async fn set_last_used_project_name(&self, project_name: &str) -> Result<()> {
+        let mut transaction = self.database.begin().await.into_core()?;
+        // only one project name is kept
+        let query1 = query("DELETE FROM last_used_project");
+        query1.execute(&mut *transaction).await.void()?;
+
+        let query2 = query("INSERT INTO last_used_project VALUES (?)").bind(project_name.to_sql());
+        query2.execute(&mut *transaction).await.void()?;
+        transaction.commit().await.void()
+    }
+
+    async fn get_last_used_project_name(&self) -> Result<Option<String>> {
+        let query = query("SELECT project_name FROM last_used_project");
+        let row: Option<SqliteRow> = query
+            .fetch_optional(&self.database.pool)
+            .await
+            .into_core()?;
+        Ok(row.map(|r| r.get(0)))
+    }
 }
 
 // Database serialization / deserialization
@@ -452,6 +472,22 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_last_used_project_name() -> Result<()> {
+        let repository = create_repository().await?;
+
+        // no project has been used yet
+        let result = repository.get_last_used_project_name().await?;
+        assert_eq!(result, None);
+
+        // only the last project name is kept
+        repository.set_last_used_project_name("name1").await?;
+        repository.set_last_used_project_name("name2").await?;
+        let result = repository.get_last_used_project_name().await?;
+        assert_eq!(result, Some("name2".to_string()));
+        Ok(())
+    }
+
     /// HELPERS
     async fn create_repository() -> Result<Arc<dyn ProjectsRepository>> {
         Ok(ProjectsSqlxDatabase::create().await?)
@@ -1,4 +1,5 @@
 use crate::cloud::enroll::auth0::UserInfo;
+use ockam::identity::models::TimestampInSeconds;
 use ockam_core::async_trait;
 use ockam_core::Result;
 
@@ -14,19 +15,32 @@ use ockam_core::Result;
 /// Even if there is a sub field supposed to uniquely identify a user we currently use
 /// the user email for this.
 ///
-/// A user can also be set as the default user via this repository.
+/// Each user belongs to a tenant, for example a space in a multi-tenant install,
+/// and a user can be set as the default user of their tenant via this repository.
+/// The users of a single-tenant install belong to the [`DEFAULT_TENANT`].
 ///
 #[async_trait]
 pub trait UsersRepository: Send + Sync + 'static {
-    /// Store (or update) some information
-    /// In case of an update, if the user was already the default user, it will stay the default user
-    async fn store_user(&self, user: &UserInfo) -> Result<()>;
+    /// Store (or update) some information for a user of a tenant
+    /// In case of an update, if the user was already the default user of that tenant,
+    /// it will stay the default user. A user moved to another tenant is not its default user
+    async fn store_user(&self, tenant: &str, user: &UserInfo) -> Result<()>;
 
-    /// Return the default user
-    async fn get_default_user(&self) -> Result<Option<UserInfo>>;
+    /// Return the default user of a tenant
+    async fn get_default_user(&self, tenant: &str) -> Result<Option<UserInfo>>;
 
-    /// Set a user as the default one
-    async fn set_default_user(&self, email: &str) -> Result<()>;
+    /// Return the default user of the [`DEFAULT_TENANT`] if there is one, otherwise the first user
+    /// of that tenant by email in alphabetical order. If `promote` is true, that first user is also set as
+    /// the default user of the [`DEFAULT_TENANT`]
+    async fn get_default_user_or_first(&self, promote: bool) -> Result<Option<UserInfo>>;
+
+    /// Set a user as the default one of a tenant.
+    /// An error is returned if the user doesn't exist or belongs to another tenant
+    async fn set_default_user(&self, tenant: &str, email: &str) -> Result<()>;
+
+    /// Store (or update) some information for a user of a tenant and set the user
+    /// as the only default one of that tenant, in a single transaction
+    async fn upsert_and_set_default(&self, tenant: &str, user: &UserInfo) -> Result<()>;
 
     /// Return a user given their email
     async fn get_user(&self, email: &str) -> Result<Option<UserInfo>>;
@@ -34,6 +48,34 @@ pub trait UsersRepository: Send + Sync + 'static {
     /// Get the list of all users
     async fn get_users(&self) -> Result<Vec<UserInfo>>;
 
+    /// Get the list of all users, sorted by the given key
+    async fn get_users_sorted(&self, by: UserSortKey, ascending: bool) -> Result<Vec<UserInfo>>;
+
+    /// Get the list of all the users having a given role
+    async fn get_users_with_role(&self, role: &str) -> Result<Vec<UserInfo>>;
+
+    /// Get the list of the users created strictly after the given time, oldest first
+    async fn get_users_created_after(&self, ts: TimestampInSeconds) -> Result<Vec<UserInfo>>;
+
     /// Delete a user given their email
     async fn delete_user(&self, email: &str) -> Result<()>;
+
+    /// Change the email of a user, keeping all their other information and their default status.
+    /// Return an error if the user does not exist or if another user already has the new email
+    async fn rename_user_email(&self, old: &str, new: &str) -> Result<()>;
+
+    /// Return the number of users for each email domain, sorted by decreasing count then by domain.
+    /// The domains are compared in lowercase
+    async fn count_users_by_domain(&self) -> Result<Vec<(String, u64)>>;
+}
+
+/// Tenant of the users of a single-tenant install
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Key used to sort the list of users
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserSortKey {
+    Email,
+    Name,
+    UpdatedAt,
 }
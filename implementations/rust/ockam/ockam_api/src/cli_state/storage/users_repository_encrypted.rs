@@ -0,0 +1,503 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use minicbor::{Decode, Encode};
+use sqlx::sqlite::SqliteRow;
+use sqlx::*;
+
+use ockam::identity::models::TimestampInSeconds;
+use ockam_core::async_trait;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_node::database::{FromSqlxError, SqlxDatabase, ToSqlxType, ToVoid};
+use ockam_vault::{
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, HandleToSecret, SecretBufferHandle,
+    VaultForSecureChannels, X25519SecretKeyHandle,
+};
+
+use crate::cloud::enroll::auth0::UserInfo;
+
+use super::{UserSortKey, UsersRepository, UsersSqlxDatabase, DEFAULT_TENANT};
+
+/// Length of the nonce used to encrypt the users data
+const NONCE_LENGTH: usize = 12;
+
+/// This implementation of the UsersRepository encrypts the users personal information
+/// before storing it in the user table:
+///
+///  - the email and the sub are stored as keyed hashes, so that a user can still be retrieved by email
+///  - the email, sub, nickname, name and picture are encrypted together and stored in the
+///    `encrypted_data` column. The corresponding plain text columns are left empty
+///
+/// The encryption key and the hashing key are derived from a static X25519 key of a vault.
+/// Since that key is persisted the users information can still be decrypted after a restart.
+#[derive(Clone)]
+pub struct EncryptedUsersRepository {
+    database: Arc<SqlxDatabase>,
+    repository: UsersSqlxDatabase,
+    vault: Arc<dyn VaultForSecureChannels>,
+    key: AeadSecretKeyHandle,
+    hash_key: SecretBufferHandle,
+}
+
+impl EncryptedUsersRepository {
+    /// Create a new repository encrypting the users information with keys derived from
+    /// the given X25519 secret key
+    pub async fn new(
+        database: Arc<SqlxDatabase>,
+        vault: Arc<dyn VaultForSecureChannels>,
+        secret_key: &X25519SecretKeyHandle,
+    ) -> Result<Self> {
+        debug!("create an encrypted repository for users");
+        let public_key = vault.get_x25519_public_key(secret_key).await?;
+        let shared_secret = vault.x25519_ecdh(secret_key, &public_key).await?;
+        let mut outputs = vault
+            .hkdf(&shared_secret, None, HKDFNumberOfOutputs::Two)
+            .await?
+            .0
+             .0;
+        vault.delete_secret_buffer(shared_secret).await?;
+
+        let hash_key = outputs.remove(1);
+        let key = vault
+            .convert_secret_buffer_to_aead_key(outputs.remove(0))
+            .await?;
+
+        Ok(Self {
+            repository: UsersSqlxDatabase::new(database.clone()),
+            database,
+            vault,
+            key,
+            hash_key,
+        })
+    }
+
+    /// Create a new repository encrypting the users information with keys derived from
+    /// the users encryption key of the vault.
+    ///
+    /// That key is generated the first time and its handle is stored in the database,
+    /// so that the users information can be decrypted by the next repositories
+    pub async fn create(
+        database: Arc<SqlxDatabase>,
+        vault: Arc<dyn VaultForSecureChannels>,
+    ) -> Result<Self> {
+        let secret_key = match Self::get_secret_key(&database).await? {
+            Some(secret_key) => secret_key,
+            None => {
+                let generated = vault.generate_static_x25519_secret_key().await?;
+                let query =
+                    query("INSERT OR IGNORE INTO user_encryption_key (id, handle) VALUES (1, ?)")
+                        .bind(generated.0.value().to_sql());
+                query.execute(&database.pool).await.void()?;
+
+                // another process might have stored its key in the meantime
+                let secret_key = Self::get_secret_key(&database).await?.ok_or_else(|| {
+                    Error::new(
+                        Origin::Api,
+                        Kind::NotFound,
+                        "the users encryption key could not be stored",
+                    )
+                })?;
+                if secret_key != generated {
+                    vault.delete_static_x25519_secret_key(generated).await?;
+                }
+                secret_key
+            }
+        };
+        Self::new(database, vault, &secret_key).await
+    }
+
+    /// Return the handle of the users encryption key, if it has already been generated
+    async fn get_secret_key(database: &SqlxDatabase) -> Result<Option<X25519SecretKeyHandle>> {
+        let query = query("SELECT handle FROM user_encryption_key WHERE id = 1");
+        let row: Option<SqliteRow> = query.fetch_optional(&database.pool).await.into_core()?;
+        Ok(row.map(|r| X25519SecretKeyHandle(HandleToSecret::new(r.get(0)))))
+    }
+
+    /// Return the hex-encoded keyed hash of a value.
+    ///
+    /// The value is used as the input key material of an HKDF salted with the hashing key,
+    /// which computes an HMAC of the value keyed with the hashing key. The derived secret can
+    /// not be read from the vault, so it is used to encrypt an empty text with a fixed nonce:
+    /// the resulting tag only depends on the value and on the hashing key
+    async fn hash(&self, value: &str) -> Result<String> {
+        let input = self
+            .vault
+            .import_secret_buffer(value.as_bytes().to_vec())
+            .await?;
+        let mut outputs = self
+            .vault
+            .hkdf(&self.hash_key, Some(&input), HKDFNumberOfOutputs::Two)
+            .await?
+            .0
+             .0;
+        self.vault.delete_secret_buffer(input).await?;
+        self.vault.delete_secret_buffer(outputs.remove(1)).await?;
+
+        let value_key = self
+            .vault
+            .convert_secret_buffer_to_aead_key(outputs.remove(0))
+            .await?;
+        let tag = self
+            .vault
+            .aead_encrypt(&value_key, &[], &[0; NONCE_LENGTH], &[])
+            .await;
+        self.vault.delete_aead_secret_key(value_key).await?;
+        Ok(hex::encode(tag?))
+    }
+
+    /// Encrypt some data and return the nonce + cipher text
+    async fn encrypt(&self, plain_text: &[u8], aad: &str) -> Result<Vec<u8>> {
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let cipher_text = self
+            .vault
+            .aead_encrypt(&self.key, plain_text, &nonce, aad.as_bytes())
+            .await?;
+        Ok([nonce.as_slice(), cipher_text.as_slice()].concat())
+    }
+
+    /// Decrypt some nonce + cipher text
+    async fn decrypt(&self, data: &[u8], aad: &str) -> Result<Vec<u8>> {
+        if data.len() < NONCE_LENGTH {
+            return Err(Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                "the encrypted user data is too short",
+            ));
+        }
+        let (nonce, cipher_text) = data.split_at(NONCE_LENGTH);
+        self.vault
+            .aead_decrypt(&self.key, cipher_text, nonce, aad.as_bytes())
+            .await
+    }
+
+    /// Return the user information as it is stored in the user table,
+    /// and the encrypted personal information of the user
+    async fn encrypt_user(&self, user: &UserInfo) -> Result<(UserInfo, Vec<u8>)> {
+        let email = self.hash(&user.email).await?;
+        let sensitive_fields = SensitiveFields {
+            email: user.email.clone(),
+            sub: user.sub.clone(),
+            nickname: user.nickname.clone(),
+            name: user.name.clone(),
+            picture: user.picture.clone(),
+        };
+        let encoded = minicbor::to_vec(&sensitive_fields).map_err(Error::from)?;
+        // the hashed email is used as additional data to bind the cipher text to its row
+        let encrypted_data = self.encrypt(&encoded, &email).await?;
+        let stored = UserInfo {
+            sub: self.hash(&user.sub).await?,
+            nickname: "".to_string(),
+            name: "".to_string(),
+            picture: "".to_string(),
+            updated_at: user.updated_at.clone(),
+            email_verified: user.email_verified,
+            roles: user.roles.clone(),
+            email,
+        };
+        Ok((stored, encrypted_data))
+    }
+
+    /// Return the user information decrypted from the user table
+    async fn decrypt_user(&self, stored: UserInfo) -> Result<UserInfo> {
+        let encrypted_data = self.get_encrypted_data(&stored.email).await?;
+        let decrypted = self.decrypt(&encrypted_data, &stored.email).await?;
+        let sensitive_fields: SensitiveFields =
+            minicbor::decode(&decrypted).map_err(Error::from)?;
+        Ok(UserInfo {
+            email: sensitive_fields.email,
+            sub: sensitive_fields.sub,
+            nickname: sensitive_fields.nickname,
+            name: sensitive_fields.name,
+            picture: sensitive_fields.picture,
+            updated_at: stored.updated_at,
+            email_verified: stored.email_verified,
+            roles: stored.roles,
+        })
+    }
+
+    /// Return the encrypted personal information of a user stored with a hashed email
+    async fn get_encrypted_data(&self, hashed_email: &str) -> Result<Vec<u8>> {
+        let query =
+            query("SELECT encrypted_data FROM user WHERE email = ?").bind(hashed_email.to_sql());
+        let row: Option<SqliteRow> = query
+            .fetch_optional(&self.database.pool)
+            .await
+            .into_core()?;
+        row.and_then(|r| r.get::<Option<Vec<u8>>, _>(0))
+            .ok_or_else(|| {
+                Error::new(
+                    Origin::Api,
+                    Kind::NotFound,
+                    "there is no encrypted data for this user",
+                )
+            })
+    }
+
+    async fn decrypt_users(&self, stored: Vec<UserInfo>) -> Result<Vec<UserInfo>> {
+        let mut users = Vec::with_capacity(stored.len());
+        for user in stored {
+            users.push(self.decrypt_user(user).await?);
+        }
+        Ok(users)
+    }
+}
+
+#[async_trait]
+impl UsersRepository for EncryptedUsersRepository {
+    async fn store_user(&self, tenant: &str, user: &UserInfo) -> Result<()> {
+        let (stored, encrypted_data) = self.encrypt_user(user).await?;
+        self.repository
+            .store_user_with_encrypted_data(tenant, &stored, Some(&encrypted_data))
+            .await
+    }
+
+    async fn get_default_user(&self, tenant: &str) -> Result<Option<UserInfo>> {
+        match self.repository.get_default_user(tenant).await? {
+            Some(user) => Ok(Some(self.decrypt_user(user).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_default_user_or_first(&self, promote: bool) -> Result<Option<UserInfo>> {
+        if let Some(user) = self.get_default_user(DEFAULT_TENANT).await? {
+            return Ok(Some(user));
+        }
+        // only the users of the default tenant can become its default user.
+        // The stored emails are hashed so the first user is found after decryption
+        let query = query("SELECT email FROM user WHERE tenant = ?").bind(DEFAULT_TENANT.to_sql());
+        let rows: Vec<SqliteRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        let mut users = vec![];
+        for row in rows {
+            if let Some(user) = self.repository.get_user(&row.get::<String, _>(0)).await? {
+                users.push(user);
+            }
+        }
+        let first = self
+            .decrypt_users(users)
+            .await?
+            .into_iter()
+            .min_by(|u1, u2| u1.email.cmp(&u2.email));
+        if let (Some(user), true) = (&first, promote) {
+            self.set_default_user(DEFAULT_TENANT, &user.email).await?;
+        }
+        Ok(first)
+    }
+
+    async fn set_default_user(&self, tenant: &str, email: &str) -> Result<()> {
+        self.repository
+            .set_default_user(tenant, &self.hash(email).await?)
+            .await
+    }
+
+    async fn upsert_and_set_default(&self, tenant: &str, user: &UserInfo) -> Result<()> {
+        let (stored, encrypted_data) = self.encrypt_user(user).await?;
+        self.repository
+            .upsert_and_set_default_with_encrypted_data(tenant, &stored, Some(&encrypted_data))
+            .await
+    }
+
+    async fn get_user(&self, email: &str) -> Result<Option<UserInfo>> {
+        match self.repository.get_user(&self.hash(email).await?).await? {
+            Some(user) => Ok(Some(self.decrypt_user(user).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_users(&self) -> Result<Vec<UserInfo>> {
+        self.decrypt_users(self.repository.get_users().await?).await
+    }
+
+    async fn get_users_sorted(&self, by: UserSortKey, ascending: bool) -> Result<Vec<UserInfo>> {
+        // the stored emails and names are not ordered like their plain text values
+        // so the users are sorted after decryption
+        let mut users = self.get_users().await?;
+        users.sort_by(|u1, u2| {
+            let ordering = match by {
+                UserSortKey::Email => u1.email.cmp(&u2.email),
+                UserSortKey::Name => u1.name.cmp(&u2.name),
+                UserSortKey::UpdatedAt => u1.updated_at.cmp(&u2.updated_at),
+            };
+            let ordering = if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            };
+            match ordering {
+                Ordering::Equal => u1.email.cmp(&u2.email),
+                _ => ordering,
+            }
+        });
+        Ok(users)
+    }
+
+    async fn get_users_with_role(&self, role: &str) -> Result<Vec<UserInfo>> {
+        // the roles are not encrypted so they can be queried directly
+        self.decrypt_users(self.repository.get_users_with_role(role).await?)
+            .await
+    }
+
+    async fn get_users_created_after(&self, ts: TimestampInSeconds) -> Result<Vec<UserInfo>> {
+        // the creation times are not encrypted so they can be queried directly
+        self.decrypt_users(self.repository.get_users_created_after(ts).await?)
+            .await
+    }
+
+    async fn delete_user(&self, email: &str) -> Result<()> {
+        self.repository.delete_user(&self.hash(email).await?).await
+    }
+
+    async fn rename_user_email(&self, old: &str, new: &str) -> Result<()> {
+        let user = match self.get_user(old).await? {
+            Some(user) => user,
+            None => {
+                return Err(Error::new(
+                    Origin::Api,
+                    Kind::NotFound,
+                    format!("no user with the email {old} was found"),
+                ))
+            }
+        };
+        let hashed_new = self.hash(new).await?;
+        self.repository
+            .rename_user_email(&self.hash(old).await?, &hashed_new)
+            .await?;
+
+        // the sensitive fields contain the email and are bound to the hashed email
+        // so they need to be encrypted again, in the tenant of the user
+        let query = query("SELECT tenant FROM user WHERE email = ?").bind(hashed_new.to_sql());
+        let row: SqliteRow = query.fetch_one(&self.database.pool).await.into_core()?;
+        let tenant: String = row.get(0);
+        let renamed = UserInfo {
+            email: new.to_string(),
+            ..user
+        };
+        self.store_user(&tenant, &renamed).await
+    }
+
+    async fn count_users_by_domain(&self) -> Result<Vec<(String, u64)>> {
+        // the stored emails are hashed so the domains are counted after decryption
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for user in self.get_users().await? {
+            if let Some((_, domain)) = user.email.split_once('@') {
+                *counts.entry(domain.to_lowercase()).or_default() += 1;
+            }
+        }
+        let mut counts: Vec<(String, u64)> = counts.into_iter().collect();
+        counts.sort_by(|(d1, c1), (d2, c2)| c2.cmp(c1).then_with(|| d1.cmp(d2)));
+        Ok(counts)
+    }
+}
+
+/// Fields of the user information which are encrypted together
+#[derive(Encode, Decode)]
+#[rustfmt::skip]
+struct SensitiveFields {
+    #[n(0)] email: String,
+    #[n(1)] sub: String,
+    #[n(2)] name: String,
+    #[n(3)] picture: String,
+    #[n(4)] nickname: String,
+}
+
+#[cfg(test)]
+mod test {
+    use ockam_vault::SoftwareVaultForSecureChannels;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encrypted_repository() -> Result<()> {
+        let database = SqlxDatabase::in_memory("users").await?;
+        let vault = SoftwareVaultForSecureChannels::create().await?;
+        let secret_key = vault.generate_static_x25519_secret_key().await?;
+        let repository =
+            EncryptedUsersRepository::new(database.clone(), vault.clone(), &secret_key).await?;
+
+        let user = UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "my name".to_string(),
+            picture: "my picture".to_string(),
+            updated_at: "today".to_string(),
+            email: "me@ockam.io".into(),
+            email_verified: false,
+            roles: vec!["admin".to_string()],
+        };
+        repository.store_user(DEFAULT_TENANT, &user).await?;
+        repository
+            .set_default_user(DEFAULT_TENANT, &user.email)
+            .await?;
+
+        // the sensitive information is not stored in plain text
+        let rows: Vec<(String, String, String, String, String, Vec<u8>)> =
+            query_as("SELECT email, sub, nickname, name, picture, encrypted_data FROM user")
+                .fetch_all(&database.pool)
+                .await
+                .into_core()?;
+        assert_eq!(rows.len(), 1);
+        let (email, sub, nickname, name, picture, encrypted_data) = &rows[0];
+        for stored in [email, sub, nickname, name, picture] {
+            for plain in [
+                &user.email,
+                &user.sub,
+                &user.nickname,
+                &user.name,
+                &user.picture,
+            ] {
+                assert!(!stored.contains(plain.as_str()));
+            }
+        }
+        assert!(!encrypted_data.is_empty());
+
+        // the hashes are keyed with the vault secret: another vault key gives different hashes
+        let other_secret_key = vault.generate_static_x25519_secret_key().await?;
+        let other_repository =
+            EncryptedUsersRepository::new(database.clone(), vault.clone(), &other_secret_key)
+                .await?;
+        assert_ne!(&other_repository.hash(&user.email).await?, email);
+        assert_eq!(&repository.hash(&user.email).await?, email);
+
+        // the users can still be retrieved by email, in plain text
+        let result = repository.get_user(&user.email).await?;
+        assert_eq!(result, Some(user.clone()));
+
+        let result = repository.get_default_user(DEFAULT_TENANT).await?;
+        assert_eq!(result, Some(user.clone()));
+
+        let result = repository.get_users().await?;
+        assert_eq!(result, vec![user.clone()]);
+
+        let result = repository.get_users_with_role("admin").await?;
+        assert_eq!(result, vec![user.clone()]);
+
+        let result = repository.count_users_by_domain().await?;
+        assert_eq!(result, vec![("ockam.io".to_string(), 1)]);
+
+        // a repository using the same vault key can read the data again
+        let repository =
+            EncryptedUsersRepository::new(database.clone(), vault, &secret_key).await?;
+        let result = repository.get_user(&user.email).await?;
+        assert_eq!(result, Some(user.clone()));
+
+        // a user email can be changed
+        repository
+            .rename_user_email(&user.email, "new-me@ockam.io")
+            .await?;
+        let renamed = UserInfo {
+            email: "new-me@ockam.io".into(),
+            ..user.clone()
+        };
+        assert_eq!(repository.get_user(&user.email).await?, None);
+        assert_eq!(
+            repository.get_default_user(DEFAULT_TENANT).await?,
+            Some(renamed.clone())
+        );
+
+        repository.delete_user(&renamed.email).await?;
+        let result = repository.get_users().await?;
+        assert!(result.is_empty());
+        Ok(())
+    }
+}
@@ -1,21 +1,43 @@
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 
-use sqlx::sqlite::SqliteRow;
+use serde::{Deserialize, Serialize};
+
+use sqlx::query::Query;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
 use sqlx::*;
 
-use ockam_core::async_trait;
-use ockam_core::Result;
+use ockam::identity::models::TimestampInSeconds;
+use ockam::identity::utils::now;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
 use ockam_node::database::{FromSqlxError, SqlxDatabase, ToSqlxType, ToVoid};
 
 use crate::cloud::enroll::auth0::UserInfo;
 
-use super::UsersRepository;
+use super::{UserSortKey, UsersRepository, DEFAULT_TENANT};
 
 #[derive(Clone)]
 pub struct UsersSqlxDatabase {
     database: Arc<SqlxDatabase>,
 }
 
+/// Columns expected in the user table
+const USER_COLUMNS: &[&str] = &[
+    "email",
+    "sub",
+    "nickname",
+    "name",
+    "picture",
+    "updated_at",
+    "email_verified",
+    "is_default",
+    "roles",
+    "created_at",
+    "tenant",
+    "encrypted_data",
+];
+
 impl UsersSqlxDatabase {
     /// Create a new database
     pub fn new(database: Arc<SqlxDatabase>) -> Self {
@@ -27,31 +49,177 @@ impl UsersSqlxDatabase {
     pub async fn create() -> Result<Arc<Self>> {
         Ok(Arc::new(Self::new(SqlxDatabase::in_memory("users").await?)))
     }
+
+    /// Return an error if the schema of the user table needs to be migrated.
+    /// This check is meant to be done once, when the database is opened
+    pub async fn check_schema(database: &SqlxDatabase) -> Result<()> {
+        database.check_columns("user", USER_COLUMNS).await
+    }
+
+    /// Export all the users as a JSON array.
+    /// Each user is serialized as a `UserInfo`, with additional `tenant` and `is_default` fields
+    pub async fn export_users_json(&self) -> Result<String> {
+        let query = query_as("SELECT * FROM user ORDER BY email ASC");
+        let rows: Vec<UserRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        let users = rows
+            .iter()
+            .map(|r| {
+                Ok(ExportedUser {
+                    user: r.user()?,
+                    tenant: r.tenant.clone(),
+                    is_default: r.is_default,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        serde_json::to_string(&users)
+            .map_err(|e| Error::new(Origin::Api, Kind::Serialization, e.to_string()))
+    }
+
+    /// Import users from a JSON array of `UserInfo`, as exported by `export_users_json`,
+    /// and return the number of imported users.
+    ///
+    /// If `replace` is true the existing users are deleted first. The users without a tenant
+    /// are imported in the [`DEFAULT_TENANT`]. After the import there is exactly one default user
+    /// in each imported tenant: the user marked as default in the payload if there is one,
+    /// otherwise the current default user if it is still present, otherwise the first imported user.
+    pub async fn import_users_json(&self, json: &str, replace: bool) -> Result<usize> {
+        let users: Vec<ExportedUser> = serde_json::from_str(json)
+            .map_err(|e| Error::new(Origin::Api, Kind::Serialization, e.to_string()))?;
+
+        let mut emails = HashSet::new();
+        for exported in users.iter() {
+            if !emails.insert(exported.user.email.as_str()) {
+                return Err(Error::new(
+                    Origin::Api,
+                    Kind::Invalid,
+                    format!("the email {} is used by several users", exported.user.email),
+                ));
+            }
+        }
+        let mut defaults: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for exported in users.iter().filter(|u| u.is_default) {
+            defaults
+                .entry(exported.tenant.as_str())
+                .or_default()
+                .push(exported.user.email.as_str());
+        }
+        if let Some((tenant, emails)) = defaults.iter().find(|(_, emails)| emails.len() > 1) {
+            return Err(Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!(
+                    "several users are marked as default in the tenant {tenant}: {}",
+                    emails.join(", ")
+                ),
+            ));
+        }
+
+        let mut transaction = self.database.begin().await.into_core()?;
+        if replace {
+            query("DELETE FROM user")
+                .execute(&mut *transaction)
+                .await
+                .void()?;
+        }
+
+        // the default user of each tenant, in the order of the first imported user of that tenant
+        let mut default_emails: Vec<(&str, String)> = vec![];
+        for exported in users.iter() {
+            let tenant = exported.tenant.as_str();
+            if default_emails.iter().any(|(t, _)| *t == tenant) {
+                continue;
+            }
+            let default_email = match defaults.get(tenant) {
+                Some(emails) => emails[0].to_string(),
+                None => {
+                    let query1 = query("SELECT email FROM user WHERE is_default=$1 AND tenant=$2")
+                        .bind(true.to_sql())
+                        .bind(tenant.to_sql());
+                    let row: Option<SqliteRow> =
+                        query1.fetch_optional(&mut *transaction).await.into_core()?;
+                    row.map(|r| r.get(0))
+                        .unwrap_or_else(|| exported.user.email.clone())
+                }
+            };
+            let query2 = query("UPDATE user SET is_default=$1 WHERE tenant=$2 AND email<>$3")
+                .bind(false.to_sql())
+                .bind(tenant.to_sql())
+                .bind(default_email.to_sql());
+            query2.execute(&mut *transaction).await.void()?;
+            default_emails.push((tenant, default_email));
+        }
+
+        for exported in users.iter() {
+            let is_default = default_emails
+                .iter()
+                .any(|(t, email)| *t == exported.tenant && *email == exported.user.email);
+            insert_user_query(&exported.user, &exported.tenant, is_default, None)?
+                .execute(&mut *transaction)
+                .await
+                .void()?;
+        }
+
+        transaction.commit().await.void()?;
+        Ok(users.len())
+    }
+
+    /// Store a user with its encrypted personal information, if any.
+    /// An existing user of the tenant keeps their default status
+    pub(super) async fn store_user_with_encrypted_data(
+        &self,
+        tenant: &str,
+        user: &UserInfo,
+        encrypted_data: Option<&[u8]>,
+    ) -> Result<()> {
+        let mut transaction = self.database.begin().await.into_core()?;
+
+        let query1 = query("SELECT is_default FROM user WHERE email=$1 AND tenant=$2")
+            .bind(user.email.to_sql())
+            .bind(tenant.to_sql());
+        let row: Option<SqliteRow> = query1.fetch_optional(&mut *transaction).await.into_core()?;
+        let is_default: bool = row.map(|r| r.get(0)).unwrap_or(false);
+
+        let query2 = insert_user_query(user, tenant, is_default, encrypted_data)?;
+        query2.execute(&mut *transaction).await.void()?;
+
+        transaction.commit().await.void()
+    }
+
+    /// Store a user with its encrypted personal information, if any,
+    /// and set it as the default user of its tenant
+    pub(super) async fn upsert_and_set_default_with_encrypted_data(
+        &self,
+        tenant: &str,
+        user: &UserInfo,
+        encrypted_data: Option<&[u8]>,
+    ) -> Result<()> {
+        let insert = insert_user_query(user, tenant, true, encrypted_data)?;
+        let mut transaction = self.database.begin().await.into_core()?;
+
+        // set all the users of the tenant as non-default
+        let query1 = query("UPDATE user SET is_default = ? WHERE tenant = ?")
+            .bind(false.to_sql())
+            .bind(tenant.to_sql());
+        query1.execute(&mut *transaction).await.void()?;
+
+        // store the user as the default one
+        insert.execute(&mut *transaction).await.void()?;
+
+        transaction.commit().await.void()
+    }
 }
 
 #[async_trait]
 impl UsersRepository for UsersSqlxDatabase {
-    async fn store_user(&self, user: &UserInfo) -> Result<()> {
-        let is_already_default = self
-            .get_default_user()
-            .await?
-            .map(|u| u.email == user.email)
-            .unwrap_or(false);
-
-        let query = query("INSERT OR REPLACE INTO user VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
-            .bind(user.email.to_sql())
-            .bind(user.sub.to_sql())
-            .bind(user.nickname.to_sql())
-            .bind(user.name.to_sql())
-            .bind(user.picture.to_sql())
-            .bind(user.updated_at.to_sql())
-            .bind(user.email_verified.to_sql())
-            .bind(is_already_default.to_sql());
-        query.execute(&self.database.pool).await.void()
-    }
-
-    async fn get_default_user(&self) -> Result<Option<UserInfo>> {
-        let query = query("SELECT email FROM user WHERE is_default=$1").bind(true.to_sql());
+    async fn store_user(&self, tenant: &str, user: &UserInfo) -> Result<()> {
+        self.store_user_with_encrypted_data(tenant, user, None)
+            .await
+    }
+
+    async fn get_default_user(&self, tenant: &str) -> Result<Option<UserInfo>> {
+        let query = query("SELECT email FROM user WHERE is_default=$1 AND tenant=$2")
+            .bind(true.to_sql())
+            .bind(tenant.to_sql());
         let row: Option<SqliteRow> = query
             .fetch_optional(&self.database.pool)
             .await
@@ -63,11 +231,69 @@ impl UsersRepository for UsersSqlxDatabase {
         }
     }
 
-    async fn set_default_user(&self, email: &str) -> Result<()> {
-        let query = query("UPDATE user SET is_default = ? WHERE email = ?")
+    async fn get_default_user_or_first(&self, promote: bool) -> Result<Option<UserInfo>> {
+        if let Some(user) = self.get_default_user(DEFAULT_TENANT).await? {
+            return Ok(Some(user));
+        }
+        // only the users of the default tenant can become its default user
+        let query = query_as("SELECT * FROM user WHERE tenant = ? ORDER BY email ASC LIMIT 1")
+            .bind(DEFAULT_TENANT.to_sql());
+        let row: Option<UserRow> = query
+            .fetch_optional(&self.database.pool)
+            .await
+            .into_core()?;
+        let first = row.map(|u| u.user()).transpose()?;
+        if let (Some(user), true) = (&first, promote) {
+            self.set_default_user(DEFAULT_TENANT, &user.email).await?;
+        }
+        Ok(first)
+    }
+
+    async fn set_default_user(&self, tenant: &str, email: &str) -> Result<()> {
+        // All the queries run in the same transaction, which starts with a write,
+        // so that concurrent callers are serialized and exactly one default user
+        // survives in the tenant
+        let mut transaction = self.database.begin().await.into_core()?;
+
+        // set all the users of the tenant as non-default
+        let query1 = query("UPDATE user SET is_default = ? WHERE tenant = ?")
+            .bind(false.to_sql())
+            .bind(tenant.to_sql());
+        query1.execute(&mut *transaction).await.void()?;
+
+        // the user must belong to the tenant. If not, the transaction is rolled back when dropped
+        let query2 = query("SELECT tenant FROM user WHERE email = ?").bind(email.to_sql());
+        let row: Option<SqliteRow> = query2.fetch_optional(&mut *transaction).await.into_core()?;
+        match row.map(|r| r.get::<String, _>(0)) {
+            Some(user_tenant) if user_tenant == tenant => (),
+            Some(user_tenant) => {
+                return Err(Error::new(
+                    Origin::Api,
+                    Kind::Conflict,
+                    format!("the user {email} belongs to the tenant {user_tenant}, not to the tenant {tenant}"),
+                ))
+            }
+            None => {
+                return Err(Error::new(
+                    Origin::Api,
+                    Kind::NotFound,
+                    format!("no user with the email {email} was found"),
+                ))
+            }
+        }
+
+        // set the user as the default one
+        let query3 = query("UPDATE user SET is_default = ? WHERE email = ?")
             .bind(true.to_sql())
             .bind(email.to_sql());
-        query.execute(&self.database.pool).await.void()
+        query3.execute(&mut *transaction).await.void()?;
+
+        transaction.commit().await.void()
+    }
+
+    async fn upsert_and_set_default(&self, tenant: &str, user: &UserInfo) -> Result<()> {
+        self.upsert_and_set_default_with_encrypted_data(tenant, user, None)
+            .await
     }
 
     async fn get_user(&self, email: &str) -> Result<Option<UserInfo>> {
@@ -76,23 +302,141 @@ impl UsersRepository for UsersSqlxDatabase {
             .fetch_optional(&self.database.pool)
             .await
             .into_core()?;
-        Ok(row.map(|u| u.user()))
+        row.map(|u| u.user()).transpose()
     }
 
     async fn get_users(&self) -> Result<Vec<UserInfo>> {
         let query = query_as("SELECT * FROM user");
         let rows: Vec<UserRow> = query.fetch_all(&self.database.pool).await.into_core()?;
-        Ok(rows.iter().map(|u| u.user()).collect())
+        rows.iter().map(|u| u.user()).collect()
+    }
+
+    async fn get_users_sorted(&self, by: UserSortKey, ascending: bool) -> Result<Vec<UserInfo>> {
+        // the column and the direction can't be bound as parameters but they
+        // only come from a closed set of values
+        let column = match by {
+            UserSortKey::Email => "email",
+            UserSortKey::Name => "name",
+            UserSortKey::UpdatedAt => "updated_at",
+        };
+        let direction = if ascending { "ASC" } else { "DESC" };
+        let sql = format!("SELECT * FROM user ORDER BY {column} {direction}, email ASC");
+        let query = query_as(&sql);
+        let rows: Vec<UserRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        rows.iter().map(|u| u.user()).collect()
+    }
+
+    async fn get_users_with_role(&self, role: &str) -> Result<Vec<UserInfo>> {
+        let query = query_as(
+            "SELECT * FROM user WHERE EXISTS (SELECT 1 FROM json_each(user.roles) WHERE json_each.value = $1)",
+        )
+        .bind(role.to_sql());
+        let rows: Vec<UserRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        rows.iter().map(|u| u.user()).collect()
+    }
+
+    async fn get_users_created_after(&self, ts: TimestampInSeconds) -> Result<Vec<UserInfo>> {
+        let query =
+            query_as("SELECT * FROM user WHERE created_at > $1 ORDER BY created_at ASC, email ASC")
+                .bind(ts.to_sql());
+        let rows: Vec<UserRow> = query.fetch_all(&self.database.pool).await.into_core()?;
+        rows.iter().map(|u| u.user()).collect()
     }
 
     async fn delete_user(&self, email: &str) -> Result<()> {
         let query1 = query("DELETE FROM user WHERE email=?").bind(email.to_sql());
         query1.execute(&self.database.pool).await.void()
     }
+
+    async fn rename_user_email(&self, old: &str, new: &str) -> Result<()> {
+        let mut transaction = self.database.begin().await.into_core()?;
+
+        let query1 = query("SELECT email FROM user WHERE email=$1").bind(new.to_sql());
+        let existing: Option<SqliteRow> =
+            query1.fetch_optional(&mut *transaction).await.into_core()?;
+        if existing.is_some() {
+            return Err(Error::new(
+                Origin::Api,
+                Kind::AlreadyExists,
+                format!("a user with the email {new} already exists"),
+            ));
+        }
+
+        // the email is updated in place so that the other columns, including is_default, are kept
+        let query2 = query("UPDATE user SET email=$1 WHERE email=$2")
+            .bind(new.to_sql())
+            .bind(old.to_sql());
+        let result = query2.execute(&mut *transaction).await.into_core()?;
+        if result.rows_affected() == 0 {
+            return Err(Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("no user with the email {old} was found"),
+            ));
+        }
+
+        transaction.commit().await.void()
+    }
+
+    async fn count_users_by_domain(&self) -> Result<Vec<(String, u64)>> {
+        let query = query_as(
+            "SELECT lower(substr(email, instr(email, '@') + 1)) AS domain, COUNT(*) AS count FROM user \
+             WHERE instr(email, '@') > 0 GROUP BY domain ORDER BY count DESC, domain ASC",
+        );
+        let rows: Vec<(String, i64)> = query.fetch_all(&self.database.pool).await.into_core()?;
+        Ok(rows
+            .into_iter()
+            .map(|(domain, count)| (domain, count as u64))
+            .collect())
+    }
 }
 
 // Database serialization / deserialization
 
+/// User, as exported to / imported from JSON
+#[derive(Serialize, Deserialize)]
+struct ExportedUser {
+    #[serde(flatten)]
+    user: UserInfo,
+    #[serde(default = "default_tenant")]
+    tenant: String,
+    #[serde(default)]
+    is_default: bool,
+}
+
+fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+/// Return a query inserting or replacing a user, with its encrypted personal information if any.
+/// The creation time of an existing user is kept when the user is replaced
+fn insert_user_query(
+    user: &UserInfo,
+    tenant: &str,
+    is_default: bool,
+    encrypted_data: Option<&[u8]>,
+) -> Result<Query<'static, Sqlite, SqliteArguments<'static>>> {
+    let roles = serde_json::to_string(&user.roles)
+        .map_err(|e| Error::new(Origin::Api, Kind::Serialization, e.to_string()))?;
+    Ok(query(
+        "INSERT OR REPLACE INTO user \
+         (email, sub, nickname, name, picture, updated_at, email_verified, is_default, roles, created_at, tenant, encrypted_data) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, COALESCE((SELECT created_at FROM user WHERE email = $1), $10), $11, $12)",
+    )
+    .bind(user.email.to_sql())
+    .bind(user.sub.to_sql())
+    .bind(user.nickname.to_sql())
+    .bind(user.name.to_sql())
+    .bind(user.picture.to_sql())
+    .bind(user.updated_at.to_sql())
+    .bind(user.email_verified.to_sql())
+    .bind(is_default.to_sql())
+    .bind(roles.to_sql())
+    .bind(now()?.to_sql())
+    .bind(tenant.to_sql())
+    .bind(encrypted_data.map(|d| d.to_vec().to_sql())))
+}
+
 /// Low-level representation of a row in the user table
 #[derive(sqlx::FromRow)]
 struct UserRow {
@@ -103,13 +447,16 @@ struct UserRow {
     picture: String,
     updated_at: String,
     email_verified: bool,
-    #[allow(unused)]
     is_default: bool,
+    roles: String,
+    tenant: String,
 }
 
 impl UserRow {
-    fn user(&self) -> UserInfo {
-        UserInfo {
+    fn user(&self) -> Result<UserInfo> {
+        let roles = serde_json::from_str(&self.roles)
+            .map_err(|e| Error::new(Origin::Api, Kind::Serialization, e.to_string()))?;
+        Ok(UserInfo {
             email: self.email.clone(),
             sub: self.sub.clone(),
             nickname: self.nickname.clone(),
@@ -117,7 +464,8 @@ impl UserRow {
             picture: self.picture.clone(),
             updated_at: self.updated_at.clone(),
             email_verified: self.email_verified,
-        }
+            roles,
+        })
     }
 }
 
@@ -138,6 +486,7 @@ mod test {
             updated_at: "today".to_string(),
             email: "me@ockam.io".into(),
             email_verified: false,
+            roles: vec![],
         };
         let user2 = UserInfo {
             sub: "sub".into(),
@@ -147,10 +496,11 @@ mod test {
             updated_at: "today".to_string(),
             email: "you@ockam.io".into(),
             email_verified: false,
+            roles: vec![],
         };
 
-        repository.store_user(&user1).await?;
-        repository.store_user(&user2).await?;
+        repository.store_user(DEFAULT_TENANT, &user1).await?;
+        repository.store_user(DEFAULT_TENANT, &user2).await?;
 
         // retrieve them as a vector or by name
         let result = repository.get_users().await?;
@@ -160,8 +510,10 @@ mod test {
         assert_eq!(result, Some(user1.clone()));
 
         // a user can be set created as the default user
-        repository.set_default_user("me@ockam.io").await?;
-        let result = repository.get_default_user().await?;
+        repository
+            .set_default_user(DEFAULT_TENANT, "me@ockam.io")
+            .await?;
+        let result = repository.get_default_user(DEFAULT_TENANT).await?;
         assert_eq!(result, Some(user1.clone()));
 
         // a user can be deleted
@@ -174,6 +526,585 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_users_with_role() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let user = |email: &str, roles: &[&str]| UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "me".to_string(),
+            picture: "me".to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        };
+        let admin = user("admin@ockam.io", &["admin", "developer"]);
+        let developer = user("developer@ockam.io", &["developer"]);
+        let guest = user("guest@ockam.io", &[]);
+        repository.store_user(DEFAULT_TENANT, &admin).await?;
+        repository.store_user(DEFAULT_TENANT, &developer).await?;
+        repository.store_user(DEFAULT_TENANT, &guest).await?;
+
+        // the roles are returned with the users
+        let result = repository.get_user("admin@ockam.io").await?;
+        assert_eq!(result, Some(admin.clone()));
+
+        // the users can be queried by role
+        let result = repository.get_users_with_role("admin").await?;
+        assert_eq!(result, vec![admin.clone()]);
+
+        let result = repository.get_users_with_role("developer").await?;
+        assert_eq!(result, vec![admin.clone(), developer.clone()]);
+
+        let result = repository.get_users_with_role("dev").await?;
+        assert!(result.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_users_sorted() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let user = |email: &str, name: &str, updated_at: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: name.to_string(),
+            name: name.to_string(),
+            picture: name.to_string(),
+            updated_at: updated_at.to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        let alice = user("alice@ockam.io", "Carol", "2023-11-02T10:00:00Z");
+        let bob = user("bob@ockam.io", "Alice", "2023-11-03T10:00:00Z");
+        let carol = user("carol@ockam.io", "Bob", "2023-11-01T10:00:00Z");
+
+        repository.store_user(DEFAULT_TENANT, &carol).await?;
+        repository.store_user(DEFAULT_TENANT, &alice).await?;
+        repository.store_user(DEFAULT_TENANT, &bob).await?;
+
+        let result = repository
+            .get_users_sorted(UserSortKey::Email, true)
+            .await?;
+        assert_eq!(result, vec![alice.clone(), bob.clone(), carol.clone()]);
+
+        let result = repository
+            .get_users_sorted(UserSortKey::Email, false)
+            .await?;
+        assert_eq!(result, vec![carol.clone(), bob.clone(), alice.clone()]);
+
+        let result = repository.get_users_sorted(UserSortKey::Name, true).await?;
+        assert_eq!(result, vec![bob.clone(), carol.clone(), alice.clone()]);
+
+        let result = repository
+            .get_users_sorted(UserSortKey::UpdatedAt, true)
+            .await?;
+        assert_eq!(result, vec![carol.clone(), alice.clone(), bob.clone()]);
+
+        let result = repository
+            .get_users_sorted(UserSortKey::UpdatedAt, false)
+            .await?;
+        assert_eq!(result, vec![bob.clone(), alice.clone(), carol.clone()]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_set_default_user() -> Result<()> {
+        // use a database on disk so that concurrent calls use different connections
+        let dir = tempfile::tempdir().unwrap();
+        let database = SqlxDatabase::create(dir.path().join("database.sqlite3")).await?;
+        let repository = UsersSqlxDatabase::new(Arc::new(database));
+
+        let user = |email: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "me".to_string(),
+            picture: "me".to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        let user1 = user("me@ockam.io");
+        let user2 = user("you@ockam.io");
+        repository.store_user(DEFAULT_TENANT, &user1).await?;
+        repository.store_user(DEFAULT_TENANT, &user2).await?;
+
+        for _ in 0..10 {
+            let (r1, r2) = tokio::join!(
+                repository.set_default_user(DEFAULT_TENANT, "me@ockam.io"),
+                repository.set_default_user(DEFAULT_TENANT, "you@ockam.io")
+            );
+            r1?;
+            r2?;
+
+            // exactly one user is the default one
+            let query = query("SELECT email FROM user WHERE is_default = ?").bind(true.to_sql());
+            let rows: Vec<SqliteRow> = query
+                .fetch_all(&repository.database.pool)
+                .await
+                .into_core()?;
+            assert_eq!(rows.len(), 1);
+
+            let default_user = repository.get_default_user(DEFAULT_TENANT).await?;
+            assert!(default_user == Some(user1.clone()) || default_user == Some(user2.clone()));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_default_user_or_first() -> Result<()> {
+        let repository = create_repository().await?;
+        assert_eq!(repository.get_default_user_or_first(false).await?, None);
+
+        let user = |email: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "me".to_string(),
+            picture: "me".to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        let user1 = user("me@ockam.io");
+        let user2 = user("anne@ockam.io");
+        repository.store_user(DEFAULT_TENANT, &user1).await?;
+        repository.store_user(DEFAULT_TENANT, &user2).await?;
+
+        // without a default user, the first user by email is returned
+        let result = repository.get_default_user_or_first(false).await?;
+        assert_eq!(result, Some(user2.clone()));
+        assert_eq!(repository.get_default_user(DEFAULT_TENANT).await?, None);
+
+        // that user can be promoted to default user
+        let result = repository.get_default_user_or_first(true).await?;
+        assert_eq!(result, Some(user2.clone()));
+        assert_eq!(
+            repository.get_default_user(DEFAULT_TENANT).await?,
+            Some(user2.clone())
+        );
+
+        // the default user takes precedence over the first user
+        repository
+            .set_default_user(DEFAULT_TENANT, &user1.email)
+            .await?;
+        let result = repository.get_default_user_or_first(false).await?;
+        assert_eq!(result, Some(user1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_default_user_per_tenant() -> Result<()> {
+        let repository = UsersSqlxDatabase::create().await?;
+
+        let user = |email: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "me".to_string(),
+            picture: "me".to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        let user1 = user("me@ockam.io");
+        let user2 = user("you@ockam.io");
+        let user3 = user("them@ockam.io");
+        for (user, tenant) in [
+            (&user1, "space-1"),
+            (&user2, "space-2"),
+            (&user3, "space-1"),
+        ] {
+            repository.store_user(tenant, user).await?;
+        }
+
+        // each tenant has its own default user
+        repository.set_default_user("space-1", &user1.email).await?;
+        repository.set_default_user("space-2", &user2.email).await?;
+        assert_eq!(
+            repository.get_default_user("space-1").await?,
+            Some(user1.clone())
+        );
+        assert_eq!(
+            repository.get_default_user("space-2").await?,
+            Some(user2.clone())
+        );
+        assert_eq!(repository.get_default_user(DEFAULT_TENANT).await?, None);
+
+        // changing the default user of a tenant doesn't change the other tenants
+        repository.set_default_user("space-1", &user3.email).await?;
+        assert_eq!(
+            repository.get_default_user("space-1").await?,
+            Some(user3.clone())
+        );
+        assert_eq!(
+            repository.get_default_user("space-2").await?,
+            Some(user2.clone())
+        );
+
+        // a user which is updated stays the default user of their tenant
+        let updated_user2 = UserInfo {
+            nickname: "updated you".to_string(),
+            ..user2.clone()
+        };
+        repository.store_user("space-2", &updated_user2).await?;
+        assert_eq!(
+            repository.get_default_user("space-2").await?,
+            Some(updated_user2)
+        );
+
+        // a user can not be the default user of another tenant
+        let result = repository.set_default_user("space-2", &user3.email).await;
+        assert!(result.is_err());
+        let result = repository
+            .set_default_user("space-2", "unknown@ockam.io")
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            repository.get_default_user("space-2").await?,
+            Some(updated_user2)
+        );
+
+        // the users of the other tenants are not candidates for the default user of the default tenant
+        assert_eq!(repository.get_default_user_or_first(true).await?, None);
+        assert_eq!(repository.get_default_user(DEFAULT_TENANT).await?, None);
+
+        // the default tenant has its own default user
+        repository
+            .store_user(DEFAULT_TENANT, &user("anne@ockam.io"))
+            .await?;
+        repository
+            .set_default_user(DEFAULT_TENANT, "anne@ockam.io")
+            .await?;
+        assert_eq!(
+            repository.get_default_user(DEFAULT_TENANT).await?,
+            Some(user("anne@ockam.io"))
+        );
+        assert_eq!(
+            repository.get_default_user("space-1").await?,
+            Some(user3.clone())
+        );
+
+        // a user moved to another tenant is not the default user of their previous tenant anymore
+        repository.store_user("space-2", &user3).await?;
+        assert_eq!(repository.get_default_user("space-1").await?, None);
+        assert_eq!(
+            repository.get_default_user("space-2").await?,
+            Some(updated_user2)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_set_default() -> Result<()> {
+        let repository = UsersSqlxDatabase::create().await?;
+
+        let user = |email: &str, name: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: name.to_string(),
+            name: name.to_string(),
+            picture: name.to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        let user1 = user("me@ockam.io", "me");
+        let user2 = user("you@ockam.io", "you");
+        repository.store_user(DEFAULT_TENANT, &user1).await?;
+        repository
+            .set_default_user(DEFAULT_TENANT, &user1.email)
+            .await?;
+
+        // a new user is stored and becomes the only default user
+        repository
+            .upsert_and_set_default(DEFAULT_TENANT, &user2)
+            .await?;
+        let result = repository.get_user("you@ockam.io").await?;
+        assert_eq!(result, Some(user2.clone()));
+
+        let query = query("SELECT email FROM user WHERE is_default = ?").bind(true.to_sql());
+        let rows: Vec<SqliteRow> = query
+            .fetch_all(&repository.database.pool)
+            .await
+            .into_core()?;
+        let emails: Vec<String> = rows.iter().map(|r| r.get(0)).collect();
+        assert_eq!(emails, vec!["you@ockam.io".to_string()]);
+
+        // an existing user is updated and becomes the default user again
+        let updated_user1 = user("me@ockam.io", "updated me");
+        repository
+            .upsert_and_set_default(DEFAULT_TENANT, &updated_user1)
+            .await?;
+        let result = repository.get_default_user(DEFAULT_TENANT).await?;
+        assert_eq!(result, Some(updated_user1.clone()));
+        assert_eq!(repository.get_users().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_user_email() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let user = |email: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "me".to_string(),
+            picture: "me".to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: true,
+            roles: vec!["admin".to_string()],
+        };
+        repository
+            .store_user(DEFAULT_TENANT, &user("me@ockam.io"))
+            .await?;
+        repository
+            .store_user(DEFAULT_TENANT, &user("you@ockam.io"))
+            .await?;
+        repository
+            .set_default_user(DEFAULT_TENANT, "me@ockam.io")
+            .await?;
+
+        // the user is renamed and stays the default user
+        repository
+            .rename_user_email("me@ockam.io", "new-me@ockam.io")
+            .await?;
+        let result = repository.get_user("me@ockam.io").await?;
+        assert_eq!(result, None);
+
+        let result = repository.get_user("new-me@ockam.io").await?;
+        assert_eq!(result, Some(user("new-me@ockam.io")));
+
+        let result = repository.get_default_user(DEFAULT_TENANT).await?;
+        assert_eq!(result, Some(user("new-me@ockam.io")));
+        assert_eq!(repository.get_users().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_user_email_conflict() -> Result<()> {
+        let repository = create_repository().await?;
+
+        let user = |email: &str, name: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: name.to_string(),
+            name: name.to_string(),
+            picture: name.to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        let me = user("me@ockam.io", "me");
+        let you = user("you@ockam.io", "you");
+        repository.store_user(DEFAULT_TENANT, &me).await?;
+        repository.store_user(DEFAULT_TENANT, &you).await?;
+
+        // a user can not take the email of another user
+        let result = repository
+            .rename_user_email("me@ockam.io", "you@ockam.io")
+            .await;
+        assert!(result.is_err());
+
+        // the users are unchanged
+        assert_eq!(repository.get_user("me@ockam.io").await?, Some(me));
+        assert_eq!(repository.get_user("you@ockam.io").await?, Some(you));
+
+        // a missing user can not be renamed
+        let result = repository
+            .rename_user_email("unknown@ockam.io", "other@ockam.io")
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_count_users_by_domain() -> Result<()> {
+        let repository = create_repository().await?;
+        assert!(repository.count_users_by_domain().await?.is_empty());
+
+        for email in [
+            "me@ockam.io",
+            "you@example.com",
+            "them@Ockam.io",
+            "us@ockam.io",
+        ] {
+            let user = UserInfo {
+                sub: "sub".into(),
+                nickname: "me".to_string(),
+                name: "me".to_string(),
+                picture: "me".to_string(),
+                updated_at: "today".to_string(),
+                email: email.into(),
+                email_verified: false,
+                roles: vec![],
+            };
+            repository.store_user(DEFAULT_TENANT, &user).await?;
+        }
+
+        // the users are counted by lowercase domain, the largest domain first
+        let result = repository.count_users_by_domain().await?;
+        assert_eq!(
+            result,
+            vec![("ockam.io".to_string(), 3), ("example.com".to_string(), 1)]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_import_users_json() -> Result<()> {
+        let repository = UsersSqlxDatabase::create().await?;
+
+        let user = |email: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "me".to_string(),
+            picture: "me".to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: true,
+            roles: vec!["admin".to_string()],
+        };
+        for email in ["alice@ockam.io", "bob@ockam.io", "carol@ockam.io"] {
+            repository.store_user(DEFAULT_TENANT, &user(email)).await?;
+        }
+        repository
+            .set_default_user(DEFAULT_TENANT, "bob@ockam.io")
+            .await?;
+        let json = repository.export_users_json().await?;
+
+        // the users and the default user are preserved on another machine
+        let other = UsersSqlxDatabase::create().await?;
+        other
+            .store_user(DEFAULT_TENANT, &user("dave@ockam.io"))
+            .await?;
+        other
+            .set_default_user(DEFAULT_TENANT, "dave@ockam.io")
+            .await?;
+        assert_eq!(other.import_users_json(&json, true).await?, 3);
+        assert_eq!(other.get_users().await?, repository.get_users().await?);
+        assert_eq!(
+            other.get_default_user(DEFAULT_TENANT).await?,
+            Some(user("bob@ockam.io"))
+        );
+
+        // without replacement the existing users are kept, and there is still one default user
+        let other = UsersSqlxDatabase::create().await?;
+        other
+            .store_user(DEFAULT_TENANT, &user("dave@ockam.io"))
+            .await?;
+        other
+            .set_default_user(DEFAULT_TENANT, "dave@ockam.io")
+            .await?;
+        assert_eq!(other.import_users_json(&json, false).await?, 3);
+        assert_eq!(other.get_users().await?.len(), 4);
+        assert_eq!(
+            other.get_default_user(DEFAULT_TENANT).await?,
+            Some(user("bob@ockam.io"))
+        );
+        let query = query("SELECT email FROM user WHERE is_default = ?").bind(true.to_sql());
+        let rows: Vec<SqliteRow> = query.fetch_all(&other.database.pool).await.into_core()?;
+        assert_eq!(rows.len(), 1);
+
+        // a plain array of users can be imported, the first user becomes the default one
+        let other = UsersSqlxDatabase::create().await?;
+        let json =
+            serde_json::to_string(&vec![user("erin@ockam.io"), user("frank@ockam.io")]).unwrap();
+        assert_eq!(other.import_users_json(&json, false).await?, 2);
+        assert_eq!(
+            other.get_default_user(DEFAULT_TENANT).await?,
+            Some(user("erin@ockam.io"))
+        );
+
+        // the emails must be unique in the payload
+        let json =
+            serde_json::to_string(&vec![user("erin@ockam.io"), user("erin@ockam.io")]).unwrap();
+        let result = other.import_users_json(&json, true).await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Invalid);
+        assert_eq!(other.get_users().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_users_created_after() -> Result<()> {
+        let repository = UsersSqlxDatabase::create().await?;
+
+        let user = |email: &str| UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "me".to_string(),
+            picture: "me".to_string(),
+            updated_at: "today".to_string(),
+            email: email.into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        for (email, created_at) in [
+            ("alice@ockam.io", 100u64),
+            ("bob@ockam.io", 300),
+            ("carol@ockam.io", 200),
+            ("dave@ockam.io", 150),
+        ] {
+            repository.store_user(DEFAULT_TENANT, &user(email)).await?;
+            query("UPDATE user SET created_at = $1 WHERE email = $2")
+                .bind(created_at.to_sql())
+                .bind(email.to_sql())
+                .execute(&repository.database.pool)
+                .await
+                .void()?;
+        }
+
+        // only the users created after the given time are returned, oldest first
+        let result = repository
+            .get_users_created_after(TimestampInSeconds(150))
+            .await?;
+        assert_eq!(result, vec![user("carol@ockam.io"), user("bob@ockam.io")]);
+
+        // updating a user keeps its creation time
+        repository
+            .store_user(
+                DEFAULT_TENANT,
+                &UserInfo {
+                    nickname: "alice".to_string(),
+                    ..user("alice@ockam.io")
+                },
+            )
+            .await?;
+        let result = repository
+            .get_users_created_after(TimestampInSeconds(150))
+            .await?;
+        assert_eq!(result, vec![user("carol@ockam.io"), user("bob@ockam.io")]);
+
+        // a new user is created now
+        repository
+            .store_user(DEFAULT_TENANT, &user("erin@ockam.io"))
+            .await?;
+        let result = repository
+            .get_users_created_after(TimestampInSeconds(300))
+            .await?;
+        assert_eq!(result, vec![user("erin@ockam.io")]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_column() -> Result<()> {
+        let database = SqlxDatabase::in_memory("users").await?;
+        query("ALTER TABLE user DROP COLUMN picture")
+            .execute(&database.pool)
+            .await
+            .void()?;
+
+        let result = UsersSqlxDatabase::check_schema(&database).await;
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("needs a migration"), "{error}");
+        assert!(error.contains("`picture`"), "{error}");
+        Ok(())
+    }
+
     /// HELPERS
     async fn create_repository() -> Result<Arc<dyn UsersRepository>> {
         Ok(UsersSqlxDatabase::create().await?)
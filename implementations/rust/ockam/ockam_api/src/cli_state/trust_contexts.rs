@@ -104,6 +104,27 @@ impl CliState {
             .await?)
     }
 
+    /// Return the trust context whose authority can be reached with the given route
+    pub async fn get_trust_context_by_authority_route(
+        &self,
+        authority_route: &MultiAddr,
+    ) -> Result<NamedTrustContext> {
+        match self
+            .get_trust_contexts()
+            .await?
+            .into_iter()
+            .find(|tc| tc.authority_route().as_ref() == Some(authority_route))
+        {
+            Some(trust_context) => Ok(trust_context),
+            None => Err(Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("there is no trust context with an authority at {authority_route}"),
+            )
+            .into()),
+        }
+    }
+
     pub async fn delete_trust_context(&self, name: &str) -> Result<()> {
         Ok(self
             .trust_contexts_repository()
@@ -434,6 +455,48 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_trust_context_by_authority_route() -> Result<()> {
+        let cli = CliState::test().await?;
+        let identities = identities().await?;
+        let authority_identifier = identities.identities_creation().create_identity().await?;
+        let authority = identities.get_identity(&authority_identifier).await?;
+
+        let route1 = MultiAddr::from_string("/dnsaddr/127.0.0.1/tcp/5000/service/api")?;
+        let route2 = MultiAddr::from_string("/dnsaddr/127.0.0.1/tcp/6000/service/api")?;
+        cli.create_trust_context(
+            Some("trust-context-1".into()),
+            None,
+            None,
+            Some(authority.clone()),
+            Some(route1.clone()),
+        )
+        .await?;
+        cli.create_trust_context(
+            Some("trust-context-2".into()),
+            None,
+            None,
+            Some(authority.clone()),
+            Some(route2.clone()),
+        )
+        .await?;
+
+        // the trust context of the specified authority is selected
+        let result = cli.get_trust_context_by_authority_route(&route2).await?;
+        assert_eq!(result.name(), "trust-context-2");
+        assert_eq!(result.authority_route(), Some(route2));
+
+        // an unknown authority returns an error
+        let unknown = MultiAddr::from_string("/dnsaddr/127.0.0.1/tcp/7000/service/api")?;
+        let result = cli.get_trust_context_by_authority_route(&unknown).await;
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("there is no trust context with an authority at"));
+        Ok(())
+    }
+
     /// HELPERS
     pub async fn create_credential(
         identities: Arc<Identities>,
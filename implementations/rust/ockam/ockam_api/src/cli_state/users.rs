@@ -3,13 +3,14 @@ use ockam_core::Error;
 
 use crate::cli_state::CliState;
 use crate::cli_state::Result;
+use crate::cli_state::DEFAULT_TENANT;
 use crate::cloud::enroll::auth0::UserInfo;
 
 impl CliState {
     pub async fn store_user(&self, user: &UserInfo) -> Result<()> {
         let repository = self.users_repository().await?;
         let is_first_user = repository.get_users().await?.is_empty();
-        repository.store_user(user).await?;
+        repository.store_user(DEFAULT_TENANT, user).await?;
 
         // if this is the first user we store we mark it as the default user
         if is_first_user {
@@ -18,19 +19,67 @@ impl CliState {
         Ok(())
     }
 
+    /// Set a user as the default user of the default tenant
     pub async fn set_default_user(&self, email: &str) -> Result<()> {
         self.users_repository()
             .await?
-            .set_default_user(email)
+            .set_default_user(DEFAULT_TENANT, email)
             .await?;
         Ok(())
     }
 
+    /// Store (or update) a user and set it as the default user of the default tenant
+    pub async fn upsert_and_set_default_user(&self, user: &UserInfo) -> Result<()> {
+        self.users_repository()
+            .await?
+            .upsert_and_set_default(DEFAULT_TENANT, user)
+            .await?;
+        Ok(())
+    }
+
+    /// Return the default user of the default tenant
     pub async fn get_default_user(&self) -> Result<UserInfo> {
         let repository = self.users_repository().await?;
-        match repository.get_default_user().await? {
+        match repository.get_default_user(DEFAULT_TENANT).await? {
             Some(user) => Ok(user),
             None => Err(Error::new(Origin::Api, Kind::NotFound, "there is no default user").into()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlx::query_as;
+
+    use ockam_node::database::FromSqlxError;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encrypted_users() -> Result<()> {
+        let cli = CliState::test().await?.with_encrypted_users(true);
+        let user = UserInfo {
+            sub: "sub".into(),
+            nickname: "me".to_string(),
+            name: "my name".to_string(),
+            picture: "my picture".to_string(),
+            updated_at: "today".to_string(),
+            email: "me@ockam.io".into(),
+            email_verified: false,
+            roles: vec![],
+        };
+        cli.store_user(&user).await?;
+
+        // the user email is not stored in plain text
+        let emails: Vec<(String,)> = query_as("SELECT email FROM user")
+            .fetch_all(&cli.database().pool)
+            .await
+            .into_core()?;
+        assert_eq!(emails.len(), 1);
+        assert_ne!(emails[0].0, user.email);
+
+        // the same encryption key is used by every repository
+        assert_eq!(cli.get_default_user().await?, user);
+        Ok(())
+    }
+}
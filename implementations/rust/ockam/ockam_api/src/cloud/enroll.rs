@@ -2,6 +2,7 @@ use miette::IntoDiagnostic;
 use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::time::SystemTime;
 
 use crate::cloud::enroll::enrollment_token::{
     AuthenticateEnrollmentToken, EnrollmentToken, RequestEnrollmentToken,
@@ -114,6 +115,15 @@ pub mod auth0 {
     pub struct OidcToken {
         pub token_type: TokenType,
         pub access_token: Token,
+        /// Lifetime of the access token in seconds, if returned by the OIDC provider
+        #[serde(default)]
+        pub expires_in: Option<u64>,
+        /// Token used to get a new access token, if returned by the OIDC provider
+        #[serde(default)]
+        pub refresh_token: Option<Token>,
+        /// Time at which the token was received
+        #[serde(skip, default = "SystemTime::now")]
+        pub received_at: SystemTime,
     }
 
     #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -126,6 +136,9 @@ pub mod auth0 {
         pub updated_at: String,
         pub email: String,
         pub email_verified: bool,
+        /// Roles (or groups) of the user, as returned by the identity provider
+        #[serde(default)]
+        pub roles: Vec<String>,
     }
 
     #[derive(Encode, Decode, Debug)]
@@ -1,4 +1,4 @@
-use miette::{miette, IntoDiagnostic};
+use miette::miette;
 use std::str::FromStr;
 
 use minicbor::{Decode, Encode};
@@ -393,23 +393,25 @@ impl Controller {
         trace!(target: TARGET, %space_id, project_name = name, "creating project");
         let req = Request::post(format!("/v1/spaces/{space_id}/projects"))
             .body(CreateProject::new(name.to_string(), users));
-        self.secure_client
+        Ok(self
+            .secure_client
             .ask(ctx, "projects", req)
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn get_project(&self, ctx: &Context, project_id: &str) -> miette::Result<Project> {
         trace!(target: TARGET, %project_id, "getting project");
         let req = Request::get(format!("/v0/{project_id}"));
-        self.secure_client
+        Ok(self
+            .secure_client
             .ask(ctx, "projects", req)
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn delete_project(
@@ -420,12 +422,13 @@ impl Controller {
     ) -> miette::Result<()> {
         trace!(target: TARGET, %space_id, %project_id, "deleting project");
         let req = Request::delete(format!("/v0/{space_id}/{project_id}"));
-        self.secure_client
+        Ok(self
+            .secure_client
             .tell(ctx, "projects", req)
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn get_orchestrator_version_info(
@@ -433,22 +436,24 @@ impl Controller {
         ctx: &Context,
     ) -> miette::Result<OrchestratorVersionInfo> {
         trace!(target: TARGET, "getting orchestrator version information");
-        self.secure_client
+        Ok(self
+            .secure_client
             .ask(ctx, "version_info", Request::get(""))
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn list_projects(&self, ctx: &Context) -> miette::Result<Vec<Project>> {
         let req = Request::get("/v0");
-        self.secure_client
+        Ok(self
+            .secure_client
             .ask(ctx, "projects", req)
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn wait_until_project_is_ready(
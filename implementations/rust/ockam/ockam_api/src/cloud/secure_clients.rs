@@ -6,13 +6,13 @@ use tokio::spawn;
 use ockam::identity::{Identifier, SecureChannel, SecureChannels, SecureClient, DEFAULT_TIMEOUT};
 use ockam_core::compat::sync::Arc;
 use ockam_core::env::{get_env, get_env_with_default, FromString};
-use ockam_core::errcode::Kind;
-use ockam_core::{AsyncTryClone, Result};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{AsyncTryClone, Error, Result};
 use ockam_multiaddr::MultiAddr;
 use ockam_node::Context;
 use ockam_transport_tcp::{TcpConnection, TcpTransport};
 
-use crate::error::ApiError;
+use crate::cli_state::ControllerConfig;
 use crate::nodes::NodeManager;
 use crate::{multiaddr_to_route, MultiAddrToRouteResult};
 
@@ -34,12 +34,14 @@ pub const ORCHESTRATOR_AWAIT_TIMEOUT: Duration = Duration::from_secs(60 * 10);
 impl NodeManager {
     pub(crate) async fn create_controller_client(
         &self,
+        controller_config: Option<&ControllerConfig>,
         timeout: Option<Duration>,
     ) -> Result<Controller> {
         NodeManager::controller_node(
             &self.tcp_transport,
             self.secure_channels.clone(),
             &self.identifier(),
+            controller_config,
             timeout,
         )
         .await
@@ -93,14 +95,38 @@ impl NodeManager {
         .await
     }
 
+    /// Create a client for the controller.
+    /// If a controller configuration is given, its address and identifier are used instead of the
+    /// default ones, for example to connect to a staging or self-hosted Orchestrator.
+    /// An error is returned if that address can not be reached
     pub async fn controller_node(
         tcp_transport: &TcpTransport,
         secure_channels: Arc<SecureChannels>,
         caller_identifier: &Identifier,
+        controller_config: Option<&ControllerConfig>,
         timeout: Option<Duration>,
     ) -> Result<Controller> {
-        let mut controller_route = Self::controller_route(tcp_transport).await?;
-        let controller_identifier = Self::load_controller_identifier()?;
+        let mut controller_route = match controller_config {
+            Some(controller_config) => {
+                let controller_multiaddr = &controller_config.multiaddr;
+                Self::resolve_secure_route(tcp_transport, controller_multiaddr)
+                    .await
+                    .map_err(|e| {
+                        Error::new(
+                            Origin::Api,
+                            e.code().kind,
+                            format!(
+                                "The controller at {controller_multiaddr} is not reachable: {e}"
+                            ),
+                        )
+                    })?
+            }
+            None => Self::controller_route(tcp_transport).await?,
+        };
+        let controller_identifier = match controller_config.and_then(|c| c.identifier.clone()) {
+            Some(controller_identifier) => controller_identifier,
+            None => Self::load_controller_identifier()?,
+        };
 
         let tcp_connection = if let Some(tcp_connection) = controller_route.tcp_connection.take() {
             Some((tcp_connection, tcp_transport.ctx().async_try_clone().await?))
@@ -231,9 +257,12 @@ impl NodeManager {
         let resolved = multiaddr_to_route(multiaddr, tcp_transport)
             .await
             .ok_or_else(|| {
-                ApiError::core(format!(
-                    "Couldn't convert MultiAddr to route: multiaddr={multiaddr}"
-                ))
+                // the route can not be created when the address can not be resolved or reached
+                Error::new(
+                    Origin::Api,
+                    Kind::Io,
+                    format!("Couldn't convert MultiAddr to route: multiaddr={multiaddr}"),
+                )
             })?;
         debug!("using the secure route {}", resolved.route);
         Ok(resolved)
@@ -363,3 +392,69 @@ impl Drop for GenericSecureClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ockam_transport_tcp::TcpListenerOptions;
+
+    use crate::address::get_free_address;
+
+    use super::*;
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn test_controller_client_with_an_overridden_address(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+
+        // the client connects to the overridden controller address
+        let listener = handler
+            .tcp
+            .listen("127.0.0.1:0", TcpListenerOptions::new())
+            .await?;
+        let controller_multiaddr = MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{}/service/api",
+            listener.socket_address().port()
+        ))
+        .unwrap();
+        let controller_config = ControllerConfig::new(controller_multiaddr, None);
+        let controller = node_manager
+            .create_controller_client(Some(&controller_config), None)
+            .await?;
+        assert!(controller.tcp_connection.is_some());
+        assert_eq!(
+            controller.secure_client.server_identifier(),
+            NodeManager::load_controller_identifier()?
+        );
+
+        // the controller identifier can be overridden as well
+        let controller_identifier = node_manager.identifier();
+        let controller_config = ControllerConfig::new(
+            controller_config.multiaddr.clone(),
+            Some(controller_identifier.clone()),
+        );
+        let controller = node_manager
+            .create_controller_client(Some(&controller_config), None)
+            .await?;
+        assert_eq!(
+            controller.secure_client.server_identifier(),
+            controller_identifier
+        );
+
+        // an unreachable controller address is rejected before sending any request
+        let unreachable = MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{}/service/api",
+            get_free_address().unwrap().port()
+        ))
+        .unwrap();
+        let error = node_manager
+            .create_controller_client(Some(&ControllerConfig::new(unreachable, None)), None)
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("is not reachable"));
+
+        context.stop().await
+    }
+}
@@ -1,17 +1,17 @@
-use miette::IntoDiagnostic;
 use minicbor::{Decode, Encode};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use ockam_core::api::Request;
 use ockam_core::async_trait;
 use ockam_node::Context;
 
 use crate::cloud::Controller;
+use crate::error::ApiError;
 use crate::nodes::InMemoryNode;
 
 const TARGET: &str = "ockam_api::cloud::space";
 
-#[derive(Encode, Decode, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Encode, Decode, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[rustfmt::skip]
 #[cbor(map)]
 pub struct Space {
@@ -159,44 +159,48 @@ impl Controller {
             name.into(),
             users.iter().map(|u| u.to_string()).collect(),
         ));
-        self.secure_client
+        Ok(self
+            .secure_client
             .ask(ctx, "spaces", req)
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn get_space(&self, ctx: &Context, space_id: &str) -> miette::Result<Space> {
         trace!(target: TARGET, space = %space_id, "getting space");
         let req = Request::get(format!("/v0/{space_id}"));
-        self.secure_client
+        Ok(self
+            .secure_client
             .ask(ctx, "spaces", req)
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn delete_space(&self, ctx: &Context, space_id: &str) -> miette::Result<()> {
         trace!(target: TARGET, space = %space_id, "deleting space");
         let req = Request::delete(format!("/v0/{space_id}"));
-        self.secure_client
+        Ok(self
+            .secure_client
             .tell(ctx, "spaces", req)
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub async fn list_spaces(&self, ctx: &Context) -> miette::Result<Vec<Space>> {
         trace!(target: TARGET, "listing spaces");
-        self.secure_client
+        Ok(self
+            .secure_client
             .ask(ctx, "spaces", Request::get("/v0/"))
             .await
-            .into_diagnostic()?
+            .map_err(ApiError::from)?
             .success()
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 }
 
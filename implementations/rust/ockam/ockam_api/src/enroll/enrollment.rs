@@ -1,5 +1,6 @@
 use crate::cloud::enroll::auth0::{AuthenticateOidcToken, OidcToken};
 use crate::cloud::HasSecureClient;
+use crate::error::ApiError;
 use crate::nodes::service::default_address::DefaultAddress;
 use miette::IntoDiagnostic;
 use ockam::identity::models::CredentialAndPurposeKey;
@@ -79,7 +80,7 @@ impl Enrollment for SecureClient {
         let reply = self
             .tell(ctx, "auth0_authenticator", req)
             .await
-            .into_diagnostic()?;
+            .map_err(ApiError::from)?;
         match reply {
             Reply::Successful(_) => Ok(EnrollStatus::EnrolledSuccessfully),
             Reply::Failed(_, Some(Status::BadRequest)) => Ok(EnrollStatus::AlreadyEnrolled),
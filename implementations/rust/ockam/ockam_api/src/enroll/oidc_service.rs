@@ -1,5 +1,6 @@
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use miette::miette;
 use reqwest::{StatusCode, Url};
@@ -15,6 +16,7 @@ use crate::enroll::oidc_provider::OidcProvider;
 use crate::error::ApiError;
 use ockam::compat::fmt::Debug;
 use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::Result;
 use ockam_node::callback::{new_callback, CallbackSender};
 use ockam_vault::SoftwareVaultForVerifyingSignatures;
@@ -295,6 +297,40 @@ impl OidcService {
         )))
     }
 
+    /// Return the time at which the access token of a token expires,
+    /// if the OIDC provider returned its lifetime
+    pub fn token_expiry(&self, token: &OidcToken) -> Option<SystemTime> {
+        token
+            .expires_in
+            .map(|expires_in| token.received_at + Duration::from_secs(expires_in))
+    }
+
+    /// Get a new token from the OIDC provider with the refresh token of an existing token
+    /// See: https://datatracker.ietf.org/doc/html/rfc6749#section-6
+    pub async fn refresh_token(&self, token: &OidcToken) -> Result<OidcToken> {
+        let refresh_token = token
+            .refresh_token
+            .clone()
+            .ok_or_else(|| ApiError::core("the OIDC token can not be refreshed"))?;
+        info!("refreshing an OIDC token");
+        let mut refreshed: OidcToken = self
+            .request_code(
+                self.provider().token_request_url(),
+                vec![
+                    ("grant_type", "refresh_token".to_string()),
+                    ("refresh_token", refresh_token.0.clone()),
+                ]
+                .as_slice(),
+            )
+            .await?;
+
+        // the provider only returns a new refresh token when it rotates them
+        if refreshed.refresh_token.is_none() {
+            refreshed.refresh_token = Some(refresh_token);
+        }
+        Ok(refreshed)
+    }
+
     pub async fn get_user_info(&self, token: &OidcToken) -> Result<UserInfo> {
         let client = self.provider().build_http_client()?;
         let access_token = token.access_token.0.clone();
@@ -306,13 +342,30 @@ impl OidcService {
         let retry_strategy = ExponentialBackoff::from_millis(10).take(3);
         let res = Retry::spawn(retry_strategy, move || req().send())
             .await
-            .map_err(|e| ApiError::core(e.to_string()))?;
-        res.json().await.map_err(|e| ApiError::core(e.to_string()))
+            .map_err(request_error)?;
+        res.json().await.map_err(request_error)
     }
 }
 
+/// Convert a failed request into an error whose kind tells if the request can be retried:
+/// timeouts and connection errors are transient, other errors are not
+fn request_error(e: reqwest::Error) -> ockam_core::Error {
+    let kind = if e.is_timeout() {
+        Kind::Timeout
+    } else if e.is_connect() || e.status().map(|s| s.is_server_error()).unwrap_or(false) {
+        Kind::Io
+    } else {
+        Kind::Unknown
+    };
+    ockam_core::Error::new(Origin::Application, kind, e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Read;
+
+    use ockam_node::callback::CallbackReceiver;
+
     use super::*;
 
     #[tokio::test]
@@ -375,10 +428,106 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_refresh_token() -> Result<()> {
+        let (provider, request_body) = StubOidcProvider::start(
+            r#"{"token_type": "Bearer", "access_token": "new-access-token", "expires_in": 3600}"#,
+        );
+        let oidc_service = OidcService::new(Arc::new(provider));
+
+        let token: OidcToken = serde_json::from_str(
+            r#"{"token_type": "Bearer", "access_token": "access-token", "refresh_token": "refresh-token"}"#,
+        )
+        .unwrap();
+        assert_eq!(oidc_service.token_expiry(&token), None);
+
+        let before = SystemTime::now();
+        let refreshed = oidc_service.refresh_token(&token).await?;
+        let after = SystemTime::now();
+
+        // the refresh token is sent to the provider
+        let request_body = request_body.receive_timeout(Duration::from_secs(1)).await?;
+        assert!(request_body.contains("grant_type=refresh_token"));
+        assert!(request_body.contains("refresh_token=refresh-token"));
+
+        // the new token expires after its lifetime and keeps the same refresh token
+        assert_eq!(refreshed.access_token.0, "new-access-token");
+        assert_eq!(refreshed.refresh_token, token.refresh_token);
+        let expiry = oidc_service.token_expiry(&refreshed).unwrap();
+        assert!(expiry >= before + Duration::from_secs(3600));
+        assert!(expiry <= after + Duration::from_secs(3600));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_without_refresh_token() -> Result<()> {
+        let oidc_service = OidcService::default();
+        let token: OidcToken =
+            serde_json::from_str(r#"{"token_type": "Bearer", "access_token": "access-token"}"#)
+                .unwrap();
+        assert!(oidc_service.refresh_token(&token).await.is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_parse_path_query_parameters() {
         let code = OidcService::get_code("/callback?code=12345");
         assert!(code.is_ok());
         assert_eq!(code.unwrap(), "12345".to_string())
     }
+
+    /// OIDC provider serving a single token request on a local server
+    struct StubOidcProvider {
+        base_url: Url,
+    }
+
+    impl StubOidcProvider {
+        /// Start a server responding to the first request with the given token,
+        /// and return the body of that request with a callback
+        fn start(token: &'static str) -> (Self, CallbackReceiver<String>) {
+            let server = Server::http("127.0.0.1:0").unwrap();
+            let port = server.server_addr().to_ip().unwrap().port();
+            let (request_body_receiver, request_body_sender) = new_callback();
+            tokio::task::spawn_blocking(move || {
+                if let Ok(Some(mut request)) = server.recv_timeout(Duration::from_secs(5)) {
+                    let mut body = String::new();
+                    let _ = request.as_reader().read_to_string(&mut body);
+                    let _ = request_body_sender.send(body);
+                    let _ = request.respond(Response::from_string(token));
+                }
+            });
+            let base_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+            (Self { base_url }, request_body_receiver)
+        }
+    }
+
+    impl OidcProvider for StubOidcProvider {
+        fn client_id(&self) -> String {
+            "client-id".to_string()
+        }
+
+        fn redirect_timeout(&self) -> Duration {
+            Duration::from_secs(1)
+        }
+
+        fn redirect_url(&self) -> Url {
+            self.base_url.join("callback").unwrap()
+        }
+
+        fn device_code_url(&self) -> Url {
+            self.base_url.join("oauth/device/code").unwrap()
+        }
+
+        fn authorization_url(&self) -> Url {
+            self.base_url.join("authorize").unwrap()
+        }
+
+        fn token_request_url(&self) -> Url {
+            self.base_url.join("oauth/token").unwrap()
+        }
+
+        fn build_http_client(&self) -> Result<reqwest::Client> {
+            Ok(reqwest::Client::new())
+        }
+    }
 }
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_node::tracing_subscriber::filter::Directive;
+use ockam_node::tracing_subscriber::{reload, EnvFilter, Registry};
+use ockam_transport_tcp::INLET_SPAN_NAME;
+use tracing::Level;
+
+/// Handle used to modify the log filter of a running node
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+static LOG_FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// Log filter of a running node, with the log levels of the inlets overriding it
+struct LogFilter {
+    handle: LogFilterHandle,
+    /// Directives of the log filter installed when setting up the logging of the node
+    node_directives: String,
+    /// Log level of each inlet, by alias
+    inlet_levels: Mutex<BTreeMap<String, Level>>,
+}
+
+/// Register the handle of the log filter installed when setting up the logging of the node.
+/// Only the first registered handle is kept
+pub fn set_log_filter_handle(handle: LogFilterHandle) {
+    let node_directives = handle
+        .with_current(|filter| filter.to_string())
+        .unwrap_or_default();
+    let _ = LOG_FILTER.set(LogFilter {
+        handle,
+        node_directives,
+        inlet_levels: Default::default(),
+    });
+}
+
+/// Log the events of the inlet with the given alias at the given level.
+/// The other inlets keep the log level of the node.
+/// Nothing is changed if the logging of the node has not been set up with a reloadable filter
+pub fn set_inlet_log_level(alias: &str, level: Level) -> Result<()> {
+    match LOG_FILTER.get() {
+        Some(filter) => filter.set_inlet_level(alias, Some(level)),
+        None => Ok(()),
+    }
+}
+
+/// Log the events of the inlet with the given alias at the level of the node again.
+/// This is done when the inlet is deleted, so that its alias can be reused
+pub fn remove_inlet_log_level(alias: &str) -> Result<()> {
+    match LOG_FILTER.get() {
+        Some(filter) => filter.set_inlet_level(alias, None),
+        None => Ok(()),
+    }
+}
+
+impl LogFilter {
+    fn set_inlet_level(&self, alias: &str, level: Option<Level>) -> Result<()> {
+        let mut inlet_levels = self.inlet_levels.lock().unwrap();
+        match level {
+            Some(level) => {
+                // check that the alias can be used in a directive before keeping it
+                inlet_directive(alias, level)?;
+                inlet_levels.insert(alias.to_string(), level);
+            }
+            None => {
+                if inlet_levels.remove(alias).is_none() {
+                    return Ok(());
+                }
+            }
+        }
+
+        // a directive can not be removed from a filter, so the filter is rebuilt
+        let mut filter = EnvFilter::new(&self.node_directives);
+        for (alias, level) in inlet_levels.iter() {
+            filter = filter.add_directive(inlet_directive(alias, *level)?);
+        }
+        self.handle
+            .reload(filter)
+            .map_err(|e| Error::new(Origin::Api, Kind::Internal, e.to_string()))
+    }
+}
+
+/// Return a directive enabling the events emitted in the span of an inlet, at the given level
+fn inlet_directive(alias: &str, level: Level) -> Result<Directive> {
+    format!(
+        "[{INLET_SPAN_NAME}{{alias={alias}}}]={}",
+        level.as_str().to_ascii_lowercase()
+    )
+    .parse()
+    .map_err(|e| {
+        Error::new(
+            Origin::Api,
+            Kind::Invalid,
+            format!("the alias {alias} can not be used in a log filter: {e}"),
+        )
+    })
+}
@@ -1,6 +1,7 @@
 use ockam_core::env::FromString;
 
 pub mod env;
+pub mod filter;
 #[allow(unused, clippy::enum_variant_names)]
 pub mod rolling;
 
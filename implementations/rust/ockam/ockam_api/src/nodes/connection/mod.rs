@@ -42,6 +42,18 @@ pub struct Connection {
 }
 
 impl Connection {
+    /// Connection which is not established yet, it doesn't hold any resources
+    pub(crate) fn pending(original_addr: &MultiAddr) -> Self {
+        Self {
+            transport_route: route![],
+            normalized_addr: MultiAddr::default(),
+            original_addr: original_addr.clone(),
+            secure_channel_encryptors: vec![],
+            tcp_connection: None,
+            flow_control_id: None,
+        }
+    }
+
     /// Shorthand to add the address as consumer to the flow control
     pub fn add_consumer(&self, context: Arc<Context>, address: &Address) {
         if let Some(flow_control_id) = &self.flow_control_id {
@@ -61,6 +73,11 @@ impl Connection {
         self.transport_route.clone()
     }
 
+    /// Return the [`MultiAddr`] this connection was created for
+    pub fn original_addr(&self) -> &MultiAddr {
+        &self.original_addr
+    }
+
     pub async fn route(&self, tcp_transport: &TcpTransport) -> Result<Route> {
         multiaddr_to_route(&self.normalized_addr, tcp_transport)
             .await
@@ -1,6 +1,7 @@
 use crate::error::ApiError;
 use crate::nodes::connection::{Changes, ConnectionBuilder, Instantiator};
-use crate::{multiaddr_to_route, route_to_multiaddr};
+use crate::{multiaddr_to_route_with_options, route_to_multiaddr};
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use crate::nodes::NodeManager;
@@ -8,13 +9,23 @@ use ockam_core::{async_trait, Error, Route};
 use ockam_multiaddr::proto::{DnsAddr, Ip4, Ip6, Tcp};
 use ockam_multiaddr::{Match, MultiAddr, Protocol};
 use ockam_node::Context;
+use ockam_transport_tcp::TcpKeepaliveOptions;
 
 /// Creates the tcp connection.
-pub(crate) struct PlainTcpInstantiator {}
+pub(crate) struct PlainTcpInstantiator {
+    keepalive: Option<TcpKeepaliveOptions>,
+    bind_address: Option<SocketAddr>,
+}
 
 impl PlainTcpInstantiator {
-    pub(crate) fn new() -> Self {
-        Self {}
+    pub(crate) fn new(
+        keepalive: Option<TcpKeepaliveOptions>,
+        bind_address: Option<SocketAddr>,
+    ) -> Self {
+        Self {
+            keepalive,
+            bind_address,
+        }
     }
 }
 
@@ -37,13 +48,18 @@ impl Instantiator for PlainTcpInstantiator {
     ) -> Result<Changes, Error> {
         let (before, tcp_piece, after) = extracted;
 
-        let mut tcp = multiaddr_to_route(&tcp_piece, &node_manager.tcp_transport)
-            .await
-            .ok_or_else(|| {
-                ApiError::core(format!(
-                    "Couldn't convert MultiAddr to route: tcp_piece={tcp_piece}"
-                ))
-            })?;
+        let mut tcp = multiaddr_to_route_with_options(
+            &tcp_piece,
+            &node_manager.tcp_transport,
+            self.keepalive,
+            self.bind_address,
+        )
+        .await
+        .ok_or_else(|| {
+            ApiError::core(format!(
+                "Couldn't convert MultiAddr to route: tcp_piece={tcp_piece}"
+            ))
+        })?;
 
         let multiaddr = route_to_multiaddr(&tcp.route).ok_or_else(|| {
             ApiError::core(format!(
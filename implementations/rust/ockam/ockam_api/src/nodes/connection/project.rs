@@ -1,13 +1,15 @@
 use crate::error::ApiError;
 use crate::nodes::connection::{Changes, Instantiator};
 use crate::nodes::NodeManager;
-use crate::{multiaddr_to_route, try_address_to_multiaddr};
+use crate::{multiaddr_to_route_with_options, try_address_to_multiaddr};
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use ockam_core::{async_trait, Error, Route};
 use ockam_multiaddr::proto::Project;
 use ockam_multiaddr::{Match, MultiAddr, Protocol};
 use ockam_node::Context;
+use ockam_transport_tcp::TcpKeepaliveOptions;
 
 use ockam::identity::models::CredentialAndPurposeKey;
 use ockam::identity::Identifier;
@@ -18,6 +20,8 @@ pub(crate) struct ProjectInstantiator {
     identifier: Identifier,
     timeout: Option<Duration>,
     credential: Option<CredentialAndPurposeKey>,
+    keepalive: Option<TcpKeepaliveOptions>,
+    bind_address: Option<SocketAddr>,
 }
 
 impl ProjectInstantiator {
@@ -25,11 +29,15 @@ impl ProjectInstantiator {
         identifier: Identifier,
         timeout: Option<Duration>,
         credential: Option<CredentialAndPurposeKey>,
+        keepalive: Option<TcpKeepaliveOptions>,
+        bind_address: Option<SocketAddr>,
     ) -> Self {
         Self {
             identifier,
             timeout,
             credential,
+            keepalive,
+            bind_address,
         }
     }
 }
@@ -61,13 +69,18 @@ impl Instantiator for ProjectInstantiator {
             node_manager.resolve_project(&project).await?;
 
         debug!(addr = %project_multiaddr, "creating secure channel");
-        let tcp = multiaddr_to_route(&project_multiaddr, &node_manager.tcp_transport)
-            .await
-            .ok_or_else(|| {
-                ApiError::core(format!(
-                    "Couldn't convert MultiAddr to route: project_multiaddr={project_multiaddr}"
-                ))
-            })?;
+        let tcp = multiaddr_to_route_with_options(
+            &project_multiaddr,
+            &node_manager.tcp_transport,
+            self.keepalive,
+            self.bind_address,
+        )
+        .await
+        .ok_or_else(|| {
+            ApiError::core(format!(
+                "Couldn't convert MultiAddr to route: project_multiaddr={project_multiaddr}"
+            ))
+        })?;
 
         debug!("create a secure channel to the project {project_identifier}");
         let sc = node_manager
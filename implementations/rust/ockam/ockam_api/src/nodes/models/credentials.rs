@@ -1,7 +1,12 @@
 //! Credential request/response types
 
+use std::time::Duration;
+
 use minicbor::{Decode, Encode};
+use ockam::identity::models::TimestampInSeconds;
+use ockam::identity::Identifier;
 use ockam_core::compat::borrow::Cow;
+use ockam_core::{Address, Route};
 use ockam_multiaddr::MultiAddr;
 
 #[derive(Clone, Debug, Decode, Encode)]
@@ -10,13 +15,19 @@ use ockam_multiaddr::MultiAddr;
 pub struct GetCredentialRequest {
     #[n(1)] overwrite: bool,
     #[n(2)] pub identity_name: Option<String>,
+    #[n(3)] pub authority: Option<MultiAddr>,
 }
 
 impl GetCredentialRequest {
-    pub fn new(overwrite: bool, identity_name: Option<String>) -> Self {
+    pub fn new(
+        overwrite: bool,
+        identity_name: Option<String>,
+        authority: Option<MultiAddr>,
+    ) -> Self {
         Self {
             overwrite,
             identity_name,
+            authority,
         }
     }
 
@@ -31,13 +42,250 @@ impl GetCredentialRequest {
 pub struct PresentCredentialRequest<'a> {
     #[b(1)] pub route: Cow<'a, str>,
     #[n(2)] pub oneway: bool,
+    /// Optional context, for example a nonce, which the other node must echo
+    #[cbor(n(3), with = "minicbor::bytes")] pub context: Option<Vec<u8>>,
+    /// Address of an existing secure channel of the node to present the credential over.
+    /// The route is then the route to the credentials service at the other end of the channel
+    #[n(4)] pub secure_channel: Option<String>,
+    /// Maximum amount of time to wait for the other node to present its credential back.
+    /// This is only used for a mutual presentation
+    #[n(5)] pub timeout: Option<Duration>,
+    /// Identifier which the other node must have. The credential is not presented
+    /// if the secure channel used to reach the other node was established with another identity
+    #[n(6)] pub expected_peer: Option<Identifier>,
+    /// If true, the credential and the route are checked but the credential is not presented.
+    /// Optional so that the requests of older clients can still be decoded, false if missing
+    #[n(7)] pub dry_run: Option<bool>,
 }
 
 impl<'a> PresentCredentialRequest<'a> {
-    pub fn new(route: &MultiAddr, oneway: bool) -> Self {
+    pub fn new(route: &MultiAddr, options: PresentCredentialOptions) -> Self {
         Self {
             route: route.to_string().into(),
-            oneway,
+            oneway: options.oneway,
+            context: options.context,
+            secure_channel: options.secure_channel.map(|a| a.to_string()),
+            timeout: options.timeout,
+            expected_peer: options.expected_peer,
+            dry_run: Some(options.dry_run),
+        }
+    }
+
+    /// Return the options of the presentation
+    pub fn options(&self) -> PresentCredentialOptions {
+        PresentCredentialOptions {
+            secure_channel: self.secure_channel.clone().map(Address::from),
+            oneway: self.oneway,
+            context: self.context.clone(),
+            timeout: self.timeout,
+            expected_peer: self.expected_peer.clone(),
+            dry_run: self.is_dry_run(),
+        }
+    }
+
+    /// Return true if the credential must only be checked
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(false)
+    }
+}
+
+/// Options used to present the node credential to another node, in addition to the route
+/// to that node. They are sent to a node with a [`PresentCredentialRequest`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PresentCredentialOptions {
+    pub(crate) secure_channel: Option<Address>,
+    pub(crate) oneway: bool,
+    pub(crate) context: Option<Vec<u8>>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) expected_peer: Option<Identifier>,
+    pub(crate) dry_run: bool,
+}
+
+impl PresentCredentialOptions {
+    /// Present the credential over an existing secure channel instead of creating a new one.
+    /// The route is then the route to the credentials service at the other end of the channel
+    pub fn with_secure_channel(mut self, secure_channel: &Address) -> Self {
+        self.secure_channel = Some(secure_channel.clone());
+        self
+    }
+
+    /// If true, the other node doesn't present its credential back
+    pub fn with_oneway(mut self, oneway: bool) -> Self {
+        self.oneway = oneway;
+        self
+    }
+
+    /// Context, for example a nonce, which the other node must echo
+    pub fn with_context(mut self, context: Vec<u8>) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Wait at most `timeout` for the other node to present its credential back
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Only present the credential if the other node has the expected identifier
+    pub fn with_expected_peer(mut self, expected_peer: Option<Identifier>) -> Self {
+        self.expected_peer = expected_peer;
+        self
+    }
+
+    /// Only check that the credential is valid and that the route can be used,
+    /// without sending anything to the other node
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Request to export the credential of an identity
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ExportCredentialRequest {
+    #[n(1)] pub identity_name: Option<String>,
+}
+
+impl ExportCredentialRequest {
+    pub fn new(identity_name: Option<String>) -> Self {
+        Self { identity_name }
+    }
+}
+
+/// Credential exported as the CBOR encoding of a `CredentialAndPurposeKey`
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ExportedCredential {
+    #[cbor(n(1), with = "minicbor::bytes")] pub bytes: Vec<u8>,
+}
+
+/// Response returned after presenting a credential to another node.
+/// It states if the other node accepted the credential and, if not, why it rejected it.
+///
+/// For a dry run, nothing is presented and the receipt contains the expiration time
+/// of the credential and the route which would have been used
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CredentialPresentationReceipt {
+    #[n(1)] accepted: bool,
+    #[n(2)] reason: Option<String>,
+    /// Optional so that the responses of older nodes can still be decoded, false if missing
+    #[n(3)] dry_run: Option<bool>,
+    #[n(4)] expires_at: Option<TimestampInSeconds>,
+    #[n(5)] route: Option<String>,
+}
+
+impl CredentialPresentationReceipt {
+    pub fn accepted() -> Self {
+        Self {
+            accepted: true,
+            reason: None,
+            dry_run: None,
+            expires_at: None,
+            route: None,
+        }
+    }
+
+    pub fn rejected(reason: impl Into<String>) -> Self {
+        Self {
+            accepted: false,
+            reason: Some(reason.into()),
+            dry_run: None,
+            expires_at: None,
+            route: None,
         }
     }
+
+    pub fn dry_run(expires_at: TimestampInSeconds, route: &Route) -> Self {
+        Self {
+            accepted: false,
+            reason: None,
+            dry_run: Some(true),
+            expires_at: Some(expires_at),
+            route: Some(route.to_string()),
+        }
+    }
+
+    /// Return true if the credential was not presented because of a dry run
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(false)
+    }
+
+    /// Return the expiration time of the credential, for a dry run
+    pub fn expires_at(&self) -> Option<TimestampInSeconds> {
+        self.expires_at
+    }
+
+    /// Return the route which would have been used to present the credential, for a dry run
+    pub fn route(&self) -> Option<String> {
+        self.route.clone()
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.accepted
+    }
+
+    /// Return the reason of a rejection
+    pub fn reason(&self) -> Option<String> {
+        self.reason.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    #[derive(Encode)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct OldPresentCredentialRequest {
+        #[n(1)] route: String,
+        #[n(2)] oneway: bool,
+    }
+
+    #[derive(Encode)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct OldCredentialPresentationReceipt {
+        #[n(1)] accepted: bool,
+        #[n(2)] reason: Option<String>,
+    }
+
+    #[test]
+    fn test_decode_the_messages_of_older_versions() {
+        let request = OldPresentCredentialRequest {
+            route: "/service/credentials".to_string(),
+            oneway: true,
+        };
+        let bytes = minicbor::to_vec(&request).unwrap();
+        let decoded: PresentCredentialRequest = minicbor::decode(&bytes).unwrap();
+        assert!(!decoded.is_dry_run());
+
+        let response = OldCredentialPresentationReceipt {
+            accepted: true,
+            reason: None,
+        };
+        let decoded: CredentialPresentationReceipt =
+            minicbor::decode(&minicbor::to_vec(&response).unwrap()).unwrap();
+        assert!(!decoded.is_dry_run());
+        assert_eq!(decoded, CredentialPresentationReceipt::accepted());
+    }
+
+    #[test]
+    fn test_dry_run_is_sent_with_a_present_credential_request() {
+        let route = MultiAddr::from_str("/service/credentials").unwrap();
+        let options = PresentCredentialOptions::default()
+            .with_oneway(true)
+            .with_dry_run(true);
+        let request = PresentCredentialRequest::new(&route, options);
+        let bytes = minicbor::to_vec(&request).unwrap();
+        let decoded: PresentCredentialRequest = minicbor::decode(&bytes).unwrap();
+        assert!(decoded.is_dry_run());
+    }
 }
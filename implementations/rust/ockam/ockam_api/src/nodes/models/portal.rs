@@ -1,14 +1,24 @@
 //! Inlets and outlet request/response types
 
+use std::collections::{BTreeMap, HashMap};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use minicbor::{Decode, Encode};
 use ockam::identity::Identifier;
 use ockam::route;
-use ockam_core::{Address, Route};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, IncomingAccessControl, Route};
 use ockam_multiaddr::MultiAddr;
+use ockam_transport_tcp::{
+    IpCidr, ProxyProtocolVersion, TcpInletOptions, TcpKeepaliveOptions, DEFAULT_INLET_BUFFER_SIZE,
+    MAX_INLET_BUFFER_SIZE, MIN_INLET_BUFFER_SIZE,
+};
 use serde::{Deserialize, Serialize};
+use tracing::Level;
 
 use crate::error::ApiError;
 use crate::route_to_multiaddr;
@@ -39,6 +49,49 @@ pub struct CreateInlet {
     #[n(6)] pub(crate) suffix_route: Route,
     /// The maximum duration to wait for an outlet to be available
     #[n(7)] pub(crate) wait_for_outlet_duration: Option<Duration>,
+    /// If false, the inlet is created right away and the connection
+    /// to the outlet is established in the background.
+    /// Optional so that the requests of older clients can still be decoded, true if missing
+    #[n(8)] pub(crate) wait_connection: Option<bool>,
+    /// If true, only the peers presenting a valid credential for the
+    /// trust context of the node are allowed to use the inlet. False if missing
+    #[n(9)] pub(crate) require_credential: Option<bool>,
+    /// If set, the version of the PROXY protocol used to send
+    /// the address of the clients to the target of the outlet
+    #[n(10)] pub(crate) proxy_protocol: Option<u8>,
+    /// If set, the inlet listens at the current address of this network interface,
+    /// on the port of the listen address
+    #[n(11)] pub(crate) listen_interface: Option<String>,
+    /// If set, the connections of the inlet are held for at most this duration
+    /// while the inlet reconnects to its outlet
+    #[n(12)] pub(crate) hold_on_reconnect: Option<Duration>,
+    /// If set, the connections of the inlet are closed when no data is exchanged
+    /// for this duration
+    #[n(13)] pub(crate) idle_timeout: Option<Duration>,
+    /// If set, the keepalive configuration of the inlet sockets: the idle time before
+    /// the first probe, the interval between probes and the number of probes
+    #[n(14)] pub(crate) keepalive_time: Option<Duration>,
+    #[n(15)] pub(crate) keepalive_interval: Option<Duration>,
+    #[n(16)] pub(crate) keepalive_retries: Option<u32>,
+    /// If set, the connection to the outlet is made from this local address
+    #[n(17)] pub(crate) egress_bind: Option<SocketAddr>,
+    /// If set, only the client connections coming from these ranges of addresses,
+    /// in the CIDR notation, are accepted by the inlet
+    #[n(18)] pub(crate) allowed_sources: Option<Vec<String>>,
+    /// Labels used to group and filter the inlets
+    #[n(19)] pub(crate) labels: Option<BTreeMap<String, String>>,
+    /// If true, the inlet acts as a SOCKS5 proxy and the clients choose the target
+    /// the outlet connects to. False if missing
+    #[n(20)] pub(crate) socks5: Option<bool>,
+    /// If set, the size of the buffer used to relay the data of each client connection
+    #[n(21)] pub(crate) buffer_size: Option<usize>,
+    /// If set, the level of the logs emitted by the inlet, overriding the log level of the node
+    #[n(22)] pub(crate) log_level: Option<String>,
+    /// If set, the number of tunnels connected to the outlet before any client connects
+    #[n(23)] pub(crate) prewarm: Option<u32>,
+    /// If set, the maximum number of client connections served at the same time,
+    /// the prewarmed tunnels included
+    #[n(24)] pub(crate) max_connections: Option<u32>,
 }
 
 impl CreateInlet {
@@ -56,6 +109,23 @@ impl CreateInlet {
             prefix_route,
             suffix_route,
             wait_for_outlet_duration: None,
+            wait_connection: Some(true),
+            require_credential: Some(false),
+            proxy_protocol: None,
+            listen_interface: None,
+            hold_on_reconnect: None,
+            idle_timeout: None,
+            keepalive_time: None,
+            keepalive_interval: None,
+            keepalive_retries: None,
+            egress_bind: None,
+            allowed_sources: None,
+            labels: None,
+            socks5: Some(false),
+            buffer_size: None,
+            log_level: None,
+            prewarm: None,
+            max_connections: None,
         }
     }
 
@@ -74,6 +144,23 @@ impl CreateInlet {
             prefix_route,
             suffix_route,
             wait_for_outlet_duration: None,
+            wait_connection: Some(true),
+            require_credential: Some(false),
+            proxy_protocol: None,
+            listen_interface: None,
+            hold_on_reconnect: None,
+            idle_timeout: None,
+            keepalive_time: None,
+            keepalive_interval: None,
+            keepalive_retries: None,
+            egress_bind: None,
+            allowed_sources: None,
+            labels: None,
+            socks5: Some(false),
+            buffer_size: None,
+            log_level: None,
+            prewarm: None,
+            max_connections: None,
         }
     }
 
@@ -85,6 +172,76 @@ impl CreateInlet {
         self.wait_for_outlet_duration = Some(Duration::from_millis(ms))
     }
 
+    pub fn set_wait_connection(&mut self, wait_connection: bool) {
+        self.wait_connection = Some(wait_connection)
+    }
+
+    pub fn set_require_credential(&mut self, require_credential: bool) {
+        self.require_credential = Some(require_credential)
+    }
+
+    pub fn set_proxy_protocol(&mut self, proxy_protocol: Option<ProxyProtocolVersion>) {
+        self.proxy_protocol = proxy_protocol.map(|version| version.number())
+    }
+
+    pub fn set_listen_interface(&mut self, listen_interface: Option<String>) {
+        self.listen_interface = listen_interface
+    }
+
+    pub fn set_hold_on_reconnect(&mut self, hold_on_reconnect: Option<Duration>) {
+        self.hold_on_reconnect = hold_on_reconnect
+    }
+
+    pub fn set_idle_timeout(&mut self, idle_timeout: Option<Duration>) {
+        self.idle_timeout = idle_timeout
+    }
+
+    pub fn set_keepalive(&mut self, keepalive: Option<TcpKeepaliveOptions>) {
+        self.keepalive_time = keepalive.map(|k| k.time());
+        self.keepalive_interval = keepalive.and_then(|k| k.interval());
+        self.keepalive_retries = keepalive.and_then(|k| k.retries());
+    }
+
+    pub fn set_egress_bind(&mut self, egress_bind: Option<SocketAddr>) {
+        self.egress_bind = egress_bind
+    }
+
+    pub fn set_allowed_sources(&mut self, allowed_sources: Vec<IpCidr>) {
+        self.allowed_sources = if allowed_sources.is_empty() {
+            None
+        } else {
+            Some(allowed_sources.iter().map(|s| s.to_string()).collect())
+        }
+    }
+
+    pub fn set_labels(&mut self, labels: BTreeMap<String, String>) {
+        self.labels = if labels.is_empty() {
+            None
+        } else {
+            Some(labels)
+        }
+    }
+
+    pub fn set_socks5(&mut self, socks5: bool) {
+        self.socks5 = Some(socks5)
+    }
+
+    pub fn set_buffer_size(&mut self, buffer_size: Option<usize>) {
+        self.buffer_size = buffer_size
+    }
+
+    pub fn set_log_level(&mut self, log_level: Option<Level>) {
+        self.log_level = log_level.map(|l| l.to_string())
+    }
+
+    pub fn set_prewarm(&mut self, prewarm: Option<u32>) {
+        self.prewarm = prewarm
+    }
+
+    pub fn set_max_connections(&mut self, max_connections: Option<u32>) {
+        self.max_connections = max_connections
+    }
+
     pub fn listen_addr(&self) -> String {
         self.listen_addr.clone()
     }
@@ -112,6 +269,400 @@ impl CreateInlet {
     pub fn wait_for_outlet_duration(&self) -> Option<Duration> {
         self.wait_for_outlet_duration
     }
+
+    pub fn wait_connection(&self) -> bool {
+        self.wait_connection.unwrap_or(true)
+    }
+
+    pub fn require_credential(&self) -> bool {
+        self.require_credential.unwrap_or(false)
+    }
+
+    pub fn socks5(&self) -> bool {
+        self.socks5.unwrap_or(false)
+    }
+
+    pub fn listen_interface(&self) -> Option<&str> {
+        self.listen_interface.as_deref()
+    }
+
+    pub fn hold_on_reconnect(&self) -> Option<Duration> {
+        self.hold_on_reconnect
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    pub fn keepalive(&self) -> Option<TcpKeepaliveOptions> {
+        self.keepalive_time.map(|time| {
+            let keepalive = TcpKeepaliveOptions::new(time);
+            let keepalive = match self.keepalive_interval {
+                Some(interval) => keepalive.with_interval(interval),
+                None => keepalive,
+            };
+            match self.keepalive_retries {
+                Some(retries) => keepalive.with_retries(retries),
+                None => keepalive,
+            }
+        })
+    }
+
+    pub fn egress_bind(&self) -> Option<SocketAddr> {
+        self.egress_bind
+    }
+
+    pub fn proxy_protocol(&self) -> ockam_core::Result<Option<ProxyProtocolVersion>> {
+        self.proxy_protocol
+            .map(ProxyProtocolVersion::try_from)
+            .transpose()
+    }
+
+    pub fn allowed_sources(&self) -> ockam_core::Result<Vec<IpCidr>> {
+        self.allowed_sources
+            .iter()
+            .flatten()
+            .map(|s| s.parse())
+            .collect()
+    }
+
+    pub fn buffer_size(&self) -> ockam_core::Result<Option<usize>> {
+        self.buffer_size.map(validate_buffer_size).transpose()
+    }
+
+    pub fn log_level(&self) -> ockam_core::Result<Option<Level>> {
+        self.log_level.as_deref().map(parse_log_level).transpose()
+    }
+
+    pub fn prewarm(&self) -> Option<u32> {
+        self.prewarm
+    }
+
+    pub fn max_connections(&self) -> Option<u32> {
+        self.max_connections
+    }
+
+    pub fn labels(&self) -> ockam_core::Result<BTreeMap<String, String>> {
+        let labels = self.labels.clone().unwrap_or_default();
+        for (key, value) in &labels {
+            validate_label(key, value)?;
+        }
+        Ok(labels)
+    }
+
+    /// Set all the options of the inlet
+    pub fn set_options(&mut self, options: &InletOptions) {
+        self.wait_for_outlet_duration = options.wait_for_outlet_timeout;
+        self.set_wait_connection(options.wait_connection);
+        self.set_require_credential(options.require_credential);
+        self.set_proxy_protocol(options.proxy_protocol);
+        self.set_listen_interface(options.listen_interface.clone());
+        self.set_hold_on_reconnect(options.hold_on_reconnect);
+        self.set_idle_timeout(options.idle_timeout);
+        self.set_keepalive(options.keepalive);
+        self.set_egress_bind(options.egress_bind);
+        self.set_allowed_sources(options.allowed_sources.clone());
+        self.set_labels(options.labels.clone());
+        self.set_socks5(options.socks5);
+        self.set_buffer_size(options.buffer_size);
+        self.set_log_level(options.log_level);
+        self.set_prewarm(options.prewarm);
+        self.set_max_connections(options.max_connections);
+    }
+
+    /// Return the options of the inlet, or an error if one of them is invalid
+    pub fn options(&self) -> ockam_core::Result<InletOptions> {
+        Ok(InletOptions {
+            wait_for_outlet_timeout: self.wait_for_outlet_duration,
+            wait_connection: self.wait_connection(),
+            require_credential: self.require_credential(),
+            proxy_protocol: self.proxy_protocol()?,
+            listen_interface: self.listen_interface.clone(),
+            hold_on_reconnect: self.hold_on_reconnect,
+            idle_timeout: self.idle_timeout,
+            keepalive: self.keepalive(),
+            egress_bind: self.egress_bind,
+            allowed_sources: self.allowed_sources()?,
+            labels: self.labels()?,
+            socks5: self.socks5(),
+            buffer_size: self.buffer_size()?,
+            log_level: self.log_level()?,
+            prewarm: self.prewarm,
+            max_connections: self.max_connections,
+        })
+    }
+}
+
+/// Options used to create an inlet, in addition to its listen address, its outlet and its alias.
+/// They are sent to a node with a [`CreateInlet`] request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InletOptions {
+    pub(crate) wait_for_outlet_timeout: Option<Duration>,
+    pub(crate) wait_connection: bool,
+    pub(crate) require_credential: bool,
+    pub(crate) proxy_protocol: Option<ProxyProtocolVersion>,
+    pub(crate) listen_interface: Option<String>,
+    pub(crate) hold_on_reconnect: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) keepalive: Option<TcpKeepaliveOptions>,
+    pub(crate) egress_bind: Option<SocketAddr>,
+    pub(crate) allowed_sources: Vec<IpCidr>,
+    pub(crate) labels: BTreeMap<String, String>,
+    pub(crate) socks5: bool,
+    pub(crate) buffer_size: Option<usize>,
+    pub(crate) log_level: Option<Level>,
+    pub(crate) prewarm: Option<u32>,
+    pub(crate) max_connections: Option<u32>,
+}
+
+impl Default for InletOptions {
+    fn default() -> Self {
+        Self {
+            wait_for_outlet_timeout: None,
+            wait_connection: true,
+            require_credential: false,
+            proxy_protocol: None,
+            listen_interface: None,
+            hold_on_reconnect: None,
+            idle_timeout: None,
+            keepalive: None,
+            egress_bind: None,
+            allowed_sources: vec![],
+            labels: BTreeMap::new(),
+            socks5: false,
+            buffer_size: None,
+            log_level: None,
+            prewarm: None,
+            max_connections: None,
+        }
+    }
+}
+
+impl InletOptions {
+    /// The maximum duration to wait for the outlet to be available
+    pub fn with_wait_for_outlet_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_for_outlet_timeout = Some(timeout);
+        self
+    }
+
+    /// If false, the inlet is created right away and the connection
+    /// to the outlet is established in the background, once a first client connects to the inlet
+    pub fn with_wait_connection(mut self, wait_connection: bool) -> Self {
+        self.wait_connection = wait_connection;
+        self
+    }
+
+    /// If true, only the peers presenting a valid credential for the
+    /// trust context of the node are allowed to use the inlet
+    pub fn with_require_credential(mut self, require_credential: bool) -> Self {
+        self.require_credential = require_credential;
+        self
+    }
+
+    /// The version of the PROXY protocol used to send the address of the clients
+    /// to the target of the outlet
+    pub fn with_proxy_protocol(mut self, proxy_protocol: Option<ProxyProtocolVersion>) -> Self {
+        self.proxy_protocol = proxy_protocol;
+        self
+    }
+
+    /// The network interface the inlet listens at, on the port of the listen address
+    pub fn with_listen_interface(mut self, listen_interface: Option<String>) -> Self {
+        self.listen_interface = listen_interface;
+        self
+    }
+
+    /// The maximum duration the connections of the inlet are held
+    /// while the inlet reconnects to its outlet
+    pub fn with_hold_on_reconnect(mut self, hold_on_reconnect: Option<Duration>) -> Self {
+        self.hold_on_reconnect = hold_on_reconnect;
+        self
+    }
+
+    /// The duration after which a connection exchanging no data is closed
+    pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// The keepalive configuration of the inlet sockets
+    pub fn with_keepalive(mut self, keepalive: Option<TcpKeepaliveOptions>) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    /// The local address used for the connection to the outlet
+    pub fn with_egress_bind(mut self, egress_bind: Option<SocketAddr>) -> Self {
+        self.egress_bind = egress_bind;
+        self
+    }
+
+    /// The ranges of addresses the client connections are accepted from.
+    /// All the addresses are accepted when no range is given
+    pub fn with_allowed_sources(mut self, allowed_sources: Vec<IpCidr>) -> Self {
+        self.allowed_sources = allowed_sources;
+        self
+    }
+
+    /// The labels used to group and filter the inlets
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// If true, the inlet acts as a SOCKS5 proxy and the clients choose the target
+    /// the outlet connects to
+    pub fn with_socks5(mut self, socks5: bool) -> Self {
+        self.socks5 = socks5;
+        self
+    }
+
+    /// The size of the buffer used to relay the data of each client connection
+    pub fn with_buffer_size(mut self, buffer_size: Option<usize>) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// The level of the logs emitted by the inlet, overriding the log level of the node
+    pub fn with_log_level(mut self, log_level: Option<Level>) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    /// The number of tunnels established before any client connects
+    pub fn with_prewarm(mut self, prewarm: Option<u32>) -> Self {
+        self.prewarm = prewarm;
+        self
+    }
+
+    /// The maximum number of client connections served at the same time,
+    /// the prewarmed tunnels included
+    pub fn with_max_connections(mut self, max_connections: Option<u32>) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Return the idle timeout of the inlet connections.
+    /// A zero idle timeout keeps the connections open, like an unset one
+    pub(crate) fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout.filter(|d| !d.is_zero())
+    }
+
+    /// Return the size of the buffer used by the inlet connections
+    pub(crate) fn buffer_size(&self) -> usize {
+        self.buffer_size.unwrap_or(DEFAULT_INLET_BUFFER_SIZE)
+    }
+
+    /// Return the options used to create the TCP inlet of a portal
+    pub(crate) fn tcp_inlet_options(
+        &self,
+        alias: &str,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> TcpInletOptions {
+        let options = TcpInletOptions::new()
+            .with_alias(alias)
+            .with_incoming_access_control(access_control)
+            .with_allowed_sources(self.allowed_sources.clone())
+            .with_buffer_size(self.buffer_size());
+        let options = if self.socks5 {
+            options.with_socks5()
+        } else {
+            options
+        };
+        let options = match self.proxy_protocol {
+            Some(version) => options.with_proxy_protocol(version),
+            None => options,
+        };
+        let options = match self.hold_on_reconnect {
+            Some(duration) => options.with_hold_on_reconnect(duration),
+            None => options,
+        };
+        let options = match self.idle_timeout() {
+            Some(duration) => options.with_idle_timeout(duration),
+            None => options,
+        };
+        let options = match self.keepalive {
+            Some(keepalive) => options.with_keepalive(keepalive),
+            None => options,
+        };
+        let options = match self.prewarm {
+            Some(prewarm) => options.with_prewarm(prewarm as usize),
+            None => options,
+        };
+        match self.max_connections {
+            Some(max_connections) => options.with_max_connections(max_connections as usize),
+            None => options,
+        }
+    }
+}
+
+/// Maximum length of the key or the value of an inlet label
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Parse an inlet label given as `key=value`
+pub fn parse_label(label: &str) -> ockam_core::Result<(String, String)> {
+    let (key, value) = label.split_once('=').ok_or_else(|| {
+        ockam_core::Error::new(
+            Origin::Api,
+            Kind::Invalid,
+            format!("invalid label {label}. Expected <KEY>=<VALUE>"),
+        )
+    })?;
+    validate_label(key, value)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Check that the key of a label is not empty and that its key and value only contain
+/// alphanumeric characters, '-', '_' or '.', starting with an alphanumeric character
+pub fn validate_label(key: &str, value: &str) -> ockam_core::Result<()> {
+    let is_valid = |s: &str| {
+        s.len() <= MAX_LABEL_LENGTH
+            && s.chars().next().map_or(true, |c| c.is_ascii_alphanumeric())
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    };
+    if key.is_empty() || !is_valid(key) || !is_valid(value) {
+        return Err(ockam_core::Error::new(
+            Origin::Api,
+            Kind::Invalid,
+            format!(
+                "invalid label {key}={value}. The key must not be empty, the key and the value must have \
+                 at most {MAX_LABEL_LENGTH} characters, start with a letter or a digit and only contain \
+                 letters, digits, '-', '_' or '.'"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that the size of the buffer used to relay the data of the inlet connections
+/// is between [`MIN_INLET_BUFFER_SIZE`] and [`MAX_INLET_BUFFER_SIZE`]
+pub fn validate_buffer_size(buffer_size: usize) -> ockam_core::Result<usize> {
+    if !(MIN_INLET_BUFFER_SIZE..=MAX_INLET_BUFFER_SIZE).contains(&buffer_size) {
+        return Err(ockam_core::Error::new(
+            Origin::Api,
+            Kind::Invalid,
+            format!(
+                "invalid buffer size {buffer_size}. The buffer size must be between \
+                 {MIN_INLET_BUFFER_SIZE} and {MAX_INLET_BUFFER_SIZE} bytes"
+            ),
+        ));
+    }
+    Ok(buffer_size)
+}
+
+/// Parse the log level of an inlet: trace, debug, info, warn or error
+pub fn parse_log_level(log_level: &str) -> ockam_core::Result<Level> {
+    Level::from_str(log_level).map_err(|_| {
+        ockam_core::Error::new(
+            Origin::Api,
+            Kind::Invalid,
+            format!(
+                "invalid log level {log_level}. The log level must be one of \
+                 trace, debug, info, warn or error"
+            ),
+        )
+    })
 }
 
 /// Request body to create an outlet
@@ -146,6 +697,51 @@ impl CreateOutlet {
     }
 }
 
+/// Request body to wait until an inlet is connected to its outlet
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct WaitForInlet {
+    /// The maximum duration to wait for the inlet to be connected
+    #[n(1)] pub timeout: Duration,
+}
+
+impl WaitForInlet {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+/// Request body to drain an inlet
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct DrainInlet {
+    /// The maximum duration to wait for the existing connections to finish
+    #[n(1)] pub timeout: Duration,
+}
+
+impl DrainInlet {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+/// Request body to delete the inlet bound to an address
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct DeleteInletByAddr {
+    /// The bind address of the inlet. When its IP is unspecified, the inlet is only selected by port
+    #[n(1)] pub addr: SocketAddr,
+}
+
+impl DeleteInletByAddr {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+}
+
 /// Response body when interacting with a portal endpoint
 #[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
 #[rustfmt::skip]
@@ -158,6 +754,21 @@ pub struct InletStatus {
     #[n(4)] pub payload: Option<String>,
     #[n(5)] pub outlet_route: String,
     #[n(6)] pub status: ConnectionStatus,
+    /// Duration after which the idle connections of the inlet are closed
+    #[n(7)] pub idle_timeout: Option<Duration>,
+    /// Labels used to group and filter the inlets
+    #[n(8)] pub labels: Option<BTreeMap<String, String>>,
+    /// Number of times the connection to the outlet was re-established since the inlet creation.
+    /// Optional so that the responses of older nodes can still be decoded
+    #[n(9)] pub reconnect_count: Option<u32>,
+    /// Size of the buffer used to relay the data of each client connection
+    #[n(10)] pub buffer_size: Option<usize>,
+    /// Number of tunnels connected to the outlet before any client connects
+    #[n(11)] pub prewarm: Option<u32>,
+    /// Number of prewarmed tunnels currently waiting for a client connection
+    #[n(12)] pub prewarmed: Option<u32>,
+    /// Address of the outlet the inlet was created for, before its normalization
+    #[n(13)] pub outlet_addr: Option<String>,
 }
 
 impl InletStatus {
@@ -169,6 +780,13 @@ impl InletStatus {
             payload: Some(reason.into()),
             outlet_route: "".into(),
             status: ConnectionStatus::Down,
+            idle_timeout: None,
+            labels: None,
+            reconnect_count: None,
+            buffer_size: None,
+            prewarm: None,
+            prewarmed: None,
+            outlet_addr: None,
         }
     }
 
@@ -187,8 +805,142 @@ impl InletStatus {
             payload: payload.into(),
             outlet_route: outlet_route.into(),
             status,
+            idle_timeout: None,
+            labels: None,
+            reconnect_count: None,
+            buffer_size: None,
+            prewarm: None,
+            prewarmed: None,
+            outlet_addr: None,
+        }
+    }
+
+    pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = if labels.is_empty() {
+            None
+        } else {
+            Some(labels)
+        };
+        self
+    }
+
+    pub fn with_reconnect_count(mut self, reconnect_count: u32) -> Self {
+        self.reconnect_count = Some(reconnect_count);
+        self
+    }
+
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Return true if the inlet has all the given labels
+    pub fn has_labels(&self, labels: &BTreeMap<String, String>) -> bool {
+        labels.iter().all(|(key, value)| {
+            self.labels
+                .as_ref()
+                .and_then(|l| l.get(key))
+                .map_or(false, |v| v == value)
+        })
+    }
+
+    pub fn with_prewarm(mut self, prewarm: Option<u32>, prewarmed: Option<u32>) -> Self {
+        self.prewarm = prewarm;
+        self.prewarmed = prewarmed;
+        self
+    }
+
+    pub fn with_outlet_addr(mut self, outlet_addr: &MultiAddr) -> Self {
+        self.outlet_addr = Some(outlet_addr.to_string());
+        self
+    }
+}
+
+/// Criteria used to select some inlets when listing them.
+/// An inlet is selected when it matches all the criteria which are set
+#[derive(Clone, Debug, Default)]
+pub struct InletFilter {
+    /// Pattern for the alias of the inlets, where `*` matches any sequence of characters
+    alias_pattern: Option<String>,
+    /// Labels that the inlets must all have
+    labels: BTreeMap<String, String>,
+    /// Connection status of the inlets
+    status: Option<ConnectionStatus>,
+    /// Range of ports the inlets must be bound to
+    ports: Option<RangeInclusive<u16>>,
+}
+
+impl InletFilter {
+    pub fn with_alias_pattern(mut self, alias_pattern: impl Into<String>) -> Self {
+        self.alias_pattern = Some(alias_pattern.into());
+        self
+    }
+
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn with_status(mut self, status: ConnectionStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_ports(mut self, ports: RangeInclusive<u16>) -> Self {
+        self.ports = Some(ports);
+        self
+    }
+
+    /// Return true if the inlet matches all the criteria of this filter
+    pub fn matches(&self, inlet: &InletStatus) -> bool {
+        if let Some(alias_pattern) = &self.alias_pattern {
+            if !matches_pattern(alias_pattern, &inlet.alias) {
+                return false;
+            }
+        }
+        if let Some(status) = &self.status {
+            if &inlet.status != status {
+                return false;
+            }
+        }
+        if let Some(ports) = &self.ports {
+            let port = inlet
+                .bind_addr
+                .rsplit_once(':')
+                .and_then(|(_, port)| port.parse::<u16>().ok());
+            if !port.map_or(false, |port| ports.contains(&port)) {
+                return false;
+            }
+        }
+        inlet.has_labels(&self.labels)
+    }
+}
+
+/// Return true if the value matches the pattern, where `*` matches any sequence of characters
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // the pattern always has a first part, possibly empty
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // there is no `*` in the pattern
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
         }
     }
+    rest.ends_with(last)
 }
 
 /// Response body when interacting with a portal endpoint
@@ -253,6 +1005,67 @@ impl InletList {
     pub fn new(list: Vec<InletStatus>) -> Self {
         Self { list }
     }
+
+    /// Group the inlets by the normalized route to their outlet.
+    /// The inlets of each group are sorted by alias
+    pub fn grouped_by_route(self) -> HashMap<String, Vec<InletStatus>> {
+        let mut groups: HashMap<String, Vec<InletStatus>> = HashMap::new();
+        for inlet in self.list {
+            groups
+                .entry(inlet.outlet_route.clone())
+                .or_default()
+                .push(inlet);
+        }
+        for inlets in groups.values_mut() {
+            inlets.sort_by(|a, b| a.alias.cmp(&b.alias));
+        }
+        groups
+    }
+
+    /// Return the inlet bound to an address.
+    ///
+    /// When the IP of the address is unspecified, the inlet is only selected by port.
+    /// In that case an error is returned if several inlets use that port, for example
+    /// an IPv4 inlet and an IPv6 one
+    pub fn find_by_bind_addr(&self, addr: &SocketAddr) -> ockam_core::Result<&InletStatus> {
+        let bound_addrs = self.list.iter().filter_map(|inlet| {
+            SocketAddr::from_str(&inlet.bind_addr)
+                .ok()
+                .map(|bind_addr| (inlet, bind_addr))
+        });
+        if let Some((inlet, _)) = bound_addrs.clone().find(|(_, bind_addr)| bind_addr == addr) {
+            return Ok(inlet);
+        }
+        let candidates: Vec<&InletStatus> = if addr.ip().is_unspecified() {
+            bound_addrs
+                .filter(|(_, bind_addr)| bind_addr.port() == addr.port())
+                .map(|(inlet, _)| inlet)
+                .collect()
+        } else {
+            vec![]
+        };
+        match candidates.as_slice() {
+            [inlet] => Ok(*inlet),
+            [] => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("No inlet bound to {addr} was found"),
+            )),
+            _ => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Conflict,
+                format!(
+                    "Several inlets are bound to the port {}: {}. Please specify the IP address of the inlet",
+                    addr.port(),
+                    candidates
+                        .iter()
+                        .map(|i| format!("{} ({})", i.alias, i.bind_addr))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )),
+        }
+    }
 }
 
 /// Response body when returning a list of Outlets
@@ -268,3 +1081,231 @@ impl OutletList {
         Self { list }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inlet_options_are_sent_with_a_create_inlet_request() {
+        let options = InletOptions::default()
+            .with_wait_for_outlet_timeout(Duration::from_secs(3))
+            .with_wait_connection(false)
+            .with_require_credential(true)
+            .with_proxy_protocol(Some(ProxyProtocolVersion::V2))
+            .with_listen_interface(Some("lo".to_string()))
+            .with_hold_on_reconnect(Some(Duration::from_secs(10)))
+            .with_idle_timeout(Some(Duration::from_secs(60)))
+            .with_keepalive(Some(
+                TcpKeepaliveOptions::new(Duration::from_secs(30)).with_retries(3),
+            ))
+            .with_egress_bind(Some(SocketAddr::from_str("127.0.0.1:0").unwrap()))
+            .with_allowed_sources(vec![IpCidr::from_str("10.0.0.0/8").unwrap()])
+            .with_labels(BTreeMap::from([("env".to_string(), "prod".to_string())]))
+            .with_socks5(true)
+            .with_buffer_size(Some(64 * 1024))
+            .with_log_level(Some(Level::DEBUG))
+            .with_prewarm(Some(2))
+            .with_max_connections(Some(10));
+
+        let mut request = CreateInlet::via_project(
+            "127.0.0.1:0".to_string(),
+            MultiAddr::from_str("/service/outlet").unwrap(),
+            route![],
+            route![],
+        );
+        request.set_options(&options);
+        let decoded: CreateInlet = minicbor::decode(&minicbor::to_vec(&request).unwrap()).unwrap();
+        assert_eq!(decoded.options().unwrap(), options);
+
+        // an invalid option is rejected when the request is received
+        request.set_buffer_size(Some(MAX_INLET_BUFFER_SIZE + 1));
+        assert!(request.options().is_err());
+    }
+
+    /// Request sent by a client created before the options were added
+    #[derive(Encode)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct OldCreateInlet {
+        #[n(1)] listen_addr: String,
+        #[n(2)] outlet_addr: MultiAddr,
+        #[n(5)] prefix_route: Route,
+        #[n(6)] suffix_route: Route,
+    }
+
+    /// Response sent by a node created before the reconnections were counted
+    #[derive(Encode)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct OldInletStatus {
+        #[n(1)] bind_addr: String,
+        #[n(2)] worker_addr: String,
+        #[n(3)] alias: String,
+        #[n(5)] outlet_route: String,
+        #[n(6)] status: ConnectionStatus,
+    }
+
+    #[test]
+    fn test_decode_the_messages_of_older_versions() {
+        let request = OldCreateInlet {
+            listen_addr: "127.0.0.1:0".to_string(),
+            outlet_addr: MultiAddr::from_str("/service/outlet").unwrap(),
+            prefix_route: route![],
+            suffix_route: route![],
+        };
+        let decoded: CreateInlet = minicbor::decode(&minicbor::to_vec(&request).unwrap()).unwrap();
+        assert!(decoded.wait_connection());
+        assert!(!decoded.require_credential());
+        assert!(!decoded.socks5());
+
+        let response = OldInletStatus {
+            bind_addr: "127.0.0.1:4000".to_string(),
+            worker_addr: "inlet".to_string(),
+            alias: "inlet".to_string(),
+            outlet_route: "/service/outlet".to_string(),
+            status: ConnectionStatus::Up,
+        };
+        let decoded: InletStatus = minicbor::decode(&minicbor::to_vec(&response).unwrap()).unwrap();
+        assert_eq!(decoded.reconnect_count, None);
+    }
+
+    #[test]
+    fn test_inlet_options_defaults_for_the_tcp_inlet() {
+        let options = InletOptions::default();
+        assert_eq!(options.buffer_size(), DEFAULT_INLET_BUFFER_SIZE);
+        assert_eq!(options.idle_timeout(), None);
+
+        // a zero idle timeout keeps the connections open
+        let options = options
+            .with_idle_timeout(Some(Duration::ZERO))
+            .with_buffer_size(Some(64 * 1024));
+        assert_eq!(options.idle_timeout(), None);
+        assert_eq!(options.buffer_size(), 64 * 1024);
+    }
+
+    fn inlet(alias: &str, port: u16, status: ConnectionStatus, env: &str) -> InletStatus {
+        InletStatus::new(
+            format!("127.0.0.1:{port}"),
+            format!("inlet_worker_{alias}"),
+            alias,
+            None,
+            "/service/outlet",
+            status,
+        )
+        .with_labels(BTreeMap::from([("env".to_string(), env.to_string())]))
+    }
+
+    fn inlets() -> Vec<InletStatus> {
+        vec![
+            inlet("db-prod", 5432, ConnectionStatus::Up, "prod"),
+            inlet("db-dev", 5433, ConnectionStatus::Down, "dev"),
+            inlet("web-prod", 8080, ConnectionStatus::Up, "prod"),
+            inlet("web-dev", 8081, ConnectionStatus::Pending, "dev"),
+        ]
+    }
+
+    fn aliases(filter: InletFilter) -> Vec<String> {
+        inlets()
+            .into_iter()
+            .filter(|i| filter.matches(i))
+            .map(|i| i.alias)
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_inlets_by_status() {
+        let connected = InletFilter::default().with_status(ConnectionStatus::Up);
+        assert_eq!(aliases(connected), vec!["db-prod", "web-prod"]);
+
+        let retrying = InletFilter::default().with_status(ConnectionStatus::Down);
+        assert_eq!(aliases(retrying), vec!["db-dev"]);
+
+        let connected_dev = InletFilter::default()
+            .with_status(ConnectionStatus::Up)
+            .with_labels(BTreeMap::from([("env".to_string(), "dev".to_string())]));
+        assert!(aliases(connected_dev).is_empty());
+    }
+
+    #[test]
+    fn test_filter_inlets_by_alias_labels_and_ports() {
+        assert_eq!(aliases(InletFilter::default()).len(), 4);
+        assert_eq!(
+            aliases(InletFilter::default().with_alias_pattern("db-*")),
+            vec!["db-prod", "db-dev"]
+        );
+        assert_eq!(
+            aliases(InletFilter::default().with_alias_pattern("*-prod")),
+            vec!["db-prod", "web-prod"]
+        );
+        assert_eq!(
+            aliases(InletFilter::default().with_alias_pattern("web-dev")),
+            vec!["web-dev"]
+        );
+        assert!(aliases(InletFilter::default().with_alias_pattern("web")).is_empty());
+        assert_eq!(
+            aliases(
+                InletFilter::default()
+                    .with_labels(BTreeMap::from([("env".to_string(), "dev".to_string())]))
+            ),
+            vec!["db-dev", "web-dev"]
+        );
+        assert_eq!(
+            aliases(InletFilter::default().with_ports(5000..=6000)),
+            vec!["db-prod", "db-dev"]
+        );
+    }
+
+    #[test]
+    fn test_find_inlet_by_bind_addr() {
+        let inlets = InletList::new(vec![
+            InletStatus::new(
+                "127.0.0.1:5432",
+                "w1",
+                "db-v4",
+                None,
+                "/service/outlet",
+                ConnectionStatus::Up,
+            ),
+            InletStatus::new(
+                "[::1]:5432",
+                "w2",
+                "db-v6",
+                None,
+                "/service/outlet",
+                ConnectionStatus::Up,
+            ),
+            InletStatus::new(
+                "127.0.0.1:8080",
+                "w3",
+                "web",
+                None,
+                "/service/outlet",
+                ConnectionStatus::Up,
+            ),
+        ]);
+
+        let find = |addr: &str| {
+            inlets
+                .find_by_bind_addr(&SocketAddr::from_str(addr).unwrap())
+                .map(|i| i.alias.clone())
+        };
+        assert_eq!(find("127.0.0.1:5432").unwrap(), "db-v4");
+        assert_eq!(find("[::1]:5432").unwrap(), "db-v6");
+        assert_eq!(find("0.0.0.0:8080").unwrap(), "web");
+
+        // the port alone is ambiguous when it is used by an IPv4 and an IPv6 inlet
+        assert_eq!(
+            find("0.0.0.0:5432").unwrap_err().code().kind,
+            Kind::Conflict
+        );
+        assert_eq!(
+            find("127.0.0.1:9000").unwrap_err().code().kind,
+            Kind::NotFound
+        );
+        assert_eq!(
+            find("10.0.0.1:8080").unwrap_err().code().kind,
+            Kind::NotFound
+        );
+    }
+}
@@ -4,10 +4,15 @@ use ockam::identity::{SecureChannel, SecureChannelListener};
 use ockam::remote::RemoteRelayInfo;
 use ockam_core::compat::collections::BTreeMap;
 use ockam_core::{Address, Route};
+use ockam_multiaddr::MultiAddr;
 use ockam_node::compat::asynchronous::RwLock;
 use std::borrow::Borrow;
 use std::fmt::Display;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
 
 #[derive(Default)]
 pub(crate) struct SecureChannelRegistry {
@@ -155,14 +160,31 @@ impl KafkaServiceInfo {
 pub(crate) struct InletInfo {
     pub(crate) bind_addr: String,
     pub(crate) worker_addr: Address,
+    /// Address of the outlet the inlet was created for
+    pub(crate) outlet_addr: MultiAddr,
     pub(crate) outlet_route: Route,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) labels: BTreeMap<String, String>,
+    /// Number of times the connection to the outlet was re-established since the inlet creation
+    pub(crate) reconnect_count: Arc<AtomicU32>,
+    /// Size of the buffer used to relay the data of each client connection
+    pub(crate) buffer_size: usize,
+    /// Number of tunnels prewarmed by the inlet
+    pub(crate) prewarm: Option<u32>,
+    /// Notified when a first client connects to an inlet created without waiting
+    /// for the connection to its outlet
+    pub(crate) first_client: Arc<Notify>,
 }
 
 impl InletInfo {
     pub(crate) fn new(
         bind_addr: &str,
         worker_addr: Option<&Address>,
+        outlet_addr: &MultiAddr,
         outlet_route: &Route,
+        idle_timeout: Option<Duration>,
+        labels: BTreeMap<String, String>,
+        buffer_size: usize,
     ) -> Self {
         let worker_addr = match worker_addr {
             Some(addr) => addr.clone(),
@@ -171,9 +193,32 @@ impl InletInfo {
         Self {
             bind_addr: bind_addr.to_owned(),
             worker_addr,
+            outlet_addr: outlet_addr.clone(),
             outlet_route: outlet_route.to_owned(),
+            idle_timeout,
+            labels,
+            reconnect_count: Arc::new(AtomicU32::new(0)),
+            buffer_size,
+            prewarm: None,
+            first_client: Arc::new(Notify::new()),
         }
     }
+
+    /// Set the number of prewarmed tunnels of the inlet
+    pub(crate) fn with_prewarm(mut self, prewarm: Option<u32>) -> Self {
+        self.prewarm = prewarm;
+        self
+    }
+
+    /// Notify `first_client` when a first client connects to the inlet
+    pub(crate) fn with_first_client(mut self, first_client: Arc<Notify>) -> Self {
+        self.first_client = first_client;
+        self
+    }
+
+    pub(crate) fn reconnect_count(&self) -> u32 {
+        self.reconnect_count.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Clone)]
@@ -15,7 +15,8 @@ use ockam::identity::{Credentials, CredentialsServer, Identities};
 use ockam::identity::{CredentialsServerModule, IdentityAttributesRepository};
 use ockam::identity::{Identifier, SecureChannels};
 use ockam::{
-    Address, Context, RelayService, RelayServiceOptions, Result, Routed, TcpTransport, Worker,
+    Address, Context, RelayService, RelayServiceOptions, Result, Route, Routed, TcpTransport,
+    Worker,
 };
 use ockam_abac::expr::{eq, ident, str};
 use ockam_abac::{Action, Env, Expr, Resource};
@@ -25,6 +26,9 @@ use ockam_core::flow_control::FlowControlId;
 use ockam_core::AllowAll;
 use ockam_core::IncomingAccessControl;
 use ockam_multiaddr::MultiAddr;
+use ockam_transport_tcp::TcpKeepaliveOptions;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
 
 use crate::bootstrapped_identities_store::PreTrustedIdentities;
 use crate::cli_state::CliState;
@@ -40,13 +44,13 @@ use crate::nodes::models::portal::{OutletList, OutletStatus};
 use crate::nodes::models::transport::{TransportMode, TransportType};
 use crate::nodes::models::workers::{WorkerList, WorkerStatus};
 use crate::nodes::registry::KafkaServiceKind;
+use crate::nodes::service::credentials::CredentialRefreshEvent;
 use crate::nodes::service::default_address::DefaultAddress;
 use crate::nodes::{InMemoryNode, NODEMANAGER_ADDR};
 use crate::session::MedicHandle;
 
 use super::registry::Registry;
 
-pub mod actions;
 pub(crate) mod background_node;
 pub(crate) mod credentials;
 pub mod default_address;
@@ -58,12 +62,15 @@ mod policy;
 pub mod portals;
 mod projects;
 pub mod relay;
-pub mod resources;
 mod secure_channel;
+pub mod target_authorization;
 mod transport;
 
 const TARGET: &str = "ockam_api::nodemanager::service";
 
+/// Default amount of time before its expiration when the node credential gets refreshed
+pub const DEFAULT_CREDENTIAL_REFRESH_SKEW: Duration = Duration::from_secs(5 * 60);
+
 pub(crate) type Alias = String;
 
 /// Generate a new alias for some user created extension
@@ -98,6 +105,7 @@ pub struct NodeManager {
     trust_context: Option<TrustContext>,
     pub(crate) registry: Registry,
     pub(crate) medic_handle: MedicHandle,
+    credential_refresher: Option<JoinHandle<()>>,
 }
 
 impl NodeManager {
@@ -165,6 +173,13 @@ impl NodeManager {
     }
 }
 
+impl Drop for NodeManager {
+    fn drop(&mut self) {
+        // the credential refresh must not outlive the node manager
+        self.stop_credential_refresh();
+    }
+}
+
 impl NodeManager {
     pub async fn create_authority_client(
         &self,
@@ -330,11 +345,36 @@ impl NodeManagerTransportOptions {
 
 pub struct NodeManagerTrustOptions {
     trust_context: Option<NamedTrustContext>,
+    credential_refresh: bool,
+    credential_refresh_skew: Duration,
+    credential_refresh_events: Option<UnboundedSender<CredentialRefreshEvent>>,
 }
 
 impl NodeManagerTrustOptions {
     pub fn new(trust_context: Option<NamedTrustContext>) -> Self {
-        Self { trust_context }
+        Self {
+            trust_context,
+            credential_refresh: false,
+            credential_refresh_skew: DEFAULT_CREDENTIAL_REFRESH_SKEW,
+            credential_refresh_events: None,
+        }
+    }
+
+    /// Refresh the node credential in the background, `skew` before it expires
+    pub fn with_credential_refresh(mut self, enabled: bool, skew: Duration) -> Self {
+        self.credential_refresh = enabled;
+        self.credential_refresh_skew = skew;
+        self
+    }
+
+    /// Send the events of the background credential refresh to this channel,
+    /// for example to tell a user that their credential is about to expire
+    pub fn with_credential_refresh_events(
+        mut self,
+        events: UnboundedSender<CredentialRefreshEvent>,
+    ) -> Self {
+        self.credential_refresh_events = Some(events);
+        self
     }
 }
 
@@ -393,11 +433,22 @@ impl NodeManager {
             trust_context,
             registry: Default::default(),
             medic_handle,
+            credential_refresher: None,
         };
 
         debug!("retrieve the node identifier");
         s.initialize_services(ctx, general_options.start_default_services)
             .await?;
+
+        if trust_options.credential_refresh {
+            debug!("start the credential refresh");
+            s.start_credential_refresh(
+                ctx,
+                trust_options.credential_refresh_skew,
+                trust_options.credential_refresh_events,
+            )
+            .await?;
+        }
         info!("created a node manager for the node: {}", s.node_name);
 
         Ok(s)
@@ -468,6 +519,7 @@ impl NodeManager {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn make_connection(
         &self,
         ctx: Arc<Context>,
@@ -476,14 +528,26 @@ impl NodeManager {
         authorized: Option<Identifier>,
         credential: Option<CredentialAndPurposeKey>,
         timeout: Option<Duration>,
+        keepalive: Option<TcpKeepaliveOptions>,
+        egress_bind: Option<SocketAddr>,
     ) -> Result<Connection> {
         let authorized = authorized.map(|authorized| vec![authorized]);
-        self.connect(ctx, addr, identifier, authorized, credential, timeout)
-            .await
+        self.connect(
+            ctx,
+            addr,
+            identifier,
+            authorized,
+            credential,
+            timeout,
+            keepalive,
+            egress_bind,
+        )
+        .await
     }
 
     /// Resolve project ID (if any), create secure channel (if needed) and create a tcp connection
     /// Returns [`Connection`]
+    #[allow(clippy::too_many_arguments)]
     async fn connect(
         &self,
         ctx: Arc<Context>,
@@ -492,16 +556,28 @@ impl NodeManager {
         authorized: Option<Vec<Identifier>>,
         credential: Option<CredentialAndPurposeKey>,
         timeout: Option<Duration>,
+        keepalive: Option<TcpKeepaliveOptions>,
+        egress_bind: Option<SocketAddr>,
     ) -> Result<Connection> {
         debug!(?timeout, "connecting to {}", &addr);
         let connection = ConnectionBuilder::new(addr.clone())
             .instantiate(
                 ctx.clone(),
                 self,
-                ProjectInstantiator::new(identifier.clone(), timeout, credential.clone()),
+                ProjectInstantiator::new(
+                    identifier.clone(),
+                    timeout,
+                    credential.clone(),
+                    keepalive,
+                    egress_bind,
+                ),
             )
             .await?
-            .instantiate(ctx.clone(), self, PlainTcpInstantiator::new())
+            .instantiate(
+                ctx.clone(),
+                self,
+                PlainTcpInstantiator::new(keepalive, egress_bind),
+            )
             .await?
             .instantiate(
                 ctx.clone(),
@@ -530,6 +606,7 @@ impl NodeManagerWorker {
         ctx: &mut Context,
         req: &RequestHeader,
         dec: &mut Decoder<'_>,
+        return_route: Route,
     ) -> Result<Vec<u8>> {
         debug! {
             target: TARGET,
@@ -592,6 +669,9 @@ impl NodeManagerWorker {
             (Post, ["node", "credentials", "actions", "present"]) => {
                 encode_response(self.present_credential(req, dec, ctx).await)?
             }
+            (Post, ["node", "credentials", "actions", "export"]) => {
+                encode_response(self.export_credential(req, dec, ctx).await)?
+            }
 
             // ==*== Secure channels ==*==
             (Get, ["node", "secure_channel"]) => self.list_secure_channels(req).await.to_vec()?,
@@ -683,6 +763,13 @@ impl NodeManagerWorker {
             // ==*== Inlets & Outlets ==*==
             (Get, ["node", "inlet"]) => self.get_inlets(req).await.to_vec()?,
             (Get, ["node", "inlet", alias]) => encode_response(self.show_inlet(req, alias).await)?,
+            (Get, ["node", "inlet", alias, "watch"]) => {
+                encode_response(self.watch_inlet(ctx, req, alias, return_route).await)?
+            }
+            (Get, ["node", "inlet", alias, "wait"]) => encode_response(
+                self.wait_for_inlet(ctx, req, alias, dec.decode()?, return_route)
+                    .await,
+            )?,
             (Get, ["node", "outlet"]) => self.get_outlets(req).await.to_vec()?,
             (Get, ["node", "outlet", alias]) => {
                 encode_response(self.show_outlet(req, alias).await)?
@@ -694,9 +781,15 @@ impl NodeManagerWorker {
             (Delete, ["node", "outlet", alias]) => {
                 encode_response(self.delete_outlet(req, alias).await)?
             }
+            (Delete, ["node", "inlet"]) => {
+                encode_response(self.delete_inlet_by_addr(req, dec.decode()?).await)?
+            }
             (Delete, ["node", "inlet", alias]) => {
                 encode_response(self.delete_inlet(req, alias).await)?
             }
+            (Post, ["node", "inlet", alias, "drain"]) => {
+                encode_response(self.drain_inlet(req, alias, dec.decode()?).await)?
+            }
             (Delete, ["node", "portal"]) => todo!(),
 
             // ==*== Flow Controls ==*==
@@ -765,7 +858,10 @@ impl Worker for NodeManagerWorker {
             }
         };
 
-        let r = match self.handle_request(ctx, &req, &mut dec).await {
+        let r = match self
+            .handle_request(ctx, &req, &mut dec, msg.return_route())
+            .await
+        {
             Ok(r) => r,
             Err(err) => {
                 error! {
@@ -1,3 +0,0 @@
-use ockam_abac::Action;
-
-pub const HANDLE_MESSAGE: Action = Action::assert_inline("handle_message");
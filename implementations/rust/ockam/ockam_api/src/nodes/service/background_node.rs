@@ -1,18 +1,22 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use miette::IntoDiagnostic;
 use minicbor::{Decode, Encode};
 
-use ockam_core::api::{Reply, Request};
-use ockam_core::{AsyncTryClone, Route};
+use ockam_core::api::{Reply, Request, Response};
+use ockam_core::{Address, AllowAll, AllowOnwardAddress, AsyncTryClone, Mailbox, Mailboxes, Route};
 use ockam_node::api::Client;
-use ockam_node::Context;
+use ockam_node::{Context, MessageReceiveOptions};
 use ockam_transport_tcp::{TcpConnectionOptions, TcpTransport};
 
 use crate::cli_state::CliState;
+use crate::nodes::models::base::NodeStatus;
 use crate::nodes::NODEMANAGER_ADDR;
 
+/// Maximum time to wait for a node to answer a ping when no timeout is set
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// This struct represents a node that has been started
 /// on the same machine with a given node name
 ///
@@ -94,6 +98,18 @@ impl BackgroundNode {
         &self.cli_state
     }
 
+    /// Check that the node is responsive by requesting its status,
+    /// and return the round-trip time of the request.
+    /// The request fails after the default timeout, or after [`PING_TIMEOUT`] if none is set
+    pub async fn ping(&self, ctx: &Context) -> miette::Result<Duration> {
+        let timeout = self.timeout.unwrap_or(PING_TIMEOUT);
+        let started_at = Instant::now();
+        let _: NodeStatus = self
+            .ask_with_timeout(ctx, Request::get("/node"), timeout)
+            .await?;
+        Ok(started_at.elapsed())
+    }
+
     /// Send a request and expect a decodable response
     pub async fn ask<T, R>(&self, ctx: &Context, req: Request<T>) -> miette::Result<R>
     where
@@ -168,6 +184,42 @@ impl BackgroundNode {
         client.tell(ctx, req).await.into_diagnostic()
     }
 
+    /// Send a request subscribing to some updates on the node.
+    /// The returned subscription receives all the replies sent by the node for that request
+    pub async fn subscribe<T>(&self, ctx: &Context, req: Request<T>) -> miette::Result<Subscription>
+    where
+        T: Encode<()>,
+    {
+        let route = self.create_route().await?;
+        let next = route.next().into_diagnostic()?.clone();
+        let address = Address::random_tagged("BackgroundNode.subscription");
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(
+                address.clone(),
+                Arc::new(AllowAll),
+                Arc::new(AllowOnwardAddress(next.clone())),
+            ),
+            vec![],
+        );
+        // the replies are received via the TCP connection to the node
+        if let Some(flow_control_id) = ctx
+            .flow_controls()
+            .find_flow_control_with_producer_address(&next)
+            .map(|x| x.flow_control_id().clone())
+        {
+            ctx.flow_controls().add_consumer(address, &flow_control_id);
+        }
+        let subscriber = ctx
+            .new_detached_with_mailboxes(mailboxes)
+            .await
+            .into_diagnostic()?;
+
+        let mut buf = Vec::new();
+        req.encode(&mut buf).into_diagnostic()?;
+        subscriber.send(route, buf).await.into_diagnostic()?;
+        Ok(Subscription::new(subscriber))
+    }
+
     /// Make a route to the node and connect using TCP
     async fn create_route(&self) -> miette::Result<Route> {
         let mut route = self.to.clone();
@@ -209,3 +261,62 @@ impl BackgroundNode {
         Ok(Client::new(&route, timeout))
     }
 }
+
+/// A subscription receives all the replies sent by a node for a given request
+pub struct Subscription {
+    ctx: Context,
+}
+
+impl Subscription {
+    pub(crate) fn new(ctx: Context) -> Self {
+        Self { ctx }
+    }
+
+    /// Wait for the next reply sent by the node
+    pub async fn next<R>(&mut self) -> miette::Result<Reply<R>>
+    where
+        R: for<'b> Decode<'b, ()>,
+    {
+        let message = self
+            .ctx
+            .receive_extended::<Vec<u8>>(MessageReceiveOptions::new().without_timeout())
+            .await
+            .into_diagnostic()?;
+        Response::parse_response_reply::<R>(message.body().as_slice()).into_diagnostic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ockam_core::Result;
+    use ockam_transport_tcp::TcpListenerOptions;
+
+    use super::*;
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn ping_a_running_node(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_name = handler.node_manager.node_name();
+
+        // expose the node manager API over TCP, as a background node does
+        let listener = handler
+            .tcp
+            .listen("127.0.0.1:0", TcpListenerOptions::new())
+            .await?;
+        context
+            .flow_controls()
+            .add_consumer(NODEMANAGER_ADDR, listener.flow_control_id());
+        handler
+            .cli_state
+            .set_tcp_listener_address(&node_name, listener.socket_address().to_string())
+            .await?;
+
+        let node = BackgroundNode::new(&handler.tcp, &handler.cli_state, &node_name)
+            .await
+            .unwrap();
+        let latency = node.ping(context).await.unwrap();
+        assert!(latency > Duration::ZERO);
+
+        context.stop().await
+    }
+}
@@ -1,32 +1,73 @@
 use std::str::FromStr;
+use std::time::Duration;
 
 use either::Either;
 use miette::IntoDiagnostic;
 use minicbor::Decoder;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::sleep;
 
-use ockam::identity::models::CredentialAndPurposeKey;
-use ockam::Result;
-use ockam_core::api::{Error, Request, RequestHeader, Response};
-use ockam_core::async_trait;
+use ockam::identity::models::{CredentialAndPurposeKey, TimestampInSeconds};
+use ockam::identity::utils::now;
+use ockam::identity::{Identifier, TrustContext};
+use ockam::{Address, Result, Route};
+use ockam_core::api::{Error, Reply, Request, RequestHeader, Response};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, AllowAll, DenyAll};
 use ockam_multiaddr::MultiAddr;
 use ockam_node::Context;
 
 use crate::cloud::AuthorityNode;
 use crate::error::ApiError;
 use crate::local_multiaddr_to_route;
-use crate::nodes::models::credentials::{GetCredentialRequest, PresentCredentialRequest};
+use crate::nodes::models::credentials::{
+    CredentialPresentationReceipt, ExportCredentialRequest, ExportedCredential,
+    GetCredentialRequest, PresentCredentialOptions, PresentCredentialRequest,
+};
 use crate::nodes::BackgroundNode;
 
-use super::NodeManagerWorker;
+use super::{NodeManager, NodeManagerWorker};
+
+/// Minimum amount of time between two checks of the credential expiration
+const CREDENTIAL_REFRESH_MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Amount of time to wait before retrying a failed credential refresh.
+/// That time is doubled after each failure, up to [`CREDENTIAL_REFRESH_MAX_RETRY_INTERVAL`]
+const CREDENTIAL_REFRESH_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum amount of time to wait before retrying a failed credential refresh
+const CREDENTIAL_REFRESH_MAX_RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Default amount of time to wait for the other node to complete a mutual credential presentation
+pub const DEFAULT_CREDENTIAL_PRESENTATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Amount of time before its expiration when a credential which can not be refreshed
+/// is reported as expiring soon
+pub const CREDENTIAL_EXPIRATION_WARNING_THRESHOLD: Duration = Duration::from_secs(2 * 60);
+
+/// Event sent by the background task refreshing the node credential
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CredentialRefreshEvent {
+    /// The credential has been refreshed, the new credential expires at the given time
+    Refreshed(TimestampInSeconds),
+    /// The credential expires at the given time, in less than
+    /// [`CREDENTIAL_EXPIRATION_WARNING_THRESHOLD`], and could not be refreshed
+    ExpiringSoon(TimestampInSeconds),
+}
 
 #[async_trait]
 pub trait Credentials {
+    /// Make sure that the identity has a credential.
+    /// If `refresh` is true, a new credential is retrieved even if one is already cached
     async fn authenticate(
         &self,
         ctx: &Context,
         identity_name: Option<String>,
+        refresh: bool,
     ) -> miette::Result<()> {
-        let _ = self.get_credential(ctx, false, identity_name).await?;
+        let _ = self
+            .get_credential(ctx, refresh, identity_name, None)
+            .await?;
         Ok(())
     }
 
@@ -35,14 +76,26 @@ pub trait Credentials {
         ctx: &Context,
         overwrite: bool,
         identity_name: Option<String>,
+        authority: Option<MultiAddr>,
     ) -> miette::Result<CredentialAndPurposeKey>;
 
+    /// Return the credential of an identity, encoded as CBOR bytes, so that it can be
+    /// handed to another tool. The bytes only contain the credential and the attestation
+    /// of the issuer's purpose key, not any secret
+    async fn export_credential(
+        &self,
+        ctx: &Context,
+        identity_name: Option<String>,
+    ) -> miette::Result<Vec<u8>>;
+
+    /// Present the node credential to another node and return a receipt stating
+    /// if the other node accepted it.
     async fn present_credential(
         &self,
         ctx: &Context,
         to: &MultiAddr,
-        oneway: bool,
-    ) -> miette::Result<()>;
+        options: PresentCredentialOptions,
+    ) -> miette::Result<CredentialPresentationReceipt>;
 }
 
 #[async_trait]
@@ -52,8 +105,9 @@ impl Credentials for AuthorityNode {
         ctx: &Context,
         overwrite: bool,
         identity_name: Option<String>,
+        authority: Option<MultiAddr>,
     ) -> miette::Result<CredentialAndPurposeKey> {
-        let body = GetCredentialRequest::new(overwrite, identity_name);
+        let body = GetCredentialRequest::new(overwrite, identity_name, authority);
         let req = Request::post("/node/credentials/actions/get").body(body);
         self.secure_client
             .ask(ctx, "", req)
@@ -63,16 +117,33 @@ impl Credentials for AuthorityNode {
             .into_diagnostic()
     }
 
+    async fn export_credential(
+        &self,
+        ctx: &Context,
+        identity_name: Option<String>,
+    ) -> miette::Result<Vec<u8>> {
+        let body = ExportCredentialRequest::new(identity_name);
+        let req = Request::post("/node/credentials/actions/export").body(body);
+        let exported: ExportedCredential = self
+            .secure_client
+            .ask(ctx, "", req)
+            .await
+            .into_diagnostic()?
+            .success()
+            .into_diagnostic()?;
+        Ok(exported.bytes)
+    }
+
     async fn present_credential(
         &self,
         ctx: &Context,
         to: &MultiAddr,
-        oneway: bool,
-    ) -> miette::Result<()> {
-        let body = PresentCredentialRequest::new(to, oneway);
+        options: PresentCredentialOptions,
+    ) -> miette::Result<CredentialPresentationReceipt> {
+        let body = PresentCredentialRequest::new(to, options);
         let req = Request::post("/node/credentials/actions/present").body(body);
         self.secure_client
-            .tell(ctx, "", req)
+            .ask(ctx, "", req)
             .await
             .into_diagnostic()?
             .success()
@@ -87,8 +158,9 @@ impl Credentials for BackgroundNode {
         ctx: &Context,
         overwrite: bool,
         identity_name: Option<String>,
+        authority: Option<MultiAddr>,
     ) -> miette::Result<CredentialAndPurposeKey> {
-        let body = GetCredentialRequest::new(overwrite, identity_name);
+        let body = GetCredentialRequest::new(overwrite, identity_name, authority);
         self.ask(
             ctx,
             Request::post("/node/credentials/actions/get").body(body),
@@ -96,14 +168,29 @@ impl Credentials for BackgroundNode {
         .await
     }
 
+    async fn export_credential(
+        &self,
+        ctx: &Context,
+        identity_name: Option<String>,
+    ) -> miette::Result<Vec<u8>> {
+        let body = ExportCredentialRequest::new(identity_name);
+        let exported: ExportedCredential = self
+            .ask(
+                ctx,
+                Request::post("/node/credentials/actions/export").body(body),
+            )
+            .await?;
+        Ok(exported.bytes)
+    }
+
     async fn present_credential(
         &self,
         ctx: &Context,
         to: &MultiAddr,
-        oneway: bool,
-    ) -> miette::Result<()> {
-        let body = PresentCredentialRequest::new(to, oneway);
-        self.tell(
+        options: PresentCredentialOptions,
+    ) -> miette::Result<CredentialPresentationReceipt> {
+        let body = PresentCredentialRequest::new(to, options);
+        self.ask(
             ctx,
             Request::post("/node/credentials/actions/present").body(body),
         )
@@ -111,6 +198,282 @@ impl Credentials for BackgroundNode {
     }
 }
 
+impl NodeManager {
+    /// Start a background task refreshing the node credential
+    /// `skew` before the current credential expires
+    /// The refresh events are sent to `events` if it is set
+    pub(super) async fn start_credential_refresh(
+        &mut self,
+        ctx: &Context,
+        skew: Duration,
+        events: Option<UnboundedSender<CredentialRefreshEvent>>,
+    ) -> Result<()> {
+        let trust_context = self.trust_context()?.clone();
+        let ctx = ctx
+            .new_detached(
+                Address::random_tagged("Credential.refresher"),
+                DenyAll,
+                AllowAll,
+            )
+            .await?;
+        let refresher = tokio::spawn(refresh_credential_periodically(
+            ctx,
+            trust_context,
+            self.identifier(),
+            skew,
+            events,
+        ));
+        self.credential_refresher = Some(refresher);
+        Ok(())
+    }
+
+    /// Stop the background task refreshing the node credential, if it has been started
+    pub(crate) fn stop_credential_refresh(&self) {
+        if let Some(refresher) = &self.credential_refresher {
+            debug!("stop the credential refresh");
+            refresher.abort();
+        }
+    }
+}
+
+impl NodeManager {
+    /// Present the node credential to another node and return a receipt stating
+    /// if the other node accepted it.
+    ///
+    /// If the options contain the address of an existing secure channel of this node, the
+    /// credential is presented over that channel and `to` is the route to the credentials service
+    /// at the other end of the channel.
+    ///
+    /// A mutual presentation fails with a `Kind::Timeout` error if the other node doesn't
+    /// present its credential back within the timeout of the options,
+    /// [`DEFAULT_CREDENTIAL_PRESENTATION_TIMEOUT`] by default.
+    ///
+    /// If an expected peer is given, the route must start with a secure channel established
+    /// with that peer, otherwise the credential is not presented.
+    ///
+    /// For a dry run, the credential is retrieved and the route is parsed, but nothing is sent
+    /// to the other node. The receipt then contains the expiration time of the credential
+    /// and the route which would have been used
+    pub async fn present_credential(
+        &self,
+        ctx: &Context,
+        to: &MultiAddr,
+        options: PresentCredentialOptions,
+    ) -> Result<CredentialPresentationReceipt> {
+        // TODO: Replace with self.connect?
+        let mut route = local_multiaddr_to_route(to)?;
+        if let Some(secure_channel) = &options.secure_channel {
+            let Some(channel) = self
+                .registry
+                .secure_channels
+                .get_by_addr(secure_channel)
+                .await
+            else {
+                return Err(ockam_core::Error::new(
+                    Origin::Api,
+                    Kind::NotFound,
+                    format!("there is no secure channel at {secure_channel}"),
+                ));
+            };
+            route
+                .modify()
+                .prepend(channel.sc().encryptor_address().clone());
+        }
+        if let Some(expected_peer) = &options.expected_peer {
+            self.check_peer(&route, expected_peer)?;
+        }
+
+        let identifier = self.identifier();
+        let credential = self
+            .get_credential(ctx, &identifier, None)
+            .await?
+            .unwrap_or_else(|| panic!("A credential must be retrieved for {}", identifier));
+
+        if options.dry_run {
+            let expires_at = credential.get_credential_data()?.expires_at;
+            if expires_at <= now()? {
+                return Err(ockam_core::Error::new(
+                    Origin::Api,
+                    Kind::Invalid,
+                    format!("the credential of {identifier} expired at {}", expires_at.0),
+                ));
+            }
+            return Ok(CredentialPresentationReceipt::dry_run(expires_at, &route));
+        }
+
+        let receipt = if options.oneway {
+            // relay the acceptance or the rejection of the other node
+            let reply = self
+                .credentials_service()
+                .present_credential_with_reply(ctx, route, credential, options.context)
+                .await?;
+            match reply {
+                Reply::Successful(()) => CredentialPresentationReceipt::accepted(),
+                Reply::Failed(e, _) => CredentialPresentationReceipt::rejected(
+                    e.message().unwrap_or("the credential was rejected"),
+                ),
+            }
+        } else {
+            let timeout = options
+                .timeout
+                .unwrap_or(DEFAULT_CREDENTIAL_PRESENTATION_TIMEOUT);
+            let authorities = self.trust_context()?.authorities();
+            let presentation = self.credentials_service().present_credential_mutual(
+                ctx,
+                route,
+                &authorities,
+                credential,
+                options.context,
+            );
+            tokio::time::timeout(timeout, presentation)
+                .await
+                .map_err(|_| {
+                    ockam_core::Error::new(
+                        Origin::Api,
+                        Kind::Timeout,
+                        format!("{to} did not present its credential back within {timeout:?}"),
+                    )
+                })??;
+            CredentialPresentationReceipt::accepted()
+        };
+        Ok(receipt)
+    }
+
+    /// Return an error unless the route starts with a secure channel of this node
+    /// established with the expected peer
+    fn check_peer(&self, route: &Route, expected_peer: &Identifier) -> Result<()> {
+        let peer = route.next().ok().and_then(|address| {
+            self.secure_channels
+                .secure_channel_registry()
+                .get_channel_by_encryptor_address(address)
+                .map(|channel| channel.their_id().clone())
+        });
+        match peer {
+            Some(peer) if peer == *expected_peer => Ok(()),
+            Some(peer) => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!("the credential was not presented to {peer}, expected {expected_peer}"),
+            )),
+            None => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!(
+                    "the credential was not presented: the route must start with a secure channel \
+                     to authenticate {expected_peer}"
+                ),
+            )),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Return the credential of an identity encoded as CBOR bytes.
+    ///
+    /// A credential is exported with the attestation of the purpose key used to issue it, so
+    /// that it can be verified. The attestation only contains the public key of the issuer's
+    /// purpose key, the secret key never leaves the vault of the issuer
+    pub async fn export_credential(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+    ) -> Result<Vec<u8>> {
+        match self.get_credential(ctx, identifier, None).await? {
+            Some(credential) => credential.encode_as_cbor_bytes(),
+            None => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("no credential found for {identifier}"),
+            )),
+        }
+    }
+}
+
+/// Refresh the credential of an identity, `skew` before it expires.
+/// Nothing is refreshed as long as no credential has been retrieved.
+/// Each refresh is reported to `events`, as well as a credential which can not be refreshed
+/// and is about to expire. That credential is only reported once
+async fn refresh_credential_periodically(
+    ctx: Context,
+    trust_context: TrustContext,
+    identifier: Identifier,
+    skew: Duration,
+    events: Option<UnboundedSender<CredentialRefreshEvent>>,
+) {
+    let send = |event: CredentialRefreshEvent| {
+        if let Some(events) = &events {
+            // the receiver may have been dropped, in which case the events are not needed anymore
+            let _ = events.send(event);
+        }
+    };
+    // expiration of the last credential reported as expiring soon
+    let mut reported_expiration = None;
+    let mut retry_interval = CREDENTIAL_REFRESH_RETRY_INTERVAL;
+    loop {
+        let Some(expires_at) = trust_context.credential_expiration() else {
+            sleep(CREDENTIAL_REFRESH_MIN_INTERVAL).await;
+            continue;
+        };
+        match now() {
+            Ok(now) => sleep(time_before_refresh(expires_at, skew, now)).await,
+            Err(e) => {
+                warn!(%identifier, "stop refreshing the credential: {e}");
+                break;
+            }
+        }
+        let result = trust_context.refresh_credential(&ctx, &identifier).await;
+        let new_expiration = trust_context.credential_expiration();
+        match result {
+            // a refresh returning the same credential is retried later, like a failed refresh
+            Ok(_) if new_expiration > Some(expires_at) => {
+                debug!(%identifier, "the credential has been refreshed");
+                retry_interval = CREDENTIAL_REFRESH_RETRY_INTERVAL;
+                if let Some(expires_at) = new_expiration {
+                    send(CredentialRefreshEvent::Refreshed(expires_at));
+                }
+            }
+            result => {
+                match result {
+                    Ok(_) => {
+                        warn!(%identifier, "the refreshed credential expires at the same time")
+                    }
+                    Err(e) => warn!(%identifier, "the credential could not be refreshed: {e}"),
+                }
+                let is_expiring_soon = now()
+                    .map(|now| is_expiring_soon(expires_at, now))
+                    .unwrap_or(false);
+                if is_expiring_soon && reported_expiration != Some(expires_at) {
+                    reported_expiration = Some(expires_at);
+                    send(CredentialRefreshEvent::ExpiringSoon(expires_at));
+                }
+                sleep(retry_interval).await;
+                retry_interval = (retry_interval * 2).min(CREDENTIAL_REFRESH_MAX_RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Return true if a credential expiring at `expires_at` expires
+/// within [`CREDENTIAL_EXPIRATION_WARNING_THRESHOLD`]
+fn is_expiring_soon(expires_at: TimestampInSeconds, now: TimestampInSeconds) -> bool {
+    expires_at.0 <= now.0 + CREDENTIAL_EXPIRATION_WARNING_THRESHOLD.as_secs()
+}
+
+/// Return the amount of time to wait before refreshing a credential expiring at `expires_at`.
+///
+/// The credential is refreshed `skew` before its expiration, but not before half of its
+/// remaining lifetime has elapsed, so that a skew longer than the credential lifetime
+/// doesn't make the credential be refreshed continuously
+fn time_before_refresh(
+    expires_at: TimestampInSeconds,
+    skew: Duration,
+    now: TimestampInSeconds,
+) -> Duration {
+    let remaining = expires_at.0.saturating_sub(now.0);
+    let wait = remaining.saturating_sub(skew.as_secs()).max(remaining / 2);
+    Duration::from_secs(wait).max(CREDENTIAL_REFRESH_MIN_INTERVAL)
+}
+
 impl NodeManagerWorker {
     pub(super) async fn get_credential(
         &mut self,
@@ -125,11 +488,23 @@ impl NodeManagerWorker {
             .get_identifier_by_name(request.identity_name)
             .await?;
 
-        match self
-            .node_manager
-            .get_credential(ctx, &identifier, None)
-            .await
-        {
+        let credential = match &request.authority {
+            Some(authority) => {
+                self.node_manager
+                    .get_credential_from_authority(ctx, &identifier, authority)
+                    .await
+            }
+            None if request.is_overwrite() => {
+                self.node_manager.refresh_credential(ctx, &identifier).await
+            }
+            None => {
+                self.node_manager
+                    .get_credential(ctx, &identifier, None)
+                    .await
+            }
+        };
+
+        match credential {
             Ok(Some(c)) => Ok(Either::Right(Response::ok(req).body(c))),
             Ok(None) => Ok(Either::Left(Response::not_found(
                 req,
@@ -145,48 +520,545 @@ impl NodeManagerWorker {
         }
     }
 
+    pub(super) async fn export_credential(
+        &self,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+        ctx: &Context,
+    ) -> Result<Response<ExportedCredential>, Response<Error>> {
+        let request: ExportCredentialRequest = dec.decode()?;
+        let identifier = self
+            .node_manager
+            .get_identifier_by_name(request.identity_name)
+            .await?;
+        match self.node_manager.export_credential(ctx, &identifier).await {
+            Ok(bytes) => Ok(Response::ok(req).body(ExportedCredential { bytes })),
+            Err(e) if e.code().kind == Kind::NotFound => {
+                Err(Response::not_found(req, &e.to_string()))
+            }
+            Err(e) => Err(Response::internal_error(req, &e.to_string())),
+        }
+    }
+
     pub(super) async fn present_credential(
         &self,
         req: &RequestHeader,
         dec: &mut Decoder<'_>,
         ctx: &Context,
-    ) -> Result<Response, Response<Error>> {
+    ) -> Result<Response<CredentialPresentationReceipt>, Response<Error>> {
         let request: PresentCredentialRequest = dec.decode()?;
 
-        // TODO: Replace with self.connect?
         let route = MultiAddr::from_str(&request.route).map_err(|_| {
             ApiError::core(format!(
                 "Couldn't convert String to MultiAddr: {}",
                 &request.route
             ))
         })?;
-        let route = local_multiaddr_to_route(&route)?;
-
-        let identifier = self.node_manager.identifier();
-        let credential = self
+        let receipt = self
             .node_manager
-            .get_credential(ctx, &identifier, None)
+            .present_credential(ctx, &route, request.options())
+            .await?;
+
+        Ok(Response::ok(req).body(receipt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use ockam::identity::models::CredentialSchemaIdentifier;
+    use ockam::identity::utils::AttributesBuilder;
+    use ockam::identity::{identities, AuthorityService, CredentialsRetriever, Identities};
+    use ockam_core::{route, Any, Routed, Worker};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use ockam_core::flow_control::FlowControls;
+    use ockam_core::AsyncTryClone;
+
+    use crate::cli_state::random_name;
+    use crate::nodes::service::default_address::DefaultAddress;
+    use crate::nodes::service::{
+        NodeManagerGeneralOptions, NodeManagerTransportOptions, NodeManagerTrustOptions,
+        DEFAULT_CREDENTIAL_REFRESH_SKEW,
+    };
+    use crate::nodes::InMemoryNode;
+
+    use super::*;
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_credential_is_refreshed_before_expiration(context: &mut Context) -> Result<()> {
+        let identities = identities().await?;
+        let issuer = identities.identities_creation().create_identity().await?;
+        let subject = identities.identities_creation().create_identity().await?;
+        let retriever = Arc::new(ShortLivedCredentialsRetriever {
+            identities: identities.clone(),
+            issuer: issuer.clone(),
+            retrievals: AtomicUsize::new(0),
+        });
+        let authority_service =
+            AuthorityService::new(identities.credentials(), issuer, Some(retriever.clone()));
+        let trust_context = TrustContext::new("trust_context".into(), Some(authority_service));
+
+        // retrieve a first credential, valid for 4 seconds
+        trust_context.get_credential(context, &subject).await?;
+        let expires_at = trust_context.credential_expiration().unwrap();
+        assert_eq!(retriever.retrievals.load(Ordering::SeqCst), 1);
+
+        let ctx = context
+            .new_detached(Address::random_tagged("refresher"), DenyAll, AllowAll)
+            .await?;
+        let (sender, mut receiver) = unbounded_channel();
+        tokio::spawn(refresh_credential_periodically(
+            ctx,
+            trust_context.clone(),
+            subject,
+            Duration::from_secs(2),
+            Some(sender),
+        ));
+
+        // the credential must be refreshed before the first one expires
+        while retriever.retrievals.load(Ordering::SeqCst) < 2 {
+            sleep(Duration::from_millis(100)).await;
+        }
+        assert!(now()? < expires_at);
+        assert!(trust_context.credential_expiration().unwrap() >= expires_at);
+
+        // the refresh is reported
+        let event = receiver.recv().await;
+        assert_eq!(
+            event,
+            Some(CredentialRefreshEvent::Refreshed(
+                trust_context.credential_expiration().unwrap()
+            ))
+        );
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_credential_expiring_soon_is_reported(context: &mut Context) -> Result<()> {
+        let identities = identities().await?;
+        let issuer = identities.identities_creation().create_identity().await?;
+        let subject = identities.identities_creation().create_identity().await?;
+        let retriever = Arc::new(UnreachableAfterFirstRetrievalRetriever {
+            inner: ShortLivedCredentialsRetriever {
+                identities: identities.clone(),
+                issuer: issuer.clone(),
+                retrievals: AtomicUsize::new(0),
+            },
+        });
+        let authority_service =
+            AuthorityService::new(identities.credentials(), issuer, Some(retriever));
+        let trust_context = TrustContext::new("trust_context".into(), Some(authority_service));
+
+        // retrieve a first credential, valid for 4 seconds, which is less than the warning threshold
+        trust_context.get_credential(context, &subject).await?;
+        let expires_at = trust_context.credential_expiration().unwrap();
+
+        let ctx = context
+            .new_detached(Address::random_tagged("refresher"), DenyAll, AllowAll)
+            .await?;
+        let (sender, mut receiver) = unbounded_channel();
+        tokio::spawn(refresh_credential_periodically(
+            ctx,
+            trust_context.clone(),
+            subject,
+            Duration::from_secs(2),
+            Some(sender),
+        ));
+
+        // the credential can not be refreshed and is reported as expiring soon
+        let event = receiver.recv().await;
+        assert_eq!(
+            event,
+            Some(CredentialRefreshEvent::ExpiringSoon(expires_at))
+        );
+        assert!(now()? < expires_at);
+
+        context.stop().await
+    }
+
+    #[test]
+    fn test_is_expiring_soon() {
+        let threshold = CREDENTIAL_EXPIRATION_WARNING_THRESHOLD.as_secs();
+        let now = TimestampInSeconds(1000);
+        assert!(is_expiring_soon(TimestampInSeconds(1000 + threshold), now));
+        assert!(is_expiring_soon(TimestampInSeconds(999), now));
+        assert!(!is_expiring_soon(
+            TimestampInSeconds(1000 + threshold + 1),
+            now
+        ));
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_credential_refresh_is_stopped_with_the_node(context: &mut Context) -> Result<()> {
+        let handle = crate::test_utils::start_manager_for_tests(context).await?;
+        let cli_state = handle.cli_state.clone();
+        let trust_context = cli_state.get_trust_context("trust-context").await?;
+        let node_name = random_name();
+        cli_state.create_node(&node_name).await?;
+
+        // a node refreshing its credential, like the node of the app, which is replaced on reset
+        let node = InMemoryNode::new(
+            context,
+            NodeManagerGeneralOptions::new(cli_state.clone(), node_name, None, false, false),
+            NodeManagerTransportOptions::new(
+                FlowControls::generate_flow_control_id(),
+                handle.tcp.async_try_clone().await?,
+            ),
+            NodeManagerTrustOptions::new(Some(trust_context))
+                .with_credential_refresh(true, DEFAULT_CREDENTIAL_REFRESH_SKEW),
+        )
+        .await?;
+        let is_refreshing =
+            |node: &InMemoryNode| !node.credential_refresher.as_ref().unwrap().is_finished();
+        assert!(is_refreshing(&node));
+
+        // the refresh task doesn't outlive its node
+        node.stop(context).await?;
+        while is_refreshing(&node) {
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        context.stop().await
+    }
+
+    #[test]
+    fn test_time_before_refresh() {
+        let now = TimestampInSeconds(1000);
+        let skew = Duration::from_secs(60);
+
+        // the credential is refreshed `skew` before its expiration
+        assert_eq!(
+            time_before_refresh(TimestampInSeconds(1000 + 3600), skew, now),
+            Duration::from_secs(3600 - 60)
+        );
+
+        // but not before half of its remaining lifetime when the skew is too long
+        assert_eq!(
+            time_before_refresh(TimestampInSeconds(1000 + 60), skew, now),
+            Duration::from_secs(30)
+        );
+
+        // an expired credential is refreshed after the minimum interval
+        assert_eq!(
+            time_before_refresh(TimestampInSeconds(900), skew, now),
+            CREDENTIAL_REFRESH_MIN_INTERVAL
+        );
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_refresh_bypasses_the_cached_credential(context: &mut Context) -> Result<()> {
+        let identities = identities().await?;
+        let issuer = identities.identities_creation().create_identity().await?;
+        let subject = identities.identities_creation().create_identity().await?;
+        let retriever = Arc::new(ShortLivedCredentialsRetriever {
+            identities: identities.clone(),
+            issuer: issuer.clone(),
+            retrievals: AtomicUsize::new(0),
+        });
+        let authority_service =
+            AuthorityService::new(identities.credentials(), issuer, Some(retriever.clone()));
+        let trust_context = TrustContext::new("trust_context".into(), Some(authority_service));
+
+        // the credential is cached once it has been retrieved
+        trust_context.get_credential(context, &subject).await?;
+        trust_context.get_credential(context, &subject).await?;
+        assert_eq!(retriever.retrievals.load(Ordering::SeqCst), 1);
+
+        // unless a fresh credential is requested
+        trust_context.refresh_credential(context, &subject).await?;
+        assert_eq!(retriever.retrievals.load(Ordering::SeqCst), 2);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_present_credential_over_an_existing_secure_channel(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+
+        // create a secure channel to the listener of the node itself
+        let secure_channel = node_manager
+            .create_secure_channel_internal(
+                context,
+                route![DefaultAddress::SECURE_CHANNEL_LISTENER],
+                &node_manager.identifier(),
+                None,
+                None,
+                None,
+            )
+            .await?;
+
+        // the credential is presented to the credentials service at the other end of the channel
+        let to = MultiAddr::from_str(&format!("/service/{}", DefaultAddress::CREDENTIALS_SERVICE))
+            .unwrap();
+        let receipt = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default()
+                    .with_secure_channel(secure_channel.encryptor_address())
+                    .with_oneway(true),
+            )
+            .await?;
+        assert_eq!(receipt, CredentialPresentationReceipt::accepted());
+
+        // an unknown secure channel can not be used
+        let result = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default()
+                    .with_secure_channel(&"unknown".into())
+                    .with_oneway(true),
+            )
+            .await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::NotFound);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_present_credential_to_an_unexpected_peer(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+
+        // the peer at the other end of this channel is the node itself
+        let secure_channel = node_manager
+            .create_secure_channel_internal(
+                context,
+                route![DefaultAddress::SECURE_CHANNEL_LISTENER],
+                &node_manager.identifier(),
+                None,
+                None,
+                None,
+            )
+            .await?;
+        let to = MultiAddr::from_str(&format!("/service/{}", DefaultAddress::CREDENTIALS_SERVICE))
+            .unwrap();
+
+        // the presentation is aborted when the peer is not the expected one
+        let other = identities()
             .await?
-            .unwrap_or_else(|| panic!("A credential must be retrieved for {}", identifier));
+            .identities_creation()
+            .create_identity()
+            .await?;
+        let result = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default()
+                    .with_secure_channel(secure_channel.encryptor_address())
+                    .with_oneway(true)
+                    .with_expected_peer(Some(other.clone())),
+            )
+            .await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Invalid);
 
-        if request.oneway {
-            self.node_manager
-                .credentials_service()
-                .present_credential(ctx, route, credential)
-                .await?;
-        } else {
-            self.node_manager
-                .credentials_service()
-                .present_credential_mutual(
-                    ctx,
-                    route,
-                    &self.node_manager.trust_context()?.authorities(),
-                    credential,
+        // or when the peer can't be authenticated
+        let result = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default()
+                    .with_oneway(true)
+                    .with_expected_peer(Some(other)),
+            )
+            .await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Invalid);
+
+        // the credential is presented to the expected peer
+        let receipt = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default()
+                    .with_secure_channel(secure_channel.encryptor_address())
+                    .with_oneway(true)
+                    .with_expected_peer(Some(node_manager.identifier())),
+            )
+            .await?;
+        assert_eq!(receipt, CredentialPresentationReceipt::accepted());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_mutual_presentation_times_out_if_the_peer_never_responds(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        context.start_worker("silent", SilentWorker).await?;
+
+        let to = MultiAddr::from_str("/service/silent").unwrap();
+        let result = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default().with_timeout(Duration::from_millis(500)),
+            )
+            .await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Timeout);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_present_credential_dry_run(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        let worker = CountingWorker::default();
+        let received = worker.received.clone();
+        context.start_worker("counting", worker).await?;
+
+        // the credential and the route are checked but nothing is sent to the peer
+        let to = MultiAddr::from_str("/service/counting").unwrap();
+        let receipt = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default().with_dry_run(true),
+            )
+            .await?;
+        assert!(receipt.is_dry_run());
+        assert!(!receipt.is_accepted());
+        assert_eq!(receipt.route(), Some(route!["counting"].to_string()));
+
+        let credential = node_manager
+            .get_credential(context, &node_manager.identifier(), None)
+            .await?
+            .unwrap();
+        assert_eq!(
+            receipt.expires_at(),
+            Some(credential.get_credential_data()?.expires_at)
+        );
+
+        sleep(Duration::from_millis(200)).await;
+        assert_eq!(received.load(Ordering::SeqCst), 0);
+
+        // an invalid route is still rejected
+        let to = MultiAddr::from_str("/dnsaddr/localhost/tcp/4000/service/counting").unwrap();
+        let result = node_manager
+            .present_credential(
+                context,
+                &to,
+                PresentCredentialOptions::default().with_dry_run(true),
+            )
+            .await;
+        assert!(result.is_err());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn test_export_credential(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        let identifier = node_manager.identifier();
+
+        // the exported bytes decode back to the credential of the node
+        let bytes = node_manager.export_credential(context, &identifier).await?;
+        let exported = CredentialAndPurposeKey::decode_from_cbor_bytes(&bytes)?;
+        let credential = node_manager
+            .get_credential(context, &identifier, None)
+            .await?
+            .unwrap();
+        assert_eq!(exported, credential);
+        assert_eq!(
+            exported.get_credential_data()?,
+            credential.get_credential_data()?
+        );
+
+        context.stop().await
+    }
+
+    /// This worker receives the credential presentations but never responds
+    struct SilentWorker;
+
+    #[async_trait]
+    impl Worker for SilentWorker {
+        type Message = Any;
+        type Context = Context;
+
+        async fn handle_message(&mut self, _ctx: &mut Context, _msg: Routed<Any>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// This worker counts the messages it receives
+    #[derive(Default)]
+    struct CountingWorker {
+        received: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Worker for CountingWorker {
+        type Message = Any;
+        type Context = Context;
+
+        async fn handle_message(&mut self, _ctx: &mut Context, _msg: Routed<Any>) -> Result<()> {
+            self.received.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// This retriever issues credentials which are only valid for a few seconds
+    struct ShortLivedCredentialsRetriever {
+        identities: Arc<Identities>,
+        issuer: Identifier,
+        retrievals: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl CredentialsRetriever for ShortLivedCredentialsRetriever {
+        async fn retrieve(
+            &self,
+            _ctx: &Context,
+            for_identity: &Identifier,
+        ) -> Result<CredentialAndPurposeKey> {
+            self.retrievals.fetch_add(1, Ordering::SeqCst);
+            let attributes = AttributesBuilder::with_schema(CredentialSchemaIdentifier(1))
+                .with_attribute("name".as_bytes().to_vec(), b"value".to_vec())
+                .build();
+            self.identities
+                .credentials()
+                .credentials_creation()
+                .issue_credential(
+                    &self.issuer,
+                    for_identity,
+                    attributes,
+                    Duration::from_secs(4),
                 )
-                .await?;
+                .await
         }
+    }
 
-        let response = Response::ok(req);
-        Ok(response)
+    /// This retriever issues a first credential then fails, as if the authority was not reachable
+    struct UnreachableAfterFirstRetrievalRetriever {
+        inner: ShortLivedCredentialsRetriever,
+    }
+
+    #[async_trait]
+    impl CredentialsRetriever for UnreachableAfterFirstRetrievalRetriever {
+        async fn retrieve(
+            &self,
+            ctx: &Context,
+            for_identity: &Identifier,
+        ) -> Result<CredentialAndPurposeKey> {
+            if self.inner.retrievals.load(Ordering::SeqCst) > 0 {
+                return Err(ockam_core::Error::new(
+                    Origin::Api,
+                    Kind::Io,
+                    "the authority is not reachable",
+                ));
+            }
+            self.inner.retrieve(ctx, for_identity).await
+        }
     }
 }
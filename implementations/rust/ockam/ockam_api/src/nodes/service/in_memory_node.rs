@@ -14,6 +14,7 @@ use crate::cli_state::random_name;
 use crate::cli_state::CliState;
 use crate::cli_state::NamedTrustContext;
 use crate::cloud::Controller;
+use crate::error::ApiError;
 use crate::nodes::service::default_address::DefaultAddress;
 use crate::nodes::service::{
     NodeManagerGeneralOptions, NodeManagerTransportOptions, NodeManagerTrustOptions,
@@ -128,11 +129,14 @@ impl InMemoryNode {
         Ok(node_manager)
     }
 
-    /// Return a Controller client to send requests to the Controller
+    /// Return a Controller client to send requests to the Controller.
+    /// The controller stored in the CliState, if any, is used instead of the default one
     pub async fn create_controller(&self) -> miette::Result<Controller> {
-        self.create_controller_client(self.timeout)
+        let controller_config = self.cli_state.get_controller_config().await?;
+        Ok(self
+            .create_controller_client(controller_config.as_ref(), self.timeout)
             .await
-            .into_diagnostic()
+            .map_err(ApiError::from)?)
     }
 
     pub fn add_session(&self, session: Session) {
@@ -149,6 +153,7 @@ impl InMemoryNode {
     }
 
     pub async fn stop(&self, ctx: &Context) -> Result<()> {
+        self.stop_credential_refresh();
         self.medic_handle.stop_medic(ctx).await?;
         for addr in DefaultAddress::iter() {
             let result = ctx.stop_worker(addr).await;
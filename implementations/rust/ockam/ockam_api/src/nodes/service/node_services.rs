@@ -5,7 +5,7 @@ use minicbor::Decoder;
 use ockam::identity::{identities, AuthorityService, TrustContext};
 use ockam::{Address, Context, Result};
 use ockam_abac::expr::{eq, ident, str};
-use ockam_abac::Resource;
+use ockam_abac::{Action, Resource};
 use ockam_core::api::{Error, RequestHeader, Response};
 use ockam_core::compat::net::SocketAddr;
 use ockam_core::route;
@@ -21,6 +21,7 @@ use crate::kafka::{
     KAFKA_OUTLET_BOOTSTRAP_ADDRESS, KAFKA_OUTLET_INTERCEPTOR_ADDRESS,
 };
 use crate::kafka::{OutletManagerService, PrefixRelayService};
+use crate::nodes::models::portal::InletOptions;
 use crate::nodes::models::services::{
     DeleteServiceRequest, ServiceList, ServiceStatus, StartAuthenticatedServiceRequest,
     StartCredentialsService, StartEchoerServiceRequest, StartHopServiceRequest,
@@ -35,7 +36,7 @@ use crate::nodes::NodeManager;
 use crate::port_range::PortRange;
 use crate::uppercase::Uppercase;
 
-use super::{actions, resources, NodeManagerWorker};
+use super::NodeManagerWorker;
 
 impl NodeManager {
     pub(super) async fn start_credentials_service_impl(
@@ -121,7 +122,7 @@ impl NodeManager {
         let ac = self
             .access_control(
                 &resource,
-                &actions::HANDLE_MESSAGE,
+                &Action::HANDLE_MESSAGE,
                 maybe_trust_context_id,
                 None,
             )
@@ -417,7 +418,7 @@ impl NodeManagerWorker {
                 ],
                 "/secure/api".parse().unwrap(),
                 None,
-                None,
+                InletOptions::default(),
             )
             .await?;
 
@@ -534,8 +535,8 @@ impl NodeManagerWorker {
                 self.node_manager
                     .cli_state
                     .set_policy(
-                        &resources::INLET,
-                        &actions::HANDLE_MESSAGE,
+                        &Resource::TCP_INLET,
+                        &Action::HANDLE_MESSAGE,
                         &eq([ident("subject.identifier"), str(project_identifier)]),
                     )
                     .await
@@ -572,7 +573,7 @@ impl NodeManagerWorker {
                 ],
                 outlet_node_multiaddr,
                 None,
-                None,
+                InletOptions::default(),
             )
             .await?;
 
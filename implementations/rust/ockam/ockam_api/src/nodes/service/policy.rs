@@ -115,7 +115,7 @@ impl Policies for BackgroundNode {
             let expr = eq([ident("subject.trust_context_id"), str(project_id)]);
             Policy::new(expr)
         };
-        let action = Action::new("handle_message");
+        let action = Action::HANDLE_MESSAGE;
         let request = Request::post(policy_path(&resource, &action)).body(policy);
         self.tell(ctx, request).await?;
 
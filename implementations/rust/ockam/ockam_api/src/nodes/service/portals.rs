@@ -1,37 +1,56 @@
-use std::net::SocketAddr;
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use minicbor::Decoder;
-use tokio::time::timeout;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
 
-use ockam::identity::Identifier;
+use ockam::identity::{CredentialAccessControl, Identifier, TRUST_CONTEXT_ID};
 use ockam::{Address, Result};
-use ockam_abac::Resource;
+use ockam_abac::{Action, Resource};
 use ockam_core::api::{Error, Reply, Request, RequestHeader, Response};
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{async_trait, route, AsyncTryClone, IncomingAccessControl, Route};
+use ockam_core::{
+    async_trait, route, AllIncomingAccessControl, AllowAll, AsyncTryClone, DenyAll,
+    IncomingAccessControl, Route,
+};
 use ockam_multiaddr::proto::Project;
 use ockam_multiaddr::{MultiAddr, Protocol};
 use ockam_node::Context;
-use ockam_transport_tcp::{TcpInletOptions, TcpOutletOptions};
+use ockam_transport_tcp::TcpOutletOptions;
 
+use crate::address::{interface_socket_address, SystemInterfaceLookup};
 use crate::error::ApiError;
+use crate::logs::filter::{remove_inlet_log_level, set_inlet_log_level};
 use crate::nodes::connection::Connection;
 use crate::nodes::models::portal::{
-    CreateInlet, CreateOutlet, InletList, InletStatus, OutletList, OutletStatus,
+    validate_buffer_size, CreateInlet, CreateOutlet, DeleteInletByAddr, DrainInlet, InletFilter,
+    InletList, InletOptions, InletStatus, OutletList, OutletStatus, WaitForInlet,
 };
 use crate::nodes::registry::{InletInfo, OutletInfo};
 use crate::nodes::service::default_address::DefaultAddress;
 use crate::nodes::service::policy::Policies;
-use crate::nodes::service::{actions, random_alias, resources};
-use crate::nodes::{BackgroundNode, InMemoryNode};
+use crate::nodes::service::random_alias;
+use crate::nodes::{BackgroundNode, InMemoryNode, Subscription};
 use crate::session::sessions::{
     ConnectionStatus, Replacer, Session, MAX_CONNECT_TIME, MAX_RECOVERY_TIME,
 };
 
 use super::{NodeManager, NodeManagerWorker};
 
+/// Delay between two checks of an inlet status when a subscriber watches it
+const INLET_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Maximum delay between two statuses sent to a subscriber watching an inlet.
+/// The status is sent again when it doesn't change, so that the watch stops
+/// once the subscriber can't be reached anymore
+const INLET_WATCH_HEARTBEAT: Duration = Duration::from_secs(5);
+
 /// INLETS
 impl NodeManagerWorker {
     pub(super) async fn get_inlets(&self, req: &RequestHeader) -> Response<InletList> {
@@ -46,6 +65,10 @@ impl NodeManagerWorker {
         ctx: &Context,
     ) -> Result<Response<InletStatus>, Response<Error>> {
         let create_inlet_req: CreateInlet = dec.decode()?;
+        let options = match create_inlet_req.options() {
+            Ok(options) => options,
+            Err(e) => return Err(Response::bad_request(req, &e.to_string())),
+        };
         let CreateInlet {
             listen_addr,
             outlet_addr,
@@ -53,7 +76,7 @@ impl NodeManagerWorker {
             authorized,
             prefix_route,
             suffix_route,
-            wait_for_outlet_duration,
+            ..
         } = create_inlet_req;
         match self
             .node_manager
@@ -64,8 +87,8 @@ impl NodeManagerWorker {
                 prefix_route,
                 suffix_route,
                 outlet_addr,
-                wait_for_outlet_duration,
                 authorized,
+                options,
             )
             .await
         {
@@ -85,6 +108,43 @@ impl NodeManagerWorker {
         }
     }
 
+    pub(super) async fn delete_inlet_by_addr(
+        &self,
+        req: &RequestHeader,
+        delete_inlet: DeleteInletByAddr,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        match self
+            .node_manager
+            .delete_inlet_by_addr(delete_inlet.addr)
+            .await
+        {
+            Ok(status) => Ok(Response::ok(req).body(status)),
+            Err(e) if e.code().kind == Kind::NotFound => {
+                Err(Response::not_found(req, &e.to_string()))
+            }
+            Err(e) => Err(Response::bad_request(req, &e.to_string())),
+        }
+    }
+
+    pub(super) async fn drain_inlet(
+        &self,
+        req: &RequestHeader,
+        alias: &str,
+        drain_inlet: DrainInlet,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        match self
+            .node_manager
+            .drain_inlet(alias, drain_inlet.timeout)
+            .await
+        {
+            Ok(status) => Ok(Response::ok(req).body(status)),
+            Err(e) if e.code().kind == Kind::NotFound => {
+                Err(Response::not_found(req, &e.to_string()))
+            }
+            Err(e) => Err(Response::bad_request(req, &format!("{e:?}"))),
+        }
+    }
+
     pub(super) async fn show_inlet(
         &self,
         req: &RequestHeader,
@@ -98,6 +158,66 @@ impl NodeManagerWorker {
             )),
         }
     }
+
+    pub(super) async fn watch_inlet(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        alias: &str,
+        subscriber: Route,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        match self.node_manager.show_inlet(alias).await {
+            Some(inlet) => {
+                if let Err(e) = self
+                    .node_manager
+                    .watch_inlet(ctx, req.clone(), inlet.clone(), subscriber)
+                    .await
+                {
+                    return Err(Response::internal_error(req, &e.to_string()));
+                };
+                Ok(Response::ok(req).body(inlet))
+            }
+            None => Err(Response::not_found(
+                req,
+                &format!("Inlet with alias {alias} not found"),
+            )),
+        }
+    }
+    /// Reply with the current status of an inlet then, if it is not connected yet, send
+    /// its status to the subscriber once it is connected, or an error after the timeout
+    pub(super) async fn wait_for_inlet(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        alias: &str,
+        wait_for_inlet: WaitForInlet,
+        subscriber: Route,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        match self.node_manager.show_inlet(alias).await {
+            Some(inlet) => {
+                if inlet.status != ConnectionStatus::Up {
+                    if let Err(e) = self
+                        .node_manager
+                        .notify_when_inlet_connected(
+                            ctx,
+                            req.clone(),
+                            alias,
+                            wait_for_inlet.timeout,
+                            subscriber,
+                        )
+                        .await
+                    {
+                        return Err(Response::internal_error(req, &e.to_string()));
+                    };
+                }
+                Ok(Response::ok(req).body(inlet))
+            }
+            None => Err(Response::not_found(
+                req,
+                &format!("Inlet with alias {alias} not found"),
+            )),
+        }
+    }
 }
 
 /// OUTLETS
@@ -190,7 +310,7 @@ impl NodeManager {
         let resource = alias
             .as_deref()
             .map(Resource::new)
-            .unwrap_or(resources::OUTLET);
+            .unwrap_or(Resource::TCP_OUTLET);
 
         let alias = alias.unwrap_or_else(random_alias);
 
@@ -207,13 +327,21 @@ impl NodeManager {
         let access_control = self
             .access_control(
                 &resource,
-                &actions::HANDLE_MESSAGE,
+                &Action::HANDLE_MESSAGE,
                 self.trust_context_id().as_deref(),
                 None,
             )
             .await?;
 
-        let options = TcpOutletOptions::new().with_incoming_access_control(access_control);
+        // The targets requested by SOCKS5 inlets are only reached if the policy allows them
+        let target_authorization = self
+            .cli_state
+            .make_target_authorization(&resource, &Action::CONNECT_TO_TARGET)
+            .await?;
+
+        let options = TcpOutletOptions::new()
+            .with_incoming_access_control(access_control)
+            .with_target_authorization(Arc::new(target_authorization));
         let options = if self.trust_context_id().is_none() {
             options.as_consumer(&self.api_transport_flow_control_id)
         } else {
@@ -302,6 +430,7 @@ impl NodeManager {
 
 /// INLETS
 impl NodeManager {
+    /// Create an inlet to the outlet that the connection was created for
     pub async fn create_inlet(
         &self,
         connection: Connection,
@@ -309,9 +438,16 @@ impl NodeManager {
         requested_alias: Option<String>,
         prefix_route: Route,
         suffix_route: Route,
-        outlet_addr: MultiAddr,
+        options: &InletOptions,
     ) -> Result<(InletStatus, Arc<dyn IncomingAccessControl>)> {
         info!("Handling request to create inlet portal");
+        let outlet_addr = connection.original_addr().clone();
+        let listen_addr = resolve_listen_addr(listen_addr, options.listen_interface.as_deref())?;
+        let idle_timeout = options.idle_timeout();
+        if let Some(buffer_size) = options.buffer_size {
+            validate_buffer_size(buffer_size)?;
+        }
+        let buffer_size = options.buffer_size();
 
         let alias = requested_alias.clone().unwrap_or_else(random_alias);
         debug! {
@@ -381,21 +517,42 @@ impl NodeManager {
 
         let resource = requested_alias
             .map(|a| Resource::new(a.as_str()))
-            .unwrap_or(resources::INLET);
+            .unwrap_or(Resource::TCP_INLET);
         let access_control = self
             .access_control(
                 &resource,
-                &actions::HANDLE_MESSAGE,
+                &Action::HANDLE_MESSAGE,
                 project_id.as_deref(),
                 None,
             )
             .await?;
+        let access_control = if options.require_credential {
+            self.require_credential(access_control, project_id.as_deref())?
+        } else {
+            access_control
+        };
 
-        let options = TcpInletOptions::new().with_incoming_access_control(access_control.clone());
-        let res = self
-            .tcp_transport
-            .create_inlet(listen_addr.clone(), outlet_route.clone(), options)
-            .await;
+        // the log level must be set before the inlet creates its span
+        if let Some(log_level) = options.log_level {
+            set_inlet_log_level(&alias, log_level)?;
+        }
+        // An inlet created without waiting for its connection is held until a first client
+        // connects to it, and only then connected to its outlet
+        let first_client = Arc::new(Notify::new());
+        let tcp_inlet_options = options.tcp_inlet_options(&alias, access_control.clone());
+        let prewarm = prewarm_pool_size(&tcp_inlet_options);
+        let res = if options.wait_connection {
+            self.tcp_transport
+                .create_inlet(listen_addr.clone(), outlet_route.clone(), tcp_inlet_options)
+                .await
+        } else {
+            self.tcp_transport
+                .create_held_inlet(
+                    listen_addr.clone(),
+                    tcp_inlet_options.with_held_connection_notify(first_client.clone()),
+                )
+                .await
+        };
 
         Ok(match res {
             Ok((socket_address, worker_addr)) => {
@@ -408,7 +565,17 @@ impl NodeManager {
                     .inlets
                     .insert(
                         alias.clone(),
-                        InletInfo::new(&listen_addr, Some(&worker_addr), &outlet_route),
+                        InletInfo::new(
+                            &listen_addr,
+                            Some(&worker_addr),
+                            &outlet_addr,
+                            &outlet_route,
+                            idle_timeout,
+                            options.labels.clone(),
+                            buffer_size,
+                        )
+                        .with_prewarm(prewarm)
+                        .with_first_client(first_client),
                     )
                     .await;
                 (
@@ -419,12 +586,19 @@ impl NodeManager {
                         None,
                         outlet_route.to_string(),
                         ConnectionStatus::Up,
-                    ),
+                    )
+                    .with_idle_timeout(idle_timeout)
+                    .with_labels(options.labels.clone())
+                    .with_reconnect_count(0)
+                    .with_buffer_size(buffer_size)
+                    .with_prewarm(prewarm, self.prewarmed_portals(&worker_addr))
+                    .with_outlet_addr(&outlet_addr),
                     access_control,
                 )
             }
             Err(e) => {
                 warn!(to = %outlet_addr, err = %e, "Failed to create TCP inlet");
+                reset_inlet_log_level(&alias);
                 let message = format!("Failed to create TCP inlet: {}", e);
                 return Err(ockam_core::Error::new(
                     Origin::Node,
@@ -435,10 +609,52 @@ impl NodeManager {
         })
     }
 
+    /// Extend an inlet access control to only accept messages from peers who presented
+    /// a credential for the given trust context
+    fn require_credential(
+        &self,
+        access_control: Arc<dyn IncomingAccessControl>,
+        trust_context_id: Option<&str>,
+    ) -> Result<Arc<dyn IncomingAccessControl>> {
+        let trust_context_id = trust_context_id.ok_or_else(|| {
+            ockam_core::Error::new(
+                Origin::Node,
+                Kind::Invalid,
+                "A credential can only be required on a node with a trust context",
+            )
+        })?;
+        let credential_access_control = CredentialAccessControl::new(
+            &[(
+                TRUST_CONTEXT_ID.to_vec(),
+                trust_context_id.as_bytes().to_vec(),
+            )],
+            self.identity_attributes_repository(),
+        );
+        Ok(Arc::new(AllIncomingAccessControl::new(vec![
+            access_control,
+            Arc::new(credential_access_control),
+        ])))
+    }
+
+    /// Delete the inlet bound to an address.
+    /// When the IP of the address is unspecified, the inlet is only selected by port
+    pub async fn delete_inlet_by_addr(&self, addr: SocketAddr) -> Result<InletStatus> {
+        let alias = self
+            .list_inlets()
+            .await
+            .find_by_bind_addr(&addr)?
+            .alias
+            .clone();
+        self.delete_inlet(&alias).await
+    }
+
     pub async fn delete_inlet(&self, alias: &str) -> Result<InletStatus> {
         info!(%alias, "Handling request to delete inlet portal");
         if let Some(inlet_to_delete) = self.registry.inlets.remove(alias).await {
             debug!(%alias, "Successfully removed inlet from node registry");
+            // the inlet won't be connected anymore
+            inlet_to_delete.first_client.notify_one();
+            reset_inlet_log_level(alias);
             match self
                 .tcp_transport
                 .stop_inlet(inlet_to_delete.worker_addr.clone())
@@ -453,7 +669,13 @@ impl NodeManager {
                         None,
                         inlet_to_delete.outlet_route.to_string(),
                         ConnectionStatus::Down,
-                    ))
+                    )
+                    .with_idle_timeout(inlet_to_delete.idle_timeout)
+                    .with_labels(inlet_to_delete.labels.clone())
+                    .with_reconnect_count(inlet_to_delete.reconnect_count())
+                    .with_buffer_size(inlet_to_delete.buffer_size)
+                    .with_prewarm(inlet_to_delete.prewarm, None)
+                    .with_outlet_addr(&inlet_to_delete.outlet_addr))
                 }
                 Err(e) => {
                     error!(%alias, "Failed to remove inlet from node registry");
@@ -476,6 +698,51 @@ impl NodeManager {
         }
     }
 
+    /// Drain an inlet: it stops accepting new connections right away and is removed from the
+    /// node, but its existing connections can finish for at most `drain_timeout`.
+    /// The connections still open after that are closed
+    pub async fn drain_inlet(&self, alias: &str, drain_timeout: Duration) -> Result<InletStatus> {
+        info!(%alias, "Handling request to drain inlet portal");
+        let Some(inlet_to_drain) = self.registry.inlets.remove(alias).await else {
+            error!(%alias, "Inlet not found in the node registry");
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::NotFound,
+                format!("Inlet with alias {alias} not found"),
+            ));
+        };
+        // the inlet won't be connected anymore
+        inlet_to_drain.first_client.notify_one();
+        reset_inlet_log_level(alias);
+
+        // the node keeps handling requests while the connections finish
+        let tcp_transport = self.tcp_transport.async_try_clone().await?;
+        let worker_addr = inlet_to_drain.worker_addr.clone();
+        let inlet_alias = alias.to_string();
+        tokio::spawn(async move {
+            match tcp_transport.drain_inlet(worker_addr, drain_timeout).await {
+                Ok(0) => debug!(alias = %inlet_alias, "Successfully drained inlet"),
+                Ok(closed) => info!(alias = %inlet_alias, %closed, "Drained inlet"),
+                Err(e) => error!(alias = %inlet_alias, "Failed to drain inlet: {e}"),
+            }
+        });
+
+        Ok(InletStatus::new(
+            inlet_to_drain.bind_addr,
+            inlet_to_drain.worker_addr.to_string(),
+            alias,
+            None,
+            inlet_to_drain.outlet_route.to_string(),
+            ConnectionStatus::Down,
+        )
+        .with_idle_timeout(inlet_to_drain.idle_timeout)
+        .with_labels(inlet_to_drain.labels.clone())
+        .with_reconnect_count(inlet_to_drain.reconnect_count())
+        .with_buffer_size(inlet_to_drain.buffer_size)
+        .with_prewarm(inlet_to_drain.prewarm, None)
+        .with_outlet_addr(&inlet_to_drain.outlet_addr))
+    }
+
     pub async fn show_inlet(&self, alias: &str) -> Option<InletStatus> {
         info!(%alias, "Handling request to show inlet portal");
         if let Some(inlet_to_show) = self.registry.inlets.get(alias).await {
@@ -485,20 +752,62 @@ impl NodeManager {
                 .unwrap_or(ConnectionStatus::Down);
 
             debug!(%alias, "Inlet not found in node registry");
-            Some(InletStatus::new(
-                inlet_to_show.bind_addr.to_string(),
-                inlet_to_show.worker_addr.address(),
-                alias,
-                None,
-                inlet_to_show.outlet_route.to_string(),
-                status,
-            ))
+            Some(
+                InletStatus::new(
+                    inlet_to_show.bind_addr.to_string(),
+                    inlet_to_show.worker_addr.address(),
+                    alias,
+                    None,
+                    inlet_to_show.outlet_route.to_string(),
+                    status,
+                )
+                .with_idle_timeout(inlet_to_show.idle_timeout)
+                .with_labels(inlet_to_show.labels.clone())
+                .with_reconnect_count(inlet_to_show.reconnect_count())
+                .with_buffer_size(inlet_to_show.buffer_size)
+                .with_prewarm(
+                    inlet_to_show.prewarm,
+                    self.prewarmed_portals(&inlet_to_show.worker_addr),
+                )
+                .with_outlet_addr(&inlet_to_show.outlet_addr),
+            )
         } else {
             error!(%alias, "Inlet not found in the node registry");
             None
         }
     }
 
+    /// Wait until an inlet is connected to its outlet and return its status.
+    /// An error is returned if the inlet is not connected before the timeout or if it is deleted
+    pub async fn wait_until_inlet_connected(
+        &self,
+        alias: &str,
+        wait_timeout: Duration,
+    ) -> Result<InletStatus> {
+        let wait = async {
+            loop {
+                match self.show_inlet(alias).await {
+                    Some(inlet) if inlet.status == ConnectionStatus::Up => return Ok(inlet),
+                    Some(_) => sleep(INLET_WATCH_INTERVAL).await,
+                    None => {
+                        return Err(ockam_core::Error::new(
+                            Origin::Node,
+                            Kind::NotFound,
+                            format!("Inlet with alias {alias} not found"),
+                        ))
+                    }
+                }
+            }
+        };
+        timeout(wait_timeout, wait).await.map_err(|_| {
+            ockam_core::Error::new(
+                Origin::Node,
+                Kind::Timeout,
+                format!("The inlet {alias} was not connected after {wait_timeout:?}"),
+            )
+        })?
+    }
+
     pub async fn list_inlets(&self) -> InletList {
         InletList::new(
             self.registry
@@ -520,10 +829,28 @@ impl NodeManager {
                         info.outlet_route.to_string(),
                         status,
                     )
+                    .with_idle_timeout(info.idle_timeout)
+                    .with_labels(info.labels.clone())
+                    .with_reconnect_count(info.reconnect_count())
+                    .with_buffer_size(info.buffer_size)
+                    .with_prewarm(info.prewarm, self.prewarmed_portals(&info.worker_addr))
+                    .with_outlet_addr(&info.outlet_addr)
                 })
                 .collect(),
         )
     }
+
+    /// Return the inlets of the node, grouped by the normalized route to their outlet
+    pub async fn list_inlets_grouped_by_route(&self) -> HashMap<String, Vec<InletStatus>> {
+        self.list_inlets().await.grouped_by_route()
+    }
+
+    /// Return the number of prewarmed tunnels of an inlet waiting for a client connection
+    fn prewarmed_portals(&self, inlet_address: &Address) -> Option<u32> {
+        self.tcp_transport
+            .prewarmed_portals(inlet_address)
+            .map(|ready| ready as u32)
+    }
 }
 
 impl InMemoryNode {
@@ -536,28 +863,42 @@ impl InMemoryNode {
         prefix_route: Route,
         suffix_route: Route,
         outlet_addr: MultiAddr,
-        wait_for_outlet_duration: Option<Duration>,
         authorized: Option<Identifier>,
+        options: InletOptions,
     ) -> Result<InletStatus> {
+        if let Some(egress_bind) = options.egress_bind {
+            validate_egress_bind(egress_bind)?;
+        }
+        let wait_connection = options.wait_connection;
+
         // The addressing scheme is very flexible. Typically the node connects to
         // the cloud via secure channel and the with another secure channel via
         // relay to the actual outlet on the target node. However it is also
         // possible that there is just a single secure channel used to go directly
         // to another node.
-        let duration = wait_for_outlet_duration.unwrap_or(Duration::from_secs(5));
+        let duration = options
+            .wait_for_outlet_timeout
+            .unwrap_or(Duration::from_secs(5));
         let connection_ctx = Arc::new(ctx.async_try_clone().await?);
-        let connection = self
-            .make_connection(
+        let connection = if wait_connection {
+            self.make_connection(
                 connection_ctx.clone(),
                 &outlet_addr,
                 self.identifier(),
                 authorized.clone(),
                 None,
                 Some(duration),
+                options.keepalive,
+                options.egress_bind,
             )
-            .await?;
+            .await?
+        } else {
+            // the connection is established by the session replacer once a first client
+            // connects to the inlet
+            Connection::pending(&outlet_addr)
+        };
 
-        let (inlet, access_control) = self
+        let (mut inlet, access_control) = self
             .node_manager
             .create_inlet(
                 connection.clone(),
@@ -565,10 +906,10 @@ impl InMemoryNode {
                 requested_alias,
                 prefix_route.clone(),
                 suffix_route.clone(),
-                outlet_addr.clone(),
+                &options,
             )
             .await?;
-        if !connection.route(self.tcp_transport()).await?.is_empty() {
+        if !wait_connection || !connection.route(self.tcp_transport()).await?.is_empty() {
             debug! {
                 %inlet.alias,
                 %inlet.bind_addr,
@@ -576,29 +917,180 @@ impl InMemoryNode {
                 ping_addr = %connection.transport_route(),
                 "Creating session for TCP inlet"
             };
-            let mut session = Session::new(
-                connection.transport_route(),
-                format!("inlet-{}", inlet.alias),
-            );
+            let key = format!("inlet-{}", inlet.alias);
+            let mut session = if wait_connection {
+                Session::new(connection.transport_route(), key.clone())
+            } else {
+                inlet.status = ConnectionStatus::Pending;
+                Session::pending(key.clone())
+            };
 
+            let reconnect_count = self
+                .registry
+                .inlets
+                .get(&inlet.alias)
+                .await
+                .map(|info| info.reconnect_count)
+                .unwrap_or_default();
             let repl = Self::portal_replacer(
                 self.node_manager.clone(),
                 connection_ctx,
                 connection,
+                wait_connection,
+                reconnect_count,
                 Address::from_string(inlet.worker_addr.clone()),
+                inlet.alias.clone(),
                 listen_addr,
                 outlet_addr,
                 prefix_route,
                 suffix_route,
                 authorized,
                 access_control,
+                options,
             );
             session.set_replacer(repl);
             self.add_session(session);
+            // the prewarmed tunnels need the connection before any client connects
+            if !wait_connection {
+                if inlet.prewarm.is_some() {
+                    self.node_manager.medic_handle.connect_session(&key);
+                } else {
+                    self.connect_on_first_client(&inlet.alias, key).await;
+                }
+            }
         };
         Ok(inlet)
     }
 
+    /// Request the connection of the session of a pending inlet once a first client
+    /// connects to the inlet
+    async fn connect_on_first_client(&self, alias: &str, key: String) {
+        let Some(first_client) = self
+            .registry
+            .inlets
+            .get(alias)
+            .await
+            .map(|info| info.first_client)
+        else {
+            return;
+        };
+        let node_manager = self.node_manager.clone();
+        let alias = alias.to_string();
+        tokio::spawn(async move {
+            first_client.notified().await;
+            // the waiter is also notified when the inlet is deleted
+            let is_deleted = !node_manager
+                .registry
+                .inlets
+                .get(&alias)
+                .await
+                .is_some_and(|info| Arc::ptr_eq(&info.first_client, &first_client));
+            if is_deleted {
+                debug!(%alias, "the inlet was deleted before a first client connected");
+                return;
+            }
+            debug!(%alias, "a first client connected, connecting the inlet to its outlet");
+            node_manager.medic_handle.connect_session(&key);
+        });
+    }
+
+    /// Send the status of an inlet to a subscriber every time it changes, and at least
+    /// every [`INLET_WATCH_HEARTBEAT`].
+    /// The first status sent is compared to `current`, the status already known by the subscriber.
+    /// The subscription stops when the inlet is deleted or when the subscriber can't be reached
+    async fn watch_inlet(
+        &self,
+        ctx: &Context,
+        req: RequestHeader,
+        current: InletStatus,
+        subscriber: Route,
+    ) -> Result<JoinHandle<()>> {
+        let ctx = ctx
+            .new_detached(
+                Address::random_tagged("InletStatus.watcher"),
+                DenyAll,
+                AllowAll,
+            )
+            .await?;
+        let node_manager = self.node_manager.clone();
+        let alias = current.alias.clone();
+        let mut last_status = current.status;
+        let mut last_sent = Instant::now();
+        let handle = tokio::spawn(async move {
+            loop {
+                sleep(INLET_WATCH_INTERVAL).await;
+                let (response, is_deleted) = match node_manager.show_inlet(&alias).await {
+                    Some(inlet)
+                        if inlet.status == last_status
+                            && last_sent.elapsed() < INLET_WATCH_HEARTBEAT =>
+                    {
+                        continue
+                    }
+                    Some(inlet) => {
+                        last_status = inlet.status;
+                        (Response::ok(&req).body(inlet).to_vec(), false)
+                    }
+                    None => {
+                        let message = format!("Inlet with alias {alias} not found");
+                        (Response::not_found(&req, &message).to_vec(), true)
+                    }
+                };
+                let sent = match response {
+                    Ok(response) => ctx.send(subscriber.clone(), response).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = sent {
+                    debug!(%alias, %subscriber, "stop watching the inlet status: {e}");
+                    break;
+                }
+                if is_deleted {
+                    debug!(%alias, "stop watching the inlet status, the inlet has been deleted");
+                    break;
+                }
+                last_sent = Instant::now();
+            }
+        });
+        Ok(handle)
+    }
+
+    /// Send the status of an inlet to a subscriber once it is connected to its outlet,
+    /// or an error if it is not connected before the timeout
+    async fn notify_when_inlet_connected(
+        &self,
+        ctx: &Context,
+        req: RequestHeader,
+        alias: &str,
+        wait_timeout: Duration,
+        subscriber: Route,
+    ) -> Result<()> {
+        let ctx = ctx
+            .new_detached(
+                Address::random_tagged("InletStatus.waiter"),
+                DenyAll,
+                AllowAll,
+            )
+            .await?;
+        let node_manager = self.node_manager.clone();
+        let alias = alias.to_string();
+        tokio::spawn(async move {
+            let response = match node_manager
+                .wait_until_inlet_connected(&alias, wait_timeout)
+                .await
+            {
+                Ok(inlet) => Response::ok(&req).body(inlet).to_vec(),
+                Err(e) => Response::internal_error(&req, &e.to_string()).to_vec(),
+            };
+            let sent = match response {
+                Ok(response) => ctx.send(subscriber.clone(), response).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = sent {
+                debug!(%alias, %subscriber, "cannot send the inlet status: {e}");
+            }
+        });
+        Ok(())
+    }
+
     /// Create a session replacer.
     ///
     /// This returns a function that accepts the previous ping address (e.g.
@@ -609,23 +1101,32 @@ impl InMemoryNode {
         node_manager: Arc<NodeManager>,
         ctx: Arc<Context>,
         connection: Connection,
+        is_connected: bool,
+        reconnect_count: Arc<AtomicU32>,
         inlet_address: Address,
+        alias: String,
         bind: String,
         addr: MultiAddr,
         prefix_route: Route,
         suffix_route: Route,
         authorized: Option<Identifier>,
         access: Arc<dyn IncomingAccessControl>,
+        options: InletOptions,
     ) -> Replacer {
         let connection_arc = Arc::new(Mutex::new(connection.clone()));
         let inlet_address_arc = Arc::new(Mutex::new(inlet_address));
+        // a pending inlet is connected for the first time by the replacer,
+        // which is not counted as a reconnection
+        let is_connected = Arc::new(AtomicBool::new(is_connected));
         let node_manager = node_manager.clone();
 
         Box::new(move |previous_addr| {
+            let alias = alias.clone();
             let addr = addr.clone();
             let authorized = authorized.clone();
             let bind = bind.clone();
             let access = access.clone();
+            let options = options.clone();
             let ctx = ctx.clone();
             let connection_arc = connection_arc.clone();
             let inlet_address_arc = inlet_address_arc.clone();
@@ -634,10 +1135,24 @@ impl InMemoryNode {
             let suffix_route = suffix_route.clone();
             let previous_connection = connection_arc.lock().unwrap().clone();
             let node_manager = node_manager.clone();
+            let is_connected = is_connected.clone();
+            let reconnect_count = reconnect_count.clone();
             Box::pin(async move {
                 debug!(%previous_addr, %addr, "creating new tcp inlet");
                 // The future that recreates the inlet:
                 let f = async {
+                    // When the connections are held, the inlet is kept and only its route
+                    // to the outlet is replaced once the new connection is established.
+                    // A pending inlet is created held, so that its first clients wait for
+                    // its first connection
+                    let hold_on_reconnect = options.hold_on_reconnect.is_some()
+                        || !is_connected.load(Ordering::Relaxed);
+                    if hold_on_reconnect {
+                        node_manager
+                            .tcp_transport
+                            .hold_inlet(inlet_address.clone())?;
+                    }
+
                     //stop/delete previous secure channels
                     for encryptor in &previous_connection.secure_channel_encryptors {
                         let result = node_manager.delete_secure_channel(&ctx, encryptor).await;
@@ -657,12 +1172,14 @@ impl InMemoryNode {
                     }
 
                     // The previous inlet worker needs to be stopped:
-                    if let Err(error) = node_manager
-                        .tcp_transport
-                        .stop_inlet(inlet_address.clone())
-                        .await
-                    {
-                        debug!("cannot stop inlet `{inlet_address}`: {error}");
+                    if !hold_on_reconnect {
+                        if let Err(error) = node_manager
+                            .tcp_transport
+                            .stop_inlet(inlet_address.clone())
+                            .await
+                        {
+                            debug!("cannot stop inlet `{inlet_address}`: {error}");
+                        }
                     }
 
                     // Now a connection attempt is made
@@ -674,6 +1191,8 @@ impl InMemoryNode {
                             authorized,
                             None,
                             Some(MAX_CONNECT_TIME),
+                            options.keepalive,
+                            options.egress_bind,
                         )
                         .await?;
                     *connection_arc.lock().unwrap() = new_connection.clone();
@@ -682,12 +1201,24 @@ impl InMemoryNode {
 
                     //we expect a fully normalized MultiAddr
                     let normalized_route = route![prefix_route, connection_route, suffix_route];
-                    let options = TcpInletOptions::new().with_incoming_access_control(access);
+                    if hold_on_reconnect {
+                        node_manager
+                            .tcp_transport
+                            .resume_inlet(inlet_address, normalized_route)?;
+                        return Ok(new_connection.transport_route());
+                    }
+                    // The address of the network interface may have changed since the
+                    // inlet was created
+                    let bind = resolve_listen_addr(bind, options.listen_interface.as_deref())?;
 
                     // Finally attempt to create a new inlet using the new route:
                     let new_inlet_address = node_manager
                         .tcp_transport
-                        .create_inlet(bind, normalized_route, options)
+                        .create_inlet(
+                            bind,
+                            normalized_route,
+                            options.tcp_inlet_options(&alias, access),
+                        )
                         .await?
                         .1;
                     *inlet_address_arc.lock().unwrap() = new_inlet_address;
@@ -705,13 +1236,60 @@ impl InMemoryNode {
                         warn!(%addr, err = %e, "error creating new tcp inlet");
                         Err(e)
                     }
-                    Ok(Ok(route)) => Ok(route),
+                    Ok(Ok(route)) => {
+                        if is_connected.swap(true, Ordering::Relaxed) {
+                            let count = reconnect_count.fetch_add(1, Ordering::Relaxed) + 1;
+                            info!(%addr, %count, "tcp inlet reconnected");
+                        }
+                        Ok(route)
+                    }
                 }
             })
         })
     }
 }
 
+/// Return the address an inlet must listen at. When a network interface is given,
+/// the inlet listens at the current address of that interface, on the port of `listen_addr`
+fn resolve_listen_addr(listen_addr: String, listen_interface: Option<&str>) -> Result<String> {
+    match listen_interface {
+        Some(interface) => {
+            let port = SocketAddr::from_str(&listen_addr)
+                .map_err(|e| ApiError::core(format!("invalid listen address {listen_addr}: {e}")))?
+                .port();
+            Ok(interface_socket_address(&SystemInterfaceLookup, interface, port)?.to_string())
+        }
+        None => Ok(listen_addr),
+    }
+}
+
+/// Check that the address used for the connection of an inlet to its outlet is a local address
+fn validate_egress_bind(egress_bind: SocketAddr) -> Result<()> {
+    // binding a UDP socket doesn't use any TCP port and fails if the ip address
+    // is not the address of a local network interface
+    UdpSocket::bind(SocketAddr::new(egress_bind.ip(), 0)).map_err(|e| {
+        ApiError::core(format!(
+            "the egress address {} is not a local address: {e}",
+            egress_bind.ip()
+        ))
+    })?;
+    Ok(())
+}
+
+/// Stop overriding the log level of an inlet which is deleted or could not be created.
+/// A failure is only logged since the inlet itself is not affected
+fn reset_inlet_log_level(alias: &str) {
+    if let Err(e) = remove_inlet_log_level(alias) {
+        warn!(%alias, "Failed to reset the log level of the inlet: {e}");
+    }
+}
+
+/// Return the number of tunnels prewarmed by an inlet created with the given options,
+/// if it prewarms some tunnels
+fn prewarm_pool_size(options: &TcpInletOptions) -> Option<u32> {
+    Some(options.prewarm_pool_size() as u32).filter(|size| *size > 0)
+}
+
 #[async_trait]
 pub trait Inlets {
     async fn create_inlet(
@@ -721,7 +1299,7 @@ pub trait Inlets {
         outlet_addr: &MultiAddr,
         alias: &Option<String>,
         authorized_identifier: &Option<Identifier>,
-        wait_for_outlet_timeout: Duration,
+        options: &InletOptions,
     ) -> miette::Result<Reply<InletStatus>>;
 
     async fn show_inlet(
@@ -730,7 +1308,51 @@ pub trait Inlets {
         inlet_alias: &str,
     ) -> miette::Result<Reply<InletStatus>>;
 
+    /// Return the inlets matching a filter
+    async fn list_inlets(
+        &self,
+        ctx: &Context,
+        filter: &InletFilter,
+    ) -> miette::Result<Vec<InletStatus>>;
+
+    /// Return the inlets, grouped by the normalized route to their outlet,
+    /// so that the inlets pointing at the same outlet can be found
+    async fn list_inlets_grouped_by_route(
+        &self,
+        ctx: &Context,
+    ) -> miette::Result<HashMap<String, Vec<InletStatus>>>;
+
     async fn delete_inlet(&self, ctx: &Context, inlet_alias: &str) -> miette::Result<Reply<()>>;
+
+    /// Delete the inlet bound to an address and return its last status.
+    /// When the IP of the address is unspecified, the inlet is only selected by port
+    async fn delete_inlet_by_addr(
+        &self,
+        ctx: &Context,
+        addr: SocketAddr,
+    ) -> miette::Result<Reply<InletStatus>>;
+
+    /// Drain an inlet: it stops accepting new connections, and its existing connections
+    /// are closed if they are still open after `drain_timeout`
+    async fn drain_inlet(
+        &self,
+        ctx: &Context,
+        inlet_alias: &str,
+        drain_timeout: Duration,
+    ) -> miette::Result<Reply<InletStatus>>;
+
+    /// Subscribe to the status changes of an inlet.
+    /// The first reply of the subscription contains the current status of the inlet
+    async fn watch_inlet(&self, ctx: &Context, inlet_alias: &str) -> miette::Result<Subscription>;
+
+    /// Wait until an inlet is connected to its outlet and return its status.
+    /// A failed reply is returned if the inlet is not connected before the timeout
+    async fn wait_until_inlet_connected(
+        &self,
+        ctx: &Context,
+        inlet_alias: &str,
+        timeout: Duration,
+    ) -> miette::Result<Reply<InletStatus>>;
 }
 
 #[async_trait]
@@ -742,9 +1364,10 @@ impl Inlets for BackgroundNode {
         outlet_addr: &MultiAddr,
         alias: &Option<String>,
         authorized_identifier: &Option<Identifier>,
-        wait_for_outlet_timeout: Duration,
+        options: &InletOptions,
     ) -> miette::Result<Reply<InletStatus>> {
-        self.add_policy_to_project(ctx, "tcp-inlet").await?;
+        self.add_policy_to_project(ctx, Resource::TCP_INLET.as_str())
+            .await?;
         let request = {
             let via_project = outlet_addr.matches(0, &[Project::CODE.into()]);
             let mut payload = if via_project {
@@ -766,7 +1389,7 @@ impl Inlets for BackgroundNode {
             if let Some(a) = alias {
                 payload.set_alias(a.to_string())
             }
-            payload.set_wait_ms(wait_for_outlet_timeout.as_millis() as u64);
+            payload.set_options(options);
             Request::post("/node/inlet").body(payload)
         };
         self.ask_and_get_reply(ctx, request).await
@@ -781,8 +1404,796 @@ impl Inlets for BackgroundNode {
         self.ask_and_get_reply(ctx, request).await
     }
 
+    async fn list_inlets(
+        &self,
+        ctx: &Context,
+        filter: &InletFilter,
+    ) -> miette::Result<Vec<InletStatus>> {
+        let inlets: InletList = self.ask(ctx, Request::get("/node/inlet")).await?;
+        Ok(inlets
+            .list
+            .into_iter()
+            .filter(|inlet| filter.matches(inlet))
+            .collect())
+    }
+
+    async fn list_inlets_grouped_by_route(
+        &self,
+        ctx: &Context,
+    ) -> miette::Result<HashMap<String, Vec<InletStatus>>> {
+        let inlets: InletList = self.ask(ctx, Request::get("/node/inlet")).await?;
+        Ok(inlets.grouped_by_route())
+    }
+
     async fn delete_inlet(&self, ctx: &Context, inlet_alias: &str) -> miette::Result<Reply<()>> {
         let request = Request::delete(format!("/node/inlet/{inlet_alias}"));
         self.tell_and_get_reply(ctx, request).await
     }
+
+    async fn delete_inlet_by_addr(
+        &self,
+        ctx: &Context,
+        addr: SocketAddr,
+    ) -> miette::Result<Reply<InletStatus>> {
+        let request = Request::delete("/node/inlet").body(DeleteInletByAddr::new(addr));
+        self.ask_and_get_reply(ctx, request).await
+    }
+
+    async fn drain_inlet(
+        &self,
+        ctx: &Context,
+        inlet_alias: &str,
+        drain_timeout: Duration,
+    ) -> miette::Result<Reply<InletStatus>> {
+        let request = Request::post(format!("/node/inlet/{inlet_alias}/drain"))
+            .body(DrainInlet::new(drain_timeout));
+        self.ask_and_get_reply(ctx, request).await
+    }
+
+    async fn watch_inlet(&self, ctx: &Context, inlet_alias: &str) -> miette::Result<Subscription> {
+        let request = Request::get(format!("/node/inlet/{inlet_alias}/watch"));
+        self.subscribe(ctx, request).await
+    }
+
+    async fn wait_until_inlet_connected(
+        &self,
+        ctx: &Context,
+        inlet_alias: &str,
+        timeout: Duration,
+    ) -> miette::Result<Reply<InletStatus>> {
+        let request = Request::get(format!("/node/inlet/{inlet_alias}/wait"))
+            .body(WaitForInlet::new(timeout));
+        let mut subscription = self.subscribe(ctx, request).await?;
+        // the first reply is the current status of the inlet, the node only sends
+        // another reply if the inlet is not connected yet
+        let reply: Reply<InletStatus> = subscription.next().await?;
+        match &reply {
+            Reply::Successful(inlet) if inlet.status != ConnectionStatus::Up => {
+                subscription.next().await
+            }
+            _ => Ok(reply),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ockam::identity::IdentitySecureChannelLocalInfo;
+    use ockam_abac::Expr;
+    use std::collections::BTreeMap;
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::net::TcpListener;
+
+    use ockam_core::{LocalMessage, RelayMessage, TransportMessage};
+    use ockam_transport_tcp::{
+        DEFAULT_INLET_BUFFER_SIZE, MAX_INLET_BUFFER_SIZE, MIN_INLET_BUFFER_SIZE,
+    };
+    use tokio::net::TcpStream;
+
+    use super::*;
+    use crate::address::get_free_address;
+    use crate::nodes::models::portal::parse_label;
+    use crate::nodes::NODEMANAGER_ADDR;
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn create_inlet_without_waiting_for_the_outlet(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+
+        // nothing is listening at that address, the outlet doesn't exist yet
+        let outlet_address = get_free_address().unwrap();
+        let outlet_addr = MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{}/service/outlet",
+            outlet_address.port()
+        ))
+        .unwrap();
+
+        let inlet = timeout(
+            Duration::from_secs(1),
+            handler.node_manager.create_inlet(
+                context,
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                outlet_addr,
+                None,
+                InletOptions::default()
+                    .with_wait_connection(false)
+                    .with_wait_for_outlet_timeout(Duration::from_secs(5)),
+            ),
+        )
+        .await
+        .expect("the inlet creation must not wait for the outlet")?;
+
+        assert_eq!(inlet.status, ConnectionStatus::Pending);
+        assert_eq!(
+            handler
+                .node_manager
+                .show_inlet("inlet")
+                .await
+                .unwrap()
+                .status,
+            ConnectionStatus::Pending
+        );
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn connect_a_pending_inlet_when_a_first_client_connects(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let inlet = create_pending_inlet(context, &handler.node_manager, "inlet").await?;
+
+        // the medic checks the sessions every 3 seconds,
+        // but the inlet is not connected while there is no client
+        sleep(Duration::from_secs(4)).await;
+        let status = handler
+            .node_manager
+            .show_inlet("inlet")
+            .await
+            .unwrap()
+            .status;
+        assert_eq!(status, ConnectionStatus::Pending);
+
+        // the first client triggers the connection to the outlet, which is not reachable
+        let _client = TcpStream::connect(&inlet.bind_addr).await.unwrap();
+        let connecting = async {
+            loop {
+                let inlet = handler.node_manager.show_inlet("inlet").await.unwrap();
+                if inlet.status != ConnectionStatus::Pending {
+                    return inlet.status;
+                }
+                sleep(INLET_WATCH_INTERVAL).await;
+            }
+        };
+        let status = timeout(Duration::from_secs(5), connecting).await.unwrap();
+        assert_ne!(status, ConnectionStatus::Up);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn connect_a_pending_inlet_with_prewarmed_tunnels_right_away(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let outlet_address = get_free_address().unwrap();
+        let outlet_addr = MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{}/service/outlet",
+            outlet_address.port()
+        ))
+        .unwrap();
+        handler
+            .node_manager
+            .create_inlet(
+                context,
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                outlet_addr,
+                None,
+                InletOptions::default()
+                    .with_wait_connection(false)
+                    .with_wait_for_outlet_timeout(Duration::from_secs(1))
+                    .with_prewarm(Some(1)),
+            )
+            .await?;
+
+        // the connection to the outlet, which is not reachable, is attempted without any client
+        let connecting = async {
+            loop {
+                let inlet = handler.node_manager.show_inlet("inlet").await.unwrap();
+                if inlet.status != ConnectionStatus::Pending {
+                    return inlet.status;
+                }
+                sleep(INLET_WATCH_INTERVAL).await;
+            }
+        };
+        let status = timeout(Duration::from_secs(5), connecting).await.unwrap();
+        assert_ne!(status, ConnectionStatus::Up);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn delete_inlet_by_bind_address(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+
+        let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+        let mut bind_addrs = vec![];
+        for alias in ["inlet-1", "inlet-2"] {
+            let inlet = handler
+                .node_manager
+                .create_inlet(
+                    context,
+                    "127.0.0.1:0".to_string(),
+                    Some(alias.to_string()),
+                    route![],
+                    route![],
+                    outlet_addr.clone(),
+                    None,
+                    InletOptions::default().with_wait_connection(false),
+                )
+                .await?;
+            bind_addrs.push(SocketAddr::from_str(&inlet.bind_addr).unwrap());
+        }
+
+        let deleted = handler
+            .node_manager
+            .delete_inlet_by_addr(bind_addrs[0])
+            .await?;
+        assert_eq!(deleted.alias, "inlet-1");
+        assert!(handler.node_manager.show_inlet("inlet-1").await.is_none());
+        assert!(handler.node_manager.show_inlet("inlet-2").await.is_some());
+
+        // the inlet can also be selected with its port only
+        let port_only = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), bind_addrs[1].port());
+        let deleted = handler.node_manager.delete_inlet_by_addr(port_only).await?;
+        assert_eq!(deleted.alias, "inlet-2");
+        assert!(handler.node_manager.show_inlet("inlet-2").await.is_none());
+
+        let error = handler
+            .node_manager
+            .delete_inlet_by_addr(bind_addrs[0])
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().kind, Kind::NotFound);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 10_000)]
+    async fn count_the_inlet_reconnections(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let inlet = create_pending_inlet(context, &handler.node_manager, "inlet").await?;
+        assert_eq!(inlet.reconnect_count, Some(0));
+
+        let reconnect_count = handler
+            .node_manager
+            .registry
+            .inlets
+            .get("inlet")
+            .await
+            .unwrap()
+            .reconnect_count;
+        let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+        let mut replacer = InMemoryNode::portal_replacer(
+            handler.node_manager.node_manager.clone(),
+            Arc::new(context.async_try_clone().await?),
+            Connection::pending(&outlet_addr),
+            true,
+            reconnect_count,
+            Address::from_string(inlet.worker_addr),
+            "inlet".to_string(),
+            "127.0.0.1:0".to_string(),
+            outlet_addr,
+            route![],
+            route![],
+            None,
+            Arc::new(AllowAll),
+            InletOptions::default(),
+        );
+
+        // force two reconnections
+        for _ in 0..2 {
+            replacer(route![]).await?;
+        }
+        let inlet = handler.node_manager.show_inlet("inlet").await.unwrap();
+        assert_eq!(inlet.reconnect_count, Some(2));
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn create_inlet_requiring_a_credential(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager: &NodeManager = &handler.node_manager;
+
+        // the inlet policy accepts everyone
+        let resource = Resource::new("inlet");
+        node_manager
+            .cli_state
+            .set_policy(&resource, &Action::HANDLE_MESSAGE, &Expr::Bool(true))
+            .await?;
+
+        let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+        let (_, access_control) = node_manager
+            .create_inlet(
+                Connection::pending(&outlet_addr),
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                &InletOptions::default().with_require_credential(true),
+            )
+            .await?;
+
+        let identities_creation = node_manager.identities().identities_creation();
+        let uncredentialed = identities_creation.create_identity().await?;
+        let credentialed = identities_creation.create_identity().await?;
+        node_manager
+            .identity_attributes_repository()
+            .put_attribute_value(
+                &credentialed,
+                TRUST_CONTEXT_ID.to_vec(),
+                node_manager.trust_context_id().unwrap().into_bytes(),
+            )
+            .await?;
+
+        // a peer without credential is rejected
+        let message = message_from(&uncredentialed)?;
+        assert!(!access_control.is_authorized(&message).await?);
+
+        // a peer with a credential for the node trust context is accepted
+        let message = message_from(&credentialed)?;
+        assert!(access_control.is_authorized(&message).await?);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn create_inlet_with_an_idle_timeout(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager: &NodeManager = &handler.node_manager;
+
+        let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+        let idle_timeout = Some(Duration::from_secs(60));
+        let (inlet, _) = node_manager
+            .create_inlet(
+                Connection::pending(&outlet_addr),
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                &InletOptions::default().with_idle_timeout(idle_timeout),
+            )
+            .await?;
+
+        // the idle timeout is reported in the inlet status
+        assert_eq!(inlet.idle_timeout, idle_timeout);
+        let inlet = node_manager.show_inlet("inlet").await.unwrap();
+        assert_eq!(inlet.idle_timeout, idle_timeout);
+        let inlets = node_manager.list_inlets().await;
+        assert_eq!(inlets.list[0].idle_timeout, idle_timeout);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 15_000)]
+    async fn create_inlet_with_prewarmed_tunnels(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager: &NodeManager = &handler.node_manager;
+
+        // the inlet policies accept everyone
+        for alias in ["inlet", "capped"] {
+            node_manager
+                .cli_state
+                .set_policy(
+                    &Resource::new(alias),
+                    &actions::HANDLE_MESSAGE,
+                    &Expr::Bool(true),
+                )
+                .await?;
+        }
+
+        // the target of the outlet accepts the connections of the prewarmed tunnels
+        let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        node_manager
+            .tcp_transport
+            .create_outlet(
+                "outlet",
+                target.local_addr().unwrap().to_string(),
+                TcpOutletOptions::new(),
+            )
+            .await?;
+        let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+        let mut connection = Connection::pending(&outlet_addr);
+        connection.normalized_addr = outlet_addr;
+
+        let (inlet, _) = node_manager
+            .create_inlet(
+                connection.clone(),
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                &InletOptions::default().with_prewarm(Some(2)),
+            )
+            .await?;
+        assert_eq!(inlet.prewarm, Some(2));
+
+        // with a prewarm of 2, two tunnels are established before any client connects
+        let _first = target.accept().await.unwrap();
+        let _second = target.accept().await.unwrap();
+        while node_manager.show_inlet("inlet").await.unwrap().prewarmed != Some(2) {
+            sleep(Duration::from_millis(50)).await;
+        }
+        let inlets = node_manager.list_inlets().await;
+        assert_eq!(inlets.list[0].prewarm, Some(2));
+
+        // the number of prewarmed tunnels is limited by the maximum number of connections
+        let (capped, _) = node_manager
+            .create_inlet(
+                connection,
+                "127.0.0.1:0".to_string(),
+                Some("capped".to_string()),
+                route![],
+                route![],
+                &InletOptions::default()
+                    .with_prewarm(Some(3))
+                    .with_max_connections(Some(1)),
+            )
+            .await?;
+        assert_eq!(capped.prewarm, Some(1));
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn create_inlet_with_a_buffer_size(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager: &NodeManager = &handler.node_manager;
+
+        async fn create_inlet(
+            node_manager: &NodeManager,
+            alias: &str,
+            buffer_size: Option<usize>,
+        ) -> Result<InletStatus> {
+            let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+            let (inlet, _) = node_manager
+                .create_inlet(
+                    Connection::pending(&outlet_addr),
+                    "127.0.0.1:0".to_string(),
+                    Some(alias.to_string()),
+                    route![],
+                    route![],
+                    &InletOptions::default().with_buffer_size(buffer_size),
+                )
+                .await?;
+            Ok(inlet)
+        }
+
+        // the default buffer size is used when no size is configured
+        let inlet = create_inlet(node_manager, "default", None).await?;
+        assert_eq!(inlet.buffer_size, Some(DEFAULT_INLET_BUFFER_SIZE));
+
+        // the configured buffer size is reported in the inlet status
+        let inlet = create_inlet(node_manager, "large", Some(1024 * 1024)).await?;
+        assert_eq!(inlet.buffer_size, Some(1024 * 1024));
+        let inlet = node_manager.show_inlet("large").await.unwrap();
+        assert_eq!(inlet.buffer_size, Some(1024 * 1024));
+
+        // a buffer size out of the supported range is rejected
+        for buffer_size in [MIN_INLET_BUFFER_SIZE - 1, MAX_INLET_BUFFER_SIZE + 1] {
+            let error = create_inlet(node_manager, "invalid", Some(buffer_size))
+                .await
+                .unwrap_err();
+            assert_eq!(error.code().kind, Kind::Invalid);
+        }
+        assert!(node_manager.show_inlet("invalid").await.is_none());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn list_inlets_grouped_by_route(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager: &NodeManager = &handler.node_manager;
+
+        async fn create_inlet(node_manager: &NodeManager, alias: &str, to: Route) -> Result<()> {
+            let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+            node_manager
+                .create_inlet(
+                    Connection::pending(&outlet_addr),
+                    "127.0.0.1:0".to_string(),
+                    Some(alias.to_string()),
+                    route![],
+                    to,
+                    &InletOptions::default(),
+                )
+                .await?;
+            Ok(())
+        }
+
+        // two inlets share the same route to their outlet
+        create_inlet(node_manager, "db-2", route!["db"]).await?;
+        create_inlet(node_manager, "db-1", route!["db"]).await?;
+        create_inlet(node_manager, "web", route!["web"]).await?;
+
+        let groups = node_manager.list_inlets_grouped_by_route().await;
+        assert_eq!(groups.len(), 2);
+        let aliases = |route: Route| -> Vec<String> {
+            groups[&route.to_string()]
+                .iter()
+                .map(|inlet| inlet.alias.clone())
+                .collect()
+        };
+        assert_eq!(aliases(route!["db"]), vec!["db-1", "db-2"]);
+        assert_eq!(aliases(route!["web"]), vec!["web"]);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn create_inlet_with_labels(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager: &NodeManager = &handler.node_manager;
+
+        let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+        let labels = BTreeMap::from([
+            ("env".to_string(), "prod".to_string()),
+            ("team".to_string(), "data".to_string()),
+        ]);
+        let (inlet, _) = node_manager
+            .create_inlet(
+                Connection::pending(&outlet_addr),
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                &InletOptions::default().with_labels(labels.clone()),
+            )
+            .await?;
+
+        // the labels are reported in the inlet status
+        assert_eq!(inlet.labels, Some(labels.clone()));
+        let inlet = node_manager.show_inlet("inlet").await.unwrap();
+        assert_eq!(inlet.labels, Some(labels.clone()));
+        let inlets = node_manager.list_inlets().await;
+        assert_eq!(inlets.list[0].labels, Some(labels.clone()));
+
+        // the inlets can be selected by label
+        assert!(inlet.has_labels(&BTreeMap::from([("env".to_string(), "prod".to_string())])));
+        assert!(inlet.has_labels(&labels));
+        assert!(!inlet.has_labels(&BTreeMap::from([("env".to_string(), "dev".to_string())])));
+
+        context.stop().await
+    }
+
+    #[test]
+    fn parse_inlet_labels() {
+        assert_eq!(
+            parse_label("env=prod").unwrap(),
+            ("env".to_string(), "prod".to_string())
+        );
+        assert_eq!(
+            parse_label("app.version=").unwrap(),
+            ("app.version".to_string(), "".to_string())
+        );
+        for invalid in ["env", "=prod", "env=prod ction", "-env=prod", "env=a/b"] {
+            assert!(parse_label(invalid).is_err(), "{invalid}");
+        }
+
+        // the labels sent in a request are validated
+        let mut request = CreateInlet::via_project(
+            "127.0.0.1:0".to_string(),
+            MultiAddr::from_str("/service/outlet").unwrap(),
+            route![],
+            route![],
+        );
+        request.set_labels(BTreeMap::from([("env".to_string(), "prod".to_string())]));
+        assert!(request.labels().is_ok());
+        request.set_labels(BTreeMap::from([("".to_string(), "prod".to_string())]));
+        assert!(request.labels().is_err());
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn create_inlet_with_a_non_local_egress_address(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+
+        // 192.0.2.1 is reserved for documentation and is not assigned to any local interface
+        let outlet_addr = MultiAddr::from_str("/service/outlet").unwrap();
+        let egress_bind = SocketAddr::from_str("192.0.2.1:0").unwrap();
+        let result = handler
+            .node_manager
+            .create_inlet(
+                context,
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                outlet_addr,
+                None,
+                InletOptions::default()
+                    .with_wait_connection(false)
+                    .with_egress_bind(Some(egress_bind)),
+            )
+            .await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("is not a local address"), "{error}");
+        assert!(handler.node_manager.show_inlet("inlet").await.is_none());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 30_000)]
+    async fn watch_inlet_status_changes(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+
+        // the inlet status changes as soon as the node tries to connect to the outlet
+        let outlet_address = get_free_address().unwrap();
+        let outlet_addr = MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{}/service/outlet",
+            outlet_address.port()
+        ))
+        .unwrap();
+        let inlet = handler
+            .node_manager
+            .create_inlet(
+                context,
+                "127.0.0.1:0".to_string(),
+                Some("inlet".to_string()),
+                route![],
+                route![],
+                outlet_addr,
+                None,
+                InletOptions::default()
+                    .with_wait_connection(false)
+                    .with_wait_for_outlet_timeout(Duration::from_secs(1)),
+            )
+            .await?;
+
+        let subscriber = context
+            .new_detached(Address::random_tagged("subscriber"), AllowAll, AllowAll)
+            .await?;
+        let mut buf = Vec::new();
+        Request::get("/node/inlet/inlet/watch").encode(&mut buf)?;
+        subscriber.send(route![NODEMANAGER_ADDR], buf).await?;
+        let mut subscription = Subscription::new(subscriber);
+
+        // the first reply is the current status
+        let first = subscription.next::<InletStatus>().await.unwrap();
+        let first = first.success().unwrap();
+
+        // then a status change is delivered once a client triggers the connection
+        let _client = TcpStream::connect(&inlet.bind_addr).await.unwrap();
+        let second = subscription.next::<InletStatus>().await.unwrap();
+        let second = second.success().unwrap();
+        assert_eq!(second.alias, "inlet");
+        assert_ne!(first.status, second.status);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 30_000)]
+    async fn stop_watching_an_inlet_once_the_subscriber_is_gone(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        let inlet = create_pending_inlet(context, node_manager, "inlet").await?;
+
+        let subscriber = context
+            .new_detached(Address::random_tagged("subscriber"), AllowAll, AllowAll)
+            .await?;
+        let req = Request::get("/node/inlet/inlet/watch").into_parts().0;
+        let watcher = node_manager
+            .watch_inlet(context, req, inlet.clone(), route![subscriber.address()])
+            .await?;
+
+        // the status is sent again when it doesn't change
+        let mut subscription = Subscription::new(subscriber);
+        let heartbeat = subscription.next::<InletStatus>().await.unwrap();
+        assert_eq!(heartbeat.success().unwrap().status, inlet.status);
+
+        // the watch stops at the next heartbeat once the subscriber is gone
+        drop(subscription);
+        timeout(2 * INLET_WATCH_HEARTBEAT, watcher)
+            .await
+            .expect("the watch must stop")
+            .unwrap();
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn wait_until_the_inlet_is_connected(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        create_pending_inlet(context, &handler.node_manager, "inlet").await?;
+
+        // the inlet is connected by the medic once the outlet is reachable
+        let connect = async {
+            sleep(Duration::from_millis(200)).await;
+            let medic_handle = &handler.node_manager.medic_handle;
+            medic_handle.remove_session("inlet-inlet");
+            medic_handle.add_session(Session::new(route![], "inlet-inlet".to_string()));
+            Instant::now()
+        };
+        let wait = handler
+            .node_manager
+            .wait_until_inlet_connected("inlet", Duration::from_secs(10));
+        let (connected_at, inlet) = tokio::join!(connect, wait);
+
+        // the wait returns as soon as the inlet status is checked again
+        assert_eq!(inlet?.status, ConnectionStatus::Up);
+        assert!(connected_at.elapsed() <= 2 * INLET_WATCH_INTERVAL);
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test(timeout = 5000)]
+    async fn wait_until_the_inlet_is_connected_with_a_timeout(context: &mut Context) -> Result<()> {
+        let handler = crate::test_utils::start_manager_for_tests(context).await?;
+        create_pending_inlet(context, &handler.node_manager, "inlet").await?;
+
+        let result = handler
+            .node_manager
+            .wait_until_inlet_connected("inlet", Duration::from_secs(1))
+            .await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::Timeout);
+
+        // a missing inlet is reported right away
+        let result = handler
+            .node_manager
+            .wait_until_inlet_connected("missing", Duration::from_secs(10))
+            .await;
+        assert_eq!(result.unwrap_err().code().kind, Kind::NotFound);
+
+        context.stop().await
+    }
+
+    /// Create an inlet to an outlet which is not reachable, without waiting for the connection
+    async fn create_pending_inlet(
+        context: &Context,
+        node_manager: &InMemoryNode,
+        alias: &str,
+    ) -> Result<InletStatus> {
+        let outlet_address = get_free_address().unwrap();
+        let outlet_addr = MultiAddr::from_str(&format!(
+            "/ip4/127.0.0.1/tcp/{}/service/outlet",
+            outlet_address.port()
+        ))
+        .unwrap();
+        node_manager
+            .create_inlet(
+                context,
+                "127.0.0.1:0".to_string(),
+                Some(alias.to_string()),
+                route![],
+                route![],
+                outlet_addr,
+                None,
+                InletOptions::default()
+                    .with_wait_connection(false)
+                    .with_wait_for_outlet_timeout(Duration::from_secs(1)),
+            )
+            .await
+    }
+
+    /// Return a message received from a given identity via a secure channel
+    fn message_from(identifier: &Identifier) -> Result<RelayMessage> {
+        let local_message = LocalMessage::new(
+            TransportMessage::v1(route![], route![], vec![]),
+            IdentitySecureChannelLocalInfo::mark(vec![], identifier.clone())?,
+        );
+        Ok(RelayMessage::new(
+            Address::random_local(),
+            Address::random_local(),
+            local_message,
+        ))
+    }
 }
@@ -238,6 +238,8 @@ impl InMemoryNode {
                 authorized.clone(),
                 None,
                 None,
+                None,
+                None,
             )
             .await?;
         connection.add_default_consumers(connection_ctx.clone());
@@ -342,6 +344,8 @@ impl InMemoryNode {
                             authorized,
                             None,
                             Some(MAX_CONNECT_TIME),
+                            None,
+                            None,
                         )
                         .await?;
                     connection.add_default_consumers(ctx.clone());
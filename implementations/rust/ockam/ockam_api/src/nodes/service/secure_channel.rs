@@ -277,6 +277,8 @@ impl NodeManager {
                 None,
                 credential.clone(),
                 timeout,
+                None,
+                None,
             )
             .await?;
         let sc = self
@@ -340,6 +342,39 @@ impl NodeManager {
         }
     }
 
+    /// Retrieve a new credential from the authority of the node trust context,
+    /// even if a valid credential has already been retrieved
+    pub async fn refresh_credential(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+    ) -> Result<Option<CredentialAndPurposeKey>> {
+        if let Some(tc) = self.trust_context.as_ref() {
+            debug!("refreshing the credential");
+            tc.refresh_credential(ctx, identifier).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Return a credential issued by a specific authority.
+    /// That authority must be configured in one of the trust contexts known to this node
+    pub async fn get_credential_from_authority(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+        authority: &MultiAddr,
+    ) -> Result<Option<CredentialAndPurposeKey>> {
+        debug!(%authority, "getting a credential from a specific authority");
+        let trust_context = self
+            .cli_state
+            .get_trust_context_by_authority_route(authority)
+            .await?
+            .trust_context(&self.tcp_transport, self.secure_channels.clone())
+            .await?;
+        trust_context.get_credential(ctx, identifier).await
+    }
+
     pub(crate) async fn create_secure_channel_internal(
         &self,
         ctx: &Context,
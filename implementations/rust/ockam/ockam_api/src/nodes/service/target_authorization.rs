@@ -0,0 +1,183 @@
+use std::fmt::{Debug, Formatter};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use ockam_abac::expr::{int, str};
+use ockam_abac::{eval, Action, Env, Expr, PoliciesRepository, Resource};
+use ockam_core::{async_trait, Result};
+use ockam_transport_tcp::{Socks5Target, TargetAuthorization};
+use tracing::{debug, warn};
+
+/// Authorizes the targets requested by the clients of SOCKS5 inlets with the policy
+/// set for a resource and an action.
+///
+/// The policy expression can refer to the `target.host`, `target.address` and `target.port`
+/// attributes. The policy is evaluated for each address the target host resolves to.
+/// If no policy exists for the resource and the action, all the targets are refused.
+pub struct PolicyTargetAuthorization {
+    resource: Resource,
+    action: Action,
+    policies: Arc<dyn PoliciesRepository>,
+}
+
+impl Debug for PolicyTargetAuthorization {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PolicyTargetAuthorization")
+            .field("resource", &self.resource)
+            .field("action", &self.action)
+            .finish()
+    }
+}
+
+impl PolicyTargetAuthorization {
+    pub fn new(policies: Arc<dyn PoliciesRepository>, resource: Resource, action: Action) -> Self {
+        Self {
+            resource,
+            action,
+            policies,
+        }
+    }
+
+    /// Return the environment used to evaluate the policy for a target
+    /// and one of its resolved addresses
+    fn environment(&self, target: &Socks5Target, address: IpAddr) -> Env {
+        let mut env = Env::new();
+        env.put("resource.id", str(self.resource.as_str()));
+        env.put("action.id", str(self.action.as_str()));
+        env.put("target.host", str(target.host.as_str()));
+        env.put("target.address", str(address.to_string()));
+        env.put("target.port", int(target.port));
+        env
+    }
+}
+
+#[async_trait]
+impl TargetAuthorization for PolicyTargetAuthorization {
+    async fn is_authorized(&self, target: &Socks5Target, address: IpAddr) -> Result<bool> {
+        let Some(expr) = self
+            .policies
+            .get_policy(&self.resource, &self.action)
+            .await?
+        else {
+            debug! {
+                resource = %self.resource,
+                action   = %self.action,
+                %target,
+                "no policy found; target refused"
+            }
+            return Ok(false);
+        };
+
+        match eval(&expr, &self.environment(target, address)) {
+            Ok(Expr::Bool(b)) => {
+                debug! {
+                    policy        = %expr,
+                    %target,
+                    is_authorized = %b,
+                    "target policy evaluated"
+                }
+                Ok(b)
+            }
+            Ok(x) => {
+                warn! {
+                    policy = %expr,
+                    %target,
+                    expr   = %x,
+                    "evaluation did not yield a boolean result"
+                }
+                Ok(false)
+            }
+            Err(e) => {
+                warn! {
+                    policy = %expr,
+                    %target,
+                    err    = %e,
+                    "policy evaluation failed"
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_abac::{parse, PolicySqlxDatabase};
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_authorize_targets_with_a_policy() -> Result<()> {
+        let policies = PolicySqlxDatabase::create().await?;
+        let authorization = PolicyTargetAuthorization::new(
+            policies.clone(),
+            Resource::TCP_OUTLET,
+            Action::CONNECT_TO_TARGET,
+        );
+        let target = Socks5Target::new("example.com", 443);
+        let address = IpAddr::from_str("93.184.216.34").unwrap();
+
+        // no policy
+        assert!(!authorization.is_authorized(&target, address).await?);
+
+        let policy = parse(r#"(and (= target.host "example.com") (= target.port 443))"#)
+            .unwrap()
+            .unwrap();
+        policies
+            .set_policy(
+                &Resource::TCP_OUTLET,
+                &Action::CONNECT_TO_TARGET,
+                &policy,
+                None,
+            )
+            .await?;
+        assert!(authorization.is_authorized(&target, address).await?);
+        assert!(
+            !authorization
+                .is_authorized(&Socks5Target::new("example.com", 80), address)
+                .await?
+        );
+        assert!(
+            !authorization
+                .is_authorized(&Socks5Target::new("localhost", 443), address)
+                .await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_authorize_the_resolved_addresses_of_a_target() -> Result<()> {
+        let policies = PolicySqlxDatabase::create().await?;
+        let authorization = PolicyTargetAuthorization::new(
+            policies.clone(),
+            Resource::TCP_OUTLET,
+            Action::CONNECT_TO_TARGET,
+        );
+
+        // the host name is allowed, but not if it resolves to a loopback address
+        let policy =
+            parse(r#"(and (= target.host "example.com") (not (= target.address "127.0.0.1")))"#)
+                .unwrap()
+                .unwrap();
+        policies
+            .set_policy(
+                &Resource::TCP_OUTLET,
+                &Action::CONNECT_TO_TARGET,
+                &policy,
+                None,
+            )
+            .await?;
+        let target = Socks5Target::new("example.com", 443);
+        assert!(
+            authorization
+                .is_authorized(&target, IpAddr::from_str("93.184.216.34").unwrap())
+                .await?
+        );
+        assert!(
+            !authorization
+                .is_authorized(&target, IpAddr::from_str("127.0.0.1").unwrap())
+                .await?
+        );
+        Ok(())
+    }
+}
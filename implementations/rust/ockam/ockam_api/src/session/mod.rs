@@ -83,6 +83,10 @@ impl Medic {
                 let mut sessions = self.sessions.lock().unwrap();
                 for session in sessions.iter_mut() {
                     let key = session.key().to_string();
+                    if session.is_waiting_for_connection() {
+                        log::trace!(%key, "session waiting for its connection to be requested");
+                        continue;
+                    }
                     if session.pings().len() < MAX_FAILURES {
                         let message = Message::new(session.key().to_string());
                         session.add_ping(message.ping);
@@ -123,12 +127,19 @@ impl Medic {
                             .spawn(async move { (key, sender.forward(l).await) });
                     } else {
                         match session.status() {
-                            ConnectionStatus::Up | ConnectionStatus::Down => {
+                            ConnectionStatus::Up
+                            | ConnectionStatus::Down
+                            | ConnectionStatus::Pending => {
                                 log::warn!(%key, "session unresponsive");
                                 let f = session.replacement(session.ping_route().clone());
+                                // the first connection of a pending session is not a retry
+                                let retry_delay = if session.status() == ConnectionStatus::Pending {
+                                    Duration::ZERO
+                                } else {
+                                    self.retry_delay
+                                };
                                 session.set_status(ConnectionStatus::Degraded);
                                 log::info!(%key, "replacing session");
-                                let retry_delay = self.retry_delay;
                                 self.replacements.spawn(async move {
                                     sleep(retry_delay).await;
                                     (key, f.await)
@@ -279,6 +290,14 @@ impl MedicHandle {
         sessions.retain(|s| s.key() != key)
     }
 
+    /// Request the connection of a pending session, see [`Session::request_connection`]
+    pub fn connect_session(&self, key: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.iter_mut().find(|s| s.key() == key) {
+            session.request_connection();
+        }
+    }
+
     pub fn status_of(&self, key: &str) -> Option<ConnectionStatus> {
         let sessions = self.sessions.lock().unwrap();
         sessions.iter().find(|s| s.key() == key).map(|s| s.status())
@@ -8,6 +8,7 @@ use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
 use crate::error::ApiError;
+use crate::session::MAX_FAILURES;
 use ockam_core::compat::rand;
 use ockam_core::{Error, Route};
 
@@ -36,6 +37,9 @@ pub enum ConnectionStatus {
     Degraded,
     #[n(2)]
     Up,
+    /// The connection has not been established yet
+    #[n(3)]
+    Pending,
 }
 
 impl fmt::Display for ConnectionStatus {
@@ -44,6 +48,7 @@ impl fmt::Display for ConnectionStatus {
             ConnectionStatus::Down => write!(f, "down"),
             ConnectionStatus::Degraded => write!(f, "degraded"),
             ConnectionStatus::Up => write!(f, "up"),
+            ConnectionStatus::Pending => write!(f, "pending"),
         }
     }
 }
@@ -56,6 +61,7 @@ impl TryFrom<String> for ConnectionStatus {
             "down" => Ok(ConnectionStatus::Down),
             "degraded" => Ok(ConnectionStatus::Degraded),
             "up" => Ok(ConnectionStatus::Up),
+            "pending" => Ok(ConnectionStatus::Pending),
             _ => Err(ApiError::message(format!(
                 "Invalid connection status: {value}"
             ))),
@@ -92,6 +98,26 @@ impl Session {
         }
     }
 
+    /// Create a session which is not connected yet.
+    /// The connection is created by the session replacer once it is requested
+    /// with [`Session::request_connection`]
+    pub fn pending(key: String) -> Self {
+        let mut session = Self::new(Route::new().into(), key);
+        session.status = ConnectionStatus::Pending;
+        session
+    }
+
+    /// Request the connection of the session: the session replacer is called
+    /// at the next check of the medic
+    pub fn request_connection(&mut self) {
+        self.pings = (0..MAX_FAILURES).map(|_| Ping::new()).collect();
+    }
+
+    /// Return true if the session is pending and its connection was not requested yet
+    pub fn is_waiting_for_connection(&self) -> bool {
+        self.status == ConnectionStatus::Pending && self.pings.len() < MAX_FAILURES
+    }
+
     pub fn key(&self) -> &str {
         self.key.as_str()
     }
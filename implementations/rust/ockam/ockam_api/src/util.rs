@@ -1,4 +1,4 @@
-use std::net::{SocketAddrV4, SocketAddrV6};
+use std::net::{SocketAddr, SocketAddrV4, SocketAddrV6};
 
 use miette::miette;
 
@@ -10,7 +10,7 @@ use ockam_multiaddr::proto::{
     DnsAddr, Ip4, Ip6, Node, Project, Secure, Service, Space, Tcp, Worker,
 };
 use ockam_multiaddr::{Code, MultiAddr, Protocol};
-use ockam_transport_tcp::{TcpConnection, TcpConnectionOptions, TCP};
+use ockam_transport_tcp::{TcpConnection, TcpConnectionOptions, TcpKeepaliveOptions, TCP};
 
 use crate::error::ApiError;
 
@@ -75,6 +75,23 @@ pub fn local_multiaddr_to_route(ma: &MultiAddr) -> Result<Route> {
     Ok(rb.into())
 }
 
+/// Return the options of a TCP connection, with a specific keepalive configuration
+/// and a specific local address if given
+fn tcp_connection_options(
+    keepalive: Option<TcpKeepaliveOptions>,
+    bind_address: Option<SocketAddr>,
+) -> TcpConnectionOptions {
+    let options = TcpConnectionOptions::new();
+    let options = match keepalive {
+        Some(keepalive) => options.with_keepalive(keepalive),
+        None => options,
+    };
+    match bind_address {
+        Some(bind_address) => options.with_bind_address(bind_address),
+        None => options,
+    }
+}
+
 pub struct MultiAddrToRouteResult {
     pub flow_control_id: Option<FlowControlId>,
     pub route: Route,
@@ -84,6 +101,17 @@ pub struct MultiAddrToRouteResult {
 pub async fn multiaddr_to_route(
     ma: &MultiAddr,
     tcp: &TcpTransport,
+) -> Option<MultiAddrToRouteResult> {
+    multiaddr_to_route_with_options(ma, tcp, None, None).await
+}
+
+/// Convert a MultiAddr to a route, like [`multiaddr_to_route`], and set the keepalive
+/// configuration and the local address of the TCP connection created for that route, if any
+pub async fn multiaddr_to_route_with_options(
+    ma: &MultiAddr,
+    tcp: &TcpTransport,
+    keepalive: Option<TcpKeepaliveOptions>,
+    bind_address: Option<SocketAddr>,
 ) -> Option<MultiAddrToRouteResult> {
     let mut rb = Route::new();
     let mut it = ma.iter().peekable();
@@ -103,7 +131,7 @@ pub async fn multiaddr_to_route(
                 let port = it.next()?.cast::<Tcp>()?;
                 let socket_addr = SocketAddrV4::new(*ip4, *port);
 
-                let options = TcpConnectionOptions::new();
+                let options = tcp_connection_options(keepalive, bind_address);
                 flow_control_id = Some(options.flow_control_id().clone());
 
                 let connection = match tcp.connect(socket_addr.to_string(), options).await {
@@ -128,7 +156,7 @@ pub async fn multiaddr_to_route(
                 let port = it.next()?.cast::<Tcp>()?;
                 let socket_addr = SocketAddrV6::new(*ip6, *port, 0, 0);
 
-                let options = TcpConnectionOptions::new();
+                let options = tcp_connection_options(keepalive, bind_address);
                 flow_control_id = Some(options.flow_control_id().clone());
 
                 let connection = match tcp.connect(socket_addr.to_string(), options).await {
@@ -154,7 +182,7 @@ pub async fn multiaddr_to_route(
                     if p.code() == Tcp::CODE {
                         let port = p.cast::<Tcp>()?;
 
-                        let options = TcpConnectionOptions::new();
+                        let options = tcp_connection_options(keepalive, bind_address);
                         flow_control_id = Some(options.flow_control_id().clone());
                         let peer = format!("{}:{}", &*host, *port);
 
@@ -0,0 +1,113 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use ockam::route;
+use ockam_api::logs::filter::{remove_inlet_log_level, set_inlet_log_level, set_log_filter_handle};
+use ockam_core::Result;
+use ockam_node::tracing_subscriber::layer::SubscriberExt;
+use ockam_node::tracing_subscriber::util::SubscriberInitExt;
+use ockam_node::tracing_subscriber::{fmt, reload, EnvFilter};
+use ockam_node::{Context, NodeBuilder};
+use ockam_transport_tcp::{TcpInletOptions, TcpOutletOptions, TcpTransport};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::Level;
+
+/// This test checks that the connections of an inlet are logged when its log level is
+/// raised to debug, while the connections of an inlet using the node log level are not,
+/// nor the connections of an inlet reusing the alias of a deleted inlet.
+/// The node is not started with the test macro, which installs its own tracing subscriber
+#[test]
+fn the_log_level_of_an_inlet_can_be_overridden() -> Result<()> {
+    let logs = Logs::default();
+    let writer = logs.clone();
+    let (filter, handle) = reload::Layer::new(EnvFilter::new("info"));
+    ockam_node::tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || writer.clone()),
+        )
+        .init();
+    set_log_filter_handle(handle);
+
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    executor.execute(async move {
+        let result = connect_to_inlets(&ctx).await;
+        ctx.stop().await?;
+        result
+    })??;
+
+    let connections: Vec<String> = logs
+        .lines()
+        .into_iter()
+        .filter(|l| l.contains("accepted a client connection"))
+        .collect();
+    assert_eq!(connections.len(), 1, "{connections:?}");
+    assert!(connections[0].contains("verbose"), "{connections:?}");
+    Ok(())
+}
+
+/// Create an inlet logging at the debug level and an inlet logging at the node level,
+/// then send some data through each of them.
+/// Then do the same with an inlet reusing the alias of the first inlet once its log level is removed
+async fn connect_to_inlets(ctx: &Context) -> Result<()> {
+    let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let tcp = TcpTransport::create(ctx).await?;
+    tcp.create_outlet(
+        "outlet",
+        target.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new(),
+    )
+    .await?;
+
+    set_inlet_log_level("verbose", Level::DEBUG)?;
+    for alias in ["verbose", "default", "verbose"] {
+        let (inlet_address, _) = tcp
+            .create_inlet(
+                "127.0.0.1:0",
+                route!["outlet"],
+                TcpInletOptions::new().with_alias(alias),
+            )
+            .await?;
+        let mut client = TcpStream::connect(inlet_address).await.unwrap();
+        client.write_all(b"hello").await.unwrap();
+
+        // the connection is accepted once the data reaches the target of the outlet
+        let (mut stream, _) = target.accept().await.unwrap();
+        let mut received = [0u8; 5];
+        stream.read_exact(&mut received).await.unwrap();
+        assert_eq!(&received, b"hello");
+
+        // the log level of the first inlet is removed, as when the inlet is deleted
+        if alias == "verbose" {
+            remove_inlet_log_level(alias)?;
+        }
+    }
+    Ok(())
+}
+
+/// Logs written by the tracing subscriber of the test
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Logs {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.0.lock().unwrap())
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+}
+
+impl Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -14,6 +14,9 @@
 use crate::api::state::{c, convert_runtime_information_to_c, rust};
 use crate::api::{state, to_c_string};
 use crate::cli::check_ockam_executable;
+use crate::enroll::enroll_offline::OfflineEnrollmentBundle;
+use crate::enroll::enroll_user::service_token;
+use crate::enroll::messages::{EnrollmentMessage, MessageText};
 use crate::state::AppState;
 use ockam_api::cli_state::CliState;
 use std::ffi::c_char;
@@ -281,7 +284,86 @@ extern "C" fn enroll_user() {
     app_state
         .context()
         .runtime()
-        .spawn(async move { app_state.enroll_user().await });
+        .spawn(async move { app_state.enroll_user(None, None).await });
+}
+
+/// Starts the enrollment of a service account, with a token obtained beforehand instead of
+/// the browser flow. The token must be a JWT.
+/// Returns null if the enrollment started, otherwise returns an error message.
+#[no_mangle]
+extern "C" fn enroll_user_with_service_token(token: *const c_char) -> *const c_char {
+    let token = unsafe {
+        std::ffi::CStr::from_ptr(token)
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let token = match service_token(&token) {
+        Ok(token) => token,
+        Err(err) => return to_c_string(err.to_string()),
+    };
+    let app_state = unsafe { APPLICATION_STATE.as_ref() }.expect(ERROR_NOT_INITIALIZED);
+
+    app_state
+        .context()
+        .runtime()
+        .spawn(async move { app_state.enroll_with_service_token(token).await });
+    std::ptr::null()
+}
+
+/// Enroll the user with an enrollment bundle exported from an already enrolled environment.
+/// The bundle is read from the JSON file at the provided path, without any network call.
+/// Returns null if successful, otherwise returns an error message.
+#[no_mangle]
+extern "C" fn enroll_user_with_offline_bundle(path: *const c_char) -> *const c_char {
+    let path = unsafe { std::ffi::CStr::from_ptr(path).to_str().unwrap().to_string() };
+    let app_state = unsafe { APPLICATION_STATE.as_ref() }.expect(ERROR_NOT_INITIALIZED);
+    let result = app_state.context().runtime().block_on(async {
+        let bundle = OfflineEnrollmentBundle::read(path)?;
+        app_state.enroll_with_offline_bundle(bundle).await
+    });
+
+    match result {
+        Ok(_) => std::ptr::null(),
+        Err(err) => to_c_string(err.to_string()),
+    }
+}
+
+/// Override the text of an enrollment notification, for example to translate it.
+/// The message is identified by its name, for example `enrolled_successfully`.
+/// The text of the `enrollment_failed` message can refer to the error with an `{error}` placeholder.
+/// Returns null if successful, otherwise returns an error message.
+#[no_mangle]
+extern "C" fn set_message_text(
+    name: *const c_char,
+    title: *const c_char,
+    message: *const c_char,
+) -> *const c_char {
+    let name = unsafe { std::ffi::CStr::from_ptr(name).to_str().unwrap().to_string() };
+    let title = unsafe {
+        std::ffi::CStr::from_ptr(title)
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let message = unsafe {
+        std::ffi::CStr::from_ptr(message)
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let app_state = unsafe { APPLICATION_STATE.as_ref() }.expect(ERROR_NOT_INITIALIZED);
+
+    match name.parse::<EnrollmentMessage>() {
+        Ok(enrollment_message) => {
+            let catalog = app_state
+                .message_catalog()
+                .with_text(enrollment_message, MessageText::new(title, message));
+            app_state.set_message_catalog(catalog);
+            std::ptr::null()
+        }
+        Err(err) => to_c_string(err.to_string()),
+    }
 }
 
 /// This function retrieve the current version of the application state, for polling purposes.
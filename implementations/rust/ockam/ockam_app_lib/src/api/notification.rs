@@ -4,6 +4,7 @@
 
 #[repr(C)]
 #[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Kind {
     Information = 0,
     Warning = 1,
@@ -0,0 +1,280 @@
+use std::path::Path;
+
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+use ockam::identity::models::CredentialAndPurposeKey;
+use ockam::identity::{identities, Identity};
+use ockam_api::cloud::enroll::auth0::UserInfo;
+use ockam_api::cloud::project::Project;
+use ockam_api::cloud::space::Space;
+
+use crate::api::state::OrchestratorStatus;
+use crate::enroll::messages::EnrollmentMessage;
+use crate::state::{AppState, NODE_NAME};
+use crate::Result;
+
+/// This structure contains everything needed to enroll the application without
+/// contacting the OIDC service or the Orchestrator.
+/// It is meant to be exported from an already enrolled environment, as a JSON file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfflineEnrollmentBundle {
+    pub user: UserInfo,
+    pub space: Space,
+    pub project: Project,
+    // credential issued by the project authority, if available, as hex-encoded CBOR
+    #[serde(default, with = "credential_hex")]
+    pub credential: Option<CredentialAndPurposeKey>,
+}
+
+impl OfflineEnrollmentBundle {
+    /// Read an enrollment bundle from a JSON file
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// Serialization of the bundle credential as hex-encoded CBOR
+mod credential_hex {
+    use ockam::identity::models::CredentialAndPurposeKey;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        credential: &Option<CredentialAndPurposeKey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let encoded = match credential {
+            Some(credential) => Some(hex::encode(
+                credential
+                    .encode_as_cbor_bytes()
+                    .map_err(serde::ser::Error::custom)?,
+            )),
+            None => None,
+        };
+        encoded.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<CredentialAndPurposeKey>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(encoded) => {
+                let bytes = hex::decode(encoded).map_err(Error::custom)?;
+                CredentialAndPurposeKey::decode_from_cbor_bytes(&bytes)
+                    .map(Some)
+                    .map_err(Error::custom)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl AppState {
+    /// Enroll a user with a previously exported enrollment bundle.
+    ///
+    /// This function doesn't make any network call, it:
+    ///  - stores the user, the space and the project contained in the bundle
+    ///  - stores the project credential, when provided, once it is verified with the project authority
+    ///  - associates the project to the default node and marks its identity as enrolled
+    pub async fn enroll_with_offline_bundle(&self, bundle: OfflineEnrollmentBundle) -> Result<()> {
+        if self.is_enrolled().await.unwrap_or_default() {
+            debug!("User is already enrolled");
+            return Ok(());
+        }
+
+        if let Err(err) = self.store_offline_bundle(bundle).await {
+            error!(?err, "Failed to enroll user with an offline bundle");
+            self.update_orchestrator_status(OrchestratorStatus::Disconnected);
+            self.publish_state().await;
+            self.notify_error_message(EnrollmentMessage::EnrollmentFailed, &err);
+            return Err(err);
+        }
+
+        self.notify_message(EnrollmentMessage::EnrolledSuccessfully);
+
+        // the relay refresh moves the status to connected once the project is reachable
+        self.update_orchestrator_status(OrchestratorStatus::Connecting);
+        self.publish_state().await;
+
+        // Reset the node manager to include the project's setup, see `enroll_user`
+        self.reset_node_manager().await?;
+        self.schedule_relay_refresh_now();
+
+        info!("User enrolled successfully with an offline bundle");
+        Ok(())
+    }
+
+    async fn store_offline_bundle(&self, bundle: OfflineEnrollmentBundle) -> Result<()> {
+        // nothing is stored if the credential was not issued by the project authority
+        if let Some(credential) = &bundle.credential {
+            let authority = bundle
+                .project
+                .authority_identity()
+                .await
+                .into_diagnostic()?;
+            verify_credential(&authority, credential).await?;
+        }
+
+        let cli_state = self.state().await;
+        cli_state.store_user(&bundle.user).await?;
+        cli_state.set_default_user(&bundle.user.email).await?;
+
+        let space = &bundle.space;
+        cli_state
+            .store_space(
+                &space.id,
+                &space.name,
+                space.users.iter().map(|u| u.as_str()).collect(),
+            )
+            .await?;
+
+        let project = bundle.project;
+        cli_state.store_project(project.clone()).await?;
+        if let Some(credential) = bundle.credential {
+            let authority = project.authority_identity().await.into_diagnostic()?;
+            cli_state
+                .store_credential(&project.name(), &authority, credential)
+                .await?;
+        }
+        cli_state
+            .set_node_project(NODE_NAME, &Some(project.name()))
+            .await?;
+
+        let identifier = cli_state.get_node(NODE_NAME).await?.identifier();
+        cli_state
+            .set_identifier_as_enrolled(&identifier)
+            .await
+            .into_diagnostic()?;
+        info!(%identifier, "Node identity marked as enrolled");
+        Ok(())
+    }
+}
+
+/// Return an error if the credential was not issued by the project authority
+async fn verify_credential(
+    authority: &Identity,
+    credential: &CredentialAndPurposeKey,
+) -> Result<()> {
+    let identities = identities().await.into_diagnostic()?;
+    identities
+        .identities_creation()
+        .import_from_change_history(
+            Some(authority.identifier()),
+            authority.change_history().clone(),
+        )
+        .await
+        .into_diagnostic()?;
+    identities
+        .credentials()
+        .credentials_verification()
+        .verify_credential(None, &[authority.identifier().clone()], credential)
+        .await
+        .into_diagnostic()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ockam::identity::identities;
+    use ockam::identity::models::CredentialSchemaIdentifier;
+    use ockam::identity::utils::AttributesBuilder;
+    use ockam::Context;
+    use ockam_api::cli_state::CliState;
+    use ockam_api::cloud::enroll::auth0::UserInfo;
+    use ockam_api::cloud::project::Project;
+    use ockam_api::cloud::space::Space;
+
+    use crate::enroll::enroll_offline::OfflineEnrollmentBundle;
+    use crate::state::{AppState, NODE_NAME, PROJECT_NAME};
+
+    fn bundle_fixture() -> OfflineEnrollmentBundle {
+        OfflineEnrollmentBundle {
+            user: UserInfo {
+                sub: "sub".to_string(),
+                nickname: "alice".to_string(),
+                name: "Alice".to_string(),
+                picture: "".to_string(),
+                updated_at: "2023-11-01T00:00:00Z".to_string(),
+                email: "alice@example.com".to_string(),
+                email_verified: true,
+                roles: vec![],
+            },
+            space: Space {
+                id: "space_id".to_string(),
+                name: "space_name".to_string(),
+                users: vec!["alice@example.com".to_string()],
+            },
+            project: Project {
+                id: "project_id".to_string(),
+                name: PROJECT_NAME.to_string(),
+                space_id: "space_id".to_string(),
+                space_name: "space_name".to_string(),
+                access_route: "/dnsaddr/127.0.0.1/tcp/4000/service/api".to_string(),
+                ..Default::default()
+            },
+            credential: None,
+        }
+    }
+
+    #[ockam::test(crate = "ockam")]
+    async fn test_enroll_with_offline_bundle(context: &mut Context) -> ockam::Result<()> {
+        let app_state = AppState::test(context, CliState::test().await?).await;
+        assert!(!app_state.is_enrolled().await.unwrap_or_default());
+
+        // the bundle is exported as a JSON file
+        let file = tempfile::NamedTempFile::new().unwrap();
+        serde_json::to_writer(file.as_file(), &bundle_fixture()).unwrap();
+        let bundle = OfflineEnrollmentBundle::read(file.path()).unwrap();
+
+        app_state.enroll_with_offline_bundle(bundle).await.unwrap();
+
+        assert!(app_state.is_enrolled().await.unwrap());
+        let cli_state = app_state.state().await;
+        assert_eq!(
+            cli_state.get_default_user().await?.email,
+            "alice@example.com"
+        );
+        let project = cli_state.get_node_project(NODE_NAME).await?;
+        assert_eq!(project.name(), PROJECT_NAME);
+
+        context.stop().await
+    }
+
+    #[ockam::test(crate = "ockam")]
+    async fn test_reject_a_credential_not_issued_by_the_project_authority(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let app_state = AppState::test(context, CliState::test().await?).await;
+
+        let identities = identities().await?;
+        let authority = identities.identities_creation().create_identity().await?;
+        let other = identities.identities_creation().create_identity().await?;
+        let subject = identities.identities_creation().create_identity().await?;
+        let attributes = AttributesBuilder::with_schema(CredentialSchemaIdentifier(1)).build();
+        let credential = identities
+            .credentials()
+            .credentials_creation()
+            .issue_credential(&other, &subject, attributes, Duration::from_secs(60))
+            .await?;
+
+        let mut bundle = bundle_fixture();
+        bundle.project.authority_identity = Some(
+            identities
+                .get_identity(&authority)
+                .await?
+                .export_as_string()?,
+        );
+        bundle.credential = Some(credential);
+
+        assert!(app_state.enroll_with_offline_bundle(bundle).await.is_err());
+        assert!(!app_state.is_enrolled().await.unwrap_or_default());
+        assert!(app_state.state().await.get_default_user().await.is_err());
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,119 @@
+use miette::Diagnostic;
+use thiserror::Error;
+
+use ockam_api::cli_state::CliStateError;
+use ockam_api::error::ApiError;
+use ockam_core::errcode::Kind;
+
+/// Error returned when the enrollment of a user fails, with the stage where it failed
+#[derive(Debug, Diagnostic, Error)]
+pub enum EnrollmentError {
+    #[error("failed to retrieve a token: {0}")]
+    OidcToken(Box<crate::Error>),
+    #[error("failed to retrieve the user information: {0}")]
+    UserInfo(Box<crate::Error>),
+    #[error("failed to retrieve the user's space: {0}")]
+    Space(Box<crate::Error>),
+    #[error("failed to retrieve the user's project: {0}")]
+    Project(Box<crate::Error>),
+    #[error("failed to enroll with the Orchestrator: {0}")]
+    ControllerEnroll(Box<crate::Error>),
+}
+
+impl EnrollmentError {
+    pub fn oidc_token(e: impl Into<crate::Error>) -> Self {
+        EnrollmentError::OidcToken(Box::new(e.into()))
+    }
+
+    pub fn user_info(e: impl Into<crate::Error>) -> Self {
+        EnrollmentError::UserInfo(Box::new(e.into()))
+    }
+
+    pub fn space(e: impl Into<crate::Error>) -> Self {
+        EnrollmentError::Space(Box::new(e.into()))
+    }
+
+    pub fn project(e: impl Into<crate::Error>) -> Self {
+        EnrollmentError::Project(Box::new(e.into()))
+    }
+
+    pub fn controller_enroll(e: impl Into<crate::Error>) -> Self {
+        EnrollmentError::ControllerEnroll(Box::new(e.into()))
+    }
+
+    /// Return the underlying error
+    pub fn error(&self) -> &crate::Error {
+        match self {
+            EnrollmentError::OidcToken(e)
+            | EnrollmentError::UserInfo(e)
+            | EnrollmentError::Space(e)
+            | EnrollmentError::Project(e)
+            | EnrollmentError::ControllerEnroll(e) => e,
+        }
+    }
+
+    /// Return true if the enrollment failed because of a transient error, like a network
+    /// error or a timeout, in which case it can be retried as it is.
+    /// Otherwise the user needs to act first, for example to get access to a space
+    pub fn is_transient(&self) -> bool {
+        match self.error() {
+            crate::Error::Ockam(e)
+            | crate::Error::Api(ApiError::Core(e))
+            | crate::Error::CliState(CliStateError::Ockam(e)) => is_transient_kind(e),
+            crate::Error::Api(ApiError::Io(_)) => true,
+            crate::Error::Api(ApiError::Reqwest(e)) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.status().map(|s| s.is_server_error()).unwrap_or(false)
+            }
+            crate::Error::Internal(e) => e.is::<std::io::Error>(),
+            _ => false,
+        }
+    }
+}
+
+fn is_transient_kind(e: &ockam_core::Error) -> bool {
+    matches!(
+        e.code().kind,
+        Kind::Io | Kind::Timeout | Kind::Cancelled | Kind::ResourceExhausted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use ockam_core::errcode::Origin;
+
+    use super::*;
+
+    #[test]
+    fn test_transient_errors() {
+        let timeout = ockam_core::Error::new(Origin::Api, Kind::Timeout, "timeout");
+        assert!(EnrollmentError::oidc_token(timeout).is_transient());
+
+        let connection_refused = ApiError::from(std::io::Error::from(ErrorKind::ConnectionRefused));
+        assert!(EnrollmentError::user_info(connection_refused).is_transient());
+
+        let network_error = ockam_core::Error::new(Origin::Transport, Kind::Io, "network error");
+        assert!(EnrollmentError::controller_enroll(network_error).is_transient());
+
+        let io_error = std::io::Error::from(ErrorKind::TimedOut);
+        assert!(EnrollmentError::project(io_error).is_transient());
+    }
+
+    #[test]
+    fn test_permanent_errors() {
+        let invalid_token = "The service account token is empty";
+        assert!(!EnrollmentError::oidc_token(invalid_token).is_transient());
+
+        let no_space_access = "the user is not allowed to access this space";
+        assert!(!EnrollmentError::space(no_space_access).is_transient());
+
+        let not_found = ockam_core::Error::new(Origin::Api, Kind::NotFound, "project not found");
+        assert!(!EnrollmentError::project(not_found).is_transient());
+
+        let invalid = ApiError::message("invalid user information");
+        assert!(!EnrollmentError::user_info(invalid).is_transient());
+    }
+}
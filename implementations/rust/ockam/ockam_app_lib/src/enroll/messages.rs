@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::api::notification::rust::{Kind, Notification};
+use crate::Error;
+
+/// Notifications sent to the user while enrolling, or later about the credential
+/// obtained with the enrollment
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EnrollmentMessage {
+    /// The email of the user must be verified before enrolling
+    EmailVerificationRequired,
+    /// The user has been enrolled
+    EnrolledSuccessfully,
+    /// The enrollment failed, the message can refer to the error with an `{error}` placeholder
+    EnrollmentFailed,
+    /// The project of the user is being created
+    CreatingProject,
+    /// The credential of the user has been renewed
+    CredentialRefreshed,
+    /// The credential of the user is about to expire and could not be renewed
+    CredentialExpiringSoon,
+}
+
+impl FromStr for EnrollmentMessage {
+    type Err = Error;
+
+    /// Parse the name used by the host application to refer to a message
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "email_verification_required" => Ok(EnrollmentMessage::EmailVerificationRequired),
+            "enrolled_successfully" => Ok(EnrollmentMessage::EnrolledSuccessfully),
+            "enrollment_failed" => Ok(EnrollmentMessage::EnrollmentFailed),
+            "creating_project" => Ok(EnrollmentMessage::CreatingProject),
+            "credential_refreshed" => Ok(EnrollmentMessage::CredentialRefreshed),
+            "credential_expiring_soon" => Ok(EnrollmentMessage::CredentialExpiringSoon),
+            _ => Err(format!("unknown enrollment message: {s}").into()),
+        }
+    }
+}
+
+impl EnrollmentMessage {
+    fn kind(&self) -> Kind {
+        match self {
+            EnrollmentMessage::EnrollmentFailed => Kind::Error,
+            EnrollmentMessage::CredentialExpiringSoon => Kind::Warning,
+            _ => Kind::Information,
+        }
+    }
+
+    fn default_text(&self) -> MessageText {
+        match self {
+            EnrollmentMessage::EmailVerificationRequired => MessageText::new(
+                "Email Verification Required",
+                "For security reasons, we need to confirm your email address.\
+                 A verification email has been sent to you. \
+                 Please review your inbox and follow the provided steps \
+                 to complete the verification process",
+            ),
+            EnrollmentMessage::EnrolledSuccessfully => {
+                MessageText::new("Enrolled successfully!", "You can now use the Ockam app")
+            }
+            EnrollmentMessage::EnrollmentFailed => {
+                MessageText::new("Failed to enroll user", "{error}")
+            }
+            EnrollmentMessage::CreatingProject => {
+                MessageText::new("Creating a new project...", "This might take a few minutes")
+            }
+            EnrollmentMessage::CredentialRefreshed => MessageText::new(
+                "Your credential has been renewed",
+                "You can keep accessing the services shared with you",
+            ),
+            EnrollmentMessage::CredentialExpiringSoon => MessageText::new(
+                "Your credential is about to expire",
+                "Your credential could not be renewed. \
+                 Please check your connection to the Ockam Orchestrator, \
+                 otherwise the services shared with you will soon be unavailable",
+            ),
+        }
+    }
+}
+
+/// Title and message of a notification
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageText {
+    pub title: String,
+    pub message: String,
+}
+
+impl MessageText {
+    pub fn new(title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Catalog of the texts used for the enrollment and credential notifications.
+///
+/// The host application can override some texts, for example to translate them.
+/// The texts which are not overridden default to English.
+#[derive(Clone, Debug, Default)]
+pub struct MessageCatalog {
+    overrides: HashMap<EnrollmentMessage, MessageText>,
+}
+
+impl MessageCatalog {
+    /// Override the text of a message
+    pub fn with_text(mut self, message: EnrollmentMessage, text: MessageText) -> Self {
+        self.overrides.insert(message, text);
+        self
+    }
+
+    /// Return the text of a message
+    pub fn text(&self, message: EnrollmentMessage) -> MessageText {
+        self.overrides
+            .get(&message)
+            .cloned()
+            .unwrap_or_else(|| message.default_text())
+    }
+
+    /// Create the notification for a message
+    pub(crate) fn notification(&self, message: EnrollmentMessage) -> Notification {
+        let text = self.text(message);
+        Notification {
+            kind: message.kind(),
+            title: text.title,
+            message: text.message,
+        }
+    }
+
+    /// Create the notification for a message referring to an error
+    pub(crate) fn error_notification(
+        &self,
+        message: EnrollmentMessage,
+        error: impl Display,
+    ) -> Notification {
+        let mut notification = self.notification(message);
+        notification.message = notification.message.replace("{error}", &error.to_string());
+        notification
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_enrollment_messages() {
+        let catalog = MessageCatalog::default();
+        let notification = catalog.notification(EnrollmentMessage::EnrolledSuccessfully);
+        assert_eq!(notification.title, "Enrolled successfully!");
+        assert_eq!(notification.message, "You can now use the Ockam app");
+
+        let catalog = catalog.with_text(
+            EnrollmentMessage::EnrolledSuccessfully,
+            MessageText::new(
+                "Inscription réussie !",
+                "Vous pouvez utiliser l'application",
+            ),
+        );
+        let notification = catalog.notification(EnrollmentMessage::EnrolledSuccessfully);
+        assert_eq!(notification.kind, Kind::Information);
+        assert_eq!(notification.title, "Inscription réussie !");
+        assert_eq!(notification.message, "Vous pouvez utiliser l'application");
+
+        // the other messages keep their default text
+        let notification = catalog.notification(EnrollmentMessage::CreatingProject);
+        assert_eq!(notification.title, "Creating a new project...");
+
+        // errors are inserted in the overridden text
+        let catalog = catalog.with_text(
+            EnrollmentMessage::EnrollmentFailed,
+            MessageText::new("Échec de l'inscription", "Erreur : {error}"),
+        );
+        let notification =
+            catalog.error_notification(EnrollmentMessage::EnrollmentFailed, "timeout");
+        assert_eq!(notification.kind, Kind::Error);
+        assert_eq!(notification.message, "Erreur : timeout");
+    }
+
+    #[test]
+    fn test_parse_enrollment_message() {
+        assert_eq!(
+            "credential_expiring_soon".parse::<EnrollmentMessage>().ok(),
+            Some(EnrollmentMessage::CredentialExpiringSoon)
+        );
+        assert!("unknown".parse::<EnrollmentMessage>().is_err());
+    }
+}
@@ -1 +1,4 @@
+pub(crate) mod enroll_offline;
 pub(crate) mod enroll_user;
+pub(crate) mod error;
+pub(crate) mod messages;
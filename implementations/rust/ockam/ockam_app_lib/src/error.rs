@@ -23,6 +23,9 @@ pub enum Error {
 
     #[error(transparent)]
     CliState(#[from] ockam_api::cli_state::CliStateError),
+
+    #[error(transparent)]
+    Enrollment(#[from] crate::enroll::error::EnrollmentError),
 }
 
 impl From<JoinError> for Error {
@@ -31,9 +34,18 @@ impl From<JoinError> for Error {
     }
 }
 
+/// Keep the original error when a report wraps an API or a CliState error,
+/// so that its kind can still be inspected, for example to retry an operation
 impl From<miette::Report> for Error {
     fn from(e: miette::Report) -> Self {
-        Error::App(e.to_string())
+        let e = match e.downcast::<ockam_api::error::ApiError>() {
+            Ok(e) => return Error::Api(e),
+            Err(e) => e,
+        };
+        match e.downcast::<ockam_api::cli_state::CliStateError>() {
+            Ok(e) => Error::CliState(e),
+            Err(e) => Error::App(e.to_string()),
+        }
     }
 }
 
@@ -1,11 +1,12 @@
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use miette::IntoDiagnostic;
 use tracing::{debug, info, warn};
 
 use ockam_api::address::get_free_address;
+use ockam_api::nodes::models::portal::InletOptions;
 use ockam_api::nodes::service::portals::Inlets;
 use ockam_api::ConnectionStatus;
 use ockam_core::api::Reply;
@@ -37,24 +38,35 @@ impl AppState {
             let result = self
                 .refresh_inlet(background_node_client.clone(), &service)
                 .await;
-            {
+            let connection_event = {
                 // we want to reduce the scope of the guard as much as possible
                 let mut guard = services_arc.write().await;
                 match result {
-                    Ok(port) => {
-                        if let Some(service) = guard.find_mut_by_id(service.id()) {
-                            if let Some(port) = port {
-                                service.set_port(port)
+                    Ok(port) => guard.find_mut_by_id(service.id()).and_then(|service| {
+                        if let Some(port) = port {
+                            // the inlet had to be recreated while it was previously connected
+                            if service.port().is_some() {
+                                service.connection_mut().disconnected(Instant::now());
                             }
+                            service.set_port(port)
                         }
-                    }
+                        if service.enabled() {
+                            service.connection_mut().connected()
+                        } else {
+                            None
+                        }
+                    }),
                     Err(err) => {
                         warn!(%err, "Failed to refresh TCP inlet for accepted invitation");
-                        if let Some(service) = guard.find_mut_by_id(service.id()) {
-                            service.remove_port()
-                        }
+                        guard.find_mut_by_id(service.id()).and_then(|service| {
+                            service.remove_port();
+                            service.connection_mut().disconnected(Instant::now())
+                        })
                     }
                 }
+            };
+            if let Some(event) = connection_event {
+                self.notify(event.notification(service.name()));
             }
 
             // the service resources are already cleaned up at this stage, since when it's
@@ -158,7 +170,7 @@ impl AppState {
                 &MultiAddr::from_str(&service.service_route()).into_diagnostic()?,
                 &Some(service.inlet_name().to_string()),
                 &None,
-                Duration::from_secs(5),
+                &InletOptions::default().with_wait_for_outlet_timeout(Duration::from_secs(5)),
             )
             .await?;
         Ok(bind_address.port())
@@ -1,4 +1,5 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
 
 use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
@@ -8,11 +9,15 @@ use ockam::identity::Identifier;
 use ockam_api::cli_state::enrollments::EnrollmentTicket;
 use ockam_api::cloud::share::InvitationWithAccess;
 
+use crate::api::notification::rust::{Kind, Notification};
 use crate::state::{AppState, ModelState};
 
 /// A Socket port number
 pub type Port = u16;
 
+/// Time after which a disconnected inlet is reported to the user with a warning
+pub(crate) const PROLONGED_DISCONNECTION_THRESHOLD: Duration = Duration::from_secs(60);
+
 #[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize, PartialEq)]
 #[rustfmt::skip]
 #[cbor(map)]
@@ -188,6 +193,8 @@ pub struct IncomingService {
     // When the invitation is removed, the service is marked as removed
     // to clean up the resources before removing the service from the list
     removed: bool,
+    // tracks the connection state transitions of the inlet to notify the user
+    connection: InletConnectionTracker,
 }
 
 impl IncomingService {
@@ -212,6 +219,7 @@ impl IncomingService {
             original_name,
             enrollment_ticket,
             removed: false,
+            connection: InletConnectionTracker::default(),
         }
     }
 
@@ -302,6 +310,78 @@ impl IncomingService {
     pub fn inlet_name(&self) -> &str {
         "app-inlet"
     }
+
+    /// Returns the tracker for the connection state transitions of the inlet
+    pub(crate) fn connection_mut(&mut self) -> &mut InletConnectionTracker {
+        &mut self.connection
+    }
+}
+
+/// Connection state transitions of an inlet which must be reported to the user
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum InletConnectionEvent {
+    /// The inlet is connected again after having been disconnected
+    Reconnected,
+    /// The inlet has been disconnected for longer than [`PROLONGED_DISCONNECTION_THRESHOLD`]
+    ProlongedDisconnection,
+}
+
+impl InletConnectionEvent {
+    /// Create the notification to send to the user for a given service
+    pub(crate) fn notification(&self, service_name: &str) -> Notification {
+        match self {
+            InletConnectionEvent::Reconnected => Notification {
+                kind: Kind::Information,
+                title: format!("{service_name} is reconnected"),
+                message: "The connection to the service has been restored".to_string(),
+            },
+            InletConnectionEvent::ProlongedDisconnection => Notification {
+                kind: Kind::Warning,
+                title: format!("{service_name} is disconnected"),
+                message: "The service has been unreachable for a while. \
+                 The connection will be restored as soon as it becomes available again"
+                    .to_string(),
+            },
+        }
+    }
+}
+
+/// This structure keeps track of the connection status of an inlet across refreshes
+/// in order to detect reconnections and prolonged disconnections
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InletConnectionTracker {
+    // true once the inlet has been connected at least once
+    was_connected: bool,
+    // time of the first failed refresh since the last successful connection
+    disconnected_since: Option<Instant>,
+    // true if the user has already been warned about the current disconnection
+    warned: bool,
+}
+
+impl InletConnectionTracker {
+    /// Record that the inlet is connected
+    pub(crate) fn connected(&mut self) -> Option<InletConnectionEvent> {
+        let was_disconnected = self.disconnected_since.take().is_some();
+        let event = if self.was_connected && was_disconnected {
+            Some(InletConnectionEvent::Reconnected)
+        } else {
+            None
+        };
+        self.was_connected = true;
+        self.warned = false;
+        event
+    }
+
+    /// Record that the inlet is disconnected at a given time
+    pub(crate) fn disconnected(&mut self, now: Instant) -> Option<InletConnectionEvent> {
+        let since = *self.disconnected_since.get_or_insert(now);
+        if !self.warned && now.duration_since(since) >= PROLONGED_DISCONNECTION_THRESHOLD {
+            self.warned = true;
+            Some(InletConnectionEvent::ProlongedDisconnection)
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -315,6 +395,12 @@ mod tests {
         InvitationWithAccess, ReceivedInvitation, RoleInShare, ServiceAccessDetails, ShareScope,
     };
 
+    use std::time::{Duration, Instant};
+
+    use crate::api::notification::rust::Kind;
+    use crate::incoming_services::state::{
+        InletConnectionEvent, InletConnectionTracker, PROLONGED_DISCONNECTION_THRESHOLD,
+    };
     use crate::incoming_services::PersistentIncomingService;
     use crate::state::AppState;
 
@@ -460,4 +546,41 @@ mod tests {
 
         context.stop().await
     }
+
+    #[test]
+    fn test_inlet_reconnection_notifications() {
+        let mut tracker = InletConnectionTracker::default();
+        let start = Instant::now();
+        let mut events = vec![];
+
+        // the first connection is not a reconnection
+        events.extend(tracker.connected());
+
+        // a short disconnection is not reported
+        events.extend(tracker.disconnected(start));
+        events.extend(tracker.disconnected(start + Duration::from_secs(10)));
+        assert!(events.is_empty());
+
+        // a prolonged disconnection is reported only once
+        events.extend(tracker.disconnected(start + PROLONGED_DISCONNECTION_THRESHOLD));
+        events.extend(tracker.disconnected(start + 2 * PROLONGED_DISCONNECTION_THRESHOLD));
+
+        // then the reconnection is reported
+        events.extend(tracker.connected());
+        events.extend(tracker.connected());
+
+        assert_eq!(
+            events,
+            vec![
+                InletConnectionEvent::ProlongedDisconnection,
+                InletConnectionEvent::Reconnected
+            ]
+        );
+
+        let notifications: Vec<Kind> = events
+            .iter()
+            .map(|e| e.notification("my-service").kind)
+            .collect();
+        assert_eq!(notifications, vec![Kind::Warning, Kind::Information]);
+    }
 }
@@ -7,8 +7,8 @@ use ockam_api::authenticator::enrollment_tokens::TokenIssuer;
 use ockam_api::cli_state::enrollments::EnrollmentTicket;
 use ockam_api::cloud::project::{Project, Projects};
 
-use crate::projects::error::Error::ListingFailed;
-use crate::state::{AppState, StateKind};
+use crate::projects::error::Error::{ListingFailed, ProjectInvalidState};
+use crate::state::{AppState, StateKind, NODE_NAME};
 
 use super::error::{Error, Result};
 
@@ -59,6 +59,19 @@ impl AppState {
             }
         };
 
+        // the project was not ready yet at the end of the enrollment
+        if self
+            .state()
+            .await
+            .get_node_project(NODE_NAME)
+            .await
+            .is_err()
+        {
+            self.refresh_space_and_project()
+                .await
+                .map_err(|e| ProjectInvalidState(e.to_string()))?;
+        }
+
         let node_manager = self.node_manager().await;
         let projects = node_manager
             .get_projects(&self.context())
@@ -0,0 +1,66 @@
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::debug;
+
+use ockam_api::nodes::CredentialRefreshEvent;
+
+use crate::enroll::messages::EnrollmentMessage;
+use crate::state::AppState;
+
+impl AppState {
+    /// Notify the user every time the node credential is refreshed, and warn them
+    /// when the credential is about to expire and can not be refreshed
+    pub(crate) fn notify_credential_refresh(
+        &self,
+        mut events: UnboundedReceiver<CredentialRefreshEvent>,
+    ) {
+        let this = self.clone();
+        self.context.runtime().spawn(async move {
+            while let Some(event) = events.recv().await {
+                debug!(?event, "received a credential refresh event");
+                this.notify_message(credential_message(&event));
+            }
+        });
+    }
+}
+
+/// Return the message to send to the user for a credential refresh event
+pub(crate) fn credential_message(event: &CredentialRefreshEvent) -> EnrollmentMessage {
+    match event {
+        CredentialRefreshEvent::Refreshed(_) => EnrollmentMessage::CredentialRefreshed,
+        CredentialRefreshEvent::ExpiringSoon(_) => EnrollmentMessage::CredentialExpiringSoon,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ockam::identity::models::TimestampInSeconds;
+
+    use crate::api::notification::rust::Kind;
+    use crate::enroll::messages::{MessageCatalog, MessageText};
+
+    use super::*;
+
+    #[test]
+    fn test_credential_refresh_notifications() {
+        let catalog = MessageCatalog::default();
+
+        // a credential which is about to expire and can not be refreshed produces a warning
+        let event = CredentialRefreshEvent::ExpiringSoon(TimestampInSeconds(100));
+        let notification = catalog.notification(credential_message(&event));
+        assert_eq!(notification.kind, Kind::Warning);
+
+        // a refreshed credential produces an information
+        let event = CredentialRefreshEvent::Refreshed(TimestampInSeconds(100));
+        let notification = catalog.notification(credential_message(&event));
+        assert_eq!(notification.kind, Kind::Information);
+        assert_eq!(notification.title, "Your credential has been renewed");
+
+        // the texts can be overridden
+        let catalog = catalog.with_text(
+            EnrollmentMessage::CredentialRefreshed,
+            MessageText::new("Identifiant renouvelé", "Vos services restent accessibles"),
+        );
+        let notification = catalog.notification(credential_message(&event));
+        assert_eq!(notification.title, "Identifiant renouvelé");
+    }
+}
@@ -1,7 +1,10 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
 use miette::{IntoDiagnostic, WrapErr};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio::sync::RwLock;
 use tracing::{error, info, trace, warn};
 use tracing_appender::non_blocking::WorkerGuard;
@@ -17,8 +20,11 @@ use ockam_api::cloud::{AuthorityNode, Controller};
 use ockam_api::nodes::models::portal::OutletStatus;
 use ockam_api::nodes::service::{
     NodeManagerGeneralOptions, NodeManagerTransportOptions, NodeManagerTrustOptions,
+    DEFAULT_CREDENTIAL_REFRESH_SKEW,
+};
+use ockam_api::nodes::{
+    BackgroundNode, CredentialRefreshEvent, InMemoryNode, NodeManagerWorker, NODEMANAGER_ADDR,
 };
-use ockam_api::nodes::{BackgroundNode, InMemoryNode, NodeManagerWorker, NODEMANAGER_ADDR};
 use ockam_multiaddr::MultiAddr;
 
 use crate::api::notification::rust::{Notification, NotificationCallback};
@@ -28,6 +34,7 @@ use crate::api::state::rust::{
 };
 use crate::api::state::OrchestratorStatus;
 use crate::background_node::{BackgroundNodeClient, Cli};
+use crate::enroll::messages::{EnrollmentMessage, MessageCatalog};
 use crate::incoming_services::IncomingServicesState;
 use crate::invitations::state::{InvitationState, ReceivedInvitationStatus};
 use crate::scheduler::Scheduler;
@@ -39,6 +46,7 @@ use crate::state::tasks::{
 };
 use crate::{api, Result};
 
+mod credentials;
 mod kind;
 mod model;
 mod model_state_repository;
@@ -70,7 +78,12 @@ pub struct AppState {
     incoming_services: Arc<RwLock<IncomingServicesState>>,
     application_state_callback: Option<ApplicationStateCallback>,
     notification_callback: Option<NotificationCallback>,
+    message_catalog: Arc<Mutex<MessageCatalog>>,
     node_manager: Arc<RwLock<Arc<InMemoryNode>>>,
+    // receives the credential refresh events of every node manager created by the application
+    credential_refresh_events: UnboundedSender<CredentialRefreshEvent>,
+    // incremented every time the node manager is recreated, used to skip duplicate resets
+    node_manager_generation: Arc<AtomicU64>,
     state_loaded: Arc<Mutex<u8>>,
     refresh_project_scheduler: Arc<OnceLock<Scheduler>>,
     refresh_invitations_scheduler: Arc<OnceLock<Scheduler>>,
@@ -80,8 +93,12 @@ pub struct AppState {
     pub(crate) tracing_guard: Arc<OnceLock<WorkerGuard>>,
 }
 
-async fn create_node_manager(ctx: Arc<Context>, cli_state: &CliState) -> Arc<InMemoryNode> {
-    match make_node_manager(ctx.clone(), cli_state).await {
+async fn create_node_manager(
+    ctx: Arc<Context>,
+    cli_state: &CliState,
+    credential_refresh_events: UnboundedSender<CredentialRefreshEvent>,
+) -> Arc<InMemoryNode> {
+    match make_node_manager(ctx.clone(), cli_state, credential_refresh_events).await {
         Ok(w) => w,
         Err(e) => {
             error!(%e, "Cannot load the model state");
@@ -140,21 +157,29 @@ impl AppState {
         cli_state: CliState,
     ) -> AppState {
         // create the application state and its dependencies
-        let node_manager = create_node_manager(context.clone(), &cli_state).await;
+        let (credential_refresh_events, credential_refresh_receiver) = unbounded_channel();
+        let node_manager = create_node_manager(
+            context.clone(),
+            &cli_state,
+            credential_refresh_events.clone(),
+        )
+        .await;
         let model_state_repository = create_model_state_repository(&cli_state).await;
         let model_state = model_state_repository
             .load()
             .await
             .unwrap_or(ModelState::default());
 
-        info!("AppState initialized");
-        AppState {
+        let app_state = AppState {
             context,
             application_state_callback,
             notification_callback,
+            message_catalog: Arc::new(Mutex::new(MessageCatalog::default())),
             state: Arc::new(RwLock::new(cli_state)),
             orchestrator_status: Arc::new(Mutex::new(Default::default())),
             node_manager: Arc::new(RwLock::new(node_manager)),
+            credential_refresh_events,
+            node_manager_generation: Arc::new(AtomicU64::new(0)),
             model_state: Arc::new(RwLock::new(model_state)),
             model_state_repository: Arc::new(RwLock::new(model_state_repository)),
             background_node_client: Arc::new(RwLock::new(Arc::new(Cli::new()))),
@@ -168,7 +193,10 @@ impl AppState {
             last_published_snapshot: Arc::new(Mutex::new(None)),
             tracing_guard: Arc::new(Default::default()),
             state_loaded: Arc::new(Mutex::new(0)),
-        }
+        };
+        app_state.notify_credential_refresh(credential_refresh_receiver);
+        info!("AppState initialized");
+        app_state
     }
 
     /// Load a previously persisted ModelState and start refreshing schedule
@@ -312,8 +340,17 @@ impl AppState {
 
     /// Recreate a new NodeManagerWorker instance, which will restart the necessary
     /// child workers as described in its Worker trait implementation.
+    ///
+    /// When several resets are requested concurrently, only the first one is performed
+    /// and the others return once it's completed.
     pub async fn reset_node_manager(&self) -> miette::Result<()> {
+        let generation = self.node_manager_generation.load(Ordering::SeqCst);
         let mut node_manager = self.node_manager.write().await;
+        if self.node_manager_generation.load(Ordering::SeqCst) != generation {
+            info!("the node manager has already been reset, skipping");
+            return Ok(());
+        }
+
         node_manager
             .stop(&self.context)
             .await
@@ -327,12 +364,24 @@ impl AppState {
         }
         info!("stopped all the ctx workers");
 
-        let new_node_manager = make_node_manager(self.context.clone(), &self.state().await).await?;
+        let new_node_manager = make_node_manager(
+            self.context.clone(),
+            &self.state().await,
+            self.credential_refresh_events.clone(),
+        )
+        .await?;
         *node_manager = new_node_manager;
+        self.node_manager_generation.fetch_add(1, Ordering::SeqCst);
         info!("set a new node manager");
         Ok(())
     }
 
+    /// Return the number of times the node manager has been recreated
+    #[cfg(test)]
+    pub(crate) fn node_manager_generation(&self) -> u64 {
+        self.node_manager_generation.load(Ordering::SeqCst)
+    }
+
     /// Return the application Context
     /// This can be used to run async actions involving the Router
     pub fn context(&self) -> Arc<Context> {
@@ -465,6 +514,32 @@ impl AppState {
         }
     }
 
+    /// Return the catalog used for the texts of the enrollment notifications
+    pub fn message_catalog(&self) -> MessageCatalog {
+        self.message_catalog.lock().unwrap().clone()
+    }
+
+    /// Replace the catalog used for the texts of the enrollment notifications
+    pub fn set_message_catalog(&self, catalog: MessageCatalog) {
+        *self.message_catalog.lock().unwrap() = catalog;
+    }
+
+    /// Send an enrollment notification, with a text taken from the message catalog
+    pub(crate) fn notify_message(&self, message: EnrollmentMessage) {
+        let notification = self.message_catalog.lock().unwrap().notification(message);
+        self.notify(notification);
+    }
+
+    /// Send an enrollment notification referring to an error
+    pub(crate) fn notify_error_message(&self, message: EnrollmentMessage, error: impl Display) {
+        let notification = self
+            .message_catalog
+            .lock()
+            .unwrap()
+            .error_notification(message, error);
+        self.notify(notification);
+    }
+
     /// Sends the new application state to the UI
     pub async fn publish_state(&self) {
         if let Some(callback) = self.application_state_callback.as_ref() {
@@ -669,6 +744,7 @@ impl AppState {
 pub(crate) async fn make_node_manager(
     ctx: Arc<Context>,
     cli_state: &CliState,
+    credential_refresh_events: UnboundedSender<CredentialRefreshEvent>,
 ) -> miette::Result<Arc<InMemoryNode>> {
     let tcp = TcpTransport::create(&ctx).await.into_diagnostic()?;
     let options = TcpListenerOptions::new();
@@ -681,6 +757,12 @@ pub(crate) async fn make_node_manager(
         .create_node_with_optional_values(NODE_NAME, &None, &None)
         .await?;
 
+    // the node credential is refreshed in the background, and the user is notified of its refreshes
+    let trust_context = cli_state.get_default_trust_context().await.ok();
+    let trust_options = NodeManagerTrustOptions::new(trust_context.clone())
+        .with_credential_refresh(trust_context.is_some(), DEFAULT_CREDENTIAL_REFRESH_SKEW)
+        .with_credential_refresh_events(credential_refresh_events);
+
     let node_manager = Arc::new(
         InMemoryNode::new(
             &ctx,
@@ -692,7 +774,7 @@ pub(crate) async fn make_node_manager(
                 true,
             ),
             NodeManagerTransportOptions::new(listener.flow_control_id().clone(), tcp),
-            NodeManagerTrustOptions::new(cli_state.get_default_trust_context().await.ok()),
+            trust_options,
         )
         .await
         .into_diagnostic()?,
@@ -720,3 +802,33 @@ async fn create_model_state_repository(state: &CliState) -> Arc<dyn ModelStateRe
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ockam::Context;
+    use ockam_api::cli_state::CliState;
+
+    use crate::state::AppState;
+
+    #[ockam::test(crate = "ockam")]
+    async fn test_concurrent_reset_node_manager(context: &mut Context) -> ockam::Result<()> {
+        let app_state = AppState::test(context, CliState::test().await?).await;
+        assert_eq!(app_state.node_manager_generation(), 0);
+
+        let (first, second) = tokio::join!(
+            app_state.reset_node_manager(),
+            app_state.reset_node_manager()
+        );
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+
+        // only one of the two calls actually recreated the node manager
+        assert_eq!(app_state.node_manager_generation(), 1);
+
+        // a subsequent reset is performed again
+        app_state.reset_node_manager().await.unwrap();
+        assert_eq!(app_state.node_manager_generation(), 2);
+
+        context.stop().await
+    }
+}
@@ -1,11 +1,15 @@
+use std::path::PathBuf;
+
 use clap::Args;
+use miette::IntoDiagnostic;
 
 use ockam::Context;
 use ockam_api::nodes::{BackgroundNode, Credentials};
+use ockam_multiaddr::MultiAddr;
 
 use crate::node::NodeOpts;
 use crate::util::node_rpc;
-use crate::CommandGlobalOpts;
+use crate::{fmt_log, CommandGlobalOpts};
 
 #[derive(Clone, Debug, Args)]
 pub struct GetCommand {
@@ -15,15 +19,33 @@ pub struct GetCommand {
     #[arg(long)]
     pub overwrite: bool,
 
+    /// Fetch a fresh credential from the authority instead of using the cached one,
+    /// for example to check the attributes currently granted by the authority
+    #[arg(long)]
+    pub no_cache: bool,
+
     /// Name of the Identity for which the credential was issued.
     #[arg(long = "identity", value_name = "IDENTITY_NAME")]
     identity: Option<String>,
+
+    /// Route to the authority issuing the credential. Defaults to the authority of the node's trust context.
+    #[arg(long, value_name = "AUTHORITY_ROUTE")]
+    authority: Option<MultiAddr>,
+
+    /// Write the credential to this file, CBOR-encoded, so that it can be used by another tool
+    #[arg(long, value_name = "FILE", conflicts_with = "authority")]
+    export: Option<PathBuf>,
 }
 
 impl GetCommand {
     pub fn run(self, opts: CommandGlobalOpts) {
         node_rpc(rpc, (opts, self));
     }
+
+    /// Return true if the cached credential must be replaced with a new one
+    fn overwrite(&self) -> bool {
+        self.overwrite || self.no_cache
+    }
 }
 
 async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, GetCommand)) -> miette::Result<()> {
@@ -32,7 +54,41 @@ async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, GetCommand)) -> miet
 
 async fn run_impl(ctx: &Context, opts: CommandGlobalOpts, cmd: GetCommand) -> miette::Result<()> {
     let node = BackgroundNode::create(ctx, &opts.state, &cmd.node_opts.at_node).await?;
-    node.get_credential(ctx, cmd.overwrite, cmd.identity)
+    node.get_credential(ctx, cmd.overwrite(), cmd.identity.clone(), cmd.authority)
         .await?;
+    if cmd.no_cache {
+        opts.terminal.write_line(&fmt_log!(
+            "Fetched a fresh credential from the authority, the cached credential was not used"
+        ))?;
+    }
+    if let Some(path) = cmd.export {
+        let bytes = node.export_credential(ctx, cmd.identity).await?;
+        std::fs::write(path, bytes).into_diagnostic()?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_no_cache_bypasses_the_cached_credential() {
+        assert!(!test_command(&[]).overwrite());
+        assert!(test_command(&["--overwrite"]).overwrite());
+        assert!(test_command(&["--no-cache"]).overwrite());
+    }
+
+    /// Return a command parsed from some command line arguments
+    fn test_command(args: &[&str]) -> GetCommand {
+        #[derive(clap::Parser)]
+        struct TestCommand {
+            #[command(flatten)]
+            get: GetCommand,
+        }
+        let args = ["get"].iter().chain(args);
+        TestCommand::try_parse_from(args).unwrap().get
+    }
+}
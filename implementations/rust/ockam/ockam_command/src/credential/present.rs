@@ -1,29 +1,62 @@
+use std::str::FromStr;
+
 use clap::Args;
+use colorful::Colorful;
+use miette::miette;
 
+use ockam::identity::Identifier;
 use ockam::Context;
+use ockam_api::cli_state::CliState;
+use ockam_api::nodes::models::credentials::PresentCredentialOptions;
+use ockam_api::nodes::service::default_address::DefaultAddress;
 use ockam_api::nodes::{BackgroundNode, Credentials};
 use ockam_multiaddr::MultiAddr;
 
 use crate::node::NodeOpts;
+use crate::relay::util::{relay_name_or_route, ToAddressError};
 use crate::util::node_rpc;
-use crate::CommandGlobalOpts;
+use crate::{fmt_ok, CommandGlobalOpts};
 
 #[derive(Clone, Debug, Args)]
 pub struct PresentCommand {
     #[command(flatten)]
     pub node_opts: NodeOpts,
 
+    /// Route to the node receiving the credential. Can be a full route or the name of an existing relay
     #[arg(long, display_order = 900, id = "ROUTE")]
-    pub to: MultiAddr,
+    pub to: String,
 
     #[arg(short, long)]
     pub oneway: bool,
+
+    /// Only present the credential if the node receiving it has this identifier
+    #[arg(long, display_order = 900, id = "AUTHORIZED")]
+    pub authorized: Option<Identifier>,
+
+    /// Only check that the credential is valid and that the route can be used, without presenting the credential
+    #[arg(long, display_order = 900)]
+    pub dry_run: bool,
 }
 
 impl PresentCommand {
     pub fn run(self, opts: CommandGlobalOpts) {
         node_rpc(rpc, (opts, self));
     }
+
+    async fn parse_arg_to(
+        state: &CliState,
+        to: &str,
+        default_project_name: &Option<String>,
+    ) -> Result<MultiAddr, ToAddressError> {
+        let to = relay_name_or_route(
+            state,
+            to,
+            default_project_name,
+            DefaultAddress::CREDENTIALS_SERVICE,
+        )
+        .await?;
+        MultiAddr::from_str(&to).map_err(|e| ToAddressError::InvalidRoute(e.to_string()))
+    }
 }
 
 async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, PresentCommand)) -> miette::Result<()> {
@@ -35,7 +68,94 @@ async fn run_impl(
     opts: CommandGlobalOpts,
     cmd: PresentCommand,
 ) -> miette::Result<()> {
+    let default_project_name = opts
+        .state
+        .get_default_project()
+        .await
+        .ok()
+        .map(|p| p.name());
+    let to = PresentCommand::parse_arg_to(&opts.state, &cmd.to, &default_project_name).await?;
+
     let node = BackgroundNode::create(ctx, &opts.state, &cmd.node_opts.at_node).await?;
-    node.present_credential(ctx, &cmd.to, cmd.oneway).await?;
+    let options = PresentCredentialOptions::default()
+        .with_oneway(cmd.oneway)
+        .with_expected_peer(cmd.authorized)
+        .with_dry_run(cmd.dry_run);
+    let receipt = node.present_credential(ctx, &to, options).await?;
+    if receipt.is_dry_run() {
+        let expires_at = receipt
+            .expires_at()
+            .map(|t| t.0.to_string())
+            .unwrap_or_default();
+        opts.terminal
+            .stdout()
+            .plain(fmt_ok!(
+                "The credential, expiring at {}, can be presented to {} with the route {}",
+                expires_at,
+                to,
+                receipt.route().unwrap_or_default()
+            ))
+            .write_line()?;
+        return Ok(());
+    }
+    if !receipt.is_accepted() {
+        return Err(miette!(
+            "The credential was rejected by {}: {}",
+            to,
+            receipt.reason().unwrap_or_default()
+        ));
+    }
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!("The credential was accepted by {}", to))
+        .write_line()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use miette::Result;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_arg_to() -> Result<()> {
+        let state = CliState::test().await?;
+        let default_project_name = Some("p1".to_string());
+
+        // Invalid values
+        let err = PresentCommand::parse_arg_to(&state, "/alice/service", &default_project_name)
+            .await
+            .expect_err("Invalid protocol");
+        assert_eq!(
+            err,
+            ToAddressError::InvalidRelayName("/alice/service".to_string())
+        );
+        let err = PresentCommand::parse_arg_to(&state, "alice/forwarder", &default_project_name)
+            .await
+            .expect_err("Invalid protocol");
+        assert_eq!(
+            err,
+            ToAddressError::InvalidRelayName("alice/forwarder".to_string())
+        );
+
+        // A relay name requires a default project
+        let err = PresentCommand::parse_arg_to(&state, "alice", &None)
+            .await
+            .expect_err("No default project");
+        assert_eq!(err, ToAddressError::NoDefaultProject);
+
+        // The user provides a full project route
+        let addr = "/project/p1/service/forward_to_n1/secure/api/service/credentials";
+        let res = PresentCommand::parse_arg_to(&state, addr, &default_project_name).await?;
+        assert_eq!(res.to_string(), addr);
+
+        // The user provides the name of the relay
+        let res = PresentCommand::parse_arg_to(&state, "alice", &default_project_name).await?;
+        assert_eq!(
+            res.to_string(),
+            "/project/p1/service/forward_to_alice/secure/api/service/credentials"
+        );
+        Ok(())
+    }
+}
@@ -9,8 +9,9 @@ use tokio::sync::Mutex;
 use tokio::try_join;
 use tracing::{info, warn};
 
+use ockam::identity::Identifier;
 use ockam::Context;
-use ockam_api::cli_state::random_name;
+use ockam_api::cli_state::{random_name, ControllerConfig};
 use ockam_api::cloud::enroll::auth0::*;
 use ockam_api::cloud::project::{Project, Projects};
 use ockam_api::cloud::space::{Space, Spaces};
@@ -18,6 +19,7 @@ use ockam_api::cloud::Controller;
 use ockam_api::enroll::enrollment::{EnrollStatus, Enrollment};
 use ockam_api::enroll::oidc_service::OidcService;
 use ockam_api::nodes::InMemoryNode;
+use ockam_multiaddr::MultiAddr;
 
 use crate::enroll::OidcServiceExt;
 use crate::operation::util::check_for_completion;
@@ -25,6 +27,7 @@ use crate::output::OutputFormat;
 use crate::project::util::check_project_readiness;
 use crate::terminal::OckamColor;
 use crate::util::node_rpc;
+use crate::util::parsers::identity_identifier_parser;
 use crate::{display_parse_logs, docs, fmt_log, fmt_ok, fmt_para, CommandGlobalOpts, Result};
 
 const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
@@ -44,6 +47,21 @@ pub struct EnrollCommand {
     /// Use PKCE authorization flow
     #[arg(long)]
     pub authorization_code_flow: bool,
+
+    /// Address of the Orchestrator controller to enroll with, instead of the default one.
+    /// For example, the address of a staging or self-hosted Orchestrator
+    #[arg(long, value_name = "MULTIADDR")]
+    pub controller_address: Option<MultiAddr>,
+
+    /// Identifier of the Orchestrator controller to enroll with, when it differs from the
+    /// identifier of the default controller. Requires --controller-address
+    #[arg(
+        long,
+        value_name = "IDENTIFIER",
+        value_parser = identity_identifier_parser,
+        requires = "controller_address"
+    )]
+    pub controller_identifier: Option<Identifier>,
 }
 
 impl EnrollCommand {
@@ -91,7 +109,7 @@ fn ctrlc_handler(opts: CommandGlobalOpts) {
 async fn run_impl(
     ctx: &Context,
     opts: CommandGlobalOpts,
-    _cmd: EnrollCommand,
+    cmd: EnrollCommand,
 ) -> miette::Result<()> {
     opts.terminal.write_line(&fmt_log!(
         "Enrolling your default Ockam identity with Ockam Orchestrator...\n"
@@ -101,7 +119,7 @@ async fn run_impl(
     display_parse_logs(&opts);
 
     let oidc_service = OidcService::default();
-    let token = if _cmd.authorization_code_flow {
+    let token = if cmd.authorization_code_flow {
         oidc_service.get_token_with_pkce().await.into_diagnostic()?
     } else {
         oidc_service.get_token_interactively(&opts).await?
@@ -112,6 +130,16 @@ async fn run_impl(
         .await?;
     opts.state.store_user(&user_info).await?;
 
+    // the controller is persisted so that subsequent commands use the same Orchestrator
+    if let Some(controller_address) = cmd.controller_address {
+        opts.state
+            .set_controller_config(&ControllerConfig::new(
+                controller_address,
+                cmd.controller_identifier,
+            ))
+            .await?;
+    }
+
     let node = InMemoryNode::start(ctx, &opts.state).await?;
     let controller = node.create_controller().await?;
 
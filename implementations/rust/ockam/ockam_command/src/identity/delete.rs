@@ -1,12 +1,20 @@
-use clap::Args;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
 use colorful::Colorful;
 use console::Term;
+use miette::{miette, IntoDiagnostic};
+use pem_rfc7468::LineEnding;
 
+use ockam::identity::Identity;
 use ockam::Context;
+use ockam_api::NamedIdentity;
 
+use crate::output::{versioned_json, IDENTITY_DELETE_JSON_SCHEMA_VERSION};
 use crate::terminal::tui::DeleteCommandTui;
 use crate::util::node_rpc;
-use crate::{docs, fmt_ok, fmt_warn, CommandGlobalOpts, Terminal, TerminalStream};
+use crate::{docs, fmt_log, fmt_ok, fmt_warn, CommandGlobalOpts, Terminal, TerminalStream};
 
 const LONG_ABOUT: &str = include_str!("./static/delete/long_about.txt");
 const AFTER_LONG_HELP: &str = include_str!("./static/delete/after_long_help.txt");
@@ -18,7 +26,7 @@ long_about = docs::about(LONG_ABOUT),
 after_long_help = docs::after_help(AFTER_LONG_HELP)
 )]
 pub struct DeleteCommand {
-    /// Name of the identity to be deleted
+    /// Name or identifier of the identity to be deleted
     name: Option<String>,
 
     /// Confirm the deletion without prompting
@@ -27,8 +35,49 @@ pub struct DeleteCommand {
 
     #[arg(long, short)]
     all: bool,
+
+    /// Delete the identity even if it is referenced by a policy or a trust context
+    #[arg(display_order = 901, long)]
+    force: bool,
+
+    /// Create a new identity, with new keys, under the same name after the deletion
+    #[arg(display_order = 901, long, requires = "name", conflicts_with = "all")]
+    recreate: bool,
+
+    /// Export the change history of the identity to this file before deleting it,
+    /// so that the identity can be imported again later
+    #[arg(display_order = 901, long, value_name = "FILE", conflicts_with = "all")]
+    export: Option<PathBuf>,
+
+    /// Format of the exported identity: JSON, or PEM wrapping the encoded change history
+    #[arg(display_order = 901, long, value_enum, default_value_t = ExportFormat::Json, requires = "export")]
+    export_format: ExportFormat,
+
+    /// When deleting several identities, stop at the first failed deletion
+    /// and leave the remaining identities untouched
+    #[arg(display_order = 901, long)]
+    fail_fast: bool,
+}
+
+/// Format of the file where an identity is exported before being deleted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Pem,
 }
 
+impl ExportFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Pem => "pem",
+        }
+    }
+}
+
+/// Label of the PEM block containing an exported identity
+const PEM_LABEL: &str = "OCKAM IDENTITY";
+
 impl DeleteCommand {
     pub fn run(self, options: CommandGlobalOpts) {
         node_rpc(run_impl, (options, self))
@@ -77,7 +126,7 @@ impl DeleteCommandTui for DeleteTui {
         Ok(self
             .opts
             .state
-            .get_named_identity_or_default(&self.cmd.name)
+            .get_named_identity_by_identifier_or_name(&self.cmd.name)
             .await?
             .name())
     }
@@ -95,35 +144,352 @@ impl DeleteCommandTui for DeleteTui {
 
     async fn delete_single(&self, item_name: &str) -> miette::Result<()> {
         let state = &self.opts.state;
-        state.delete_identity_by_name(item_name).await?;
+        let identifier = state.get_identifier_by_name(item_name).await?;
+        let exported = match &self.cmd.export {
+            Some(path) => {
+                let identity = state.get_identity(&identifier).await?;
+                write_export(path, &identity, self.cmd.export_format)?;
+                Some(path.as_path())
+            }
+            None => None,
+        };
+        let recreated = match self.delete_or_recreate(item_name).await {
+            Ok(recreated) => recreated,
+            Err(e) => {
+                // the identity is kept, so its export is removed
+                if let Some(path) = exported {
+                    let _ = std::fs::remove_file(path);
+                }
+                return Err(e);
+            }
+        };
+        if let Some(identity) = recreated {
+            self.terminal()
+                .stdout()
+                .plain(
+                    fmt_ok!(
+                        "The identity named '{}' has been recreated with identifier {} (previously {})",
+                        item_name,
+                        identity.identifier(),
+                        identifier
+                    ) + &self.export_summary(exported),
+                )
+                .machine(identity.identifier())
+                .json(versioned_json(
+                    IDENTITY_DELETE_JSON_SCHEMA_VERSION,
+                    &serde_json::json!({
+                        "name": item_name,
+                        "identifier": identity.identifier().to_string(),
+                        "previous_identifier": identifier.to_string(),
+                        "export": self.export_json(exported),
+                    }),
+                )?)
+                .write_line()?;
+            return Ok(());
+        }
         self.terminal()
             .stdout()
-            .plain(fmt_ok!(
-                "The identity named '{}' has been deleted",
-                item_name
-            ))
+            .plain(
+                fmt_ok!(
+                    "The identity named '{}' with identifier {} has been deleted",
+                    item_name,
+                    identifier
+                ) + &self.export_summary(exported),
+            )
             .machine(item_name)
-            .json(serde_json::json!({ "name": item_name }))
+            .json(versioned_json(
+                IDENTITY_DELETE_JSON_SCHEMA_VERSION,
+                &serde_json::json!({
+                    "name": item_name,
+                    "identifier": identifier.to_string(),
+                    "export": self.export_json(exported),
+                }),
+            )?)
             .write_line()?;
         Ok(())
     }
 
     async fn delete_multiple(&self, selected_items_names: Vec<String>) -> miette::Result<()> {
-        let mut plain = String::new();
-        for name in selected_items_names {
-            if self
-                .opts
-                .state
-                .delete_identity_by_name(name.as_ref())
-                .await
-                .is_ok()
-            {
-                plain.push_str(&fmt_ok!("Identity '{name}' deleted\n"))
-            } else {
-                plain.push_str(&fmt_warn!("Failed to delete identity '{name}'\n"))
+        // the spinner is only displayed in interactive mode
+        let progress_bar = self.terminal().progress_spinner();
+        let total = selected_items_names.len();
+        let state = &self.opts.state;
+        let force = self.cmd.force;
+        let mut i = 0;
+        let (results, error) =
+            delete_identities(selected_items_names, self.cmd.fail_fast, |name| {
+                i += 1;
+                if let Some(progress_bar) = progress_bar.as_ref() {
+                    progress_bar.set_message(format!(
+                        "Deleting identity {} ({i}/{total})...",
+                        name.clone().light_magenta(),
+                    ));
+                }
+                async move {
+                    state.delete_identity_by_name(&name, force).await?;
+                    Ok::<(), miette::Report>(())
+                }
+            })
+            .await;
+        if let Some(progress_bar) = progress_bar {
+            progress_bar.finish_and_clear();
+        }
+        self.terminal()
+            .stdout()
+            .plain(deletion_summary(&results))
+            .write_line()?;
+        match error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl DeleteTui {
+    /// Delete an identity, or recreate it with `--recreate`.
+    /// Return the recreated identity, if any
+    async fn delete_or_recreate(&self, name: &str) -> miette::Result<Option<NamedIdentity>> {
+        let state = &self.opts.state;
+        if self.cmd.recreate {
+            Ok(Some(
+                state
+                    .recreate_identity_by_name(name, self.cmd.force)
+                    .await?,
+            ))
+        } else {
+            state.delete_identity_by_name(name, self.cmd.force).await?;
+            Ok(None)
+        }
+    }
+
+    /// Return a line stating where the identity has been exported, if it has been exported
+    fn export_summary(&self, exported: Option<&Path>) -> String {
+        match exported {
+            Some(path) => format!(
+                "\n{}",
+                fmt_log!(
+                    "The identity was exported as {} to {}",
+                    self.cmd.export_format.as_str(),
+                    path.display()
+                )
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// Return the path and the format of the exported identity, if it has been exported
+    fn export_json(&self, exported: Option<&Path>) -> serde_json::Value {
+        match exported {
+            Some(path) => serde_json::json!({
+                "path": path.display().to_string(),
+                "format": self.cmd.export_format.as_str(),
+            }),
+            None => serde_json::Value::Null,
+        }
+    }
+}
+
+/// Write the change history of an identity to a file, in the given format.
+/// An existing file is not overwritten
+fn write_export(path: &Path, identity: &Identity, format: ExportFormat) -> miette::Result<()> {
+    if path.exists() {
+        return Err(miette!(
+            "The file {} already exists, the identity was not deleted",
+            path.display()
+        ));
+    }
+    let contents = export_identity(identity, format)?;
+    std::fs::write(path, contents)
+        .map_err(|e| miette!("Cannot export the identity to {}: {e}", path.display()))
+}
+
+/// Return the change history of an identity encoded in the given format
+fn export_identity(identity: &Identity, format: ExportFormat) -> miette::Result<String> {
+    let change_history = identity.change_history();
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "identifier": identity.identifier().to_string(),
+            "change_history": change_history.export_as_string().into_diagnostic()?,
+        }))
+        .into_diagnostic(),
+        ExportFormat::Pem => pem_rfc7468::encode_string(
+            PEM_LABEL,
+            LineEnding::LF,
+            &change_history.export().into_diagnostic()?,
+        )
+        .map_err(|e| miette!("Cannot encode the identity as PEM: {e}")),
+    }
+}
+
+/// Delete the identities one after the other and return, for each attempted identity, whether it
+/// has been deleted.
+/// With `fail_fast`, the deletion stops at the first failure, the following identities are not
+/// attempted and the error is returned
+async fn delete_identities<F, Fut>(
+    names: Vec<String>,
+    fail_fast: bool,
+    mut delete: F,
+) -> (Vec<(String, bool)>, Option<miette::Report>)
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = miette::Result<()>>,
+{
+    let mut results = Vec::with_capacity(names.len());
+    for name in names {
+        match delete(name.clone()).await {
+            Ok(()) => results.push((name, true)),
+            Err(e) if fail_fast => {
+                let error = miette!(
+                    "Failed to delete identity '{name}', the remaining identities were not deleted: {e}"
+                );
+                results.push((name, false));
+                return (results, Some(error));
+            }
+            Err(_) => results.push((name, false)),
+        }
+    }
+    (results, None)
+}
+
+/// Return one line per identity, stating if it has been deleted or not
+fn deletion_summary(results: &[(String, bool)]) -> String {
+    let mut plain = String::new();
+    for (name, deleted) in results {
+        if *deleted {
+            plain.push_str(&fmt_ok!("Identity '{name}' deleted\n"))
+        } else {
+            plain.push_str(&fmt_warn!("Failed to delete identity '{name}'\n"))
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use ockam::identity::identities;
+    use ockam::identity::models::ChangeHistory;
+
+    use super::*;
+
+    /// Return the change history of an identity exported with [`export_identity`]
+    fn import_identity(contents: &str, format: ExportFormat) -> miette::Result<ChangeHistory> {
+        match format {
+            ExportFormat::Json => {
+                let json: serde_json::Value = serde_json::from_str(contents).into_diagnostic()?;
+                let change_history = json["change_history"]
+                    .as_str()
+                    .ok_or_else(|| miette!("The exported identity has no change history"))?;
+                ChangeHistory::import_from_string(change_history).into_diagnostic()
+            }
+            ExportFormat::Pem => {
+                let (label, bytes) = pem_rfc7468::decode_vec(contents.as_bytes())
+                    .map_err(|e| miette!("Cannot decode the identity as PEM: {e}"))?;
+                if label != PEM_LABEL {
+                    return Err(miette!(
+                        "Unexpected PEM label {label}, expected {PEM_LABEL}"
+                    ));
+                }
+                ChangeHistory::import(&bytes).into_diagnostic()
             }
         }
-        self.terminal().stdout().plain(plain).write_line()?;
+    }
+
+    #[tokio::test]
+    async fn test_exported_identity_can_be_imported() -> miette::Result<()> {
+        let identities = identities().await.into_diagnostic()?;
+        let identifier = identities
+            .identities_creation()
+            .create_identity()
+            .await
+            .into_diagnostic()?;
+        let identity = identities
+            .get_identity(&identifier)
+            .await
+            .into_diagnostic()?;
+
+        for format in [ExportFormat::Json, ExportFormat::Pem] {
+            let contents = export_identity(&identity, format)?;
+            let change_history = import_identity(&contents, format)?;
+            assert_eq!(&change_history, identity.change_history(), "{format:?}");
+
+            // the imported change history is verified against the identifier
+            let other = ockam::identity::identities().await.into_diagnostic()?;
+            let imported = other
+                .identities_creation()
+                .import(
+                    Some(&identifier),
+                    &change_history.export().into_diagnostic()?,
+                )
+                .await
+                .into_diagnostic()?;
+            assert_eq!(imported, identifier);
+        }
+
+        // each format must be imported as such
+        let pem = export_identity(&identity, ExportFormat::Pem)?;
+        assert!(pem.starts_with("-----BEGIN OCKAM IDENTITY-----"));
+        assert!(import_identity(&pem, ExportFormat::Json).is_err());
+        let json = export_identity(&identity, ExportFormat::Json)?;
+        assert!(import_identity(&json, ExportFormat::Pem).is_err());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_fail_fast_stops_at_the_first_failed_deletion() {
+        let names: Vec<String> = ["alice", "bob", "charlie"]
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        let delete = |attempted: &mut Vec<String>, name: String| {
+            attempted.push(name.clone());
+            async move {
+                if name == "bob" {
+                    Err(miette!("bob is used by a node"))
+                } else {
+                    Ok(())
+                }
+            }
+        };
+
+        // by default all the identities are attempted
+        let mut attempted = vec![];
+        let (results, error) =
+            delete_identities(names.clone(), false, |name| delete(&mut attempted, name)).await;
+        assert!(error.is_none());
+        assert_eq!(attempted, names);
+        assert_eq!(
+            results,
+            vec![
+                ("alice".to_string(), true),
+                ("bob".to_string(), false),
+                ("charlie".to_string(), true),
+            ]
+        );
+
+        // with fail fast, the identities after the failed one are not attempted
+        let mut attempted = vec![];
+        let (results, error) =
+            delete_identities(names, true, |name| delete(&mut attempted, name)).await;
+        assert!(error.unwrap().to_string().contains("bob is used by a node"));
+        assert_eq!(attempted, vec!["alice".to_string(), "bob".to_string()]);
+        assert_eq!(
+            results,
+            vec![("alice".to_string(), true), ("bob".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_deletion_summary_has_one_line_per_identity() {
+        let results = vec![
+            ("alice".to_string(), true),
+            ("bob".to_string(), false),
+            ("charlie".to_string(), true),
+        ];
+        let summary = deletion_summary(&results);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("Identity 'alice' deleted"));
+        assert!(lines[1].contains("Failed to delete identity 'bob'"));
+        assert!(lines[2].contains("Identity 'charlie' deleted"));
+    }
 }
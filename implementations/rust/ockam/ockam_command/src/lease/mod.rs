@@ -93,7 +93,7 @@ async fn authenticate(
         .await?;
 
     authority_node
-        .authenticate(ctx, Some(identity.clone()))
+        .authenticate(ctx, Some(identity.clone()), false)
         .await?;
     node.create_project_client(
         &project.identifier().into_diagnostic()?,
@@ -1,4 +1,5 @@
 use ockam_api::logs::env::{log_format, log_level, log_max_files, log_max_size_bytes};
+use ockam_api::logs::filter::set_log_filter_handle;
 use ockam_api::logs::rolling::{RollingConditionBasic, RollingFileAppender};
 use ockam_api::logs::LogFormat;
 use std::io::stdout;
@@ -7,7 +8,7 @@ use std::str::FromStr;
 use tracing::level_filters::LevelFilter;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::fmt::layer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter};
 
 pub fn setup_logging(
     verbose: u8,
@@ -51,6 +52,9 @@ pub fn setup_logging(
             .with_default_directive(level.into())
             .parse_lossy(ockam_crates.map(|c| format!("{c}={level}")).join(","))
     };
+    // the filter can be modified while the node runs, to change the log level of an inlet
+    let (filter, filter_handle) = reload::Layer::new(filter);
+    set_log_filter_handle(filter_handle);
     let subscriber = tracing_subscriber::registry()
         .with(filter)
         .with(tracing_error::ErrorLayer::default());
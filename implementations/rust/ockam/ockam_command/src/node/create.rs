@@ -15,7 +15,7 @@ use ockam::identity::Identity;
 use ockam::{Address, AsyncTryClone, TcpListenerOptions};
 use ockam::{Context, TcpTransport};
 use ockam_api::cli_state::random_name;
-use ockam_api::nodes::service::NodeManagerTrustOptions;
+use ockam_api::nodes::service::{NodeManagerTrustOptions, DEFAULT_CREDENTIAL_REFRESH_SKEW};
 use ockam_api::nodes::BackgroundNode;
 use ockam_api::nodes::InMemoryNode;
 use ockam_api::{
@@ -34,6 +34,7 @@ use crate::secure_channel::listener::create as secure_channel_listener;
 use crate::service::config::Config;
 use crate::terminal::OckamColor;
 use crate::util::api::TrustContextOpts;
+use crate::util::duration::duration_parser;
 use crate::util::embedded_node_that_is_not_stopped;
 use crate::util::{api, exitcode};
 use crate::util::{local_cmd, node_rpc};
@@ -106,6 +107,14 @@ pub struct CreateCommand {
     #[arg(long = "credential", value_name = "CREDENTIAL_NAME")]
     pub credential: Option<String>,
 
+    /// Refresh the node credential in the background before it expires
+    #[arg(long)]
+    pub credential_refresh: bool,
+
+    /// How long before its expiration the node credential gets refreshed
+    #[arg(long, value_name = "DURATION", default_value = "5m", value_parser = duration_parser, requires = "credential_refresh")]
+    pub credential_refresh_skew: Duration,
+
     #[command(flatten)]
     pub trust_context_opts: TrustContextOpts,
 }
@@ -127,6 +136,8 @@ impl Default for CreateCommand {
             trusted_identities_file: None,
             reload_from_trusted_identities_file: None,
             credential: None,
+            credential_refresh: false,
+            credential_refresh_skew: DEFAULT_CREDENTIAL_REFRESH_SKEW,
             trust_context_opts: node_manager_defaults.trust_context_opts,
         }
     }
@@ -274,6 +285,11 @@ async fn run_foreground_node(
         )
         .await?;
     debug!("created node {node_info:?}");
+    if cmd.credential_refresh {
+        opts.state
+            .set_node_credential_refresh_skew(&node_name, cmd.credential_refresh_skew)
+            .await?;
+    }
 
     let named_trust_context = opts
         .state
@@ -315,7 +331,8 @@ async fn run_foreground_node(
             listener.flow_control_id().clone(),
             tcp.async_try_clone().await.into_diagnostic()?,
         ),
-        NodeManagerTrustOptions::new(named_trust_context),
+        NodeManagerTrustOptions::new(named_trust_context)
+            .with_credential_refresh(cmd.credential_refresh, cmd.credential_refresh_skew),
     )
     .await
     .into_diagnostic()?;
@@ -458,6 +475,8 @@ pub async fn spawn_background_node(
             .as_ref()
             .map(|config| serde_json::to_string(config).unwrap()),
         cmd.credential.as_ref(),
+        cmd.credential_refresh
+            .then_some(cmd.credential_refresh_skew),
         trust_context.as_ref(),
         cmd.trust_context_opts.project_name.clone(),
         cmd.logging_to_file(),
@@ -153,6 +153,10 @@ async fn run_node(
     opts: &CommandGlobalOpts,
 ) -> miette::Result<BackgroundNode> {
     let node_info = opts.state.get_node(node_name).await?;
+    let credential_refresh_skew = opts
+        .state
+        .get_node_credential_refresh_skew(node_name)
+        .await?;
     opts.state.stop_node(node_name, false).await?;
     let node_address = node_info
         .tcp_listener_address()
@@ -162,18 +166,19 @@ async fn run_node(
     // Restart node
     spawn_node(
         opts,
-        node_name,     // The selected node name
-        &None,         // Use the default identity
-        &None,         // Use the default vault
-        &node_address, // The selected node api address
-        None,          // No project information available
-        None,          // No trusted identities
-        None,          // "
-        None,          // Launch config
-        None,          // Authority Identity
-        None,          // Credential
-        None,          // Trust Context
-        true,          // Restarted nodes will log to files
+        node_name,               // The selected node name
+        &None,                   // Use the default identity
+        &None,                   // Use the default vault
+        &node_address,           // The selected node api address
+        None,                    // No project information available
+        None,                    // No trusted identities
+        None,                    // "
+        None,                    // Launch config
+        None,                    // Authority Identity
+        None,                    // Credential
+        credential_refresh_skew, // Keep refreshing the credential if the node did
+        None,                    // Trust Context
+        true,                    // Restarted nodes will log to files
     )
     .await?;
 
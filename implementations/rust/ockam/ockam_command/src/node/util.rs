@@ -2,6 +2,7 @@ use std::env::current_exe;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::time::Duration;
 
 use miette::IntoDiagnostic;
 use miette::{miette, Context as _};
@@ -66,6 +67,7 @@ pub async fn spawn_node(
     reload_from_trusted_identities_file: Option<&PathBuf>,
     launch_config: Option<String>,
     credential: Option<&String>,
+    credential_refresh_skew: Option<Duration>,
     trust_context: Option<&NamedTrustContext>,
     project_name: Option<String>,
     logging_to_file: bool,
@@ -126,6 +128,12 @@ pub async fn spawn_node(
         args.push(credential.to_string());
     }
 
+    if let Some(skew) = credential_refresh_skew {
+        args.push("--credential-refresh".to_string());
+        args.push("--credential-refresh-skew".to_string());
+        args.push(format!("{}ms", skew.as_millis()));
+    }
+
     if let Some(trust_context) = trust_context {
         args.push("--trust-context".to_string());
         args.push(trust_context.name());
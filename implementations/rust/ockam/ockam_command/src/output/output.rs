@@ -274,11 +274,24 @@ impl Output for InletStatus {
             self.outlet_route.to_string()
         };
 
+        let labels = match &self.labels {
+            Some(labels) => format!(
+                "\n    Labels: {}",
+                labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .color(OckamColor::PrimaryResource.color())
+            ),
+            None => "".to_string(),
+        };
+
         let output = format!(
             r#"
 Inlet {}
     TCP Address: {}
-    Outlet Address: {}
+    Outlet Address: {}{}
             "#,
             self.alias
                 .to_string()
@@ -286,7 +299,8 @@ Inlet {}
             self.bind_addr
                 .to_string()
                 .color(OckamColor::PrimaryResource.color()),
-            outlet.color(OckamColor::PrimaryResource.color())
+            outlet.color(OckamColor::PrimaryResource.color()),
+            labels
         );
 
         Ok(output)
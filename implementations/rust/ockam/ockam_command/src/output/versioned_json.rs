@@ -0,0 +1,136 @@
+use miette::IntoDiagnostic;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Version of the schema of the JSON outputs describing TCP inlets.
+/// It must be incremented every time the fields of `InletStatus` change.
+/// Version 2 added the idle timeout, the labels, the reconnect count, the buffer size
+/// and the prewarmed tunnels, version 3 the address of the outlet
+pub const INLET_JSON_SCHEMA_VERSION: u32 = 3;
+
+/// Version of the schema of the JSON output of `ockam identity delete`.
+/// Version 2 added the identifier, version 3 the exported identity and the recreated identifier
+pub const IDENTITY_DELETE_JSON_SCHEMA_VERSION: u32 = 3;
+
+/// Wrap some data in an envelope specifying the version of its schema:
+/// `{ "schema_version": 1, "data": { ... } }`
+///
+/// Each type of output has its own version, so that downstream parsers can detect
+/// changes in the shape of the data they consume
+pub fn versioned_json<T: Serialize + ?Sized>(
+    schema_version: u32,
+    data: &T,
+) -> miette::Result<Value> {
+    Ok(json!({
+        "schema_version": schema_version,
+        "data": serde_json::to_value(data).into_diagnostic()?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use ockam_api::nodes::models::portal::InletStatus;
+    use ockam_api::ConnectionStatus;
+
+    use super::*;
+
+    /// Fields of the JSON representation of an inlet, for each version of its schema.
+    /// A new version must be added here when `INLET_JSON_SCHEMA_VERSION` is incremented
+    const INLET_JSON_FIELDS: &[(u32, &[&str])] = &[
+        (
+            1,
+            &[
+                "alias",
+                "bind_addr",
+                "outlet_route",
+                "payload",
+                "status",
+                "worker_addr",
+            ],
+        ),
+        (
+            2,
+            &[
+                "alias",
+                "bind_addr",
+                "buffer_size",
+                "idle_timeout",
+                "labels",
+                "outlet_route",
+                "payload",
+                "prewarm",
+                "prewarmed",
+                "reconnect_count",
+                "status",
+                "worker_addr",
+            ],
+        ),
+        (
+            3,
+            &[
+                "alias",
+                "bind_addr",
+                "buffer_size",
+                "idle_timeout",
+                "labels",
+                "outlet_addr",
+                "outlet_route",
+                "payload",
+                "prewarm",
+                "prewarmed",
+                "reconnect_count",
+                "status",
+                "worker_addr",
+            ],
+        ),
+    ];
+
+    /// This test fails when the fields of `InletStatus` change without incrementing
+    /// `INLET_JSON_SCHEMA_VERSION`
+    #[test]
+    fn test_inlet_json_schema_snapshot() -> miette::Result<()> {
+        let inlet = InletStatus::new(
+            "127.0.0.1:4000",
+            "inlet",
+            "alias",
+            None,
+            "/service/outlet",
+            ConnectionStatus::Up,
+        );
+        let json = versioned_json(INLET_JSON_SCHEMA_VERSION, &inlet)?;
+        let mut fields: Vec<&str> = json["data"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        fields.sort();
+
+        let expected = INLET_JSON_FIELDS
+            .iter()
+            .find(|(version, _)| *version == INLET_JSON_SCHEMA_VERSION)
+            .map(|(_, fields)| fields.to_vec())
+            .expect("the fields of the current inlet schema version must be listed");
+        assert_eq!(
+            fields, expected,
+            "the fields of InletStatus changed: increment INLET_JSON_SCHEMA_VERSION \
+             and add the new fields to INLET_JSON_FIELDS"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_versioned_json() -> miette::Result<()> {
+        let json = versioned_json(
+            IDENTITY_DELETE_JSON_SCHEMA_VERSION,
+            &json!({ "name": "alice" }),
+        )?;
+        assert_eq!(json["schema_version"], IDENTITY_DELETE_JSON_SCHEMA_VERSION);
+        assert_eq!(json["data"]["name"], "alice");
+
+        let json = versioned_json(INLET_JSON_SCHEMA_VERSION, &vec!["alice", "bob"])?;
+        assert_eq!(json["schema_version"], INLET_JSON_SCHEMA_VERSION);
+        assert_eq!(json["data"][1], "bob");
+        Ok(())
+    }
+}
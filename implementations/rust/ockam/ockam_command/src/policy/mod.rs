@@ -72,7 +72,7 @@ pub(crate) async fn add_default_project_policy(
 
     let expr = eq([ident("subject.trust_context_id"), str(project_id)]);
     let bdy = Policy::new(expr);
-    let req = Request::post(policy_path(resource, &Action::new("handle_message"))).body(bdy);
+    let req = Request::post(policy_path(resource, &Action::HANDLE_MESSAGE)).body(bdy);
 
     node.tell(ctx, req).await?;
     Ok(())
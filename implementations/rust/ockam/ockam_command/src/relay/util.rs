@@ -1,5 +1,12 @@
+use std::str::FromStr;
+
+use miette::{miette, Diagnostic};
+
+use ockam_api::cli_state::CliState;
+use ockam_multiaddr::MultiAddr;
+
+use crate::util::process_nodes_multiaddr;
 use crate::Result;
-use miette::miette;
 
 pub fn relay_name_parser(arg: &str) -> Result<String> {
     if arg.starts_with("forward_to_") {
@@ -8,3 +15,56 @@ pub fn relay_name_parser(arg: &str) -> Result<String> {
         Err(miette!("The relay name must be prefixed with 'forward_to_'").into())
     }
 }
+
+/// Errors returned when a `--to` argument can't be turned into a route
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error, Diagnostic)]
+pub enum ToAddressError {
+    #[error("There is no default project defined")]
+    #[diagnostic(help("Please enroll or create a project"))]
+    NoDefaultProject,
+
+    #[error("The relay name {0} can't contain '/'")]
+    InvalidRelayName(String),
+
+    #[error("Invalid address value or relay name: {0}")]
+    InvalidRoute(String),
+
+    #[error("The variable ${0} is not set")]
+    #[diagnostic(help("Use --allow-unset-vars to keep it as is"))]
+    UnsetVariable(String),
+}
+
+/// Expand a `--to` argument into a route.
+///
+/// The argument can either be a full route, or the name of an existing relay in the default
+/// project, in which case it's expanded to the route reaching `service` through that relay:
+/// `/project/<project>/service/forward_to_<relay>/secure/api/service/<service>`
+pub async fn relay_name_or_route(
+    state: &CliState,
+    to: &str,
+    default_project_name: &Option<String>,
+    service: &str,
+) -> std::result::Result<String, ToAddressError> {
+    let ma = match MultiAddr::from_str(to) {
+        // The user provided a full route
+        Ok(ma) => ma,
+        // The user provided the name of the relay
+        Err(_) => {
+            if to.contains('/') {
+                return Err(ToAddressError::InvalidRelayName(to.to_string()));
+            }
+            let project_name = default_project_name
+                .clone()
+                .ok_or(ToAddressError::NoDefaultProject)?;
+
+            MultiAddr::from_str(&format!(
+                "/project/{project_name}/service/forward_to_{to}/secure/api/service/{service}"
+            ))
+            .map_err(|e| ToAddressError::InvalidRoute(e.to_string()))?
+        }
+    };
+    process_nodes_multiaddr(&ma, state)
+        .await
+        .map(|ma| ma.to_string())
+        .map_err(|e| ToAddressError::InvalidRoute(e.to_string()))
+}
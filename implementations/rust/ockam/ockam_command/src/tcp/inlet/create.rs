@@ -1,37 +1,58 @@
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use clap::Args;
 use colorful::Colorful;
-use miette::{miette, IntoDiagnostic};
-use miette::{Result, WrapErr};
+use indicatif::ProgressBar;
+use miette::{miette, IntoDiagnostic, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
 use tokio::sync::Mutex;
 use tokio::try_join;
-use tracing::trace;
+use tracing::{trace, warn, Level};
 
 use ockam::identity::Identifier;
 use ockam::Context;
 use ockam_api::address::extract_address_value;
 use ockam_api::cli_state::CliState;
-use ockam_api::nodes::models::portal::InletStatus;
+use ockam_api::nodes::models::portal::{
+    validate_buffer_size, validate_label, InletList, InletOptions, InletStatus, OutletList,
+    OutletStatus,
+};
 use ockam_api::nodes::service::portals::Inlets;
 use ockam_api::nodes::BackgroundNode;
-use ockam_core::api::{Reply, Status};
+use ockam_api::ConnectionStatus;
+use ockam_core::api::{Reply, Request, Status};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::Error;
-use ockam_multiaddr::proto::Project;
+use ockam_multiaddr::proto::{Project, Secure, Service};
 use ockam_multiaddr::{MultiAddr, Protocol as _};
+use ockam_transport_tcp::{IpCidr, ProxyProtocolVersion, TcpKeepaliveOptions};
 
+use crate::output::{versioned_json, INLET_JSON_SCHEMA_VERSION};
+use crate::relay::util::{relay_name_or_route, ToAddressError};
 use crate::tcp::util::alias_parser;
 use crate::terminal::OckamColor;
+use crate::util::api::list_outlets;
 use crate::util::duration::duration_parser;
-use crate::util::parsers::socket_addr_parser;
-use crate::util::{find_available_port, node_rpc, port_is_free_guard, process_nodes_multiaddr};
+use crate::util::parsers::{
+    buffer_size_parser, interface_and_port_parser, ip_and_optional_port_parser, ip_cidr_parser,
+    label_parser, log_level_parser, proxy_protocol_parser, socket_addr_parser,
+    socket_addr_range_parser, SocketAddrRange,
+};
+use crate::util::{find_available_port, node_rpc, port_is_free_guard};
 use crate::{display_parse_logs, docs, fmt_log, fmt_ok, CommandGlobalOpts};
 
 const AFTER_LONG_HELP: &str = include_str!("./static/create/after_long_help.txt");
 
+static VARIABLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\$([A-Za-z_][A-Za-z0-9_]*)").expect("Invalid regex for VARIABLE"));
+
 /// Create TCP Inlets
 #[derive(Clone, Debug, Args)]
 #[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
@@ -41,13 +62,32 @@ pub struct CreateCommand {
     at: Option<String>,
 
     /// Address on which to accept tcp connections.
-    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", hide_default_value = true, default_value_t = default_from_addr(), value_parser = socket_addr_parser)]
-    from: SocketAddr,
+    /// The port can be a range of ports, like `127.0.0.1:5000-5010`, to create one inlet per port,
+    /// each one sending its traffic to the same outlet. The alias of each inlet is then suffixed with its port
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", hide_default_value = true, default_value_t = default_from_addr().into(), value_parser = socket_addr_range_parser)]
+    from: SocketAddrRange,
+
+    /// Network interface and port on which to accept tcp connections, for example `eth0:5000`.
+    /// The inlet listens at the address of the interface when the inlet is started or restarted
+    #[arg(long, display_order = 900, value_name = "INTERFACE:PORT", conflicts_with = "SOCKET_ADDRESS", value_parser = interface_and_port_parser)]
+    from_interface: Option<(String, u16)>,
 
-    /// Route to a tcp outlet. Can be a full route or the name of an existing relay
+    /// Route to a tcp outlet. Can be a full route or the name of an existing relay.
+    /// `$VARIABLES` are replaced with the built-in values ($PROJECT_NAME, $RELAY_NAME)
+    /// or with the values of the environment variables
     #[arg(long, display_order = 900, id = "ROUTE", default_value_t = default_to_addr())]
     to: String,
 
+    /// Project used to expand `$PROJECT_NAME` and the relay names.
+    /// The default project is used otherwise, or the last project used successfully
+    /// when there is no default project
+    #[arg(long = "project", display_order = 900, value_name = "PROJECT_NAME")]
+    project_name: Option<String>,
+
+    /// Keep the `$VARIABLES` of the route which can't be resolved instead of failing
+    #[arg(long, display_order = 900)]
+    allow_unset_vars: bool,
+
     /// Authorized identity for secure channel connection
     #[arg(long, name = "AUTHORIZED", display_order = 900)]
     authorized: Option<Identifier>,
@@ -56,6 +96,11 @@ pub struct CreateCommand {
     #[arg(long, display_order = 900, id = "ALIAS", value_parser = alias_parser)]
     alias: Option<String>,
 
+    /// Replace the inlet already using the same alias, if any.
+    /// Otherwise the creation fails when the alias is already in use
+    #[arg(long, display_order = 900, requires = "ALIAS")]
+    replace: bool,
+
     /// Time to wait for the outlet to be available.
     #[arg(long, display_order = 900, id = "WAIT", default_value = "5s", value_parser = duration_parser)]
     connection_wait: Duration,
@@ -64,7 +109,117 @@ pub struct CreateCommand {
     #[arg(long, display_order = 900, id = "RETRY", default_value = "20s", value_parser = duration_parser)]
     retry_wait: Duration,
 
-    /// Override default timeout
+    /// Create the inlet without waiting for the outlet to be available.
+    /// The connection to the outlet is established in the background, once a first client
+    /// connects to the inlet
+    #[arg(long, display_order = 900, conflicts_with_all = ["WAIT", "RETRY"])]
+    no_wait: bool,
+
+    /// Only accept traffic from peers presenting a valid credential
+    /// for the trust context of the node
+    #[arg(long, display_order = 900)]
+    require_credential: bool,
+
+    /// Send a PROXY protocol header, v1 or v2, with the address of the client
+    /// at the beginning of each connection relayed to the outlet
+    #[arg(long, display_order = 900, value_name = "VERSION", value_parser = proxy_protocol_parser)]
+    proxy_protocol: Option<ProxyProtocolVersion>,
+
+    /// Keep the client connections open for at most this duration when the connection
+    /// to the outlet is re-established, instead of closing them.
+    /// The inlet then keeps listening at the same address during the reconnection
+    #[arg(long, display_order = 900, value_name = "DURATION", value_parser = duration_parser)]
+    hold_on_reconnect: Option<Duration>,
+
+    /// Close a client connection when no data is sent or received for this duration.
+    /// A zero duration keeps the connections open until they are closed by one of their ends
+    #[arg(long, display_order = 900, value_name = "DURATION", value_parser = duration_parser)]
+    idle_timeout: Option<Duration>,
+
+    /// Enable TCP keepalive on the client connections and on the connection to the outlet,
+    /// with a first probe sent after the connection has been idle for this duration.
+    /// The OS defaults are used for the client connections otherwise
+    #[arg(long, display_order = 900, value_name = "DURATION", value_parser = duration_parser)]
+    keepalive: Option<Duration>,
+
+    /// Interval between two TCP keepalive probes
+    #[arg(long, display_order = 900, value_name = "DURATION", value_parser = duration_parser, requires = "keepalive")]
+    keepalive_interval: Option<Duration>,
+
+    /// Number of unacknowledged TCP keepalive probes before a connection is dropped
+    #[arg(
+        long,
+        display_order = 900,
+        value_name = "COUNT",
+        requires = "keepalive"
+    )]
+    keepalive_retries: Option<u32>,
+
+    /// Local address, with an optional port, from which the connection to the outlet is made.
+    /// This selects the network interface used to reach the outlet when the node has several ones
+    #[arg(long, display_order = 900, value_name = "IP[:PORT]", value_parser = ip_and_optional_port_parser)]
+    egress_bind: Option<SocketAddr>,
+
+    /// Number of tunnels connected to the outlet before any client connects, so that a new client
+    /// doesn't wait for the connection to the outlet. A tunnel is prewarmed again every time
+    /// a client uses one of them. Not available with `--socks5`, since the target of a tunnel
+    /// is only known once its client is connected
+    #[arg(
+        long,
+        display_order = 900,
+        value_name = "COUNT",
+        conflicts_with = "socks5"
+    )]
+    prewarm: Option<u32>,
+
+    /// Maximum number of client connections served at the same time, the prewarmed tunnels included.
+    /// The other clients wait until a connection is closed
+    #[arg(long, display_order = 900, value_name = "COUNT", value_parser = clap::value_parser!(u32).range(1..))]
+    max_connections: Option<u32>,
+
+    /// Only accept the client connections coming from these ranges of addresses, separated by commas.
+    /// The other connections are closed right away. All the connections are accepted otherwise
+    #[arg(long, display_order = 900, value_name = "CIDR", value_parser = ip_cidr_parser, value_delimiter = ',')]
+    allow_from: Vec<IpCidr>,
+
+    /// Label of the inlet, given as `key=value`, used to group and filter the inlets
+    /// with `ockam tcp-inlet list --label`. This argument can be repeated to set several labels
+    #[arg(long = "label", display_order = 900, value_name = "KEY=VALUE", value_parser = label_parser)]
+    labels: Vec<(String, String)>,
+
+    /// Act as a SOCKS5 proxy: each client chooses the host and port the outlet connects to.
+    /// The outlet only connects to the targets allowed by its `connect_to_target` policy
+    #[arg(long, display_order = 900)]
+    socks5: bool,
+
+    /// Size, in bytes, of the buffer used to relay the data of each client connection,
+    /// in both directions. A larger buffer can improve the throughput of fast links
+    #[arg(long, display_order = 900, value_name = "BYTES", value_parser = buffer_size_parser)]
+    buffer_size: Option<usize>,
+
+    /// Level of the logs emitted by this inlet, for example `debug` to log each client connection.
+    /// The other inlets keep the log level of the node
+    #[arg(long, display_order = 900, value_name = "LEVEL", value_parser = log_level_parser)]
+    log_level: Option<Level>,
+
+    /// Refuse to create the inlet if the route to the outlet, once resolved,
+    /// does not go through a secure channel, with a `/secure/` segment
+    #[arg(long, display_order = 900)]
+    require_secure_channel: bool,
+
+    /// Check that the node is responsive before creating the inlet,
+    /// and fail immediately if it doesn't answer
+    #[arg(long, display_order = 900)]
+    precheck: bool,
+
+    /// Create the inlets described in a YAML or JSON file, instead of a single inlet.
+    /// Each inlet has a `from` address, a `to` route and an `alias`, and can override the
+    /// other arguments of this command. Either all the inlets are created or none of them
+    #[arg(long, display_order = 900, value_name = "FILE", conflicts_with_all = ["SOCKET_ADDRESS", "from_interface", "ROUTE", "ALIAS"])]
+    config: Option<PathBuf>,
+
+    /// Override default timeout.
+    /// The whole command, including the retries, returns at the latest after this duration
     #[arg(long, value_parser = duration_parser)]
     timeout: Option<Duration>,
 }
@@ -83,103 +238,141 @@ impl CreateCommand {
         node_rpc(rpc, (opts, self));
     }
 
-    fn to(&self) -> MultiAddr {
-        MultiAddr::from_str(&self.to).unwrap()
+    /// Return the address to listen at. When an interface is used,
+    /// the node replaces the IP address with the address of the interface
+    fn listen_addr(&self) -> String {
+        match &self.from_interface {
+            Some((_, port)) => {
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), *port).to_string()
+            }
+            None => self.from.to_string(),
+        }
     }
 
-    async fn parse_args(mut self, opts: &CommandGlobalOpts) -> Result<Self> {
-        let default_project_name = &opts
-            .state
-            .get_default_project()
-            .await
-            .ok()
-            .map(|p| p.name());
+    /// Return a description of the address to listen at
+    fn from_description(&self) -> String {
+        match &self.from_interface {
+            Some((interface, port)) => format!("{interface}:{port}"),
+            None => self.from.to_string(),
+        }
+    }
 
-        self.to = Self::parse_arg_to(&opts.state, self.to, default_project_name).await?;
-        Ok(self)
+    fn to(&self) -> MultiAddr {
+        MultiAddr::from_str(&self.to).unwrap()
     }
 
-    async fn parse_arg_to(
-        state: &CliState,
-        to: impl Into<String>,
-        default_project_name: &Option<String>,
-    ) -> Result<String> {
-        let mut to = to.into();
-        let missing_project_error =
-            "There is no default project defined. Please enroll or create a project.";
-
-        // Replace the placeholders in the default arg value
-        if to.starts_with("/project/") {
-            let project_name = default_project_name
-                .clone()
-                .ok_or(miette!(missing_project_error))?;
-            to = to.replace("$PROJECT_NAME", &project_name);
-            to = to.replace("$RELAY_NAME", "default");
-        }
-
-        // Parse the address
-        let ma = match MultiAddr::from_str(&to) {
-            // The user provided a full route
-            Ok(ma) => ma,
-            // The user provided the name of the relay
-            Err(_) => {
-                if to.contains('/') {
-                    return Err(miette!("The relay name can't contain '/'"));
-                }
-                let project_name = default_project_name
-                    .clone()
-                    .ok_or(miette!(missing_project_error))?;
-
-                MultiAddr::from_str(&format!(
-                    "/project/{}/service/forward_to_{to}/secure/api/service/outlet",
-                    project_name
-                ))
-                .into_diagnostic()
-                .wrap_err("Invalid address value or relay name")?
+    /// Return the keepalive configuration of the inlet sockets, if any
+    fn keepalive(&self) -> Option<TcpKeepaliveOptions> {
+        self.keepalive.map(|time| {
+            let keepalive = TcpKeepaliveOptions::new(time);
+            let keepalive = match self.keepalive_interval {
+                Some(interval) => keepalive.with_interval(interval),
+                None => keepalive,
+            };
+            match self.keepalive_retries {
+                Some(retries) => keepalive.with_retries(retries),
+                None => keepalive,
             }
-        };
-        Ok(process_nodes_multiaddr(&ma, state).await?.to_string())
+        })
     }
-}
 
-async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> Result<()> {
-    let cmd = cmd.parse_args(&opts).await?;
-    opts.terminal.write_line(&fmt_log!(
-        "Creating TCP Inlet at {}...\n",
-        cmd.from
-            .to_string()
-            .color(OckamColor::PrimaryResource.color())
-    ))?;
-    display_parse_logs(&opts);
+    /// Return the options sent to the node to create the inlet
+    fn inlet_options(&self) -> InletOptions {
+        InletOptions::default()
+            .with_wait_for_outlet_timeout(self.connection_wait)
+            .with_wait_connection(!self.no_wait)
+            .with_require_credential(self.require_credential)
+            .with_proxy_protocol(self.proxy_protocol)
+            .with_listen_interface(
+                self.from_interface
+                    .as_ref()
+                    .map(|(interface, _)| interface.clone()),
+            )
+            .with_hold_on_reconnect(self.hold_on_reconnect)
+            .with_idle_timeout(self.idle_timeout)
+            .with_keepalive(self.keepalive())
+            .with_egress_bind(self.egress_bind)
+            .with_allowed_sources(self.allow_from.clone())
+            .with_labels(self.labels.iter().cloned().collect())
+            .with_socks5(self.socks5)
+            .with_buffer_size(self.buffer_size)
+            .with_log_level(self.log_level)
+            .with_prewarm(self.prewarm)
+            .with_max_connections(self.max_connections)
+    }
 
-    let mut node = BackgroundNode::create(&ctx, &opts.state, &cmd.at).await?;
-    cmd.timeout.map(|t| node.set_timeout(t));
+    /// Create the inlet on the node, retrying until the outlet is available
+    /// unless the inlet must be created without waiting.
+    /// The spinner, if any, displays a message while the creation is retried.
+    ///
+    /// An inlet replaced with `--replace` is only deleted once the arguments are validated,
+    /// and it is recreated if the new inlet can not be created
+    async fn create_inlet(
+        &self,
+        ctx: &Context,
+        node: &BackgroundNode,
+        spinner: Option<&ProgressBar>,
+    ) -> Result<InletStatus> {
+        if self.to().matches(0, &[Project::CODE.into()]) && self.authorized.is_some() {
+            return Err(miette!(
+                "--authorized can not be used with project addresses"
+            ));
+        }
+        let replaced = match &self.alias {
+            Some(alias) => {
+                let inlets: InletList = node.ask(ctx, Request::get("/node/inlet")).await?;
+                inlet_to_replace(&inlets, alias, self.replace)?
+            }
+            None => None,
+        };
+        // the address of the replaced inlet is freed when it is deleted
+        let from = self.from.start();
+        if self.from_interface.is_none() && !replaced.as_ref().is_some_and(|i| uses_port(i, &from))
+        {
+            port_is_free_guard(&from)?;
+        }
 
-    let is_finished: Mutex<bool> = Mutex::new(false);
-    let progress_bar = opts.terminal.progress_spinner();
-    let create_inlet = async {
-        port_is_free_guard(&cmd.from)?;
-        if cmd.to().matches(0, &[Project::CODE.into()]) && cmd.authorized.is_some() {
-            return Err(miette!("--authorized can not be used with project addresses").into());
+        let Some(replaced) = replaced else {
+            return self.create_new_inlet(ctx, node, spinner).await;
+        };
+        trace!("replacing the inlet at {}", replaced.bind_addr);
+        node.delete_inlet(ctx, &replaced.alias)
+            .await?
+            .success()
+            .into_diagnostic()?;
+        match self.create_new_inlet(ctx, node, spinner).await {
+            Ok(inlet) => Ok(inlet),
+            Err(e) => match recreate_inlet(ctx, node, &replaced).await {
+                Ok(()) => Err(miette!("{e}. The replaced inlet has been recreated")),
+                Err(recreate_error) => Err(miette!(
+                    "{e}. The replaced inlet could not be recreated: {recreate_error}"
+                )),
+            },
         }
+    }
 
-        let inlet = loop {
+    /// Create the inlet on the node, retrying until the outlet is available
+    /// unless the inlet must be created without waiting
+    async fn create_new_inlet(
+        &self,
+        ctx: &Context,
+        node: &BackgroundNode,
+        spinner: Option<&ProgressBar>,
+    ) -> Result<InletStatus> {
+        loop {
             let result: Reply<InletStatus> = node
                 .create_inlet(
-                    &ctx,
-                    &cmd.from.to_string(),
-                    &cmd.to(),
-                    &cmd.alias,
-                    &cmd.authorized,
-                    cmd.connection_wait,
+                    ctx,
+                    &self.listen_addr(),
+                    &self.to(),
+                    &self.alias,
+                    &self.authorized,
+                    &self.inlet_options(),
                 )
                 .await?;
 
             match result {
-                Reply::Successful(inlet_status) => {
-                    *is_finished.lock().await = true;
-                    break inlet_status;
-                }
+                Reply::Successful(inlet_status) => return Ok(inlet_status),
                 Reply::Failed(e, s) => {
                     if let Some(status) = s {
                         if status == Status::BadRequest {
@@ -187,81 +380,672 @@ async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> R
                                 Origin::Api,
                                 Kind::Invalid,
                                 e.message().unwrap_or("bad request when creating an inlet"),
-                            ))?
+                            ))
+                            .into_diagnostic()?
                         }
                     };
                     trace!("the inlet creation returned a non-OK status: {s:?}");
 
-                    if cmd.retry_wait.as_millis() == 0 {
-                        return Err(miette!("Failed to create TCP inlet"))?;
+                    if self.no_wait || self.retry_wait.as_millis() == 0 {
+                        return Err(miette!("Failed to create TCP inlet"));
                     }
 
-                    if let Some(spinner) = progress_bar.as_ref() {
+                    if let Some(spinner) = spinner {
                         spinner.set_message(format!(
                             "Waiting for inlet {} to be available... Retrying momentarily",
-                            &cmd.to
+                            &self
+                                .to
                                 .to_string()
                                 .color(OckamColor::PrimaryResource.color())
                         ));
                     }
-                    tokio::time::sleep(cmd.retry_wait).await
+                    tokio::time::sleep(self.retry_wait).await
                 }
             }
-        };
+        }
+    }
 
-        Ok(inlet)
-    };
+    /// Return the name of the project to use in the route to the outlet
+    async fn default_project_name(&self, state: &CliState) -> Option<String> {
+        state
+            .get_project_name_or_default(&self.project_name)
+            .await
+            .ok()
+            .flatten()
+    }
 
-    let progress_messages = vec![
-        format!(
-            "Creating TCP Inlet on {}...",
-            &node.node_name().color(OckamColor::PrimaryResource.color())
-        ),
-        format!(
-            "Hosting TCP Socket at {}...",
-            &cmd.from
-                .to_string()
-                .color(OckamColor::PrimaryResource.color())
-        ),
-        format!(
-            "Establishing connection to outlet {}...",
-            &cmd.to
-                .to_string()
-                .color(OckamColor::PrimaryResource.color())
-        ),
-    ];
-    let progress_output = opts.terminal.progress_output_with_progress_bar(
-        &progress_messages,
-        &is_finished,
-        progress_bar.as_ref(),
-    );
-    let (inlet, _) = try_join!(create_inlet, progress_output)?;
-    opts.terminal
-        .stdout()
-        .plain(
-            fmt_ok!(
-                "TCP Inlet {} on node {} is now sending traffic\n",
-                &cmd.from
-                    .to_string()
-                    .color(OckamColor::PrimaryResource.color()),
+    /// Return the name of the project reached by the route to the outlet, if any
+    fn route_project_name(&self) -> Option<String> {
+        let to = self.to();
+        let project = to.first()?.cast::<Project>()?.to_string();
+        Some(project)
+    }
+
+    /// Remember the project reached by the inlet so that it can be used by the next commands
+    /// when there is no default project
+    async fn set_last_used_project(&self, state: &CliState) {
+        if let Some(project_name) = self.route_project_name() {
+            if let Err(e) = state.set_last_used_project_name(&project_name).await {
+                warn!("the last used project {project_name} could not be stored: {e}");
+            }
+        }
+    }
+
+    async fn parse_args(mut self, opts: &CommandGlobalOpts) -> Result<Self> {
+        let default_project_name = &self.default_project_name(&opts.state).await;
+
+        self.to = Self::parse_arg_to(
+            &opts.state,
+            self.to,
+            default_project_name,
+            self.allow_unset_vars,
+        )
+        .await?;
+        self.check_secure_route()?;
+        Ok(self)
+    }
+
+    /// Return an error if a secure channel is required and the route to the outlet
+    /// doesn't contain any secure channel
+    fn check_secure_route(&self) -> Result<()> {
+        if !self.require_secure_channel {
+            return Ok(());
+        }
+        let to = MultiAddr::from_str(&self.to)
+            .map_err(|e| miette!("Invalid route to the outlet {}: {e}", self.to))?;
+        if to.iter().any(|p| p.code() == Secure::CODE) {
+            Ok(())
+        } else {
+            Err(miette!(
+                "The route to the outlet {} does not use a secure channel. Add a /secure/ segment to the route or remove --require-secure-channel",
+                self.to.clone().color(OckamColor::PrimaryResource.color())
+            ))
+        }
+    }
+
+    /// Return an error if the inlet would send its traffic to an outlet of the same node
+    /// which connects back to the address of the inlet
+    async fn check_for_loop(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        node: &BackgroundNode,
+    ) -> Result<()> {
+        // the address of an interface is only known by the node
+        if self.from_interface.is_some() {
+            return Ok(());
+        }
+        let node_address = state
+            .get_node(&node.node_name())
+            .await
+            .ok()
+            .and_then(|n| n.tcp_listener_multi_address().ok());
+        let outlets: OutletList = node.ask(ctx, list_outlets()).await?;
+        match looping_outlet(&self.from.start(), &self.to(), &node_address, &outlets) {
+            Some(outlet) => Err(miette!(
+                "The TCP inlet at {} would send its traffic to the outlet {} of node {}, which connects back to the inlet",
+                self.from.to_string().color(OckamColor::PrimaryResource.color()),
+                outlet.alias.clone().color(OckamColor::PrimaryResource.color()),
+                node.node_name().color(OckamColor::PrimaryResource.color())
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Return the commands creating the inlets of a configuration file.
+    /// Each command takes the arguments of this command, overridden by the inlet configuration
+    async fn parse_config(&self, state: &CliState, config: &InletsConfig) -> Result<Vec<Self>> {
+        if config.inlets.is_empty() {
+            return Err(miette!("The configuration file does not contain any inlet"));
+        }
+        let default_project_name = &self.default_project_name(state).await;
+
+        let mut aliases = HashSet::new();
+        let mut addresses = HashSet::new();
+        let mut commands = Vec::with_capacity(config.inlets.len());
+        for inlet in &config.inlets {
+            let mut cmd = inlet.apply(self.clone())?;
+            if !aliases.insert(inlet.alias.clone()) {
+                return Err(miette!(
+                    "The alias {} is used by several inlets of the configuration file",
+                    inlet.alias
+                ));
+            }
+            if !addresses.insert(cmd.from.start()) {
+                return Err(miette!(
+                    "The address {} is used by several inlets of the configuration file",
+                    cmd.from
+                ));
+            }
+            cmd.to = Self::parse_arg_to(state, cmd.to, default_project_name, cmd.allow_unset_vars)
+                .await
+                .map_err(|e| miette!("Invalid route for the inlet {}: {e}", inlet.alias))?;
+            cmd.check_secure_route()?;
+            port_is_free_guard(&cmd.from.start())?;
+            commands.push(cmd);
+        }
+        Ok(commands)
+    }
+
+    /// Return the commands creating one inlet for each port of the range of addresses to listen at.
+    /// The alias of each inlet, if any, is suffixed with its port
+    fn parse_port_range(&self) -> Result<Vec<Self>> {
+        let mut commands = vec![];
+        for from in self.from.addresses() {
+            port_is_free_guard(&from)?;
+            let mut cmd = self.clone();
+            cmd.from = from.into();
+            cmd.alias = self
+                .alias
+                .as_ref()
+                .map(|alias| format!("{alias}-{}", from.port()));
+            commands.push(cmd);
+        }
+        Ok(commands)
+    }
+
+    async fn parse_arg_to(
+        state: &CliState,
+        to: impl Into<String>,
+        default_project_name: &Option<String>,
+        allow_unset_vars: bool,
+    ) -> std::result::Result<String, ToAddressError> {
+        let to = substitute_variables(
+            &to.into(),
+            default_project_name,
+            allow_unset_vars,
+            env_variable,
+        )?;
+        relay_name_or_route(state, &to, default_project_name, "outlet").await
+    }
+}
+
+/// Replace the `$VARIABLES` found in a route.
+///
+/// A variable is either a built-in value or a variable returned by the `env` lookup function.
+/// When a variable can't be resolved, it's left untouched if `allow_unset_vars` is true,
+/// otherwise an error is returned.
+fn substitute_variables(
+    to: &str,
+    default_project_name: &Option<String>,
+    allow_unset_vars: bool,
+    env: impl Fn(&str) -> Option<String>,
+) -> std::result::Result<String, ToAddressError> {
+    let mut result = String::with_capacity(to.len());
+    let mut last = 0;
+    for captures in VARIABLE.captures_iter(to) {
+        let placeholder = captures.get(0).expect("the whole match is always present");
+        let name = &captures[1];
+        result.push_str(&to[last..placeholder.start()]);
+        match resolve_variable(name, default_project_name, &env)? {
+            Some(value) => result.push_str(&value),
+            None if allow_unset_vars => result.push_str(placeholder.as_str()),
+            None => return Err(ToAddressError::UnsetVariable(name.to_string())),
+        }
+        last = placeholder.end();
+    }
+    result.push_str(&to[last..]);
+    Ok(result)
+}
+
+/// Return the value of a variable, looking first at the built-in values
+/// then at the variables returned by the `env` lookup function
+fn resolve_variable(
+    name: &str,
+    default_project_name: &Option<String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> std::result::Result<Option<String>, ToAddressError> {
+    match name {
+        "PROJECT_NAME" => Ok(Some(
+            default_project_name
+                .clone()
+                .ok_or(ToAddressError::NoDefaultProject)?,
+        )),
+        "RELAY_NAME" => Ok(Some("default".to_string())),
+        _ => Ok(env(name)),
+    }
+}
+
+/// Return the value of an environment variable of the process
+fn env_variable(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> Result<()> {
+    if let Some(config) = &cmd.config {
+        let config = InletsConfig::read(config)?;
+        let commands = cmd.parse_config(&opts.state, &config).await?;
+        return create_inlets(&ctx, &opts, &cmd, commands).await;
+    }
+    let cmd = cmd.parse_args(&opts).await?;
+    if cmd.from.is_range() {
+        let commands = cmd.parse_port_range()?;
+        return create_inlets(&ctx, &opts, &cmd, commands).await;
+    }
+    opts.terminal.write_line(&fmt_log!(
+        "Creating TCP Inlet at {}...\n",
+        cmd.from_description()
+            .color(OckamColor::PrimaryResource.color())
+    ))?;
+    display_parse_logs(&opts);
+
+    let is_finished: Mutex<bool> = Mutex::new(false);
+    let progress_bar = opts.terminal.progress_spinner();
+    // the deadline includes the connection to the node and the checks done before the creation
+    let result = with_deadline(cmd.timeout, async {
+        let mut node = BackgroundNode::create(&ctx, &opts.state, &cmd.at).await?;
+        cmd.timeout.map(|t| node.set_timeout(t));
+
+        if cmd.precheck {
+            let latency = node.ping(&ctx).await.map_err(|e| {
+                miette!(
+                    "The node {} is not responding, the inlet was not created: {e}",
+                    node.node_name()
+                )
+            })?;
+            trace!("the node {} answered in {latency:?}", node.node_name());
+        }
+        cmd.check_for_loop(&ctx, &opts.state, &node).await?;
+
+        let create_inlet = async {
+            let started_at = Instant::now();
+            let inlet = cmd.create_inlet(&ctx, &node, progress_bar.as_ref()).await?;
+            *is_finished.lock().await = true;
+            Ok((inlet, started_at.elapsed()))
+        };
+
+        let progress_messages = vec![
+            format!(
+                "Creating TCP Inlet on {}...",
                 &node.node_name().color(OckamColor::PrimaryResource.color())
-            ) + &fmt_log!(
-                "to the outlet at {}",
+            ),
+            format!(
+                "Hosting TCP Socket at {}...",
+                &cmd.from_description()
+                    .color(OckamColor::PrimaryResource.color())
+            ),
+            format!(
+                "Establishing connection to outlet {}...",
                 &cmd.to
                     .to_string()
                     .color(OckamColor::PrimaryResource.color())
             ),
+        ];
+        let progress_output = opts.terminal.progress_output_with_progress_bar(
+            &progress_messages,
+            &is_finished,
+            progress_bar.as_ref(),
+        );
+        let ((inlet, elapsed), _) = try_join!(create_inlet, progress_output)?;
+        Ok((node, inlet, elapsed))
+    })
+    .await;
+    if result.is_err() {
+        if let Some(progress_bar) = progress_bar.as_ref() {
+            progress_bar.finish_and_clear();
+        }
+    }
+    let (node, inlet, elapsed) = result?;
+    cmd.set_last_used_project(&opts.state).await;
+    let from = inlet
+        .bind_addr
+        .to_string()
+        .color(OckamColor::PrimaryResource.color());
+    let node_name = node.node_name().color(OckamColor::PrimaryResource.color());
+    let to = cmd
+        .to
+        .to_string()
+        .color(OckamColor::PrimaryResource.color());
+    let plain = if inlet.status == ConnectionStatus::Pending {
+        fmt_ok!(
+            "TCP Inlet {} on node {} is now listening\n",
+            from,
+            node_name
+        ) + &fmt_log!(
+            "and will send traffic to the outlet at {} once it's available",
+            to
         )
+    } else {
+        fmt_ok!(
+            "TCP Inlet {} on node {} is now sending traffic\n",
+            from,
+            node_name
+        ) + &fmt_log!("to the outlet at {}\n", to)
+            + &fmt_log!("connected in {:.1}s", elapsed.as_secs_f64())
+    };
+    opts.terminal
+        .stdout()
+        .plain(plain)
         .machine(inlet.bind_addr.to_string())
-        .json(serde_json::json!(&inlet))
+        .json(inlet_json(&inlet, elapsed)?)
         .write_line()?;
 
     Ok(())
 }
 
+/// Create several inlets, one after the other, from a configuration file or a range of ports.
+/// If one of them can't be created, the inlets already created are deleted
+async fn create_inlets(
+    ctx: &Context,
+    opts: &CommandGlobalOpts,
+    cmd: &CreateCommand,
+    commands: Vec<CreateCommand>,
+) -> Result<()> {
+    opts.terminal
+        .write_line(&fmt_log!("Creating {} TCP Inlets...\n", commands.len()))?;
+    display_parse_logs(opts);
+
+    let mut node = BackgroundNode::create(ctx, &opts.state, &cmd.at).await?;
+    cmd.timeout.map(|t| node.set_timeout(t));
+    if cmd.precheck {
+        node.ping(ctx).await.map_err(|e| {
+            miette!(
+                "The node {} is not responding, the inlets were not created: {e}",
+                node.node_name()
+            )
+        })?;
+    }
+    for inlet_cmd in &commands {
+        inlet_cmd.check_for_loop(ctx, &opts.state, &node).await?;
+    }
+
+    // the created inlets are kept outside of the creation future
+    // so that they can be deleted if the deadline is reached
+    let created: Mutex<Vec<InletStatus>> = Mutex::new(Vec::with_capacity(commands.len()));
+    let create_inlets = async {
+        for inlet_cmd in &commands {
+            let inlet = inlet_cmd
+                .create_inlet(ctx, &node, None)
+                .await
+                .map_err(|e| {
+                    miette!(
+                        "Failed to create the TCP inlet {}: {e}",
+                        inlet_cmd.alias.clone().unwrap_or_default()
+                    )
+                })?;
+            created.lock().await.push(inlet);
+        }
+        Ok(())
+    };
+    let result = with_deadline(cmd.timeout, create_inlets).await;
+    let inlets = created.into_inner();
+    if let Err(e) = result {
+        delete_inlets(ctx, &node, &inlets).await;
+        return Err(e);
+    }
+    for inlet_cmd in &commands {
+        inlet_cmd.set_last_used_project(&opts.state).await;
+    }
+
+    let node_name = node.node_name().color(OckamColor::PrimaryResource.color());
+    let mut plain = fmt_ok!(
+        "{} TCP Inlets were created on node {}\n",
+        inlets.len(),
+        node_name
+    );
+    for inlet in &inlets {
+        plain += &fmt_log!(
+            "{} is listening at {} and sends traffic to {}\n",
+            inlet
+                .alias
+                .clone()
+                .color(OckamColor::PrimaryResource.color()),
+            inlet
+                .bind_addr
+                .to_string()
+                .color(OckamColor::PrimaryResource.color()),
+            inlet
+                .outlet_route
+                .clone()
+                .color(OckamColor::PrimaryResource.color())
+        );
+    }
+    let machine = inlets
+        .iter()
+        .map(|inlet| inlet.bind_addr.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .machine(machine)
+        .json(versioned_json(INLET_JSON_SCHEMA_VERSION, &inlets)?)
+        .write_line()?;
+    Ok(())
+}
+
+/// Delete the inlets created together when one of them could not be created
+async fn delete_inlets(ctx: &Context, node: &BackgroundNode, inlets: &[InletStatus]) {
+    for inlet in inlets {
+        let alias = &inlet.alias;
+        match node.delete_inlet(ctx, alias).await {
+            Ok(Reply::Successful(_)) => trace!("the inlet {alias} was deleted"),
+            Ok(Reply::Failed(_, status)) => {
+                warn!("the inlet {alias} could not be deleted: {status:?}")
+            }
+            Err(e) => warn!("the inlet {alias} could not be deleted: {e}"),
+        }
+    }
+}
+
+/// Inlets to create with the `--config` argument
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InletsConfig {
+    inlets: Vec<InletConfig>,
+}
+
+impl InletsConfig {
+    /// Read a YAML or JSON configuration file
+    fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            miette!(
+                "Cannot read the inlets configuration file {}: {e}",
+                path.display()
+            )
+        })?;
+        Self::parse(&contents)
+    }
+
+    /// Parse a YAML or JSON configuration, JSON being a subset of YAML
+    fn parse(contents: &str) -> Result<Self> {
+        serde_yaml::from_str(contents).map_err(|e| miette!("Invalid inlets configuration: {e}"))
+    }
+}
+
+/// Configuration of an inlet in a configuration file.
+/// The optional fields override the command line arguments for that inlet
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InletConfig {
+    from: String,
+    to: String,
+    alias: String,
+    authorized: Option<String>,
+    require_credential: Option<bool>,
+    proxy_protocol: Option<String>,
+    hold_on_reconnect: Option<String>,
+    idle_timeout: Option<String>,
+    keepalive: Option<String>,
+    egress_bind: Option<String>,
+    allow_from: Option<Vec<String>>,
+    labels: Option<BTreeMap<String, String>>,
+    socks5: Option<bool>,
+    buffer_size: Option<usize>,
+    log_level: Option<String>,
+}
+
+impl InletConfig {
+    /// Return the command creating this inlet, based on the command line arguments
+    fn apply(&self, mut cmd: CreateCommand) -> Result<CreateCommand> {
+        cmd.from = socket_addr_parser(&self.from)?.into();
+        cmd.from_interface = None;
+        cmd.to = self.to.clone();
+        cmd.alias = Some(alias_parser(&self.alias)?);
+        cmd.config = None;
+        if let Some(authorized) = &self.authorized {
+            cmd.authorized = Some(Identifier::from_str(authorized).into_diagnostic()?);
+        }
+        if let Some(require_credential) = self.require_credential {
+            cmd.require_credential = require_credential;
+        }
+        if let Some(proxy_protocol) = &self.proxy_protocol {
+            cmd.proxy_protocol = Some(proxy_protocol_parser(proxy_protocol)?);
+        }
+        if let Some(hold_on_reconnect) = &self.hold_on_reconnect {
+            cmd.hold_on_reconnect = Some(duration_parser(hold_on_reconnect).into_diagnostic()?);
+        }
+        if let Some(idle_timeout) = &self.idle_timeout {
+            cmd.idle_timeout = Some(duration_parser(idle_timeout).into_diagnostic()?);
+        }
+        if let Some(keepalive) = &self.keepalive {
+            cmd.keepalive = Some(duration_parser(keepalive).into_diagnostic()?);
+        }
+        if let Some(egress_bind) = &self.egress_bind {
+            cmd.egress_bind = Some(ip_and_optional_port_parser(egress_bind)?);
+        }
+        if let Some(allow_from) = &self.allow_from {
+            cmd.allow_from = allow_from
+                .iter()
+                .map(|s| ip_cidr_parser(s))
+                .collect::<crate::Result<Vec<_>>>()?;
+        }
+        if let Some(labels) = &self.labels {
+            cmd.labels = vec![];
+            for (key, value) in labels {
+                validate_label(key, value).map_err(|e| miette!("{e}"))?;
+                cmd.labels.push((key.clone(), value.clone()));
+            }
+        }
+        if let Some(socks5) = self.socks5 {
+            cmd.socks5 = socks5;
+        }
+        if let Some(buffer_size) = self.buffer_size {
+            cmd.buffer_size = Some(validate_buffer_size(buffer_size).map_err(|e| miette!("{e}"))?);
+        }
+        if let Some(log_level) = &self.log_level {
+            cmd.log_level = Some(log_level_parser(log_level)?);
+        }
+        Ok(cmd)
+    }
+}
+
+/// Run the command with an optional deadline.
+/// If the deadline is reached, the command fails even if it is still retrying
+async fn with_deadline<T>(
+    deadline: Option<Duration>,
+    command: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout(deadline, command).await.map_err(|_| {
+            miette!(
+                "The TCP inlet could not be created within {:.1}s",
+                deadline.as_secs_f64()
+            )
+        })?,
+        None => command.await,
+    }
+}
+
+/// Return the outlet of a node which would send the traffic of an inlet back to the inlet.
+///
+/// This is the case when the route to the outlet stays on the node, either because it is a
+/// local route or because it starts with the address of the node, and when the outlet connects
+/// to the address the inlet listens at
+fn looping_outlet<'a>(
+    from: &SocketAddr,
+    to: &MultiAddr,
+    node_address: &Option<MultiAddr>,
+    outlets: &'a OutletList,
+) -> Option<&'a OutletStatus> {
+    let local_route = match node_address {
+        Some(node_address) if to.split(node_address.len()).0 == *node_address => {
+            to.split(node_address.len()).1
+        }
+        _ => to.clone(),
+    };
+    let is_local = local_route
+        .iter()
+        .all(|p| p.code() == Service::CODE || p.code() == Secure::CODE);
+    if !is_local {
+        return None;
+    }
+    let last = local_route.last()?;
+    let service = last.cast::<Service>()?;
+    outlets.list.iter().find(|outlet| {
+        outlet.worker_addr.address() == &*service
+            && outlet.socket_addr.port() == from.port()
+            && (outlet.socket_addr.ip() == from.ip() || from.ip().is_unspecified())
+    })
+}
+
+/// Return true if an inlet listens at the port of a given address
+fn uses_port(inlet: &InletStatus, address: &SocketAddr) -> bool {
+    SocketAddr::from_str(&inlet.bind_addr).is_ok_and(|a| a.port() == address.port())
+}
+
+/// Recreate an inlet deleted to be replaced, with the options reported by its status.
+/// The inlet is recreated without waiting for its outlet
+async fn recreate_inlet(ctx: &Context, node: &BackgroundNode, inlet: &InletStatus) -> Result<()> {
+    let outlet_addr = inlet
+        .outlet_addr
+        .as_ref()
+        .ok_or_else(|| miette!("the node does not report the address of the outlet"))?;
+    let outlet_addr = MultiAddr::from_str(outlet_addr).into_diagnostic()?;
+    let options = InletOptions::default()
+        .with_wait_connection(false)
+        .with_idle_timeout(inlet.idle_timeout)
+        .with_labels(inlet.labels.clone().unwrap_or_default())
+        .with_buffer_size(inlet.buffer_size)
+        .with_prewarm(inlet.prewarm);
+    node.create_inlet(
+        ctx,
+        &inlet.bind_addr,
+        &outlet_addr,
+        &Some(inlet.alias.clone()),
+        &None,
+        &options,
+    )
+    .await?
+    .success()
+    .into_diagnostic()?;
+    Ok(())
+}
+
+/// Return the inlet which already uses a given alias, if it must be replaced.
+/// Return an error if the alias is already used and the inlet must not be replaced
+fn inlet_to_replace(inlets: &InletList, alias: &str, replace: bool) -> Result<Option<InletStatus>> {
+    match inlets.list.iter().find(|inlet| inlet.alias == alias) {
+        Some(inlet) if replace => Ok(Some(inlet.clone())),
+        Some(inlet) => Err(miette!(
+            "The alias {} is already in use by the inlet at {}. Use --replace to replace it",
+            alias.color(OckamColor::PrimaryResource.color()),
+            inlet
+                .bind_addr
+                .to_string()
+                .color(OckamColor::PrimaryResource.color())
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Return the JSON representation of the created inlet, with the time it took to create it
+fn inlet_json(inlet: &InletStatus, elapsed: Duration) -> Result<serde_json::Value> {
+    let mut json = serde_json::to_value(inlet).into_diagnostic()?;
+    if let Some(fields) = json.as_object_mut() {
+        fields.insert(
+            "elapsed_ms".to_string(),
+            serde_json::json!(elapsed.as_millis() as u64),
+        );
+    }
+    versioned_json(INLET_JSON_SCHEMA_VERSION, &json)
+}
+
 #[cfg(test)]
 mod tests {
+    use clap::Parser;
     use miette::Result;
+    use ockam_api::address::get_free_address;
 
     use super::*;
 
@@ -271,16 +1055,33 @@ mod tests {
         let default_project_name = Some("p1".to_string());
 
         // Invalid values
-        CreateCommand::parse_arg_to(&state, "/alice/service", &default_project_name)
-            .await
-            .expect_err("Invalid protocol");
-        CreateCommand::parse_arg_to(&state, "alice/forwarder", &default_project_name)
+        let err =
+            CreateCommand::parse_arg_to(&state, "/alice/service", &default_project_name, false)
+                .await
+                .expect_err("Invalid protocol");
+        assert_eq!(
+            err,
+            ToAddressError::InvalidRelayName("/alice/service".to_string())
+        );
+        let err =
+            CreateCommand::parse_arg_to(&state, "alice/forwarder", &default_project_name, false)
+                .await
+                .expect_err("Invalid protocol");
+        assert_eq!(
+            err,
+            ToAddressError::InvalidRelayName("alice/forwarder".to_string())
+        );
+
+        // A default project is needed to expand a relay name
+        let err = CreateCommand::parse_arg_to(&state, "alice", &None, false)
             .await
-            .expect_err("Invalid protocol");
+            .expect_err("No default project");
+        assert_eq!(err, ToAddressError::NoDefaultProject);
 
         // The placeholders are replaced in the default value
         let res =
-            CreateCommand::parse_arg_to(&state, default_to_addr(), &default_project_name).await?;
+            CreateCommand::parse_arg_to(&state, default_to_addr(), &default_project_name, false)
+                .await?;
         assert_eq!(
             res,
             "/project/p1/service/forward_to_default/secure/api/service/outlet"
@@ -288,15 +1089,489 @@ mod tests {
 
         // The user provides a full project route
         let addr = "/project/p1/service/forward_to_n1/secure/api/service/outlet";
-        let res = CreateCommand::parse_arg_to(&state, addr, &default_project_name).await?;
+        let res = CreateCommand::parse_arg_to(&state, addr, &default_project_name, false).await?;
         assert_eq!(res, addr);
 
         // The user provides the name of the relay
-        let res = CreateCommand::parse_arg_to(&state, "alice", &default_project_name).await?;
+        let res =
+            CreateCommand::parse_arg_to(&state, "alice", &default_project_name, false).await?;
         assert_eq!(
             res,
             "/project/p1/service/forward_to_alice/secure/api/service/outlet"
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_parse_arg_to_with_variables() -> Result<()> {
+        let state = CliState::test().await?;
+        let default_project_name = Some("p1".to_string());
+
+        // Environment variables are replaced together with the built-in ones
+        let env = |name: &str| (name == "OUTLET_SERVICE").then(|| "my_outlet".to_string());
+        let addr = "/project/$PROJECT_NAME/service/forward_to_$RELAY_NAME/secure/api/service/$OUTLET_SERVICE";
+        let res = substitute_variables(addr, &default_project_name, false, env)?;
+        assert_eq!(
+            res,
+            "/project/p1/service/forward_to_default/secure/api/service/my_outlet"
+        );
+
+        // Unresolved variables are rejected
+        let addr = "/project/p1/service/forward_to_n1/secure/api/service/$OCKAM_UNSET_VARIABLE";
+        let err = CreateCommand::parse_arg_to(&state, addr, &default_project_name, false)
+            .await
+            .expect_err("Unresolved variable");
+        assert_eq!(
+            err,
+            ToAddressError::UnsetVariable("OCKAM_UNSET_VARIABLE".to_string())
+        );
+
+        // Unless they are explicitly allowed
+        let res = substitute_variables(addr, &default_project_name, true, env)?;
+        assert_eq!(res, addr);
+
+        // The project name can't be resolved without a default project
+        let err = substitute_variables("/project/$PROJECT_NAME", &None, false, env)
+            .expect_err("No default project");
+        assert_eq!(err, ToAddressError::NoDefaultProject);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_parse_arg_to_with_a_project_name() -> Result<()> {
+        let state = CliState::test().await?;
+        let to = "$RELAY_NAME";
+        let cmd = |project_name: Option<&str>| CreateCommand {
+            project_name: project_name.map(|p| p.to_string()),
+            ..test_command(&[])
+        };
+
+        // without any project, the relay name can't be expanded
+        let default_project_name = cmd(None).default_project_name(&state).await;
+        let err = CreateCommand::parse_arg_to(&state, to, &default_project_name, false)
+            .await
+            .expect_err("No project");
+        assert_eq!(err, ToAddressError::NoDefaultProject);
+
+        // the last used project is used when there is no default project
+        state.set_last_used_project_name("last").await?;
+        let default_project_name = cmd(None).default_project_name(&state).await;
+        let res = CreateCommand::parse_arg_to(&state, to, &default_project_name, false).await?;
+        assert_eq!(
+            res,
+            "/project/last/service/forward_to_default/secure/api/service/outlet"
+        );
+
+        // the default project takes precedence over the last used project
+        state
+            .import_project(
+                "project_id",
+                "p1",
+                &None,
+                &MultiAddr::from_str("/project/p1").unwrap(),
+                &None,
+                &None,
+            )
+            .await?;
+        let default_project_name = cmd(None).default_project_name(&state).await;
+        let res = CreateCommand::parse_arg_to(&state, to, &default_project_name, false).await?;
+        assert_eq!(
+            res,
+            "/project/p1/service/forward_to_default/secure/api/service/outlet"
+        );
+
+        // the --project argument takes precedence over both
+        let cmd = cmd(Some("p2"));
+        let default_project_name = cmd.default_project_name(&state).await;
+        let res = CreateCommand::parse_arg_to(&state, to, &default_project_name, false).await?;
+        assert_eq!(
+            res,
+            "/project/p2/service/forward_to_default/secure/api/service/outlet"
+        );
+
+        // the project reached by the inlet is remembered once the inlet is created
+        let cmd = CreateCommand { to: res, ..cmd };
+        assert_eq!(cmd.route_project_name(), Some("p2".to_string()));
+        cmd.set_last_used_project(&state).await;
+        assert_eq!(
+            state.get_project_name_or_default(&None).await?,
+            Some("p1".to_string())
+        );
+        assert_eq!(
+            state.get_last_used_project_name().await?,
+            Some("p2".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_inlet_json_contains_the_elapsed_time() -> Result<()> {
+        let inlet = InletStatus::new(
+            "127.0.0.1:4000",
+            "inlet",
+            "inlet",
+            None,
+            "/service/outlet",
+            ConnectionStatus::Up,
+        );
+        let json = inlet_json(&inlet, Duration::from_millis(2300))?;
+        assert_eq!(json["schema_version"], INLET_JSON_SCHEMA_VERSION);
+        assert_eq!(json["data"]["bind_addr"], "127.0.0.1:4000");
+        assert!(json["data"]["elapsed_ms"].as_u64().unwrap() > 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_times_out_at_the_deadline() -> Result<()> {
+        // an outlet which is never available makes the command retry forever
+        async fn retry_forever() -> Result<()> {
+            loop {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+        let started_at = Instant::now();
+        let err = with_deadline(Some(Duration::from_millis(200)), retry_forever())
+            .await
+            .expect_err("the deadline is reached")
+            .to_string();
+        assert!(err.contains("could not be created within 0.2s"));
+        let elapsed = started_at.elapsed();
+        assert!(elapsed >= Duration::from_millis(200));
+        assert!(elapsed < Duration::from_secs(2));
+
+        // a command finishing before the deadline is not affected
+        let result = with_deadline(Some(Duration::from_secs(5)), async { Ok(1) }).await?;
+        assert_eq!(result, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_allow_from() {
+        // all the sources are allowed by default
+        let cmd = test_command(&[]);
+        assert!(cmd.allow_from.is_empty());
+
+        let cmd = test_command(&["--allow-from", "10.0.0.0/8,192.168.1.10"]);
+        assert_eq!(
+            cmd.allow_from,
+            vec![
+                IpCidr::from_str("10.0.0.0/8").unwrap(),
+                IpCidr::from_str("192.168.1.10/32").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_labels() {
+        let cmd = test_command(&[]);
+        assert!(cmd.labels.is_empty());
+
+        let cmd = test_command(&["--label", "env=prod", "--label", "team=data"]);
+        assert_eq!(
+            cmd.labels,
+            vec![
+                ("env".to_string(), "prod".to_string()),
+                ("team".to_string(), "data".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_buffer_size() {
+        let cmd = test_command(&[]);
+        assert_eq!(cmd.buffer_size, None);
+
+        let cmd = test_command(&["--buffer-size", "262144"]);
+        assert_eq!(cmd.buffer_size, Some(262144));
+
+        // the buffer size must be within the supported range
+        for size in ["0", "1023", "16777217", "large"] {
+            assert!(buffer_size_parser(size).is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn test_parse_log_level() {
+        let cmd = test_command(&[]);
+        assert_eq!(cmd.log_level, None);
+
+        let cmd = test_command(&["--log-level", "debug"]);
+        assert_eq!(cmd.log_level, Some(Level::DEBUG));
+
+        assert!(log_level_parser("verbose").is_err());
+    }
+
+    #[test]
+    fn test_parse_socks5() {
+        let cmd = test_command(&[]);
+        assert!(!cmd.socks5);
+
+        let cmd = test_command(&["--socks5"]);
+        assert!(cmd.socks5);
+    }
+
+    #[test]
+    fn test_parse_prewarm() {
+        // no tunnel is prewarmed by default
+        let cmd = test_command(&[]);
+        assert_eq!(cmd.prewarm, None);
+        assert_eq!(cmd.max_connections, None);
+
+        let cmd = test_command(&["--prewarm", "2", "--max-connections", "10"]);
+        assert_eq!(cmd.prewarm, Some(2));
+        assert_eq!(cmd.max_connections, Some(10));
+
+        // at least one connection must be allowed
+        assert!(try_test_command(&["--max-connections", "0"]).is_err());
+
+        // a SOCKS5 inlet doesn't know the targets of its tunnels before the clients connect
+        assert!(try_test_command(&["--socks5", "--prewarm", "2"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_config() -> Result<()> {
+        let state = CliState::test().await?;
+        let cmd = test_command(&["--config", "tcp-inlets.yaml", "--idle-timeout", "1m"]);
+        let (port1, port2) = (free_port(), free_port());
+        let config = InletsConfig::parse(
+            &include_str!("../../../tests/fixtures/tcp-inlets.yaml")
+                .replace("16123", &port1.to_string())
+                .replace("16124", &port2.to_string()),
+        )?;
+        let commands = cmd.parse_config(&state, &config).await?;
+        assert_eq!(commands.len(), 2);
+
+        // the inlets take the command line arguments
+        let first = &commands[0];
+        assert_eq!(first.alias, Some("inlet-1".to_string()));
+        assert_eq!(
+            first.from,
+            SocketAddrRange::from(SocketAddr::new([127, 0, 0, 1].into(), port1))
+        );
+        assert_eq!(
+            first.to,
+            "/project/p1/service/forward_to_n1/secure/api/service/outlet"
+        );
+        assert_eq!(first.idle_timeout, Some(Duration::from_secs(60)));
+        assert_eq!(first.proxy_protocol, None);
+        assert_eq!(first.buffer_size, None);
+        assert_eq!(first.log_level, None);
+        assert!(first.config.is_none());
+
+        // unless they are overridden by the configuration file
+        let second = &commands[1];
+        assert_eq!(second.alias, Some("inlet-2".to_string()));
+        assert_eq!(
+            second.from,
+            SocketAddrRange::from(SocketAddr::new([127, 0, 0, 1].into(), port2))
+        );
+        assert_eq!(
+            second.to,
+            "/project/p1/service/forward_to_default/secure/api/service/outlet"
+        );
+        assert_eq!(second.idle_timeout, Some(Duration::from_secs(300)));
+        assert_eq!(second.proxy_protocol, Some(ProxyProtocolVersion::V2));
+        assert_eq!(second.buffer_size, Some(131072));
+        assert_eq!(second.log_level, Some(Level::DEBUG));
+
+        // an alias can only be used once
+        let config = InletsConfig::parse(&format!(
+            r#"{{"inlets": [
+                {{"from": "{}", "to": "/service/outlet", "alias": "inlet"}},
+                {{"from": "{}", "to": "/service/outlet", "alias": "inlet"}}
+            ]}}"#,
+            free_port(),
+            free_port()
+        ))?;
+        let err = cmd
+            .parse_config(&state, &config)
+            .await
+            .expect_err("duplicate alias")
+            .to_string();
+        assert!(err.contains("is used by several inlets"));
+
+        // unknown fields are rejected
+        assert!(InletsConfig::parse(&format!(
+            "inlets:\n  - {{from: {}, to: /service/outlet, alias: inlet, port: 1}}",
+            free_port()
+        ))
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_port_range() -> Result<()> {
+        let start = free_port_range(3);
+        let from = format!("127.0.0.1:{start}-{}", start + 2);
+        let cmd = test_command(&["--from", &from, "--alias", "ftp"]);
+        assert!(cmd.from.is_range());
+
+        // one inlet is created for each port, with the same route to the outlet
+        let commands = cmd.parse_port_range()?;
+        assert_eq!(commands.len(), 3);
+        for (cmd, port) in commands.iter().zip(start..) {
+            assert_eq!(
+                cmd.from,
+                SocketAddrRange::from(SocketAddr::new([127, 0, 0, 1].into(), port))
+            );
+            assert_eq!(cmd.alias, Some(format!("ftp-{port}")));
+            assert_eq!(cmd.to, default_to_addr());
+        }
+
+        // no inlet is created when one of the ports is already used
+        let used = start + 1;
+        let _listener = std::net::TcpListener::bind(("127.0.0.1", used)).unwrap();
+        let err = cmd
+            .parse_port_range()
+            .expect_err("one of the ports is used")
+            .to_string();
+        assert!(err.contains(&used.to_string()), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_require_secure_channel() -> Result<()> {
+        let cmd = |to: &str, require_secure_channel: bool| CreateCommand {
+            to: to.to_string(),
+            require_secure_channel,
+            ..test_command(&[])
+        };
+
+        // a plaintext route is only rejected when a secure channel is required
+        let plaintext = "/ip4/127.0.0.1/tcp/6000/service/outlet";
+        cmd(plaintext, false).check_secure_route()?;
+        let err = cmd(plaintext, true)
+            .check_secure_route()
+            .expect_err("no secure channel")
+            .to_string();
+        assert!(err.contains("does not use a secure channel"), "{err}");
+
+        // a route going through a secure channel is accepted
+        cmd("/ip4/127.0.0.1/tcp/6000/secure/api/service/outlet", true).check_secure_route()?;
+        cmd(
+            "/project/p1/service/forward_to_default/secure/api/service/outlet",
+            true,
+        )
+        .check_secure_route()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_looping_outlet() {
+        let node_address = Some(MultiAddr::from_str("/ip4/127.0.0.1/tcp/6000").unwrap());
+        let from = SocketAddr::from_str("127.0.0.1:5000").unwrap();
+        let outlets = OutletList::new(vec![OutletStatus::new(
+            SocketAddr::from_str("127.0.0.1:5000").unwrap(),
+            "outlet".into(),
+            "my-outlet",
+            None,
+        )]);
+
+        // an inlet sending its traffic to an outlet of the same node connecting back to the inlet
+        for to in [
+            "/ip4/127.0.0.1/tcp/6000/service/outlet",
+            "/ip4/127.0.0.1/tcp/6000/secure/api/service/outlet",
+            "/service/outlet",
+        ] {
+            let to = MultiAddr::from_str(to).unwrap();
+            let outlet = looping_outlet(&from, &to, &node_address, &outlets);
+            assert_eq!(outlet.map(|o| o.alias.as_str()), Some("my-outlet"), "{to}");
+        }
+
+        // the loop is also detected when the inlet listens on all the interfaces
+        let to = MultiAddr::from_str("/service/outlet").unwrap();
+        let any = SocketAddr::from_str("0.0.0.0:5000").unwrap();
+        assert!(looping_outlet(&any, &to, &node_address, &outlets).is_some());
+
+        // an outlet on another node
+        let to = MultiAddr::from_str("/ip4/127.0.0.1/tcp/7000/service/outlet").unwrap();
+        assert!(looping_outlet(&from, &to, &node_address, &outlets).is_none());
+
+        // an outlet of the same node connecting to another address
+        let to = MultiAddr::from_str("/service/outlet").unwrap();
+        let other = SocketAddr::from_str("127.0.0.1:5001").unwrap();
+        assert!(looping_outlet(&other, &to, &node_address, &outlets).is_none());
+
+        // another service of the same node
+        let to = MultiAddr::from_str("/service/echo").unwrap();
+        assert!(looping_outlet(&from, &to, &node_address, &outlets).is_none());
+    }
+
+    #[test]
+    fn test_inlet_to_replace() -> Result<()> {
+        let existing = InletStatus::new(
+            "127.0.0.1:4000",
+            "inlet",
+            "my-inlet",
+            None,
+            "/service/outlet",
+            ConnectionStatus::Up,
+        );
+        let inlets = InletList::new(vec![existing.clone()]);
+
+        // an alias which is not used can be taken
+        assert!(inlet_to_replace(&inlets, "other-inlet", false)?.is_none());
+
+        // an alias which is already used is rejected
+        let err = inlet_to_replace(&inlets, "my-inlet", false)
+            .expect_err("duplicate alias")
+            .to_string();
+        assert!(err.contains("is already in use by the inlet at"));
+        assert!(err.contains("127.0.0.1:4000"));
+
+        // unless the existing inlet must be replaced
+        let replaced = inlet_to_replace(&inlets, "my-inlet", true)?;
+        assert_eq!(
+            replaced.map(|inlet| inlet.bind_addr),
+            Some(existing.bind_addr.clone())
+        );
+
+        // the port of the replaced inlet can be reused by the new inlet
+        assert!(uses_port(
+            &existing,
+            &SocketAddr::from_str("127.0.0.1:4000").unwrap()
+        ));
+        assert!(uses_port(
+            &existing,
+            &SocketAddr::from_str("0.0.0.0:4000").unwrap()
+        ));
+        assert!(!uses_port(
+            &existing,
+            &SocketAddr::from_str("127.0.0.1:4001").unwrap()
+        ));
+        Ok(())
+    }
+
+    /// Return a command parsed from some command line arguments
+    fn test_command(args: &[&str]) -> CreateCommand {
+        try_test_command(args).unwrap()
+    }
+
+    /// Return a port which is currently free on the local host
+    fn free_port() -> u16 {
+        get_free_address().unwrap().port()
+    }
+
+    /// Return the first port of a range of `len` ports which are currently free on the local host
+    fn free_port_range(len: u16) -> u16 {
+        loop {
+            let start = free_port();
+            let Some(end) = start.checked_add(len - 1) else {
+                continue;
+            };
+            if (start..=end).all(|port| std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()) {
+                return start;
+            }
+        }
+    }
+
+    fn try_test_command(args: &[&str]) -> std::result::Result<CreateCommand, clap::Error> {
+        #[derive(clap::Parser)]
+        struct TestCommand {
+            #[command(flatten)]
+            create: CreateCommand,
+        }
+        let args = ["create"].iter().chain(args);
+        TestCommand::try_parse_from(args).map(|cmd| cmd.create)
+    }
 }
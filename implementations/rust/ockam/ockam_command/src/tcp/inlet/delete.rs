@@ -1,7 +1,9 @@
+use std::net::SocketAddr;
+
 use clap::Args;
 use colorful::Colorful;
 use console::Term;
-use miette::miette;
+use miette::{miette, IntoDiagnostic};
 
 use ockam::Context;
 use ockam_api::nodes::models::portal::InletList;
@@ -11,9 +13,11 @@ use ockam_core::api::Request;
 
 use crate::fmt_ok;
 use crate::node::NodeOpts;
+use crate::output::{versioned_json, INLET_JSON_SCHEMA_VERSION};
 use crate::tcp::util::alias_parser;
 use crate::terminal::tui::DeleteCommandTui;
 use crate::util::node_rpc;
+use crate::util::parsers::socket_addr_or_port_parser;
 use crate::{docs, fmt_warn, CommandGlobalOpts, Terminal, TerminalStream};
 
 const AFTER_LONG_HELP: &str = include_str!("./static/delete/after_long_help.txt");
@@ -26,6 +30,17 @@ pub struct DeleteCommand {
     #[arg(display_order = 900, id = "ALIAS", value_parser = alias_parser)]
     alias: Option<String>,
 
+    /// Delete the inlet bound to this address. When only a port is given,
+    /// the inlet is selected by port if no other inlet uses that port
+    #[arg(
+        display_order = 900,
+        long,
+        value_name = "SOCKET_ADDRESS",
+        conflicts_with = "ALIAS",
+        value_parser = socket_addr_or_port_parser
+    )]
+    from: Option<SocketAddr>,
+
     /// Node on which to stop the tcp inlet. If none are provided, the default node will be used
     #[command(flatten)]
     node_opts: NodeOpts,
@@ -45,7 +60,42 @@ pub async fn run_impl(
     ctx: Context,
     (opts, cmd): (CommandGlobalOpts, DeleteCommand),
 ) -> miette::Result<()> {
-    DeleteTui::run(ctx, opts, cmd).await
+    match cmd.from {
+        Some(addr) => delete_by_addr(&ctx, &opts, &cmd, addr).await,
+        None => DeleteTui::run(ctx, opts, cmd).await,
+    }
+}
+
+/// Delete the inlet bound to an address
+async fn delete_by_addr(
+    ctx: &Context,
+    opts: &CommandGlobalOpts,
+    cmd: &DeleteCommand,
+    addr: SocketAddr,
+) -> miette::Result<()> {
+    let node = BackgroundNode::create(ctx, &opts.state, &cmd.node_opts.at_node).await?;
+    if !opts
+        .terminal
+        .confirmed_with_flag_or_prompt(cmd.yes, "Are you sure you want to proceed?")?
+    {
+        return Ok(());
+    }
+    let inlet = node
+        .delete_inlet_by_addr(ctx, addr)
+        .await?
+        .success()
+        .into_diagnostic()?;
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!(
+            "TCP inlet with alias {} bound to {} on Node {} has been deleted",
+            inlet.alias.light_magenta(),
+            inlet.bind_addr.light_magenta(),
+            node.node_name().light_magenta()
+        ))
+        .json(versioned_json(INLET_JSON_SCHEMA_VERSION, &inlet)?)
+        .write_line()?;
+    Ok(())
 }
 
 struct DeleteTui {
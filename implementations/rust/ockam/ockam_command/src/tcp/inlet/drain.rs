@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::Context;
+use ockam_api::nodes::service::portals::Inlets;
+use ockam_api::nodes::BackgroundNode;
+
+use crate::node::NodeOpts;
+use crate::output::{versioned_json, INLET_JSON_SCHEMA_VERSION};
+use crate::tcp::util::alias_parser;
+use crate::terminal::OckamColor;
+use crate::util::duration::duration_parser;
+use crate::util::node_rpc;
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/drain/after_long_help.txt");
+
+/// Drain a TCP Inlet: stop accepting new connections and let the existing ones finish
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct DrainCommand {
+    /// Name of the inlet
+    #[arg(display_order = 900, required = true, id = "ALIAS", value_parser = alias_parser)]
+    alias: String,
+
+    /// Node on which the inlet was started
+    #[command(flatten)]
+    node_opts: NodeOpts,
+
+    /// Maximum time given to the existing connections to finish.
+    /// The connections still open after this duration are closed
+    #[arg(long, display_order = 900, default_value = "30s", value_parser = duration_parser)]
+    drain_timeout: Duration,
+}
+
+impl DrainCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(run_impl, (opts, self))
+    }
+}
+
+pub async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, DrainCommand),
+) -> miette::Result<()> {
+    let node = BackgroundNode::create(&ctx, &opts.state, &cmd.node_opts.at_node).await?;
+    let inlet_status = node
+        .drain_inlet(&ctx, &cmd.alias, cmd.drain_timeout)
+        .await?
+        .success()
+        .into_diagnostic()?;
+
+    let json = versioned_json(INLET_JSON_SCHEMA_VERSION, &inlet_status)?;
+    let plain = fmt_ok!(
+        "TCP Inlet {} on Node {} is draining. Its connections still open in {:?} will be closed",
+        inlet_status
+            .alias
+            .clone()
+            .color(OckamColor::PrimaryResource.color()),
+        node.node_name().color(OckamColor::PrimaryResource.color()),
+        cmd.drain_timeout
+    );
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .machine(inlet_status.alias.clone())
+        .json(json)
+        .write_line()?;
+    Ok(())
+}
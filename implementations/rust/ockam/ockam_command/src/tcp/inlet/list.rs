@@ -1,17 +1,22 @@
+use std::ops::RangeInclusive;
+
 use clap::Args;
 use colorful::Colorful;
 use miette::IntoDiagnostic;
 use tokio::sync::Mutex;
 use tokio::try_join;
 
-use ockam_api::nodes::models::portal::InletList;
+use ockam_api::nodes::models::portal::InletFilter;
+use ockam_api::nodes::service::portals::Inlets;
 use ockam_api::nodes::BackgroundNode;
-use ockam_core::api::Request;
+use ockam_api::ConnectionStatus;
 use ockam_node::Context;
 
 use crate::node::NodeOpts;
+use crate::output::{versioned_json, INLET_JSON_SCHEMA_VERSION};
 use crate::terminal::OckamColor;
 use crate::util::node_rpc;
+use crate::util::parsers::{connection_status_parser, label_parser, port_range_parser};
 use crate::{docs, CommandGlobalOpts};
 
 const PREVIEW_TAG: &str = include_str!("../../static/preview_tag.txt");
@@ -25,12 +30,44 @@ after_long_help = docs::after_help(AFTER_LONG_HELP))]
 pub struct ListCommand {
     #[command(flatten)]
     node: NodeOpts,
+
+    /// Only list the inlets having this label, given as `key=value`.
+    /// This argument can be repeated to only list the inlets having all the labels
+    #[arg(long = "label", value_name = "KEY=VALUE", value_parser = label_parser)]
+    labels: Vec<(String, String)>,
+
+    /// Only list the inlets having an alias matching this pattern, where `*` matches any sequence of characters
+    #[arg(long = "alias", value_name = "PATTERN")]
+    alias_pattern: Option<String>,
+
+    /// Only list the inlets having this connection status: up, down, degraded or pending
+    #[arg(long, value_name = "STATUS", value_parser = connection_status_parser)]
+    status: Option<ConnectionStatus>,
+
+    /// Only list the inlets bound to a port in this range, given as `start-end` or as a single port
+    #[arg(long, value_name = "START-END", value_parser = port_range_parser)]
+    ports: Option<RangeInclusive<u16>>,
 }
 
 impl ListCommand {
     pub fn run(self, opts: CommandGlobalOpts) {
         node_rpc(run_impl, (opts, self))
     }
+
+    /// Return the filter selecting the inlets to list
+    fn filter(&self) -> InletFilter {
+        let mut filter = InletFilter::default().with_labels(self.labels.iter().cloned().collect());
+        if let Some(alias_pattern) = &self.alias_pattern {
+            filter = filter.with_alias_pattern(alias_pattern);
+        }
+        if let Some(status) = self.status {
+            filter = filter.with_status(status);
+        }
+        if let Some(ports) = &self.ports {
+            filter = filter.with_ports(ports.clone());
+        }
+        filter
+    }
 }
 
 async fn run_impl(
@@ -40,8 +77,9 @@ async fn run_impl(
     let node = BackgroundNode::create(&ctx, &opts.state, &cmd.node.at_node).await?;
     let is_finished: Mutex<bool> = Mutex::new(false);
 
+    let filter = cmd.filter();
     let get_inlets = async {
-        let inlets: InletList = node.ask(&ctx, Request::get("/node/inlet")).await?;
+        let inlets = node.list_inlets(&ctx, &filter).await?;
         *is_finished.lock().await = true;
         Ok(inlets)
     };
@@ -58,11 +96,12 @@ async fn run_impl(
     let (inlets, _) = try_join!(get_inlets, progress_output)?;
 
     let plain = opts.terminal.build_list(
-        &inlets.list,
+        &inlets,
         "Inlets",
         &format!("No TCP Inlets found on {}", node.node_name()),
     )?;
-    let json = serde_json::to_string_pretty(&inlets.list).into_diagnostic()?;
+    let json = serde_json::to_string_pretty(&versioned_json(INLET_JSON_SCHEMA_VERSION, &inlets)?)
+        .into_diagnostic()?;
     opts.terminal
         .stdout()
         .plain(plain)
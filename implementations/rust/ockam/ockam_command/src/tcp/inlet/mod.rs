@@ -1,14 +1,20 @@
 pub(crate) mod create;
 mod delete;
+mod drain;
 mod list;
+mod monitor;
 mod show;
+mod wait_until_connected;
 
 use crate::{docs, CommandGlobalOpts};
 use clap::{Args, Subcommand};
 use create::CreateCommand;
 use delete::DeleteCommand;
+use drain::DrainCommand;
 pub(crate) use list::ListCommand;
+use monitor::MonitorCommand;
 pub(crate) use show::ShowCommand;
+use wait_until_connected::WaitUntilConnectedCommand;
 
 const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
 const AFTER_LONG_HELP: &str = include_str!("./static/after_long_help.txt");
@@ -30,8 +36,11 @@ pub struct TcpInletCommand {
 pub enum TcpInletSubCommand {
     Create(CreateCommand),
     Delete(DeleteCommand),
+    Drain(DrainCommand),
     List(ListCommand),
+    Monitor(MonitorCommand),
     Show(ShowCommand),
+    WaitUntilConnected(WaitUntilConnectedCommand),
 }
 
 impl TcpInletCommand {
@@ -39,8 +48,11 @@ impl TcpInletCommand {
         match self.subcommand {
             TcpInletSubCommand::Create(c) => c.run(options),
             TcpInletSubCommand::Delete(c) => c.run(options),
+            TcpInletSubCommand::Drain(c) => c.run(options),
             TcpInletSubCommand::List(c) => c.run(options),
+            TcpInletSubCommand::Monitor(c) => c.run(options),
             TcpInletSubCommand::Show(c) => c.run(options),
+            TcpInletSubCommand::WaitUntilConnected(c) => c.run(options),
         }
     }
 }
@@ -0,0 +1,75 @@
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::Context;
+use ockam_api::nodes::models::portal::InletStatus;
+use ockam_api::nodes::service::portals::Inlets;
+use ockam_api::nodes::BackgroundNode;
+
+use crate::node::NodeOpts;
+use crate::output::{versioned_json, INLET_JSON_SCHEMA_VERSION};
+use crate::tcp::util::alias_parser;
+use crate::terminal::OckamColor;
+use crate::util::node_rpc;
+use crate::{docs, fmt_log, CommandGlobalOpts};
+
+const PREVIEW_TAG: &str = include_str!("../../static/preview_tag.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/monitor/after_long_help.txt");
+
+/// Print the status changes of a TCP Inlet until interrupted
+#[derive(Clone, Debug, Args)]
+#[command(
+before_help = docs::before_help(PREVIEW_TAG),
+after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct MonitorCommand {
+    /// Name of the inlet
+    #[arg(display_order = 900, required = true, id = "ALIAS", value_parser = alias_parser)]
+    alias: String,
+
+    /// Node on which the inlet was started
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl MonitorCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(run_impl, (opts, self))
+    }
+}
+
+pub async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, MonitorCommand),
+) -> miette::Result<()> {
+    let node = BackgroundNode::create(&ctx, &opts.state, &cmd.node_opts.at_node).await?;
+    let mut subscription = node.watch_inlet(&ctx, &cmd.alias).await?;
+    let mut last_status = None;
+    loop {
+        // the subscription stops with an error once the inlet is deleted
+        let inlet_status: InletStatus = subscription.next().await?.success().into_diagnostic()?;
+        // the node sends the status again when it doesn't change
+        if last_status == Some(inlet_status.status) {
+            continue;
+        }
+        last_status = Some(inlet_status.status);
+        let json = versioned_json(INLET_JSON_SCHEMA_VERSION, &inlet_status)?;
+        let plain = fmt_log!(
+            "TCP Inlet {} is {}",
+            inlet_status
+                .alias
+                .clone()
+                .color(OckamColor::PrimaryResource.color()),
+            inlet_status
+                .status
+                .to_string()
+                .color(OckamColor::PrimaryResource.color())
+        );
+        opts.terminal
+            .stdout()
+            .plain(plain)
+            .machine(inlet_status.status.to_string())
+            .json(json)
+            .write_line()?;
+    }
+}
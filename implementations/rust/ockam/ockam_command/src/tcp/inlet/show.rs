@@ -9,6 +9,7 @@ use ockam_api::nodes::service::portals::Inlets;
 use ockam_api::nodes::BackgroundNode;
 
 use crate::node::NodeOpts;
+use crate::output::{versioned_json, INLET_JSON_SCHEMA_VERSION};
 use crate::tcp::util::alias_parser;
 use crate::util::node_rpc;
 use crate::{docs, fmt_ok, CommandGlobalOpts};
@@ -48,19 +49,40 @@ pub async fn run_impl(
         .success()
         .into_diagnostic()?;
 
-    let json = serde_json::to_string(&inlet_status).into_diagnostic()?;
+    let json = versioned_json(INLET_JSON_SCHEMA_VERSION, &inlet_status)?;
     let InletStatus {
         alias,
         bind_addr,
         outlet_route,
+        idle_timeout,
+        reconnect_count,
+        buffer_size,
+        prewarm,
+        prewarmed,
         ..
     } = inlet_status;
-    let plain = formatdoc! {r#"
+    let mut plain = formatdoc! {r#"
         Inlet:
           Alias: {alias}
           TCP Address: {bind_addr}
           To Outlet Address: {outlet_route}
     "#};
+    // the nodes created with an older version don't count the reconnections
+    if let Some(reconnect_count) = reconnect_count {
+        plain.push_str(&format!("  Reconnections: {reconnect_count}\n"));
+    }
+    if let Some(idle_timeout) = idle_timeout {
+        plain.push_str(&format!("  Idle Timeout: {idle_timeout:?}\n"));
+    }
+    if let Some(buffer_size) = buffer_size {
+        plain.push_str(&format!("  Buffer Size: {buffer_size} bytes\n"));
+    }
+    if let Some(prewarm) = prewarm {
+        let prewarmed = prewarmed.unwrap_or(0);
+        plain.push_str(&format!(
+            "  Prewarmed Tunnels: {prewarmed} of {prewarm} ready\n"
+        ));
+    }
     let machine = bind_addr;
     opts.terminal
         .stdout()
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+
+use ockam::Context;
+use ockam_api::nodes::service::portals::Inlets;
+use ockam_api::nodes::BackgroundNode;
+
+use crate::node::NodeOpts;
+use crate::output::{versioned_json, INLET_JSON_SCHEMA_VERSION};
+use crate::tcp::util::alias_parser;
+use crate::terminal::OckamColor;
+use crate::util::duration::duration_parser;
+use crate::util::node_rpc;
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const PREVIEW_TAG: &str = include_str!("../../static/preview_tag.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/wait_until_connected/after_long_help.txt");
+
+/// Additional time given to the node to report that the inlet is not connected
+const NODE_REPLY_DELAY: Duration = Duration::from_secs(5);
+
+/// Wait until a TCP Inlet is connected to its outlet
+#[derive(Clone, Debug, Args)]
+#[command(
+before_help = docs::before_help(PREVIEW_TAG),
+after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct WaitUntilConnectedCommand {
+    /// Name of the inlet
+    #[arg(display_order = 900, required = true, id = "ALIAS", value_parser = alias_parser)]
+    alias: String,
+
+    /// Node on which the inlet was started
+    #[command(flatten)]
+    node_opts: NodeOpts,
+
+    /// Maximum time to wait for the inlet to be connected.
+    /// The command fails if the inlet is not connected after this duration
+    #[arg(long, display_order = 900, default_value = "60s", value_parser = duration_parser)]
+    timeout: Duration,
+}
+
+impl WaitUntilConnectedCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(run_impl, (opts, self))
+    }
+}
+
+pub async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, WaitUntilConnectedCommand),
+) -> miette::Result<()> {
+    let node = BackgroundNode::create(&ctx, &opts.state, &cmd.node_opts.at_node).await?;
+    let reply = tokio::time::timeout(
+        cmd.timeout + NODE_REPLY_DELAY,
+        node.wait_until_inlet_connected(&ctx, &cmd.alias, cmd.timeout),
+    )
+    .await
+    .map_err(|_| {
+        miette!(
+            "The node {} did not report the status of the TCP Inlet {} in time",
+            node.node_name(),
+            cmd.alias
+        )
+    })??;
+    let inlet_status = reply.success().into_diagnostic()?;
+
+    let json = versioned_json(INLET_JSON_SCHEMA_VERSION, &inlet_status)?;
+    let plain = fmt_ok!(
+        "TCP Inlet {} is {}",
+        inlet_status
+            .alias
+            .clone()
+            .color(OckamColor::PrimaryResource.color()),
+        inlet_status
+            .status
+            .to_string()
+            .color(OckamColor::PrimaryResource.color())
+    );
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .machine(inlet_status.status.to_string())
+        .json(json)
+        .write_line()?;
+    Ok(())
+}
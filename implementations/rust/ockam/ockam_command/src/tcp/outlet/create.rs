@@ -68,7 +68,7 @@ pub async fn run_impl(
 
     let node_name = opts.state.get_node_or_default(&cmd.at).await?.name();
     let project = opts.state.get_node_project(&node_name).await.ok();
-    let resource = Resource::new("tcp-outlet");
+    let resource = Resource::TCP_OUTLET;
     if let Some(p) = project {
         if !has_policy(&node_name, &ctx, &opts, &resource).await? {
             add_default_project_policy(&node_name, &ctx, &opts, p.id, &resource).await?;
@@ -537,3 +537,98 @@ impl<W: TerminalWriter> Terminal<W> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_quiet_terminal_only_writes_the_final_output() -> Result<()> {
+        let terminal: Terminal<TerminalStream<Buffer>> =
+            Terminal::new(true, true, false, OutputFormat::Plain);
+        let stdout = terminal.stdout.writer.clone();
+        let stderr = terminal.stderr.writer.clone();
+
+        // no progress is displayed
+        terminal.write_line(fmt_log!("Creating TCP Inlet..."))?;
+        let progress_bar = terminal.progress_spinner();
+        assert!(progress_bar.is_none());
+        let is_finished = Mutex::new(false);
+        terminal
+            .progress_output_with_progress_bar(
+                &vec!["Establishing connection...".to_string()],
+                &is_finished,
+                progress_bar.as_ref(),
+            )
+            .await?;
+        assert_eq!(stderr.contents(), "");
+
+        // but the final output is still written
+        terminal
+            .stdout()
+            .plain("the inlet is created")
+            .machine("127.0.0.1:4000")
+            .write_line()?;
+        assert_eq!(stdout.contents(), "the inlet is created\n");
+        assert_eq!(stderr.contents(), "");
+        Ok(())
+    }
+
+    /// In-memory writer acting as a tty
+    #[derive(Clone, Debug, Default)]
+    struct Buffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Buffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl TerminalWriter for TerminalStream<Buffer> {
+        fn stdout(no_color: bool) -> Self {
+            Self {
+                writer: Buffer::default(),
+                no_color,
+            }
+        }
+
+        fn stderr(no_color: bool) -> Self {
+            Self {
+                writer: Buffer::default(),
+                no_color,
+            }
+        }
+
+        fn is_tty(&self) -> bool {
+            true
+        }
+
+        fn write(&mut self, s: impl AsRef<str>) -> Result<()> {
+            let s = self.prepare_msg(s)?;
+            self.writer.write_all(s.as_bytes())?;
+            Ok(())
+        }
+
+        fn rewrite(&mut self, s: impl AsRef<str>) -> Result<()> {
+            self.write(s)
+        }
+
+        fn write_line(&self, s: impl AsRef<str>) -> Result<()> {
+            let s = self.prepare_msg(s)?;
+            self.writer.clone().write_all(format!("{s}\n").as_bytes())?;
+            Ok(())
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
-    net::{SocketAddr, TcpListener},
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener},
     path::Path,
 };
 
@@ -227,15 +228,42 @@ pub fn comma_separated<T: AsRef<str>>(data: &[T]) -> String {
         .join(", ")
 }
 
+/// Check that a port is free for the address family of `address`.
+///
+/// When the address is unspecified, `0.0.0.0` or `::`, the port is checked on both IPv4 and IPv6
+/// since, on a dual-stack host, the port could already be taken on the other family.
+/// That other family is skipped if it is not supported by the host
 pub fn port_is_free_guard(address: &SocketAddr) -> Result<()> {
     let port = address.port();
     let ip = address.ip();
+    let other_family = match ip {
+        IpAddr::V4(ip) if ip.is_unspecified() => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        IpAddr::V6(ip) if ip.is_unspecified() => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        _ => None,
+    };
+
     if TcpListener::bind((ip, port)).is_err() {
-        return Err(miette!("Another process is already listening on port {port}!").into());
+        return Err(port_is_taken(ip, port));
+    }
+    if let Some(ip) = other_family {
+        if let Err(e) = TcpListener::bind((ip, port)) {
+            if e.kind() == ErrorKind::AddrInUse {
+                return Err(port_is_taken(ip, port));
+            }
+        }
     }
     Ok(())
 }
 
+fn port_is_taken(ip: IpAddr, port: u16) -> Error {
+    let family = if ip.is_ipv4() { "IPv4" } else { "IPv6" };
+    miette!(
+        "Another process is already listening on port {port} on {family} ({})!",
+        SocketAddr::new(ip, port)
+    )
+    .into()
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -308,6 +336,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_port_is_free_guard() {
+        // the port is only taken on IPv4
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let error = port_is_free_guard(&SocketAddr::from(([127, 0, 0, 1], port)))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("on IPv4"), "{error}");
+
+        // binding all the interfaces is not possible since the port is taken on IPv4
+        let error = port_is_free_guard(&SocketAddr::from(([0, 0, 0, 0], port)))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("on IPv4"), "{error}");
+
+        // the IPv6 checks are skipped if the host doesn't support IPv6
+        if TcpListener::bind("[::1]:0").is_err() {
+            return;
+        }
+
+        // the port is free on the IPv6 loopback address
+        assert!(port_is_free_guard(&SocketAddr::from((Ipv6Addr::LOCALHOST, port))).is_ok());
+
+        // but binding all the IPv6 interfaces also checks IPv4
+        let error = port_is_free_guard(&SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("already listening on port"), "{error}");
+    }
+
     #[test]
     fn test_comma_separated() {
         let data = vec!["a", "b", "c"];
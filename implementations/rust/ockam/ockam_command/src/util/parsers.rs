@@ -1,12 +1,17 @@
-use std::net::SocketAddr;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 use miette::miette;
 
 use ockam::identity::Identifier;
 use ockam_api::config::lookup::InternetAddress;
+use ockam_api::nodes::models::portal::{parse_label, parse_log_level, validate_buffer_size};
+use ockam_api::ConnectionStatus;
 use ockam_multiaddr::MultiAddr;
-use ockam_transport_tcp::resolve_peer;
+use ockam_transport_tcp::{resolve_peer, IpCidr, ProxyProtocolVersion};
+use tracing::Level;
 
 use crate::util::api;
 use crate::Result;
@@ -27,6 +32,68 @@ pub(crate) fn socket_addr_parser(input: &str) -> Result<SocketAddr> {
         .map_err(|e| miette!("cannot parse the address {address} as a socket address: {e}"))?)
 }
 
+/// Socket address, or range of socket addresses sharing the same IP address
+/// and having consecutive ports, like `127.0.0.1:5000-5010`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SocketAddrRange {
+    start: SocketAddr,
+    end_port: u16,
+}
+
+impl SocketAddrRange {
+    /// Return the first address of the range
+    pub fn start(&self) -> SocketAddr {
+        self.start
+    }
+
+    /// Return true if the range contains more than one address
+    pub fn is_range(&self) -> bool {
+        self.end_port > self.start.port()
+    }
+
+    /// Return all the addresses of the range, sorted by port
+    pub fn addresses(&self) -> Vec<SocketAddr> {
+        (self.start.port()..=self.end_port)
+            .map(|port| SocketAddr::new(self.start.ip(), port))
+            .collect()
+    }
+}
+
+impl From<SocketAddr> for SocketAddrRange {
+    fn from(start: SocketAddr) -> Self {
+        Self {
+            start,
+            end_port: start.port(),
+        }
+    }
+}
+
+impl Display for SocketAddrRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_range() {
+            write!(f, "{}-{}", self.start, self.end_port)
+        } else {
+            write!(f, "{}", self.start)
+        }
+    }
+}
+
+/// Helper fn for parsing a socket address, or a range of socket addresses, from user input.
+/// The port can be given as a range, like `127.0.0.1:5000-5010` or `5000-5010`.
+/// Like with [`socket_addr_parser`], the IP address is 127.0.0.1 when only the ports are given
+pub(crate) fn socket_addr_range_parser(input: &str) -> Result<SocketAddrRange> {
+    let (host, ports) = input.rsplit_once(':').unwrap_or(("127.0.0.1", input));
+    if !ports.contains('-') {
+        return Ok(socket_addr_parser(input)?.into());
+    }
+    let ports = port_range_parser(ports)?;
+    let start = socket_addr_parser(&format!("{host}:{}", ports.start()))?;
+    Ok(SocketAddrRange {
+        start,
+        end_port: *ports.end(),
+    })
+}
+
 /// Helper fn for parsing an identifier from user input by using
 /// [`ockam_identity::Identifier::from_str()`]
 pub(crate) fn identity_identifier_parser(input: &str) -> Result<Identifier> {
@@ -45,6 +112,99 @@ pub(crate) fn internet_address_parser(input: &str) -> Result<InternetAddress> {
     InternetAddress::new(input).ok_or_else(|| miette!("Invalid address: {input}").into())
 }
 
+/// Helper fn for parsing a network interface name and a port from user input, like `eth0:5000`
+pub(crate) fn interface_and_port_parser(input: &str) -> Result<(String, u16)> {
+    let (interface, port) = input.rsplit_once(':').ok_or_else(|| {
+        miette!("Invalid interface address: {input}. Expected <INTERFACE>:<PORT>")
+    })?;
+    let port = port
+        .parse::<u16>()
+        .map_err(|_| miette!("Invalid port in the interface address: {input}"))?;
+    if interface.is_empty() {
+        return Err(miette!("Missing interface name in the interface address: {input}").into());
+    }
+    Ok((interface.to_string(), port))
+}
+
+/// Helper fn for parsing an IP address with an optional port from user input,
+/// like `10.0.0.2` or `10.0.0.2:5000`. When the port is missing it is set to 0
+pub(crate) fn ip_and_optional_port_parser(input: &str) -> Result<SocketAddr> {
+    if let Ok(address) = SocketAddr::from_str(input) {
+        return Ok(address);
+    }
+    let ip = IpAddr::from_str(input)
+        .map_err(|_| miette!("Invalid address: {input}. Expected <IP> or <IP>:<PORT>"))?;
+    Ok(SocketAddr::new(ip, 0))
+}
+
+/// Helper fn for parsing a socket address, or a port alone, from user input.
+/// When only a port is given, the IP of the returned address is unspecified
+pub(crate) fn socket_addr_or_port_parser(input: &str) -> Result<SocketAddr> {
+    if let Ok(port) = input.parse::<u16>() {
+        return Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port));
+    }
+    SocketAddr::from_str(input)
+        .map_err(|_| miette!("Invalid address: {input}. Expected <IP>:<PORT> or <PORT>").into())
+}
+
+/// Helper fn for parsing a PROXY protocol version (v1 or v2) from user input
+pub(crate) fn proxy_protocol_parser(input: &str) -> Result<ProxyProtocolVersion> {
+    ProxyProtocolVersion::from_str(input)
+        .map_err(|_| miette!("Invalid PROXY protocol version: {input}. Expected v1 or v2").into())
+}
+
+/// Helper fn for parsing a range of IP addresses in the CIDR notation from user input,
+/// like `10.0.0.0/8`. A single IP address is the range containing only that address
+pub(crate) fn ip_cidr_parser(input: &str) -> Result<IpCidr> {
+    IpCidr::from_str(input).map_err(|_| {
+        miette!("Invalid CIDR: {input}. Expected <IP>/<PREFIX LENGTH>, for example 10.0.0.0/8")
+            .into()
+    })
+}
+
+/// Helper fn for parsing a label given as `key=value` from user input
+pub(crate) fn label_parser(input: &str) -> Result<(String, String)> {
+    parse_label(input).map_err(|e| miette!("{e}").into())
+}
+
+/// Helper fn for parsing the size, in bytes, of the buffer used by the connections of an inlet
+pub(crate) fn buffer_size_parser(input: &str) -> Result<usize> {
+    let buffer_size = input
+        .parse::<usize>()
+        .map_err(|_| miette!("Invalid buffer size: {input}. Expected a number of bytes"))?;
+    validate_buffer_size(buffer_size).map_err(|e| miette!("{e}").into())
+}
+
+/// Helper fn for parsing the log level of an inlet: trace, debug, info, warn or error
+pub(crate) fn log_level_parser(input: &str) -> Result<Level> {
+    parse_log_level(input).map_err(|e| miette!("{e}").into())
+}
+
+/// Helper fn for parsing a connection status (up, down, degraded or pending) from user input
+pub(crate) fn connection_status_parser(input: &str) -> Result<ConnectionStatus> {
+    ConnectionStatus::try_from(input.to_string()).map_err(|e| miette!("{e}").into())
+}
+
+/// Helper fn for parsing a range of ports given as `start-end`, or a single port, from user input
+pub(crate) fn port_range_parser(input: &str) -> Result<RangeInclusive<u16>> {
+    let parse_port = |port: &str| {
+        port.trim()
+            .parse::<u16>()
+            .map_err(|_| miette!("Invalid port range: {input}. Expected <START>-<END> or <PORT>"))
+    };
+    let range = match input.split_once('-') {
+        Some((start, end)) => parse_port(start)?..=parse_port(end)?,
+        None => parse_port(input)?..=parse_port(input)?,
+    };
+    if range.is_empty() {
+        return Err(miette!(
+            "Invalid port range: {input}. The start must not be greater than the end"
+        )
+        .into());
+    }
+    Ok(range)
+}
+
 pub(crate) fn validate_project_name(s: &str) -> Result<String> {
     match api::validate_cloud_resource_name(s) {
         Ok(_) => Ok(s.to_string()),
@@ -123,4 +283,96 @@ mod tests {
         let invalid_input = "192,166,0.1:9999";
         assert!(socket_addr_parser(invalid_input).is_err());
     }
+
+    #[test]
+    fn test_ip_and_optional_port() {
+        let result = ip_and_optional_port_parser("10.0.0.2").unwrap();
+        assert_eq!(
+            result,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 0)
+        );
+
+        let result = ip_and_optional_port_parser("10.0.0.2:5000").unwrap();
+        assert_eq!(
+            result,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 5000)
+        );
+
+        let result = ip_and_optional_port_parser("::1").unwrap();
+        assert_eq!(result, SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 0));
+
+        assert!(ip_and_optional_port_parser("localhost").is_err());
+        assert!(ip_and_optional_port_parser("10.0.0.2:port").is_err());
+    }
+
+    #[test]
+    fn test_interface_and_port() {
+        let result = interface_and_port_parser("eth0:5000").unwrap();
+        assert_eq!(result, ("eth0".to_string(), 5000));
+
+        assert!(interface_and_port_parser("eth0").is_err());
+        assert!(interface_and_port_parser("eth0:port").is_err());
+        assert!(interface_and_port_parser(":5000").is_err());
+    }
+
+    #[test]
+    fn test_socket_addr_or_port() {
+        let result = socket_addr_or_port_parser("8080").unwrap();
+        assert_eq!(
+            result,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8080)
+        );
+
+        let result = socket_addr_or_port_parser("[::1]:8080").unwrap();
+        assert_eq!(
+            result,
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 8080)
+        );
+
+        assert!(socket_addr_or_port_parser("localhost:8080").is_err());
+    }
+
+    #[test]
+    fn test_port_range() {
+        assert_eq!(port_range_parser("5000-6000").unwrap(), 5000..=6000);
+        assert_eq!(port_range_parser("5432").unwrap(), 5432..=5432);
+        assert!(port_range_parser("6000-5000").is_err());
+        assert!(port_range_parser("5000-").is_err());
+        assert!(port_range_parser("port").is_err());
+    }
+
+    #[test]
+    fn test_socket_addr_range() {
+        let range = socket_addr_range_parser("127.0.0.1:5000-5002").unwrap();
+        assert!(range.is_range());
+        assert_eq!(range.to_string(), "127.0.0.1:5000-5002");
+        assert_eq!(
+            range.addresses(),
+            vec![
+                SocketAddr::from_str("127.0.0.1:5000").unwrap(),
+                SocketAddr::from_str("127.0.0.1:5001").unwrap(),
+                SocketAddr::from_str("127.0.0.1:5002").unwrap(),
+            ]
+        );
+
+        // the ports alone are bound to the local address
+        let range = socket_addr_range_parser("5000-5001").unwrap();
+        assert_eq!(
+            range.start(),
+            SocketAddr::from_str("127.0.0.1:5000").unwrap()
+        );
+        assert_eq!(range.addresses().len(), 2);
+
+        // a single address is a range of one address
+        let range = socket_addr_range_parser("[::1]:9999").unwrap();
+        assert!(!range.is_range());
+        assert_eq!(range.to_string(), "[::1]:9999");
+        assert_eq!(
+            range,
+            SocketAddrRange::from(SocketAddr::from_str("[::1]:9999").unwrap())
+        );
+
+        assert!(socket_addr_range_parser("127.0.0.1:5010-5000").is_err());
+        assert!(socket_addr_range_parser("127.0.0.1:5000-").is_err());
+    }
 }
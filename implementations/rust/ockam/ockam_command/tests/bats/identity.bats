@@ -74,6 +74,46 @@ teardown() {
   assert_output --regexp "There are no identit.*s to delete"
 }
 
+@test "identity - recreate an identity under the same name" {
+  i=$(random_str)
+  run_success "$OCKAM" identity create "${i}"
+  run_success "$OCKAM" identity show "${i}"
+  identifier="$output"
+
+  run_success "$OCKAM" identity delete "${i}" --recreate --yes
+  run_success "$OCKAM" identity show "${i}"
+  refute_output "${identifier}"
+
+  # an identity name is required to recreate an identity
+  run_failure "$OCKAM" identity delete --all --recreate --yes
+}
+
+@test "identity - export an identity before deleting it" {
+  i=$(random_str)
+  run_success "$OCKAM" identity create "${i}"
+  run_success "$OCKAM" identity delete "${i}" --export "$OCKAM_HOME/${i}.json" --yes --output json
+  assert_output --partial "$OCKAM_HOME/${i}.json"
+  assert_output --partial '"format": "json"'
+  run_success cat "$OCKAM_HOME/${i}.json"
+  assert_output --partial '"change_history"'
+
+  j=$(random_str)
+  run_success "$OCKAM" identity create "${j}"
+  run_success "$OCKAM" identity delete "${j}" --export "$OCKAM_HOME/${j}.pem" --export-format pem --yes
+  assert_output --partial "$OCKAM_HOME/${j}.pem"
+  run_success cat "$OCKAM_HOME/${j}.pem"
+  assert_output --partial "BEGIN OCKAM IDENTITY"
+}
+
+@test "identity - the export of an identity which cannot be deleted is removed" {
+  i=$(random_str)
+  n=$(random_str)
+  run_success "$OCKAM" identity create "${i}"
+  run_success "$OCKAM" node create "${n}" --identity "${i}"
+  run_failure "$OCKAM" identity delete "${i}" --export "$OCKAM_HOME/${i}.json" --yes
+  run_failure test -e "$OCKAM_HOME/${i}.json"
+}
+
 @test "identity - set default" {
   i=$(random_str)
 
@@ -43,6 +43,71 @@ teardown() {
   assert_output --partial "not found"
 }
 
+@test "portals - create a tcp inlet quietly" {
+  port="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" node create n2
+
+  # only the address of the inlet is printed
+  run_success $OCKAM tcp-inlet create --at /node/n2 --from 127.0.0.1:$port --to /node/n1/service/outlet --quiet
+  assert_output "127.0.0.1:$port"
+}
+
+@test "portals - delete a tcp inlet by bind address" {
+  port="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+  run_success "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$port" --to /node/n1/service/outlet --alias test-inlet
+
+  run_success "$OCKAM" tcp-inlet delete --from "127.0.0.1:$port" --at /node/n1 --yes
+  assert_output --partial "test-inlet"
+  run_failure "$OCKAM" tcp-inlet show test-inlet --at /node/n1
+
+  run_failure "$OCKAM" tcp-inlet delete --from "127.0.0.1:$port" --at /node/n1 --yes
+  assert_output --partial "No inlet bound to"
+}
+
+@test "portals - create tcp inlets from a configuration file" {
+  port_1="$(random_port)"
+  port_2="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" node create n2
+
+  cat <<EOF >"$OCKAM_HOME/inlets.yaml"
+inlets:
+  - from: 127.0.0.1:$port_1
+    to: /node/n1/service/outlet
+    alias: inlet-1
+  - from: 127.0.0.1:$port_2
+    to: /node/n1/service/outlet
+    alias: inlet-2
+EOF
+  run_success $OCKAM tcp-inlet create --at /node/n2 --config "$OCKAM_HOME/inlets.yaml"
+
+  run_success $OCKAM tcp-inlet list --at /node/n2
+  assert_output --partial "inlet-1"
+  assert_output --partial "127.0.0.1:$port_1"
+  assert_output --partial "inlet-2"
+  assert_output --partial "127.0.0.1:$port_2"
+}
+
+@test "portals - create tcp inlets on a range of ports" {
+  port="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+
+  run_success "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$port-$((port + 2))" --to /node/n1/service/outlet --alias ftp
+  run_success "$OCKAM" tcp-inlet list --at /node/n1
+  assert_output --partial "ftp-$port"
+  assert_output --partial "ftp-$((port + 1))"
+  assert_output --partial "ftp-$((port + 2))"
+
+  # none of the inlets is created when one of the ports is already used
+  run_failure "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$((port + 2))-$((port + 4))" --to /node/n1/service/outlet --alias other
+  run_success "$OCKAM" tcp-inlet list --at /node/n1
+  refute_output --partial "other-"
+}
+
 @test "portals - tcp outlet CRUD" {
   port="$(random_port)"
   run_success "$OCKAM" node create n1
@@ -133,6 +198,88 @@ teardown() {
   run_success curl --fail --head --max-time 10 "127.0.0.1:$port"
 }
 
+@test "portals - only accept the client connections coming from allowed sources" {
+  port="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" node create n2
+
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+  run_success "$OCKAM" tcp-inlet create --at /node/n2 --from "127.0.0.1:$port" --to /node/n1/service/outlet --allow-from 10.0.0.0/8
+  run_failure curl --fail --head --max-time 5 "127.0.0.1:$port"
+
+  port="$(random_port)"
+  run_success "$OCKAM" tcp-inlet create --at /node/n2 --from "127.0.0.1:$port" --to /node/n1/service/outlet --allow-from 10.0.0.0/8,127.0.0.0/8
+  run_success curl --fail --head --max-time 10 "127.0.0.1:$port"
+}
+
+@test "portals - drain a tcp inlet" {
+  port="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+  run_success "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$port" --to /node/n1/service/outlet --alias test-inlet
+  run_success curl --fail --head --max-time 10 "127.0.0.1:$port"
+
+  run_success "$OCKAM" tcp-inlet drain test-inlet --at /node/n1 --drain-timeout 5s
+  sleep 1
+
+  # the drained inlet doesn't accept new connections anymore
+  run_failure curl --fail --head --max-time 5 "127.0.0.1:$port"
+  run_failure "$OCKAM" tcp-inlet show test-inlet --at /node/n1
+
+  run_failure "$OCKAM" tcp-inlet drain test-inlet --at /node/n1
+  assert_output --partial "not found"
+}
+
+@test "portals - list the inlets having some labels" {
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+
+  run_success "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$(random_port)" --to /node/n1/service/outlet --alias prod-inlet --label env=prod
+  run_success "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$(random_port)" --to /node/n1/service/outlet --alias dev-inlet --label env=dev
+  run_failure "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$(random_port)" --to /node/n1/service/outlet --label "env=not valid"
+
+  run_success "$OCKAM" tcp-inlet list --at /node/n1 --label env=prod --output json
+  assert_output --partial "prod-inlet"
+  refute_output --partial "dev-inlet"
+  assert_output --partial '"env": "prod"'
+}
+
+@test "portals - list the inlets matching an alias pattern and a port range" {
+  port_1="$(random_port)"
+  port_2="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+
+  run_success "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$port_1" --to /node/n1/service/outlet --alias db-inlet
+  run_success "$OCKAM" tcp-inlet create --at /node/n1 --from "127.0.0.1:$port_2" --to /node/n1/service/outlet --alias web-inlet
+
+  run_success "$OCKAM" tcp-inlet list --at /node/n1 --alias 'db-*' --output json
+  assert_output --partial "db-inlet"
+  refute_output --partial "web-inlet"
+
+  run_success "$OCKAM" tcp-inlet list --at /node/n1 --ports "$port_2" --output json
+  assert_output --partial "web-inlet"
+  refute_output --partial "db-inlet"
+
+  run_failure "$OCKAM" tcp-inlet list --at /node/n1 --status unknown
+}
+
+@test "portals - reach the targets allowed by the outlet policy through a SOCKS5 inlet" {
+  port="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" node create n2
+
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+  run_success "$OCKAM" tcp-inlet create --at /node/n2 --from "127.0.0.1:$port" --to /node/n1/service/outlet --socks5
+
+  # without any policy, the targets are refused
+  run_failure curl --fail --head --max-time 5 --socks5 "127.0.0.1:$port" "127.0.0.1:5000"
+
+  run_success "$OCKAM" policy create --at n1 --resource tcp-outlet --action connect_to_target --expression '(= target.port 5000)'
+  run_success curl --fail --head --max-time 10 --socks5 "127.0.0.1:$port" "127.0.0.1:5000"
+  run_failure curl --fail --head --max-time 5 --socks5 "127.0.0.1:$port" "127.0.0.1:5001"
+}
+
 @test "portals - create an inlet/outlet pair with relay through a relay and move tcp traffic through it" {
   port="$(random_port)"
   run_success "$OCKAM" node create relay
@@ -189,6 +336,29 @@ teardown() {
   run_failure "$OCKAM" tcp-inlet create --at "$n" --from "127.0.0.1:$port" --to "/node/$n/service/outlet" --alias "$i"
 }
 
+@test "portals - replace a TCP inlet and keep it when its replacement is invalid" {
+  n="$(random_str)"
+  run_success "$OCKAM" node create "$n"
+
+  o="$(random_str)"
+  port="$(random_port)"
+  run_success "$OCKAM" tcp-outlet create --at "$n" --from /service/outlet --to "127.0.0.1:$port" --alias "$o"
+
+  i="$(random_str)"
+  port="$(random_port)"
+  run_success "$OCKAM" tcp-inlet create --at "$n" --from "127.0.0.1:$port" --to "/node/$n/service/outlet" --alias "$i"
+
+  # the replacement can listen at the address of the replaced inlet
+  run_success "$OCKAM" tcp-inlet create --at "$n" --from "127.0.0.1:$port" --to "/node/$n/service/outlet" --alias "$i" --replace
+
+  # an invalid replacement is rejected before the inlet is deleted
+  other_port="$(random_port)"
+  run_success "$OCKAM" tcp-inlet create --at "$n" --from "127.0.0.1:$other_port" --to "/node/$n/service/outlet"
+  run_failure "$OCKAM" tcp-inlet create --at "$n" --from "127.0.0.1:$other_port" --to "/node/$n/service/outlet" --alias "$i" --replace
+  run_success "$OCKAM" tcp-inlet show "$i" --at "$n"
+  assert_output --partial "127.0.0.1:$port"
+}
+
 @test "portals - fail to create two TCP inlets at the same address" {
   n="$(random_str)"
   run_success "$OCKAM" node create "$n"
@@ -57,6 +57,16 @@ impl AuthorityService {
             }
         }
 
+        self.refresh_credential(ctx, subject).await
+    }
+
+    /// Retrieve a new credential for an identity within this authority,
+    /// even if the cached credential is still valid
+    pub async fn refresh_credential(
+        &self,
+        ctx: &Context,
+        subject: &Identifier,
+    ) -> Result<CredentialAndPurposeKey> {
         // in order to keep the locking schema simple, we allow multiple concurrent retrievals
         let retriever = self
             .own_credential
@@ -83,6 +93,15 @@ impl AuthorityService {
         Ok(credential)
     }
 
+    /// Return the expiration time of the cached credential, if a credential has been retrieved
+    pub fn credential_expiration(&self) -> Option<TimestampInSeconds> {
+        self.inner_cache
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|cache| cache.valid_until)
+    }
+
     /// Issuer [`Identifier`]
     pub fn identifier(&self) -> &Identifier {
         &self.identifier
@@ -1,8 +1,10 @@
 use async_trait::async_trait;
+use minicbor::{Decode, Encode};
 
-use ockam_core::api::Request;
+use ockam_core::api::{Reply, Request};
 use ockam_core::compat::boxed::Box;
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
 use ockam_core::{Address, Result, Route};
 use ockam_node::api::Client;
 use ockam_node::{Context, WorkerBuilder};
@@ -10,7 +12,7 @@ use ockam_node::{Context, WorkerBuilder};
 use crate::credentials::credentials_server_worker::CredentialsServerWorker;
 use crate::credentials::Credentials;
 use crate::models::{CredentialAndPurposeKey, Identifier};
-use crate::{IdentitySecureChannelLocalInfo, TrustContext};
+use crate::{IdentityError, IdentitySecureChannelLocalInfo, TrustContext};
 
 /// This trait allows an identity to send its credential to another identity
 /// located at the end of a secure channel route
@@ -19,22 +21,38 @@ pub trait CredentialsServer: Send + Sync {
     /// Present credential to other party, route shall use secure channel. Other party is expected
     /// to present its credential in response, otherwise this call errors.
     ///
+    /// If a context is given, for example a nonce, the other party must echo it in its response,
+    /// otherwise this call errors. See [`CredentialPresentation`]
     async fn present_credential_mutual(
         &self,
         ctx: &Context,
         route: Route,
         authorities: &[Identifier],
         credential: CredentialAndPurposeKey,
+        context: Option<Vec<u8>>,
     ) -> Result<()>;
 
-    /// Present credential to other party, route shall use secure channel
+    /// Present credential to other party, route shall use secure channel.
+    /// If a context is given, the other party must echo it in its response
     async fn present_credential(
         &self,
         ctx: &Context,
         route: Route,
         credential: CredentialAndPurposeKey,
+        context: Option<Vec<u8>>,
     ) -> Result<()>;
 
+    /// Present credential to other party, route shall use secure channel.
+    /// The reply is failed, with the reason of the rejection, if the other party rejects the credential.
+    /// If a context is given, the other party must echo it in its response
+    async fn present_credential_with_reply(
+        &self,
+        ctx: &Context,
+        route: Route,
+        credential: CredentialAndPurposeKey,
+        context: Option<Vec<u8>>,
+    ) -> Result<Reply<()>>;
+
     /// Start this service as a worker
     async fn start(
         &self,
@@ -61,17 +79,33 @@ impl CredentialsServer for CredentialsServerModule {
         route: Route,
         authorities: &[Identifier],
         credential: CredentialAndPurposeKey,
+        context: Option<Vec<u8>>,
     ) -> Result<()> {
         let path = "actions/present_mutual";
         let client = Client::new(&route, None);
-        let (reply, local_info) = client
-            .ask_with_local_info(ctx, Request::post(path).body(credential), None)
-            .await?;
+        let (credential_and_purpose_key, local_info) = match context {
+            // without a context the credential is presented on its own,
+            // as expected by the parties which don't support presentation contexts
+            None => {
+                let (reply, local_info) = client
+                    .ask_with_local_info(ctx, Request::post(path).body(credential), None)
+                    .await?;
+                (reply.success()?, local_info)
+            }
+            Some(context) => {
+                let presentation = CredentialPresentation::new(credential, Some(context.clone()));
+                let (reply, local_info) = client
+                    .ask_with_local_info(ctx, Request::post(path).body(presentation), None)
+                    .await?;
+                let presentation: CredentialPresentation = reply.success()?;
+                check_echoed_context(&context, presentation.context.as_deref())?;
+                (presentation.credential, local_info)
+            }
+        };
 
         let their_id =
             IdentitySecureChannelLocalInfo::find_info_from_list(&local_info)?.their_identity_id();
 
-        let credential_and_purpose_key: CredentialAndPurposeKey = reply.success()?;
         self.credentials
             .credentials_verification()
             .receive_presented_credential(&their_id, authorities, &credential_and_purpose_key)
@@ -86,14 +120,40 @@ impl CredentialsServer for CredentialsServerModule {
         ctx: &Context,
         route: Route,
         credential: CredentialAndPurposeKey,
+        context: Option<Vec<u8>>,
     ) -> Result<()> {
-        let client = Client::new(&route, None);
-        client
-            .tell(ctx, Request::post("actions/present").body(credential))
+        self.present_credential_with_reply(ctx, route, credential, context)
             .await?
             .success()
     }
 
+    /// Present credential to other party, route shall use secure channel.
+    /// The reply is failed, with the reason of the rejection, if the other party rejects the credential
+    async fn present_credential_with_reply(
+        &self,
+        ctx: &Context,
+        route: Route,
+        credential: CredentialAndPurposeKey,
+        context: Option<Vec<u8>>,
+    ) -> Result<Reply<()>> {
+        let path = "actions/present";
+        let client = Client::new(&route, None);
+        let Some(context) = context else {
+            return client.tell(ctx, Request::post(path).body(credential)).await;
+        };
+        let presentation = CredentialPresentation::new(credential, Some(context.clone()));
+        let reply: Reply<CredentialPresentationEcho> = client
+            .ask(ctx, Request::post(path).body(presentation))
+            .await?;
+        match reply {
+            Reply::Successful(echo) => {
+                check_echoed_context(&context, Some(echo.context.as_slice()))?;
+                Ok(Reply::Successful(()))
+            }
+            Reply::Failed(e, status) => Ok(Reply::Failed(e, status)),
+        }
+    }
+
     /// Start worker that will be available to receive others attributes and put them into storage,
     /// after successful verification
     async fn start(
@@ -126,3 +186,50 @@ impl CredentialsServerModule {
         Self { credentials }
     }
 }
+
+/// Credential presented to another party, with a context chosen by the presenter, for example
+/// a nonce. The other party echoes the context in its response so that a presentation and its
+/// response can be correlated, and a replayed response detected.
+///
+/// A credential presented without a context is sent on its own.
+#[derive(Clone, Debug, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CredentialPresentation {
+    #[n(1)] pub credential: CredentialAndPurposeKey,
+    #[cbor(n(2), with = "minicbor::bytes")] pub context: Option<Vec<u8>>,
+}
+
+impl CredentialPresentation {
+    /// Create a new credential presentation
+    pub fn new(credential: CredentialAndPurposeKey, context: Option<Vec<u8>>) -> Self {
+        Self {
+            credential,
+            context,
+        }
+    }
+}
+
+/// Response to a one-way credential presentation made with a context
+#[derive(Clone, Debug, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CredentialPresentationEcho {
+    #[cbor(n(1), with = "minicbor::bytes")] pub context: Vec<u8>,
+}
+
+impl CredentialPresentationEcho {
+    /// Create a new response echoing the context of a presentation
+    pub fn new(context: Vec<u8>) -> Self {
+        Self { context }
+    }
+}
+
+/// Return an error if the context echoed by the other party is not the presented one
+fn check_echoed_context(presented: &[u8], echoed: Option<&[u8]>) -> Result<()> {
+    if echoed == Some(presented) {
+        Ok(())
+    } else {
+        Err(IdentityError::CredentialPresentationContextMismatch.into())
+    }
+}
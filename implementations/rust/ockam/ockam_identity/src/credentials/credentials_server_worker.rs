@@ -1,3 +1,4 @@
+use minicbor::data::Type;
 use minicbor::Decoder;
 use tracing::{debug, error, info, trace, warn};
 
@@ -8,8 +9,8 @@ use ockam_core::compat::{string::ToString, sync::Arc, vec::Vec};
 use ockam_core::{Result, Routed, Worker};
 use ockam_node::Context;
 
-use crate::credentials::Credentials;
-use crate::models::{CredentialAndPurposeKey, Identifier};
+use crate::credentials::{CredentialPresentation, CredentialPresentationEcho, Credentials};
+use crate::models::Identifier;
 use crate::{IdentitySecureChannelLocalInfo, TrustContext};
 
 const TARGET: &str = "ockam::credential_exchange_worker::service";
@@ -71,7 +72,7 @@ impl CredentialsServerWorker {
                     "Received one-way credential presentation request from {}",
                     sender
                 );
-                let credential_and_purpose_key: CredentialAndPurposeKey = dec.decode()?;
+                let presentation = decode_presentation(dec)?;
 
                 let res = self
                     .credentials
@@ -79,14 +80,19 @@ impl CredentialsServerWorker {
                     .receive_presented_credential(
                         &sender,
                         &self.trust_context.authorities(),
-                        &credential_and_purpose_key,
+                        &presentation.credential,
                     )
                     .await;
 
                 match res {
                     Ok(()) => {
                         debug!("One-way credential presentation request processed successfully with {}", sender);
-                        Response::ok(req).to_vec()?
+                        match presentation.context {
+                            Some(context) => Response::ok(req)
+                                .body(CredentialPresentationEcho::new(context))
+                                .to_vec()?,
+                            None => Response::ok(req).to_vec()?,
+                        }
                     }
                     Err(err) => {
                         debug!(
@@ -102,7 +108,7 @@ impl CredentialsServerWorker {
                     "Received mutual credential presentation request from {}",
                     sender
                 );
-                let credential_and_purpose_key: CredentialAndPurposeKey = dec.decode()?;
+                let presentation = decode_presentation(dec)?;
 
                 // FIXME info!("presented credential {}", credential);
                 let res = self
@@ -111,7 +117,7 @@ impl CredentialsServerWorker {
                     .receive_presented_credential(
                         &sender,
                         &self.trust_context.authorities(),
-                        &credential_and_purpose_key,
+                        &presentation.credential,
                     )
                     .await;
 
@@ -133,7 +139,12 @@ impl CredentialsServerWorker {
                     match credential.as_ref() {
                         Some(c) if self.present_back => {
                             info!("Mutual credential presentation request processed successfully with {}. Responding with own credential...", sender);
-                            Response::ok(req).body(c).to_vec()?
+                            match presentation.context {
+                                Some(context) => Response::ok(req)
+                                    .body(CredentialPresentation::new(c.clone(), Some(context)))
+                                    .to_vec()?,
+                                None => Response::ok(req).body(c).to_vec()?,
+                            }
                         }
                         _ => {
                             info!("Mutual credential presentation request processed successfully with {}. No credential to respond!", sender);
@@ -153,6 +164,15 @@ impl CredentialsServerWorker {
     }
 }
 
+/// Decode a presented credential, which is either sent on its own or with a context
+fn decode_presentation(dec: &mut Decoder<'_>) -> Result<CredentialPresentation> {
+    if dec.datatype()? == Type::Map {
+        Ok(dec.decode()?)
+    } else {
+        Ok(CredentialPresentation::new(dec.decode()?, None))
+    }
+}
+
 #[async_trait]
 impl Worker for CredentialsServerWorker {
     type Message = Vec<u8>;
@@ -4,7 +4,7 @@ use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
 use ockam_node::Context;
 
-use crate::models::{CredentialAndPurposeKey, Identifier};
+use crate::models::{CredentialAndPurposeKey, Identifier, TimestampInSeconds};
 use crate::AuthorityService;
 
 /// A trust context defines which authorities are trusted to attest to which attributes, within a context.
@@ -63,6 +63,30 @@ impl TrustContext {
         }
     }
 
+    /// Retrieve a new credential for a given identity, even if a valid credential has already
+    /// been retrieved, if an Authority has been defined and can issue a credential for that identity
+    pub async fn refresh_credential(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+    ) -> Result<Option<CredentialAndPurposeKey>> {
+        match self.authority_service().ok() {
+            Some(authority_service) => Ok(Some(
+                authority_service
+                    .refresh_credential(ctx, identifier)
+                    .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Return the expiration time of the last retrieved credential, if any
+    pub fn credential_expiration(&self) -> Option<TimestampInSeconds> {
+        self.authority_service
+            .as_ref()
+            .and_then(|a| a.credential_expiration())
+    }
+
     /// Return the authority service
     fn authority_service(&self) -> Result<AuthorityService> {
         self.authority_service.clone().ok_or_else(|| {
@@ -64,6 +64,8 @@ pub enum IdentityError {
     InvalidHex,
     /// Secret Key doesn't correspond to the Identity
     WrongSecretKey,
+    /// The context of a credential presentation was not echoed by the other party
+    CredentialPresentationContextMismatch,
 }
 
 impl ockam_core::compat::error::Error for IdentityError {}
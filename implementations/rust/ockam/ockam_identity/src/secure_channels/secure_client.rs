@@ -56,6 +56,11 @@ impl SecureClient {
             timeout,
         }
     }
+
+    /// Identifier of the secure channel responder
+    pub fn server_identifier(&self) -> Identifier {
+        self.server_identifier.clone()
+    }
 }
 
 impl SecureClient {
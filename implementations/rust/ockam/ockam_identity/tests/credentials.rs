@@ -1,6 +1,7 @@
 use std::sync::atomic::{AtomicI8, Ordering};
 use std::time::Duration;
 
+use ockam_core::api::{Reply, Request, Status};
 use ockam_core::compat::sync::Arc;
 use ockam_core::{async_trait, Any, DenyAll};
 use ockam_core::{route, Result, Routed, Worker};
@@ -8,9 +9,11 @@ use ockam_identity::models::CredentialSchemaIdentifier;
 use ockam_identity::secure_channels::secure_channels;
 use ockam_identity::utils::AttributesBuilder;
 use ockam_identity::{
-    AuthorityService, CredentialAccessControl, CredentialsMemoryRetriever,
-    SecureChannelListenerOptions, SecureChannelOptions, TrustContext, TrustIdentifierPolicy,
+    AuthorityService, CredentialAccessControl, CredentialPresentation, CredentialPresentationEcho,
+    CredentialsMemoryRetriever, SecureChannelListenerOptions, SecureChannelOptions, TrustContext,
+    TrustIdentifierPolicy,
 };
+use ockam_node::api::Client;
 use ockam_node::{Context, WorkerBuilder};
 
 #[ockam_macros::test]
@@ -79,7 +82,12 @@ async fn full_flow_oneway(ctx: &mut Context) -> Result<()> {
         .await?;
 
     credentials_service
-        .present_credential(ctx, route![channel, "credential_exchange"], credential)
+        .present_credential(
+            ctx,
+            route![channel, "credential_exchange"],
+            credential,
+            None,
+        )
         .await?;
 
     let attrs = identity_attributes_repository
@@ -94,6 +102,220 @@ async fn full_flow_oneway(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+#[ockam_macros::test]
+async fn present_credential_with_reply(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let credentials = identities.credentials();
+    let credentials_service = identities.credentials_server();
+
+    let authority = identities_creation.create_identity().await?;
+    let impostor = identities_creation.create_identity().await?;
+    let server = identities_creation.create_identity().await?;
+    let client = identities_creation.create_identity().await?;
+
+    let listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            &server,
+            "listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let trust_context = TrustContext::new(
+        "test_trust_context_id".to_string(),
+        Some(AuthorityService::new(
+            secure_channels.identities().credentials(),
+            authority.clone(),
+            None,
+        )),
+    );
+
+    ctx.flow_controls()
+        .add_consumer("credential_exchange", listener.flow_control_id());
+    credentials_service
+        .start(
+            ctx,
+            trust_context,
+            server.clone(),
+            "credential_exchange".into(),
+            false,
+        )
+        .await?;
+
+    let channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            &client,
+            route!["listener"],
+            SecureChannelOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(server.clone())),
+        )
+        .await?;
+
+    // a credential issued by the trusted authority is accepted
+    let credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            &authority,
+            &client,
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_superuser", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    let reply = credentials_service
+        .present_credential_with_reply(
+            ctx,
+            route![channel.clone(), "credential_exchange"],
+            credential,
+            None,
+        )
+        .await?;
+    assert!(matches!(reply, Reply::Successful(())));
+
+    // a credential issued by another identity is rejected, with a reason
+    let credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            &impostor,
+            &client,
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_superuser", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    let reply = credentials_service
+        .present_credential_with_reply(
+            ctx,
+            route![channel, "credential_exchange"],
+            credential,
+            None,
+        )
+        .await?;
+    match reply {
+        Reply::Failed(error, status) => {
+            assert_eq!(status, Some(Status::BadRequest));
+            assert!(error.message().is_some());
+        }
+        Reply::Successful(_) => panic!("the credential should be rejected"),
+    }
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn present_credential_with_a_context(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels().await?;
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let identity_attributes_repository = identities.identity_attributes_repository();
+    let credentials = identities.credentials();
+    let credentials_service = identities.credentials_server();
+
+    let authority = identities_creation.create_identity().await?;
+    let server = identities_creation.create_identity().await?;
+    let client = identities_creation.create_identity().await?;
+
+    let issue_credential = |subject| {
+        let credentials = credentials.clone();
+        let authority = authority.clone();
+        async move {
+            credentials
+                .credentials_creation()
+                .issue_credential(
+                    &authority,
+                    &subject,
+                    AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                        .with_attribute("is_user", "true")
+                        .build(),
+                    Duration::from_secs(60),
+                )
+                .await
+        }
+    };
+
+    let listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            &server,
+            "listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+    let trust_context = TrustContext::new(
+        "test_trust_context_id".to_string(),
+        Some(AuthorityService::new(
+            secure_channels.identities().credentials(),
+            authority.clone(),
+            Some(Arc::new(CredentialsMemoryRetriever::new(
+                issue_credential(server.clone()).await?,
+            ))),
+        )),
+    );
+    ctx.flow_controls()
+        .add_consumer("credential_exchange", listener.flow_control_id());
+    credentials_service
+        .start(
+            ctx,
+            trust_context.clone(),
+            server.clone(),
+            "credential_exchange".into(),
+            true,
+        )
+        .await?;
+
+    let channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            &client,
+            route!["listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let nonce = b"nonce".to_vec();
+
+    // the server sends its context back with its own credential
+    credentials_service
+        .present_credential_mutual(
+            ctx,
+            route![channel.clone(), "credential_exchange"],
+            &trust_context.authorities(),
+            issue_credential(client.clone()).await?,
+            Some(nonce.clone()),
+        )
+        .await?;
+    assert!(identity_attributes_repository
+        .get_attributes(&server)
+        .await?
+        .is_some());
+
+    // the server echoes the context of a one-way presentation
+    let reply = credentials_service
+        .present_credential_with_reply(
+            ctx,
+            route![channel.clone(), "credential_exchange"],
+            issue_credential(client.clone()).await?,
+            Some(nonce.clone()),
+        )
+        .await?;
+    assert!(matches!(reply, Reply::Successful(())));
+
+    let presentation =
+        CredentialPresentation::new(issue_credential(client.clone()).await?, Some(nonce.clone()));
+    let reply: Reply<CredentialPresentationEcho> =
+        Client::new(&route![channel, "credential_exchange"], None)
+            .ask(ctx, Request::post("actions/present").body(presentation))
+            .await?;
+    assert_eq!(reply.success()?.context, nonce);
+
+    ctx.stop().await
+}
+
 #[ockam_macros::test]
 async fn full_flow_twoway(ctx: &mut Context) -> Result<()> {
     let secure_channels = secure_channels().await?;
@@ -175,6 +397,7 @@ async fn full_flow_twoway(ctx: &mut Context) -> Result<()> {
             route![channel, "credential_exchange"],
             &trust_context.authorities(),
             credential,
+            None,
         )
         .await?;
 
@@ -299,6 +522,7 @@ async fn access_control(ctx: &mut Context) -> Result<()> {
             ctx,
             route![channel.clone(), "credential_exchange"],
             credential,
+            None,
         )
         .await?;
 
@@ -1,6 +1,6 @@
-use crate::channel_types::{SmallReceiver, SmallSender};
+use crate::channel_types::{OneshotSender, SmallReceiver, SmallSender};
 use crate::tokio::runtime::Handle;
-use crate::{error::*, AsyncDropSender, NodeMessage};
+use crate::{error::*, AsyncDropSender, NodeMessage, RouteRewriter};
 use core::sync::atomic::AtomicUsize;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::sync::{Arc, RwLock};
@@ -13,6 +13,15 @@ use ockam_core::{async_trait, Address, Mailboxes, RelayMessage, Result, Transpor
 use core::fmt::{Debug, Formatter};
 use ockam_transport_core::Transport;
 
+/// Senders to notify, for each transport type, once a transport is registered
+pub(crate) type TransportRegistrations = HashMap<TransportType, Vec<OneshotSender<()>>>;
+
+/// Route rewriters applied, in registration order, before resolving transport addresses
+pub(crate) type RouteRewriters = Vec<Arc<dyn RouteRewriter>>;
+
+/// Flow controls used by default to resolve the addresses of each type of transport
+pub(crate) type TransportFlowControls = HashMap<TransportType, FlowControls>;
+
 /// A default timeout in seconds
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -26,6 +35,14 @@ pub struct Context {
     pub(super) mailbox_count: Arc<AtomicUsize>,
     /// List of transports used to resolve external addresses to local workers in routes
     pub(super) transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
+    /// Transport addresses already resolved to the local address of a transport worker
+    pub(super) resolved_transport_addresses: Arc<RwLock<HashMap<Address, Address>>>,
+    /// Senders notified when a transport of a given type gets registered
+    pub(super) transport_registrations: Arc<RwLock<TransportRegistrations>>,
+    /// Rewriters applied to routes before their transport addresses are resolved
+    pub(super) route_rewriters: Arc<RwLock<RouteRewriters>>,
+    /// Flow controls registered with a transport, used by default to resolve its addresses
+    pub(super) transport_flow_controls: Arc<RwLock<TransportFlowControls>>,
     pub(super) flow_controls: FlowControls,
 }
 
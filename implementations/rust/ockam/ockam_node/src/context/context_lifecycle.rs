@@ -16,6 +16,8 @@ use crate::tokio::{self, runtime::Handle};
 use crate::{debugger, Context};
 use crate::{error::*, relay::CtrlSignal, router::SenderPair, NodeMessage};
 
+use super::context::{RouteRewriters, TransportFlowControls, TransportRegistrations};
+
 /// A special type of `Context` that has no worker relay and inherits
 /// the parent `Context`'s access control
 pub type DetachedContext = Context;
@@ -55,12 +57,17 @@ impl Context {
     ///
     /// `async_drop_sender` must be provided when creating a detached
     /// Context type (i.e. not backed by a worker relay).
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         rt: Handle,
         sender: SmallSender<NodeMessage>,
         mailboxes: Mailboxes,
         async_drop_sender: Option<AsyncDropSender>,
         transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
+        resolved_transport_addresses: Arc<RwLock<HashMap<Address, Address>>>,
+        transport_registrations: Arc<RwLock<TransportRegistrations>>,
+        route_rewriters: Arc<RwLock<RouteRewriters>>,
+        transport_flow_controls: Arc<RwLock<TransportFlowControls>>,
         flow_controls: &FlowControls,
     ) -> (Self, SenderPair, SmallReceiver<CtrlSignal>) {
         let (mailbox_tx, receiver) = message_channel();
@@ -74,6 +81,10 @@ impl Context {
                 async_drop_sender,
                 mailbox_count: Arc::new(0.into()),
                 transports,
+                resolved_transport_addresses,
+                transport_registrations,
+                route_rewriters,
+                transport_flow_controls,
                 flow_controls: flow_controls.clone(),
             },
             SenderPair {
@@ -94,6 +105,10 @@ impl Context {
             mailboxes,
             None,
             self.transports.clone(),
+            self.resolved_transport_addresses.clone(),
+            self.transport_registrations.clone(),
+            self.route_rewriters.clone(),
+            self.transport_flow_controls.clone(),
             &self.flow_controls,
         )
     }
@@ -109,6 +124,10 @@ impl Context {
             mailboxes,
             Some(drop_sender),
             self.transports.clone(),
+            self.resolved_transport_addresses.clone(),
+            self.transport_registrations.clone(),
+            self.route_rewriters.clone(),
+            self.transport_flow_controls.clone(),
             &self.flow_controls,
         )
     }
@@ -14,6 +14,7 @@ impl Context {
     pub async fn stop_now(&self) -> Result<()> {
         let tx = self.sender.clone();
         info!("Immediately shutting down all workers");
+        self.clear_transports();
         let (msg, _) = NodeMessage::stop_node(ShutdownType::Immediate);
 
         match tx.send(msg).await {
@@ -36,6 +37,8 @@ impl Context {
     ///
     /// This call will hang until a safe shutdown has been completed
     /// or the desired timeout has been reached.
+    /// The registered transports are deregistered once the shutdown is over, so that
+    /// the workers can still use them while they are stopping.
     pub async fn stop_timeout(&self, seconds: u8) -> Result<()> {
         let (req, mut rx) = NodeMessage::stop_node(ShutdownType::Graceful(seconds));
         self.sender
@@ -44,9 +47,12 @@ impl Context {
             .map_err(NodeError::from_send_err)?;
 
         // Wait until we get the all-clear
-        rx.recv()
+        let result = rx
+            .recv()
             .await
-            .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal())??;
+            .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal());
+        self.clear_transports();
+        result??;
         Ok(())
     }
 }
@@ -1,15 +1,97 @@
-use ockam_core::compat::sync::Arc;
+use core::future::Future;
+use core::time::Duration;
+
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{Error, Result, Route, TransportType};
+use ockam_core::flow_control::FlowControls;
+use ockam_core::{Address, Error, Result, Route, TransportType};
 use ockam_transport_core::Transport;
+use tracing::{field, Instrument};
 
+use super::context::TransportRegistrations;
+use crate::channel_types::{oneshot_channel, OneshotReceiver};
+use crate::tokio::time::timeout;
 use crate::Context;
 
+/// A route rewriter can be registered on a [`Context`] to modify routes before their
+/// transport addresses are resolved. For example, a public hostname can be mapped to the
+/// address of a local stub in tests
+pub trait RouteRewriter: Send + Sync + 'static {
+    /// Return the rewritten route
+    fn rewrite(&self, route: Route) -> Route;
+}
+
+impl<F> RouteRewriter for F
+where
+    F: Fn(Route) -> Route + Send + Sync + 'static,
+{
+    fn rewrite(&self, route: Route) -> Route {
+        self(route)
+    }
+}
+
 impl Context {
-    /// Return the list of supported transports
-    pub fn register_transport(&self, transport: Arc<dyn Transport>) {
-        let mut transports = self.transports.write().unwrap();
-        transports.insert(transport.transport_type(), transport);
+    /// Register a route rewriter.
+    /// The rewriters are applied in registration order, before resolving the transport addresses
+    /// of a route with [`Context::resolve_transport_route`]
+    pub fn register_route_rewriter(&self, rewriter: Arc<dyn RouteRewriter>) {
+        self.route_rewriters.write().unwrap().push(rewriter);
+    }
+
+    /// Apply all the registered route rewriters to a route
+    fn rewrite_route(&self, route: Route) -> Route {
+        let rewriters = self.route_rewriters.read().unwrap().clone();
+        rewriters
+            .iter()
+            .fold(route, |route, rewriter| rewriter.rewrite(route))
+    }
+
+    /// Register a transport and return the transport previously registered for the same type, if any
+    pub fn register_transport(&self, transport: Arc<dyn Transport>) -> Option<Arc<dyn Transport>> {
+        let transport_type = transport.transport_type();
+        let previous = self
+            .transports
+            .write()
+            .unwrap()
+            .insert(transport_type, transport);
+        if previous.is_some() {
+            warn!("a transport of type {transport_type} has been replaced");
+        }
+
+        // notify the contexts waiting for this type of transport
+        let senders = self
+            .transport_registrations
+            .write()
+            .unwrap()
+            .remove(&transport_type);
+        for sender in senders.unwrap_or_default() {
+            let _ = sender.send(());
+        }
+
+        // the flow controls of a replaced transport don't apply to the new one
+        self.transport_flow_controls
+            .write()
+            .unwrap()
+            .remove(&transport_type);
+        previous
+    }
+
+    /// Register a transport like [`Context::register_transport`], with the flow controls used
+    /// to resolve its addresses when a route is resolved with
+    /// [`Context::resolve_transport_route_default`]
+    pub fn register_transport_with_flow_controls(
+        &self,
+        transport: Arc<dyn Transport>,
+        flow_controls: FlowControls,
+    ) -> Option<Arc<dyn Transport>> {
+        let transport_type = transport.transport_type();
+        let previous = self.register_transport(transport);
+        self.transport_flow_controls
+            .write()
+            .unwrap()
+            .insert(transport_type, flow_controls);
+        previous
     }
 
     /// Return true if a given transport has already been registered
@@ -18,54 +100,385 @@ impl Context {
         transports.contains_key(&transport_type)
     }
 
+    /// Return a copy of all the registered transports, sorted by transport type.
+    /// The lock on the transports is released before returning
+    pub fn transports_snapshot(&self) -> Vec<(TransportType, Arc<dyn Transport>)> {
+        let transports = self.transports.read().unwrap().clone();
+        let mut snapshot: Vec<_> = transports.into_iter().collect();
+        snapshot.sort_by_key(|(transport_type, _)| *transport_type);
+        snapshot
+    }
+
+    /// Deregister all the transports and forget the addresses they resolved.
+    /// This is done when the node is stopped, so that the transports are released
+    /// even if some copies of this context are still alive. The contexts waiting for
+    /// a transport to be registered stop waiting
+    pub fn clear_transports(&self) {
+        self.transports.write().unwrap().clear();
+        self.resolved_transport_addresses.write().unwrap().clear();
+        self.transport_registrations.write().unwrap().clear();
+        self.transport_flow_controls.write().unwrap().clear();
+    }
+
     /// For each address handled by a given transport in a route, for example, (TCP, "127.0.0.1:4000")
     /// Create a worker supporting the routing of messages for this transport and replace the address
     /// in the route with the worker address
     pub async fn resolve_transport_route(&self, route: Route) -> Result<Route> {
-        let transports = self.transports.read().unwrap().clone();
+        self.resolve_transport_route_with(route, |transport, address| {
+            self.resolve_with_transport(transport, address)
+        })
+        .await
+    }
 
-        // check the number of transport hops, there can be only one
-        // we do this first pass over the list of addresses to avoid creating connections
-        // and then having to close them if we find several hops
-        let number_of_transport_hops = route.iter().filter(|a| !a.is_local()).count();
-        if number_of_transport_hops > 1 {
-            return Err(Error::new(
-                Origin::Transport,
-                Kind::Invalid,
-                "only one transport hop is allowed in a route",
-            ));
-        }
-        // return the route if there are no transport hops
-        else if number_of_transport_hops == 0 {
-            return Ok(route);
+    /// Resolve a route like [`Context::resolve_transport_route`], the transport workers
+    /// being registered in `flow_controls`.
+    /// The resolved addresses are not cached since they depend on the flow controls
+    pub async fn resolve_transport_route_with_flow_controls(
+        &self,
+        route: Route,
+        flow_controls: &FlowControls,
+    ) -> Result<Route> {
+        self.resolve_transport_route_with(route, |transport, address| async move {
+            transport
+                .resolve_address_with_flow_controls(address, flow_controls)
+                .await
+        })
+        .await
+    }
+
+    /// Resolve a route like [`Context::resolve_transport_route_with_flow_controls`], with the
+    /// flow controls registered for each transport with
+    /// [`Context::register_transport_with_flow_controls`].
+    /// The addresses of a transport registered without flow controls are resolved with
+    /// [`Context::resolve_transport_route`]
+    pub async fn resolve_transport_route_default(&self, route: Route) -> Result<Route> {
+        let defaults = self.transport_flow_controls.read().unwrap().clone();
+        self.resolve_transport_route_with(route, |transport, address| {
+            let flow_controls = defaults.get(&transport.transport_type()).cloned();
+            async move {
+                match flow_controls {
+                    Some(flow_controls) => {
+                        transport
+                            .resolve_address_with_flow_controls(address, &flow_controls)
+                            .await
+                    }
+                    None => self.resolve_with_transport(transport, address).await,
+                }
+            }
+        })
+        .await
+    }
+
+    /// Resolve the transport addresses of a route, using `resolve` to resolve an address
+    /// with the registered transport of the same type
+    async fn resolve_transport_route_with<F, Fut>(&self, route: Route, resolve: F) -> Result<Route>
+    where
+        F: Fn(Arc<dyn Transport>, Address) -> Fut,
+        Fut: Future<Output = Result<Address>>,
+    {
+        let route = self.rewrite_route(route);
+        let transports: Vec<Arc<dyn Transport>> = self
+            .transports_snapshot()
+            .into_iter()
+            .map(|(_, transport)| transport)
+            .collect();
+        resolve_route(&transports, route, resolve).await
+    }
+
+    /// Resolve a route which can remain partly non-local, because its last addresses are
+    /// resolved by a remote node.
+    ///
+    /// Contrary to [`Context::resolve_transport_route`], the resolution stops at the first
+    /// transport hop: that hop is resolved if its transport is registered, and the remaining
+    /// addresses, local or not, are returned intact
+    pub async fn resolve_transport_route_partial(&self, route: Route) -> Result<Route> {
+        let route = self.rewrite_route(route);
+        let transports: Vec<Arc<dyn Transport>> = self
+            .transports_snapshot()
+            .into_iter()
+            .map(|(_, transport)| transport)
+            .collect();
+        resolve_route_partial(&transports, route, |transport, address| {
+            self.resolve_with_transport(transport, address)
+        })
+        .await
+    }
+
+    /// Resolve a route like [`Context::resolve_transport_route`].
+    /// If the resolution fails because the transport for an address is not registered
+    /// (for example if it is temporarily deregistered while reconnecting), then wait up to `wait`
+    /// for that transport to be registered before retrying the resolution
+    pub async fn resolve_transport_route_waiting(
+        &self,
+        route: Route,
+        wait: Duration,
+    ) -> Result<Route> {
+        let error = match self.resolve_transport_route(route.clone()).await {
+            Ok(resolved) => return Ok(resolved),
+            Err(e) => e,
+        };
+
+        let missing_transport_type = self
+            .rewrite_route(route.clone())
+            .iter()
+            .filter(|a| !a.is_local())
+            .map(|a| a.transport_type())
+            .find(|t| !self.is_transport_registered(*t));
+        let transport_type = match missing_transport_type {
+            Some(transport_type) => transport_type,
+            None => return Err(error),
         };
 
-        // otherwise resolve the hop address
-        let mut resolved = Route::new();
-        for address in route.iter() {
-            if !address.is_local() {
-                if let Some(transport) = transports.get(&address.transport_type()) {
-                    let resolved_address = transport.resolve_address(address.clone()).await?;
-                    resolved = resolved.append(resolved_address);
-                } else {
-                    return Err(Error::new(
+        let mut waiter =
+            TransportRegistrationWaiter::new(&self.transport_registrations, transport_type);
+        // the transport might have been registered before the waiter was added
+        if !self.is_transport_registered(transport_type) {
+            waiter.wait(wait).await;
+        }
+        drop(waiter);
+        self.resolve_transport_route(route).await
+    }
+
+    /// Resolve an address with a transport.
+    /// A previous resolution is reused as long as the transport reports that the resolved route
+    /// is still alive, otherwise it is evicted and the address is resolved again
+    async fn resolve_with_transport(
+        &self,
+        transport: Arc<dyn Transport>,
+        address: Address,
+    ) -> Result<Address> {
+        let cached = self
+            .resolved_transport_addresses
+            .read()
+            .unwrap()
+            .get(&address)
+            .cloned();
+        if let Some(resolved) = cached {
+            if transport.is_route_alive(&resolved.clone().into()).await {
+                return Ok(resolved);
+            }
+            self.resolved_transport_addresses
+                .write()
+                .unwrap()
+                .remove(&address);
+        }
+
+        let resolved = transport.resolve_address(address.clone()).await?;
+        if transport.is_route_alive(&resolved.clone().into()).await {
+            self.resolved_transport_addresses
+                .write()
+                .unwrap()
+                .insert(address, resolved.clone());
+        }
+        Ok(resolved)
+    }
+
+    /// Resolve a single address handled by a transport, for example, (TCP, "127.0.0.1:4000")
+    /// and return the local route to the worker supporting the routing of messages for that address
+    pub async fn resolve_transport_address(&self, address: Address) -> Result<Route> {
+        self.resolve_transport_route(Route::new().append(address).into())
+            .await
+    }
+}
+
+/// Wait for the registration of a transport of a given type.
+/// The waiter is removed from the registrations when it is dropped, after a timeout
+/// or if the waiting future is cancelled
+struct TransportRegistrationWaiter<'a> {
+    registrations: &'a RwLock<TransportRegistrations>,
+    transport_type: TransportType,
+    receiver: Option<OneshotReceiver<()>>,
+}
+
+impl<'a> TransportRegistrationWaiter<'a> {
+    fn new(
+        registrations: &'a RwLock<TransportRegistrations>,
+        transport_type: TransportType,
+    ) -> Self {
+        let (sender, receiver) = oneshot_channel();
+        registrations
+            .write()
+            .unwrap()
+            .entry(transport_type)
+            .or_default()
+            .push(sender);
+        Self {
+            registrations,
+            transport_type,
+            receiver: Some(receiver),
+        }
+    }
+
+    /// Wait at most `wait` for the transport to be registered
+    async fn wait(&mut self, wait: Duration) {
+        if let Some(receiver) = self.receiver.take() {
+            let _ = timeout(wait, receiver).await;
+        }
+    }
+}
+
+impl Drop for TransportRegistrationWaiter<'_> {
+    fn drop(&mut self) {
+        // the senders of the dropped receivers can not notify anyone anymore
+        self.receiver.take();
+        let mut registrations = self.registrations.write().unwrap();
+        if let Some(senders) = registrations.get_mut(&self.transport_type) {
+            senders.retain(|sender| !sender.is_closed());
+            if senders.is_empty() {
+                registrations.remove(&self.transport_type);
+            }
+        }
+    }
+}
+
+/// Resolve the transport addresses of a route with a list of transports, without using
+/// the registered transports and the resolution cache of a [`Context`].
+///
+/// Only one transport hop is allowed in a route. The transport address is replaced with the
+/// address resolved by the transport of the same type, and the local addresses are kept as they are
+pub async fn resolve_route_with(transports: &[Arc<dyn Transport>], route: Route) -> Result<Route> {
+    resolve_route(transports, route, |transport, address| async move {
+        transport.resolve_address(address).await
+    })
+    .await
+}
+
+/// Resolve the transport addresses of a route, using `resolve` to resolve an address
+/// with the transport of the same type
+async fn resolve_route<F, Fut>(
+    transports: &[Arc<dyn Transport>],
+    route: Route,
+    resolve: F,
+) -> Result<Route>
+where
+    F: Fn(Arc<dyn Transport>, Address) -> Fut,
+    Fut: Future<Output = Result<Address>>,
+{
+    // check the number of transport hops, there can be only one
+    // we do this first pass over the list of addresses to avoid creating connections
+    // and then having to close them if we find several hops
+    let number_of_transport_hops = route.iter().filter(|a| !a.is_local()).count();
+    if number_of_transport_hops > 1 {
+        return Err(Error::new(
+            Origin::Transport,
+            Kind::Invalid,
+            "only one transport hop is allowed in a route",
+        ));
+    }
+    // return the route if there are no transport hops
+    else if number_of_transport_hops == 0 {
+        return Ok(route);
+    };
+
+    // otherwise resolve the hop address
+    let mut resolved = Route::new();
+    for address in route.iter() {
+        if !address.is_local() {
+            let transport = transports
+                .iter()
+                .find(|t| t.transport_type() == address.transport_type());
+            if let Some(transport) = transport {
+                let resolution = resolve(transport.clone(), address.clone());
+                let resolved_address = resolve_in_span(address.transport_type(), resolution)
+                    .await
+                    .map_err(|e| transport_error(&route, address.transport_type(), e))?;
+                resolved = resolved.append(resolved_address);
+            } else {
+                return Err(transport_error(
+                    &route,
+                    address.transport_type(),
+                    Error::new(
                         Origin::Transport,
                         Kind::NotFound,
                         format!("the transport is not registered for address {}", address),
-                    ));
-                }
-            } else {
-                resolved = resolved.append(address.clone());
-            };
-        }
-        let result: Route = resolved.into();
-        Ok(result)
+                    ),
+                ));
+            }
+        } else {
+            resolved = resolved.append(address.clone());
+        };
     }
+
+    let result: Route = resolved.into();
+    Ok(result)
+}
+
+/// Resolve the first transport hop of a route, if its transport is registered, using `resolve`.
+/// The addresses before that hop are local, and the addresses after it are kept as they are
+async fn resolve_route_partial<F, Fut>(
+    transports: &[Arc<dyn Transport>],
+    route: Route,
+    resolve: F,
+) -> Result<Route>
+where
+    F: Fn(Arc<dyn Transport>, Address) -> Fut,
+    Fut: Future<Output = Result<Address>>,
+{
+    let addresses: Vec<Address> = route.iter().cloned().collect();
+    let Some(hop) = addresses.iter().position(|a| !a.is_local()) else {
+        return Ok(route);
+    };
+    let address = &addresses[hop];
+    let Some(transport) = transports
+        .iter()
+        .find(|t| t.transport_type() == address.transport_type())
+    else {
+        debug!(
+            "the transport is not registered for address {}, the route {} is not resolved",
+            address, route
+        );
+        return Ok(route);
+    };
+
+    let resolution = resolve(transport.clone(), address.clone());
+    let resolved_address = resolve_in_span(address.transport_type(), resolution).await?;
+    let mut resolved = addresses;
+    resolved[hop] = resolved_address;
+    Ok(Route::create(resolved))
+}
+
+/// Resolve an address in a span recording the transport type, whether the address
+/// could be resolved and, with the standard library, the duration of the resolution
+async fn resolve_in_span<Fut>(transport_type: TransportType, resolution: Fut) -> Result<Address>
+where
+    Fut: Future<Output = Result<Address>>,
+{
+    let span = debug_span!(
+        "resolve_transport_address",
+        transport_type = %transport_type,
+        resolved = field::Empty,
+        duration_us = field::Empty,
+    );
+    #[cfg(feature = "std")]
+    let started_at = std::time::Instant::now();
+    let result = resolution.instrument(span.clone()).await;
+    span.record("resolved", result.is_ok());
+    #[cfg(feature = "std")]
+    span.record("duration_us", started_at.elapsed().as_micros() as u64);
+    result
+}
+
+/// Return an error naming the transport which could not resolve the transport hop of a route.
+/// The returned error keeps the kind of the transport error
+fn transport_error(route: &Route, transport_type: TransportType, error: Error) -> Error {
+    Error::new(
+        Origin::Transport,
+        error.code().kind,
+        format!("the route {route} could not be resolved: {transport_type} -> {error}"),
+    )
 }
 
 #[cfg(test)]
 mod tests {
-    use ockam_core::{async_trait, route, Address, LOCAL};
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_core::{async_trait, route, Any, AsyncTryClone, Worker, LOCAL};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context as LayerContext, SubscriberExt};
+    use tracing_subscriber::Layer;
 
     use super::*;
 
@@ -77,6 +490,104 @@ mod tests {
         ctx.stop().await
     }
 
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_transports_snapshot(ctx: &mut Context) -> Result<()> {
+        assert!(ctx.transports_snapshot().is_empty());
+
+        ctx.register_transport(Arc::new(SomeTransport()));
+        ctx.register_transport(Arc::new(FailingTransport()));
+
+        let transport_types: Vec<TransportType> = ctx
+            .transports_snapshot()
+            .iter()
+            .map(|(transport_type, transport)| {
+                assert_eq!(*transport_type, transport.transport_type());
+                *transport_type
+            })
+            .collect();
+        assert_eq!(
+            transport_types,
+            vec![TransportType::new(10), TransportType::new(11)]
+        );
+
+        // the lock is released, so transports can still be registered
+        ctx.register_transport(Arc::new(SomeTransport()));
+        assert_eq!(ctx.transports_snapshot().len(), 2);
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_transports_are_cleared_on_stop(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(SomeTransport());
+        ctx.register_transport(transport.clone());
+        let copy = ctx.async_try_clone().await?;
+        let route = route![(transport.transport_type(), "address")];
+        assert!(copy.resolve_transport_route(route.clone()).await.is_ok());
+
+        // once the node is stopped no transport is registered, even for a copy of the context
+        ctx.stop().await?;
+        assert!(ctx.transports_snapshot().is_empty());
+        let error = copy.resolve_transport_route(route).await.unwrap_err();
+        assert_eq!(error.code().kind, Kind::NotFound);
+        assert!(
+            error
+                .to_string()
+                .contains("the transport is not registered"),
+            "{error}"
+        );
+
+        // the transport is not referenced by the contexts anymore
+        assert_eq!(Arc::strong_count(&transport), 1);
+        Ok(())
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_transports_are_available_while_the_workers_stop(ctx: &mut Context) -> Result<()> {
+        ctx.register_transport_with_flow_controls(Arc::new(SomeTransport()), FlowControls::new());
+        let transports_on_shutdown = Arc::new(AtomicUsize::new(0));
+        ctx.start_worker(
+            "worker",
+            TransportsOnShutdown(transports_on_shutdown.clone()),
+        )
+        .await?;
+
+        ctx.stop().await?;
+        assert_eq!(transports_on_shutdown.load(Ordering::Relaxed), 1);
+        assert!(ctx.transports_snapshot().is_empty());
+        assert!(ctx.transport_flow_controls.read().unwrap().is_empty());
+        Ok(())
+    }
+
+    /// Worker recording the number of registered transports when it is stopped
+    struct TransportsOnShutdown(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl Worker for TransportsOnShutdown {
+        type Message = Any;
+        type Context = Context;
+
+        async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+            self.0
+                .store(ctx.transports_snapshot().len(), Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_register_transport_returns_the_replaced_transport(
+        ctx: &mut Context,
+    ) -> Result<()> {
+        let transport: Arc<dyn Transport> = Arc::new(SomeTransport());
+        assert!(ctx.register_transport(transport.clone()).is_none());
+
+        let replaced = ctx.register_transport(Arc::new(SomeTransport())).unwrap();
+        assert_eq!(
+            Arc::as_ptr(&replaced) as *const (),
+            Arc::as_ptr(&transport) as *const ()
+        );
+        ctx.stop().await
+    }
+
     #[ockam_macros::test(crate = "crate")]
     async fn test_resolve_route(ctx: &mut Context) -> Result<()> {
         let transport = Arc::new(SomeTransport());
@@ -97,6 +608,72 @@ mod tests {
         ctx.stop().await
     }
 
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_resolve_route_partially(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(SomeTransport());
+        ctx.register_transport(transport.clone());
+        let known = transport.transport_type();
+        let unknown = TransportType::new(1);
+
+        // the first hop is resolved, the external address is kept
+        let result = ctx
+            .resolve_transport_route_partial(route![
+                "worker1",
+                (known, "address1"),
+                (unknown, "address2"),
+                "worker2"
+            ])
+            .await?;
+        assert_eq!(
+            result,
+            route![
+                "worker1",
+                (LOCAL, "address1"),
+                (unknown, "address2"),
+                "worker2"
+            ]
+        );
+
+        // the strict resolution fails for the same route
+        let result = ctx
+            .resolve_transport_route(route![(known, "address1"), (unknown, "address2")])
+            .await;
+        assert!(result.is_err());
+
+        // the resolution stops at the first hop, even if its transport is not registered
+        let route = route![(unknown, "address2"), (known, "address1")];
+        let result = ctx.resolve_transport_route_partial(route.clone()).await?;
+        assert_eq!(result, route);
+
+        // the errors of a registered transport are still reported
+        let transport = Arc::new(FailingTransport());
+        ctx.register_transport(transport.clone());
+        let result = ctx
+            .resolve_transport_route_partial(route![(transport.transport_type(), "address")])
+            .await;
+        assert!(result.is_err());
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_resolve_address(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(SomeTransport());
+        ctx.register_transport(transport.clone());
+
+        // resolve an address with a known transport
+        let result = ctx
+            .resolve_transport_address(Address::new(transport.transport_type(), "address"))
+            .await?;
+        assert_eq!(result, route![(LOCAL, "address")]);
+
+        // resolve an address with an unknown transport
+        let result = ctx
+            .resolve_transport_address(Address::new(TransportType::new(1), "address"))
+            .await;
+        assert!(result.is_err());
+        ctx.stop().await
+    }
+
     #[ockam_macros::test(crate = "crate")]
     async fn test_resolve_route_only_single_hop_is_allowed(ctx: &mut Context) -> Result<()> {
         let result = ctx
@@ -115,6 +692,280 @@ mod tests {
         ctx.stop().await
     }
 
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_resolve_route_reports_the_transport_error(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(FailingTransport());
+        ctx.register_transport(transport.clone());
+
+        // the error returned by the transport is reported, with its kind
+        let error = ctx
+            .resolve_transport_route(route![(transport.transport_type(), "address")])
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().kind, Kind::Unsupported);
+        assert!(error.to_string().contains(&format!(
+            "{} -> cannot resolve address",
+            transport.transport_type()
+        )));
+
+        // a transport which is not registered is reported
+        let error = ctx
+            .resolve_transport_route(route![(TransportType::new(1), "address")])
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().kind, Kind::NotFound);
+        assert!(error.to_string().contains(&format!(
+            "{} -> the transport is not registered",
+            TransportType::new(1)
+        )));
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_resolve_route_reuses_alive_routes(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(ReusableTransport::default());
+        ctx.register_transport(transport.clone());
+        let route = route![(transport.transport_type(), "address")];
+
+        // the second resolution reuses the first one
+        let first = ctx.resolve_transport_route(route.clone()).await?;
+        let second = ctx.resolve_transport_route(route.clone()).await?;
+        assert_eq!(first, second);
+        assert_eq!(transport.resolutions.load(Ordering::Relaxed), 1);
+
+        // once the route is dead it is evicted and the address is resolved again
+        transport.alive.store(false, Ordering::Relaxed);
+        let third = ctx.resolve_transport_route(route.clone()).await?;
+        assert_ne!(first, third);
+        assert_eq!(transport.resolutions.load(Ordering::Relaxed), 2);
+        assert!(ctx.resolved_transport_addresses.read().unwrap().is_empty());
+
+        // transports which don't opt in are never cached
+        let transport = Arc::new(SomeTransport());
+        ctx.register_transport(transport.clone());
+        ctx.resolve_transport_route(route![(transport.transport_type(), "address")])
+            .await?;
+        assert!(ctx.resolved_transport_addresses.read().unwrap().is_empty());
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_resolve_route_waiting_for_a_transport(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(SomeTransport());
+        let route = route![(transport.transport_type(), "address")];
+
+        // the resolution fails if the transport is not registered within the wait window
+        let result = ctx
+            .resolve_transport_route_waiting(route.clone(), Duration::from_millis(100))
+            .await;
+        assert!(result.is_err());
+        // and the waiter is removed
+        assert!(ctx.transport_registrations.read().unwrap().is_empty());
+
+        // the resolution succeeds once the transport is registered within the wait window
+        let child = ctx.async_try_clone().await?;
+        crate::tokio::spawn(async move {
+            crate::tokio::time::sleep(Duration::from_millis(100)).await;
+            child.register_transport(transport);
+        });
+        let result = ctx
+            .resolve_transport_route_waiting(route, Duration::from_secs(5))
+            .await?;
+        assert_eq!(result, route![(LOCAL, "address")]);
+        assert!(ctx.transport_registrations.read().unwrap().is_empty());
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_resolve_route_with_route_rewriters(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(SomeTransport());
+        ctx.register_transport(transport.clone());
+        let transport_type = transport.transport_type();
+
+        // the address A is rewritten to B before being resolved
+        ctx.register_route_rewriter(Arc::new(move |route: Route| {
+            Route::create(
+                route
+                    .iter()
+                    .map(|address| {
+                        if address == &Address::new(transport_type, "A") {
+                            Address::new(transport_type, "B")
+                        } else {
+                            address.clone()
+                        }
+                    })
+                    .collect(),
+            )
+        }));
+        let result = ctx
+            .resolve_transport_route(route![(transport_type, "A"), "worker"])
+            .await?;
+        assert_eq!(result, route![(LOCAL, "B"), "worker"]);
+
+        // the rewriters are applied in registration order
+        ctx.register_route_rewriter(Arc::new(move |route: Route| {
+            if route.next().ok() == Some(&Address::new(transport_type, "B")) {
+                route![(transport_type, "C")]
+            } else {
+                route
+            }
+        }));
+        let result = ctx
+            .resolve_transport_route(route![(transport_type, "A")])
+            .await?;
+        assert_eq!(result, route![(LOCAL, "C")]);
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test(crate = "crate")]
+    async fn test_resolve_route_with_the_default_flow_controls(ctx: &mut Context) -> Result<()> {
+        let transport = Arc::new(FlowControlledTransport());
+        let route = route![(transport.transport_type(), "address")];
+        let flow_controls = FlowControls::new();
+        ctx.register_transport_with_flow_controls(transport.clone(), flow_controls.clone());
+
+        // the registered flow controls are used when none is given
+        let resolved = ctx.resolve_transport_route_default(route.clone()).await?;
+        assert!(flow_controls
+            .find_flow_control_with_producer_address(resolved.next()?)
+            .is_some());
+
+        // other flow controls can be given for a single resolution
+        let other_flow_controls = FlowControls::new();
+        let resolved = ctx
+            .resolve_transport_route_with_flow_controls(route.clone(), &other_flow_controls)
+            .await?;
+        assert!(other_flow_controls
+            .find_flow_control_with_producer_address(resolved.next()?)
+            .is_some());
+
+        // once the transport is registered again without flow controls,
+        // its addresses are resolved as usual
+        ctx.register_transport(transport);
+        let resolved = ctx.resolve_transport_route_default(route).await?;
+        assert_eq!(resolved, route![(LOCAL, "address")]);
+        ctx.stop().await
+    }
+
+    #[tokio::test]
+    async fn test_resolve_route_with() -> Result<()> {
+        let transports: Vec<Arc<dyn Transport>> =
+            vec![Arc::new(SomeTransport()), Arc::new(FailingTransport())];
+
+        // the transport address is resolved by the transport of the same type
+        let result = resolve_route_with(
+            &transports,
+            route!["worker1", (TransportType::new(10), "address"), "worker2"],
+        )
+        .await?;
+        assert_eq!(result, route!["worker1", (LOCAL, "address"), "worker2"]);
+
+        // a local route is returned as it is, even without transports
+        let result = resolve_route_with(&[], route!["worker"]).await?;
+        assert_eq!(result, route!["worker"]);
+
+        // the transport errors are reported
+        let error = resolve_route_with(&transports, route![(TransportType::new(11), "address")])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("cannot resolve address"));
+
+        // an address without a transport can not be resolved
+        let error = resolve_route_with(&transports, route![(TransportType::new(1), "address")])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("the transport is not registered"));
+
+        // only one transport hop is allowed
+        let error = resolve_route_with(
+            &transports,
+            route![
+                (TransportType::new(10), "address1"),
+                (TransportType::new(10), "address2")
+            ],
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("only one transport hop is allowed in a route"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_route_spans() -> Result<()> {
+        let recorder = SpansRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let transports: Vec<Arc<dyn Transport>> =
+            vec![Arc::new(SomeTransport()), Arc::new(FailingTransport())];
+        resolve_route_with(&transports, route![(TransportType::new(10), "address")]).await?;
+        let result =
+            resolve_route_with(&transports, route![(TransportType::new(11), "address")]).await;
+        assert!(result.is_err());
+
+        // there is one span per transport resolution
+        let spans = recorder.spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            spans[0]["transport_type"],
+            TransportType::new(10).to_string()
+        );
+        assert_eq!(spans[0]["resolved"], "true");
+        assert!(spans[0].contains_key("duration_us"));
+        assert_eq!(
+            spans[1]["transport_type"],
+            TransportType::new(11).to_string()
+        );
+        assert_eq!(spans[1]["resolved"], "false");
+        assert!(spans[1].contains_key("duration_us"));
+        Ok(())
+    }
+
+    /// This layer records the fields of the transport resolution spans, in creation order
+    #[derive(Clone, Default)]
+    struct SpansRecorder {
+        spans: Arc<std::sync::Mutex<Vec<(Id, BTreeMap<String, String>)>>>,
+    }
+
+    impl SpansRecorder {
+        fn spans(&self) -> Vec<BTreeMap<String, String>> {
+            let spans = self.spans.lock().unwrap();
+            spans.iter().map(|(_, fields)| fields.clone()).collect()
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpansRecorder {
+        fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, _ctx: LayerContext<'_, S>) {
+            if attributes.metadata().name() == "resolve_transport_address" {
+                let mut fields = FieldsVisitor::default();
+                attributes.record(&mut fields);
+                self.spans.lock().unwrap().push((id.clone(), fields.0));
+            }
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: LayerContext<'_, S>) {
+            let mut spans = self.spans.lock().unwrap();
+            if let Some((_, fields)) = spans.iter_mut().find(|(span_id, _)| span_id == id) {
+                let mut visitor = FieldsVisitor::default();
+                values.record(&mut visitor);
+                fields.extend(visitor.0);
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct FieldsVisitor(BTreeMap<String, String>);
+
+    impl Visit for FieldsVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
     struct SomeTransport();
 
     #[async_trait]
@@ -128,4 +979,83 @@ mod tests {
             Ok(Address::new(LOCAL, address.address()))
         }
     }
+
+    struct FailingTransport();
+
+    #[async_trait]
+    impl Transport for FailingTransport {
+        fn transport_type(&self) -> TransportType {
+            TransportType::new(11)
+        }
+
+        async fn resolve_address(&self, address: Address) -> Result<Address> {
+            Err(Error::new(
+                Origin::Transport,
+                Kind::Unsupported,
+                format!("cannot resolve address {address}"),
+            ))
+        }
+    }
+
+    /// This transport registers the local address it resolves as a producer
+    /// in the flow controls it is given
+    struct FlowControlledTransport();
+
+    #[async_trait]
+    impl Transport for FlowControlledTransport {
+        fn transport_type(&self) -> TransportType {
+            TransportType::new(13)
+        }
+
+        async fn resolve_address(&self, address: Address) -> Result<Address> {
+            Ok(Address::new(LOCAL, address.address()))
+        }
+
+        async fn resolve_address_with_flow_controls(
+            &self,
+            address: Address,
+            flow_controls: &FlowControls,
+        ) -> Result<Address> {
+            let resolved = Address::new(LOCAL, format!("{}-producer", address.address()));
+            flow_controls.add_producer(
+                resolved.clone(),
+                &FlowControls::generate_flow_control_id(),
+                None,
+                vec![],
+            );
+            Ok(resolved)
+        }
+    }
+
+    /// This transport creates a new local address for each resolution
+    /// and reports its routes as alive until told otherwise
+    struct ReusableTransport {
+        resolutions: AtomicUsize,
+        alive: AtomicBool,
+    }
+
+    impl Default for ReusableTransport {
+        fn default() -> Self {
+            Self {
+                resolutions: AtomicUsize::new(0),
+                alive: AtomicBool::new(true),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Transport for ReusableTransport {
+        fn transport_type(&self) -> TransportType {
+            TransportType::new(12)
+        }
+
+        async fn resolve_address(&self, address: Address) -> Result<Address> {
+            let n = self.resolutions.fetch_add(1, Ordering::Relaxed);
+            Ok(Address::new(LOCAL, format!("{}-{n}", address.address())))
+        }
+
+        async fn is_route_alive(&self, _route: &Route) -> bool {
+            self.alive.load(Ordering::Relaxed)
+        }
+    }
 }
@@ -31,6 +31,11 @@ pub use ockam_executor::tokio;
 #[cfg(feature = "std")]
 pub use tokio;
 
+/// The subscriber used to set up the logging of a node, re-exported so that the log filter
+/// of a running node can be modified with the same version of the crate
+#[cfg(feature = "std")]
+pub use tracing_subscriber;
+
 /// Async Mutex and RwLock
 pub mod compat;
 
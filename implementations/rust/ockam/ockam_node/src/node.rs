@@ -64,6 +64,10 @@ impl NodeBuilder {
             ),
             None,
             Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
             &flow_controls,
         );
 
@@ -0,0 +1,3 @@
+-- This column stores the roles (or groups) of a user, as returned by the identity provider.
+-- The roles are stored as a JSON array of strings
+ALTER TABLE user ADD COLUMN roles TEXT NOT NULL DEFAULT '[]';
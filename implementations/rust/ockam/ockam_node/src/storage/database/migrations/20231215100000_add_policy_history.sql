@@ -0,0 +1,9 @@
+-- This table stores the policies which have been deleted, with their deletion time,
+-- in order to keep an audit trail of the deleted policies
+CREATE TABLE policy_history
+(
+    resource   TEXT    NOT NULL, -- resource name
+    action     TEXT    NOT NULL, -- action name
+    expression BLOB    NOT NULL, -- encoded expression which was deleted
+    deleted_at INTEGER NOT NULL  -- deletion time, as a number of seconds since the Unix epoch
+);
@@ -0,0 +1,6 @@
+-- This table stores the name of the last project successfully used by a command.
+-- It is used to expand the project routes when no default project is set
+CREATE TABLE last_used_project
+(
+    project_name TEXT NOT NULL -- Project name
+);
@@ -0,0 +1,3 @@
+-- A policy can now expire. Once the expiration time has passed, the policy is not returned anymore
+-- and it can be removed from the table. A NULL value means that the policy never expires
+ALTER TABLE policy ADD COLUMN expires_at INTEGER;
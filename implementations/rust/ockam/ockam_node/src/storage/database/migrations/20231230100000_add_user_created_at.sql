@@ -0,0 +1,3 @@
+-- The creation time of a user is now stored, in seconds since the Unix epoch.
+-- The users created before this migration get a creation time of 0
+ALTER TABLE user ADD COLUMN created_at INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- The users now belong to a tenant, and there is one default user per tenant.
+-- The users created before this migration belong to the 'default' tenant
+ALTER TABLE user ADD COLUMN tenant TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,8 @@
+-- This table stores the controller used by the enrollment commands when it is
+-- not the default Orchestrator controller.
+-- It contains at most one row
+CREATE TABLE controller
+(
+    multiaddr  TEXT NOT NULL, -- Address of the controller
+    identifier TEXT           -- Identifier of the controller, if different from the default one
+);
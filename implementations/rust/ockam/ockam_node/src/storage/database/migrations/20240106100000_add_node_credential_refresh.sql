@@ -0,0 +1,7 @@
+-- This table stores how long before its expiration the credential of a node is refreshed,
+-- for the nodes created with a credential refresh, so that they can be restarted with it
+CREATE TABLE node_credential_refresh
+(
+    node_name TEXT PRIMARY KEY, -- Node name
+    skew      INTEGER NOT NULL  -- Amount of time, in seconds, before the credential expiration when it gets refreshed
+);
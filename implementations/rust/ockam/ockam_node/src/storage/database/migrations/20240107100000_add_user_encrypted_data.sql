@@ -0,0 +1,3 @@
+-- The personal information of a user can be encrypted at rest.
+-- In that case it is stored in this column and the plain text columns are left empty
+ALTER TABLE user ADD COLUMN encrypted_data BLOB;
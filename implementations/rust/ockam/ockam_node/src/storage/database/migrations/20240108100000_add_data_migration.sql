@@ -0,0 +1,7 @@
+-- The data migrations which are done in Rust, rather than in SQL, are recorded in this table
+-- once they have been applied, so that they are only applied once
+CREATE TABLE data_migration
+(
+    name       TEXT PRIMARY KEY NOT NULL, -- name of the migration
+    applied_at INTEGER          NOT NULL  -- application time, as a number of seconds since the Unix epoch
+);
@@ -0,0 +1,8 @@
+-- When the personal information of the users is encrypted at rest, the encryption keys are derived
+-- from a static X25519 key of the vault stored in the database.
+-- This table stores the handle of that key so that the same key is used every time the database is opened
+CREATE TABLE user_encryption_key
+(
+    id     INTEGER PRIMARY KEY CHECK (id = 1), -- There is a single key
+    handle BLOB NOT NULL                      -- Handle of the X25519 secret key in the x25519_secret table
+);
@@ -5,7 +5,7 @@ use std::ops::Deref;
 use std::path::Path;
 
 use ockam_core::errcode::{Kind, Origin};
-use sqlx::{ConnectOptions, SqlitePool};
+use sqlx::{ConnectOptions, Connection, SqlitePool};
 use tokio_retry::strategy::{jitter, FixedInterval};
 use tokio_retry::Retry;
 use tracing::debug;
@@ -71,6 +71,82 @@ impl SqlxDatabase {
         Ok(Arc::new(db))
     }
 
+    /// Copy the contents of this database, for example an in-memory database, to a new
+    /// database file. Return an error if the file already exists
+    pub async fn dump_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        path.parent()
+            .map(std::fs::create_dir_all)
+            .transpose()
+            .map_err(|e| Error::new(Origin::Api, Kind::Io, e.to_string()))?;
+        debug!("dump the database to {}", path.display());
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&self.pool)
+            .await
+            .void()
+    }
+
+    /// Create an in-memory database with the contents of a database file,
+    /// for example a file created with [`Self::dump_to_file`].
+    /// The contents are copied in a single transaction, so the file is either fully loaded or not at all.
+    /// The database is migrated if the file was created with an older schema
+    pub async fn load_from_file(path: impl AsRef<Path>) -> Result<Arc<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("the database file {} does not exist", path.display()),
+            ));
+        }
+        debug!("load an in memory database from {}", path.display());
+        let pool = Self::create_in_memory_connection_pool().await?;
+
+        // the attached database is only visible to the connection attaching it
+        let mut connection = pool.acquire().await.into_core()?;
+        sqlx::query("ATTACH DATABASE ? AS source")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&mut *connection)
+            .await
+            .void()?;
+
+        // a database can not be attached in a transaction, but the copy is done in one.
+        // It is rolled back if any statement fails and all the tables are read from the same snapshot
+        let mut transaction = connection.begin().await.into_core()?;
+        // the tables must be created before their indexes, triggers and views
+        let schema: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT type, name, sql FROM source.sqlite_master \
+             WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' \
+             ORDER BY type <> 'table', rowid",
+        )
+        .fetch_all(&mut *transaction)
+        .await
+        .into_core()?;
+        for (object_type, name, sql) in schema {
+            sqlx::query(&sql).execute(&mut *transaction).await.void()?;
+            if object_type == "table" {
+                sqlx::query(&format!(
+                    "INSERT INTO main.\"{name}\" SELECT * FROM source.\"{name}\""
+                ))
+                .execute(&mut *transaction)
+                .await
+                .void()?;
+            }
+        }
+        transaction.commit().await.void()?;
+
+        sqlx::query("DETACH DATABASE source")
+            .execute(&mut *connection)
+            .await
+            .void()?;
+        drop(connection);
+
+        let db = SqlxDatabase { pool };
+        db.migrate().await?;
+        Ok(Arc::new(db))
+    }
+
     async fn create_at(path: &Path) -> Result<Self> {
         // Creates database file if it doesn't exist
         let pool = Self::create_connection_pool(path).await?;
@@ -107,6 +183,30 @@ impl SqlxDatabase {
             .map_err(Self::map_migrate_err)
     }
 
+    /// Check that a table contains all the expected columns.
+    /// This returns an error naming the first missing column if the database schema
+    /// is older than the schema expected by the code and needs to be migrated
+    pub async fn check_columns(&self, table: &str, columns: &[&str]) -> Result<()> {
+        let existing: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await
+            .into_core()?;
+        match columns
+            .iter()
+            .find(|c| !existing.iter().any(|e| e == *c))
+        {
+            Some(missing) => Err(Error::new(
+                Origin::Application,
+                Kind::Io,
+                format!(
+                    "the database needs a migration: the column `{missing}` is missing from the table `{table}`"
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
     /// Map a sqlx error into an ockam error
     pub fn map_sql_err(err: sqlx::Error) -> Error {
         Error::new(Origin::Application, Kind::Io, err)
@@ -207,6 +307,78 @@ mod tests {
         Ok(())
     }
 
+    /// This test checks that a missing column is reported with its name
+    #[tokio::test]
+    async fn test_check_columns() -> Result<()> {
+        let db = SqlxDatabase::in_memory("check columns").await?;
+        db.check_columns("identity", &["identifier", "change_history"])
+            .await?;
+
+        sqlx::query("ALTER TABLE identity DROP COLUMN change_history")
+            .execute(&db.pool)
+            .await
+            .into_core()?;
+        let error = db
+            .check_columns("identity", &["identifier", "change_history"])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("needs a migration"));
+        assert!(error.to_string().contains("`change_history`"));
+        Ok(())
+    }
+
+    /// This test checks that an in-memory database can be dumped to a file and loaded back
+    #[tokio::test]
+    async fn test_dump_and_load_in_memory_database() -> Result<()> {
+        let db = SqlxDatabase::in_memory("dump").await?;
+        insert_identity(&db).await?;
+        sqlx::query("INSERT INTO vault VALUES (?1, ?2, ?3, ?4)")
+            .bind("vault")
+            .bind("path")
+            .bind(true.to_sql())
+            .bind(false.to_sql())
+            .execute(&db.pool)
+            .await
+            .void()?;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("dump.sqlite3");
+        db.dump_to_file(&path).await?;
+        // an existing file is not overwritten
+        assert!(db.dump_to_file(&path).await.is_err());
+
+        let loaded = SqlxDatabase::load_from_file(&path).await?;
+        let identifiers: Vec<IdentifierRow> = sqlx::query_as("SELECT identifier FROM identity")
+            .fetch_all(&loaded.pool)
+            .await
+            .into_core()?;
+        assert_eq!(
+            identifiers,
+            vec![IdentifierRow(
+                "Ifa804b7fca12a19eed206ae180b5b576860ae651".into()
+            )]
+        );
+
+        let vaults: Vec<(String, String)> = sqlx::query_as("SELECT name, path FROM vault")
+            .fetch_all(&loaded.pool)
+            .await
+            .into_core()?;
+        assert_eq!(vaults, vec![("vault".into(), "path".into())]);
+
+        // a missing file can not be loaded
+        assert!(
+            SqlxDatabase::load_from_file(directory.path().join("missing"))
+                .await
+                .is_err()
+        );
+
+        // a file which is not a database can not be loaded
+        let invalid = directory.path().join("invalid.sqlite3");
+        std::fs::write(&invalid, "not a database").unwrap();
+        assert!(SqlxDatabase::load_from_file(&invalid).await.is_err());
+        Ok(())
+    }
+
     /// HELPERS
     async fn insert_identity(db: &SqlxDatabase) -> Result<SqliteQueryResult> {
         sqlx::query("INSERT INTO identity VALUES (?1, ?2)")
@@ -1,5 +1,6 @@
 use ockam_core::compat::boxed::Box;
-use ockam_core::{async_trait, Address, Result, TransportType};
+use ockam_core::flow_control::FlowControls;
+use ockam_core::{async_trait, Address, Result, Route, TransportType};
 
 /// Generic representation of a Transport
 /// At minimum, a Transport must be able
@@ -13,4 +14,22 @@ pub trait Transport: Send + Sync + 'static {
     /// Instantiate transport workers for in order to communicate with a remote address
     /// and return the local address of the transport worker
     async fn resolve_address(&self, address: Address) -> Result<Address>;
+
+    /// Resolve an address like `resolve_address`, registering the flow control
+    /// of the created transport workers in `flow_controls`.
+    /// By default the flow controls are ignored and the address is resolved with `resolve_address`
+    async fn resolve_address_with_flow_controls(
+        &self,
+        address: Address,
+        _flow_controls: &FlowControls,
+    ) -> Result<Address> {
+        self.resolve_address(address).await
+    }
+
+    /// Return true if the connection behind a route previously returned by `resolve_address`
+    /// is still alive and can be reused.
+    /// Transports returning `true` opt in the caching of their resolved addresses by the node.
+    async fn is_route_alive(&self, _route: &Route) -> bool {
+        false
+    }
 }
@@ -24,8 +24,11 @@ mod registry;
 mod transport;
 
 use ockam_core::TransportType;
-pub use options::{TcpConnectionOptions, TcpListenerOptions};
-pub use portal::{PortalInternalMessage, PortalMessage, MAX_PAYLOAD_SIZE};
+pub use options::{TcpConnectionOptions, TcpKeepaliveOptions, TcpListenerOptions};
+pub use portal::{
+    IpCidr, PortalInternalMessage, PortalMessage, ProxyProtocolVersion, Socks5Target,
+    TargetAuthorization, MAX_PAYLOAD_SIZE,
+};
 pub use registry::*;
 pub use transport::common::*;
 pub use transport::*;
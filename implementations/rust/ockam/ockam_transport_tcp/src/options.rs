@@ -1,7 +1,13 @@
 use crate::workers::Addresses;
+use cfg_if::cfg_if;
+use core::time::Duration;
+use ockam_core::compat::net::SocketAddr;
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
-use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl};
+use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl, Result};
+use ockam_transport_core::TransportError;
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::TcpStream;
 
 pub(crate) struct TcpConnectionAccessControl {
     pub sender_incoming_access_control: Arc<dyn IncomingAccessControl>,
@@ -13,6 +19,8 @@ pub(crate) struct TcpConnectionAccessControl {
 pub struct TcpConnectionOptions {
     pub(super) consumer: Vec<FlowControlId>,
     pub(crate) flow_control_id: FlowControlId,
+    pub(crate) keepalive: TcpKeepaliveOptions,
+    pub(crate) bind_address: Option<SocketAddr>,
 }
 
 impl TcpConnectionOptions {
@@ -22,9 +30,25 @@ impl TcpConnectionOptions {
         Self {
             consumer: vec![],
             flow_control_id: FlowControls::generate_flow_control_id(),
+            keepalive: TcpKeepaliveOptions::default(),
+            bind_address: None,
         }
     }
 
+    /// Set the keepalive configuration of the connection socket
+    pub fn with_keepalive(mut self, keepalive: TcpKeepaliveOptions) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    /// Bind the local end of the connection to a specific address, for example to
+    /// select the network interface used by the connection.
+    /// If the port is 0, the port is chosen by the OS
+    pub fn with_bind_address(mut self, bind_address: SocketAddr) -> Self {
+        self.bind_address = Some(bind_address);
+        self
+    }
+
     /// Mark that this Connection is a Consumer for to the given [`FlowControlId`]
     pub fn as_consumer(mut self, id: &FlowControlId) -> Self {
         self.consumer.push(id.clone());
@@ -131,3 +155,106 @@ impl TcpListenerOptions {
         }
     }
 }
+
+/// TCP keepalive configuration of a socket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TcpKeepaliveOptions {
+    time: Duration,
+    interval: Option<Duration>,
+    retries: Option<u32>,
+}
+
+impl TcpKeepaliveOptions {
+    /// Send keepalive probes once the connection has been idle for `time`.
+    /// The interval between probes and their number are the OS defaults unless they are set
+    pub fn new(time: Duration) -> Self {
+        Self {
+            time,
+            interval: None,
+            retries: None,
+        }
+    }
+
+    /// Set the interval between two keepalive probes
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Set the number of unacknowledged probes after which the connection is dropped.
+    /// This setting is only supported on unix platforms
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Idle time before the first keepalive probe
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Interval between two keepalive probes
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Number of unacknowledged probes after which the connection is dropped
+    pub fn retries(&self) -> Option<u32> {
+        self.retries
+    }
+
+    /// Enable SO_KEEPALIVE on a socket and set its keepalive timers
+    pub(crate) fn apply(&self, stream: &TcpStream) -> Result<()> {
+        let mut keepalive = TcpKeepalive::new().with_time(self.time);
+        if let Some(interval) = self.interval {
+            keepalive = keepalive.with_interval(interval);
+        }
+        cfg_if! {
+            if #[cfg(unix)] {
+                if let Some(retries) = self.retries {
+                    keepalive = keepalive.with_retries(retries);
+                }
+            }
+        }
+        SockRef::from(stream)
+            .set_tcp_keepalive(&keepalive)
+            .map_err(TransportError::from)?;
+        Ok(())
+    }
+}
+
+impl Default for TcpKeepaliveOptions {
+    /// Keepalive of the outgoing TCP connections
+    fn default() -> Self {
+        Self::new(Duration::from_secs(300))
+            .with_interval(Duration::from_secs(75))
+            .with_retries(2)
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "macos")))]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_keepalive_is_applied_to_a_socket() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let socket = SockRef::from(&stream);
+        assert!(!socket.keepalive().unwrap());
+
+        TcpKeepaliveOptions::new(Duration::from_secs(42))
+            .with_interval(Duration::from_secs(7))
+            .with_retries(3)
+            .apply(&stream)?;
+
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.keepalive_time().unwrap(), Duration::from_secs(42));
+        assert_eq!(socket.keepalive_interval().unwrap(), Duration::from_secs(7));
+        assert_eq!(socket.keepalive_retries().unwrap(), 3);
+        Ok(())
+    }
+}
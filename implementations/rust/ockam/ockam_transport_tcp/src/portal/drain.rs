@@ -0,0 +1,56 @@
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+/// Connections accepted by an inlet, shared by the inlet listener and its portals.
+///
+/// An inlet is drained by stopping its listener, so that no new connections are accepted,
+/// and by letting its open connections finish. The connections which are still open at the
+/// end of the drain timeout are closed.
+pub(crate) struct InletConnections {
+    /// Number of open connections
+    open: watch::Sender<usize>,
+    /// Set to true when the open connections must be closed
+    close: watch::Sender<bool>,
+}
+
+/// Signals a portal receiver that its connection must be closed
+pub(crate) type CloseReceiver = watch::Receiver<bool>;
+
+impl InletConnections {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            open: watch::channel(0).0,
+            close: watch::channel(false).0,
+        })
+    }
+
+    /// Register a new connection
+    pub(crate) fn opened(&self) {
+        self.open.send_modify(|open| *open += 1);
+    }
+
+    /// Unregister a connection which has been closed
+    pub(crate) fn closed(&self) {
+        self.open.send_modify(|open| *open = open.saturating_sub(1));
+    }
+
+    /// Return a receiver notified when the connections must be closed
+    pub(crate) fn close_receiver(&self) -> CloseReceiver {
+        self.close.subscribe()
+    }
+
+    /// Wait for the open connections to be closed, for at most `drain_timeout`.
+    /// The connections which are still open after that are closed, and their number is returned
+    pub(crate) async fn drain(&self, drain_timeout: Duration) -> usize {
+        let mut open = self.open.subscribe();
+        // the sender is kept by self, so waiting can't fail
+        let _ = timeout(drain_timeout, open.wait_for(|open| *open == 0)).await;
+        let remaining = *self.open.borrow();
+        if remaining > 0 {
+            self.close.send_replace(true);
+        }
+        remaining
+    }
+}
@@ -0,0 +1,134 @@
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::{async_trait, Address, OutgoingAccessControl, RelayMessage, Result, Route};
+use tokio::sync::watch;
+use tokio::time::{sleep_until, Instant};
+
+/// Route from an inlet to its outlet listener, shared by the inlet listener and its portals.
+///
+/// The route is unset while the inlet is held, and set again with the new route to the outlet
+/// listener when the inlet is resumed.
+pub(crate) type OutletRouteSender = watch::Sender<Option<Route>>;
+
+/// Receiving side of an [`OutletRouteSender`]
+pub(crate) type OutletRouteReceiver = watch::Receiver<Option<Route>>;
+
+/// Configuration of an inlet portal which holds its client connection while the inlet reconnects
+pub(crate) struct InletHold {
+    /// Maximum time a connection is held before being closed
+    pub(crate) duration: Duration,
+    /// Route to the outlet listener of the inlet
+    pub(crate) outlet_route: OutletRouteReceiver,
+}
+
+/// Event observed by a portal receiver holding its connection
+pub(crate) enum HoldEvent {
+    /// The inlet is held, the data read from the client must not be sent anymore
+    Held,
+    /// The inlet has been resumed with a new route to its outlet listener
+    Reconnecting(Route),
+    /// The portal is connected to a new outlet
+    Reconnected(Route),
+    /// The portal has not been reconnected before the end of the hold duration
+    Expired,
+    /// Nothing needs to be done
+    Unchanged,
+}
+
+/// State of a portal receiver holding its connection while the inlet reconnects
+pub(crate) struct ReceiverHold {
+    duration: Duration,
+    outlet_route: OutletRouteReceiver,
+    onward_route: watch::Receiver<Route>,
+    held_since: Option<Instant>,
+    is_listener_running: bool,
+    is_worker_running: bool,
+}
+
+impl ReceiverHold {
+    /// Create a new hold state for a portal receiver.
+    /// `onward_route` is updated by the portal worker once it is connected to a new outlet
+    pub(crate) fn new(hold: InletHold, onward_route: watch::Receiver<Route>) -> Self {
+        Self {
+            duration: hold.duration,
+            outlet_route: hold.outlet_route,
+            onward_route,
+            held_since: None,
+            is_listener_running: true,
+            is_worker_running: true,
+        }
+    }
+
+    /// Return true if the data read from the client must not be sent to the outlet
+    pub(crate) fn is_held(&self) -> bool {
+        self.held_since.is_some()
+    }
+
+    /// Wait for the next change of the outlet route or of the portal onward route,
+    /// or for the end of the hold duration
+    pub(crate) async fn next_event(&mut self) -> HoldEvent {
+        let deadline = self.held_since.map(|since| since + self.duration);
+        tokio::select! {
+            changed = self.outlet_route.changed(), if self.is_listener_running => {
+                if changed.is_err() {
+                    self.is_listener_running = false;
+                    return HoldEvent::Unchanged;
+                }
+                if self.held_since.is_none() {
+                    self.held_since = Some(Instant::now());
+                }
+                let outlet_route = self.outlet_route.borrow_and_update().clone();
+                match outlet_route {
+                    Some(outlet_route) => HoldEvent::Reconnecting(outlet_route),
+                    None => HoldEvent::Held,
+                }
+            }
+            changed = self.onward_route.changed(), if self.is_worker_running => {
+                if changed.is_err() {
+                    self.is_worker_running = false;
+                    return HoldEvent::Unchanged;
+                }
+                self.held_since = None;
+                let onward_route = self.onward_route.borrow_and_update().clone();
+                HoldEvent::Reconnected(onward_route)
+            }
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                HoldEvent::Expired
+            }
+            else => core::future::pending().await,
+        }
+    }
+}
+
+/// Outgoing access control of a portal receiver holding its connection.
+///
+/// It allows messages to the next hop of the current onward route of the portal, which
+/// changes when the portal is reconnected, and to the portal worker.
+#[derive(Debug)]
+pub(crate) struct AllowPortalOnwardRoute {
+    worker: Address,
+    onward_route: watch::Receiver<Route>,
+}
+
+impl AllowPortalOnwardRoute {
+    /// Create a new access control for the receiver of a given portal worker
+    pub(crate) fn new(worker: Address, onward_route: watch::Receiver<Route>) -> Self {
+        Self {
+            worker,
+            onward_route,
+        }
+    }
+}
+
+#[async_trait]
+impl OutgoingAccessControl for AllowPortalOnwardRoute {
+    async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
+        let onward_route = relay_msg.onward_route();
+        let next = onward_route.next()?;
+        let is_onward_route = self.onward_route.borrow().next().ok() == Some(next);
+        if next == &self.worker || is_onward_route {
+            return ockam_core::allow();
+        }
+        ockam_core::deny()
+    }
+}
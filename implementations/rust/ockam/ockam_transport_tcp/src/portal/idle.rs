@@ -0,0 +1,44 @@
+use core::time::Duration;
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+/// Notifies the portal receiver that some data has been written to the client connection
+pub(crate) type ActivitySender = watch::Sender<()>;
+
+/// Idle timeout of a portal connection.
+///
+/// The portal receiver observes the data read from the client connection itself, and
+/// is notified by the portal worker when some data is written to the client connection
+pub(crate) struct IdleTimeout {
+    timeout: Duration,
+    activity: watch::Receiver<()>,
+    is_worker_running: bool,
+}
+
+impl IdleTimeout {
+    /// Create a new idle timeout and the sender used to signal some activity on the connection
+    pub(crate) fn new(timeout: Duration) -> (Self, ActivitySender) {
+        let (sender, receiver) = watch::channel(());
+        let idle_timeout = Self {
+            timeout,
+            activity: receiver,
+            is_worker_running: true,
+        };
+        (idle_timeout, sender)
+    }
+
+    /// Wait until either some data is written to the connection, in which case false is returned,
+    /// or until no data has been written for the duration of the timeout, in which case true is
+    /// returned
+    pub(crate) async fn wait_until_idle(&mut self) -> bool {
+        tokio::select! {
+            changed = self.activity.changed(), if self.is_worker_running => {
+                if changed.is_err() {
+                    self.is_worker_running = false;
+                }
+                false
+            }
+            _ = sleep(self.timeout) => true,
+        }
+    }
+}
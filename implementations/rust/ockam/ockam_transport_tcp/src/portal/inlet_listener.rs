@@ -1,12 +1,22 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalWorker, TcpInletOptions, TcpRegistry};
+use crate::portal::{
+    ClientConnection, ConnectionPermit, InletConnections, InletHold, IpCidr, OutletRouteReceiver,
+    PortalOptions, PrewarmedPortals,
+};
+use crate::{
+    portal::TcpPortalWorker, PortalInternalMessage, TcpInletOptions, TcpRegistry,
+    DEFAULT_INLET_BUFFER_SIZE,
+};
 use ockam_core::compat::net::SocketAddr;
-use ockam_core::{async_trait, compat::boxed::Box};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, compat::boxed::Box, route, DenyAll, OutgoingAccessControl};
 use ockam_core::{Address, Processor, Result, Route};
-use ockam_node::Context;
+use ockam_node::{Context, ProcessorBuilder};
 use ockam_transport_core::TransportError;
-use tokio::net::TcpListener;
-use tracing::{debug, error};
+use socket2::SockRef;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Semaphore};
+use tracing::{debug, error, warn, Instrument, Span};
 
 /// A TCP Portal Inlet listen processor
 ///
@@ -16,22 +26,44 @@ use tracing::{debug, error};
 pub(crate) struct TcpInletListenProcessor {
     registry: TcpRegistry,
     inner: TcpListener,
-    outlet_listener_route: Route,
+    outlet_listener_route: OutletRouteReceiver,
     options: TcpInletOptions,
+    connections: Option<Arc<Semaphore>>,
+    prewarmed: Option<PrewarmedPortals>,
+    inlet_connections: Arc<InletConnections>,
+    span: Span,
+}
+
+/// Portal used for a client connection accepted by an inlet listener
+enum InletPortal {
+    /// A ready prewarmed portal, with its internal address
+    Prewarmed(PrewarmedPortals, Address),
+    /// A new portal, counted against the maximum number of connections of the inlet
+    New(Option<ConnectionPermit>),
 }
 
 impl TcpInletListenProcessor {
     pub fn new(
         registry: TcpRegistry,
         inner: TcpListener,
-        outlet_listener_route: Route,
+        outlet_listener_route: OutletRouteReceiver,
         options: TcpInletOptions,
+        prewarmed: Option<PrewarmedPortals>,
+        inlet_connections: Arc<InletConnections>,
     ) -> Self {
+        let connections = options
+            .max_connections
+            .map(|max_connections| Arc::new(Semaphore::new(max_connections)));
+        let span = options.span();
         Self {
             registry,
             inner,
             outlet_listener_route,
             options,
+            connections,
+            prewarmed,
+            inlet_connections,
+            span,
         }
     }
 
@@ -39,7 +71,7 @@ impl TcpInletListenProcessor {
     pub(crate) async fn start(
         ctx: &Context,
         registry: TcpRegistry,
-        outlet_listener_route: Route,
+        outlet_listener_route: Option<Route>,
         addr: SocketAddr,
         options: TcpInletOptions,
     ) -> Result<(SocketAddr, Address)> {
@@ -54,13 +86,175 @@ impl TcpInletListenProcessor {
             }
         };
         let socket_addr = inner.local_addr().map_err(TransportError::from)?;
-        let processor = Self::new(registry, inner, outlet_listener_route, options);
+        let (route_sender, route_receiver) = watch::channel(outlet_listener_route);
+
+        // The listener only sends messages to attach the client connections to its
+        // prewarmed portals
+        let prewarm_pool_size = options.prewarm_pool_size();
+        let prewarmed = (prewarm_pool_size > 0)
+            .then(|| PrewarmedPortals::new(processor_address.clone(), prewarm_pool_size));
+        let outgoing_access_control: Arc<dyn OutgoingAccessControl> = match &prewarmed {
+            Some(prewarmed) => Arc::new(prewarmed.clone()),
+            None => Arc::new(DenyAll),
+        };
+        let inlet_connections = InletConnections::new();
+        let processor = Self::new(
+            registry.clone(),
+            inner,
+            route_receiver,
+            options,
+            prewarmed.clone(),
+            inlet_connections.clone(),
+        );
 
-        ctx.start_processor(processor_address.clone(), processor)
+        ProcessorBuilder::new(processor)
+            .with_address(processor_address.clone())
+            .with_outgoing_access_control_arc(outgoing_access_control)
+            .start(ctx)
             .await?;
+        registry.add_inlet_outlet_route(&processor_address, route_sender);
+        registry.add_inlet_connections(&processor_address, inlet_connections);
+        if let Some(prewarmed) = prewarmed {
+            registry.add_prewarmed_portals(&processor_address, prewarmed);
+        }
 
         Ok((socket_addr, processor_address))
     }
+
+    /// Return the current route to the outlet listener, waiting for the inlet to be resumed
+    /// if it is held. Return None if the route can not be updated anymore
+    async fn current_outlet_listener_route(&mut self) -> Option<Route> {
+        loop {
+            let outlet_listener_route = self.outlet_listener_route.borrow_and_update().clone();
+            if outlet_listener_route.is_some() {
+                return outlet_listener_route;
+            }
+            if self.outlet_listener_route.changed().await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// Accept a client connection. An inlet with prewarmed portals also starts the portals
+    /// missing from its pool when one of them is stopped, or when its route to the outlet
+    /// listener changes. Return None when no connection has been accepted
+    async fn accept(&mut self, ctx: &Context) -> Result<Option<(TcpStream, SocketAddr)>> {
+        let prewarmed = match &self.prewarmed {
+            Some(prewarmed) => prewarmed.clone(),
+            None => {
+                let accepted = self.inner.accept().await.map_err(TransportError::from)?;
+                return Ok(Some(accepted));
+            }
+        };
+        tokio::select! {
+            accepted = self.inner.accept() => {
+                return Ok(Some(accepted.map_err(TransportError::from)?));
+            }
+            _ = prewarmed.changed() => {}
+            // until the route can't be updated anymore
+            _ = self.outlet_listener_route.changed(),
+                if self.outlet_listener_route.has_changed().is_ok() => {}
+        }
+        self.prewarm(ctx).await?;
+        Ok(None)
+    }
+
+    /// Start the portals missing from the pool of prewarmed portals, within the maximum number
+    /// of connections of the inlet. The ready portals connected with a previous route to the
+    /// outlet listener are stopped
+    async fn prewarm(&self, ctx: &Context) -> Result<()> {
+        let prewarmed = match &self.prewarmed {
+            Some(prewarmed) => prewarmed.clone(),
+            None => return Ok(()),
+        };
+        // No portal can be connected while the inlet is held, or before it has a route
+        // to its outlet listener
+        let outlet_listener_route = match self.outlet_listener_route.borrow().clone() {
+            Some(outlet_listener_route) if !outlet_listener_route.is_empty() => {
+                outlet_listener_route
+            }
+            _ => return Ok(()),
+        };
+
+        for internal in prewarmed.take_stale(&outlet_listener_route) {
+            ctx.send(route![internal], PortalInternalMessage::Disconnect)
+                .await?;
+        }
+
+        let listen_addr = self.inner.local_addr().map_err(TransportError::from)?;
+        for _ in 0..prewarmed.missing() {
+            let connection_permit = match &self.connections {
+                Some(connections) => match connections.clone().try_acquire_owned() {
+                    Ok(permit) => Some(ConnectionPermit::new(permit, Some(prewarmed.clone()))),
+                    Err(_) => break,
+                },
+                None => None,
+            };
+
+            let addresses = Addresses::generate(PortalType::Inlet);
+            TcpInletOptions::setup_flow_control(
+                ctx.flow_controls(),
+                &addresses,
+                outlet_listener_route.next()?,
+            );
+            prewarmed.add(&addresses.internal, outlet_listener_route.clone());
+            let options = PortalOptions::default()
+                .with_idle_timeout(self.options.idle_timeout)
+                .with_connection_permit(connection_permit)
+                .with_prewarmed(prewarmed.clone())
+                .with_buffer_size(self.options.buffer_size)
+                .with_span(self.span.clone());
+            TcpPortalWorker::start_prewarmed_inlet(
+                ctx,
+                self.registry.clone(),
+                listen_addr,
+                outlet_listener_route.clone(),
+                addresses,
+                self.options.incoming_access_control.clone(),
+                options,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Return a ready prewarmed portal, or wait until a new portal can be started without
+    /// exceeding the maximum number of connections of the inlet
+    async fn next_portal(&self, outlet_listener_route: &Route) -> Result<InletPortal> {
+        loop {
+            if let Some(prewarmed) = &self.prewarmed {
+                if let Some(internal) = prewarmed.take_ready(outlet_listener_route) {
+                    return Ok(InletPortal::Prewarmed(prewarmed.clone(), internal));
+                }
+            }
+            let connections = match &self.connections {
+                Some(connections) => connections.clone(),
+                None => return Ok(InletPortal::New(None)),
+            };
+            match &self.prewarmed {
+                // A prewarmed portal may become ready before a connection is closed
+                Some(prewarmed) => {
+                    tokio::select! {
+                        permit = connections.acquire_owned() => {
+                            let permit = permit.map_err(|_| TransportError::PortalInvalidState)?;
+                            let connection_permit =
+                                ConnectionPermit::new(permit, Some(prewarmed.clone()));
+                            return Ok(InletPortal::New(Some(connection_permit)));
+                        }
+                        _ = prewarmed.changed() => {}
+                    }
+                }
+                None => {
+                    let permit = connections
+                        .acquire_owned()
+                        .await
+                        .map_err(|_| TransportError::PortalInvalidState)?;
+                    return Ok(InletPortal::New(Some(ConnectionPermit::new(permit, None))));
+                }
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -69,6 +263,7 @@ impl Processor for TcpInletListenProcessor {
 
     async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
         self.registry.add_inlet_listener_processor(&ctx.address());
+        self.prewarm(ctx).await?;
 
         Ok(())
     }
@@ -76,31 +271,128 @@ impl Processor for TcpInletListenProcessor {
     async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
         self.registry
             .remove_inlet_listener_processor(&ctx.address());
+        self.registry.remove_inlet_outlet_route(&ctx.address());
+        self.registry.remove_inlet_connections(&ctx.address());
+        self.registry.remove_prewarmed_portals(&ctx.address());
+
+        // The prewarmed portals can't be used without the listener. The portals
+        // with a client connection keep running until the connection is closed
+        if let Some(prewarmed) = &self.prewarmed {
+            for internal in prewarmed.unattached() {
+                ctx.send(route![internal], PortalInternalMessage::Disconnect)
+                    .await?;
+            }
+        }
 
         Ok(())
     }
 
     async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
-        let addresses = Addresses::generate(PortalType::Inlet);
-        let outlet_listener_route = self.outlet_listener_route.clone();
+        let span = self.span.clone();
+        self.accept_connection(ctx).instrument(span).await
+    }
+}
 
-        self.options.setup_flow_control(
-            ctx.flow_controls(),
-            &addresses,
-            outlet_listener_route.next()?,
-        );
+impl TcpInletListenProcessor {
+    /// Accept a client connection and start a portal for it.
+    /// Return false if the inlet can not accept connections anymore
+    async fn accept_connection(&mut self, ctx: &mut Context) -> Result<bool> {
+        let (stream, peer) = match self.accept(ctx).await? {
+            Some(accepted) => accepted,
+            None => return Ok(true),
+        };
+        if !IpCidr::any_contains(&self.options.allowed_sources, &peer.ip()) {
+            // the connection is closed when the stream is dropped
+            debug!(%peer, "rejected a client connection from a source which is not allowed");
+            return Ok(true);
+        }
+        debug!(%peer, "accepted a client connection");
+        if let Some(keepalive) = &self.options.keepalive {
+            if let Err(err) = keepalive.apply(&stream) {
+                warn!(%peer, %err, "could not set the keepalive of the client connection");
+            }
+        }
+        if self.options.buffer_size != DEFAULT_INLET_BUFFER_SIZE {
+            let socket = SockRef::from(&stream);
+            if let Err(err) = socket
+                .set_recv_buffer_size(self.options.buffer_size)
+                .and_then(|_| socket.set_send_buffer_size(self.options.buffer_size))
+            {
+                warn!(%peer, %err, "could not set the buffer size of the client connection");
+            }
+        }
+
+        // The connections accepted while the inlet is held wait for the inlet to be resumed
+        if self.outlet_listener_route.borrow().is_none() {
+            if let Some(notify) = &self.options.held_connection_notify {
+                notify.notify_one();
+            }
+        }
+        let outlet_listener_route = match self.current_outlet_listener_route().await {
+            Some(outlet_listener_route) => outlet_listener_route,
+            None => return Ok(false),
+        };
 
-        let (stream, peer) = self.inner.accept().await.map_err(TransportError::from)?;
-        TcpPortalWorker::start_new_inlet(
-            ctx,
-            self.registry.clone(),
+        let proxy_protocol_header = match self.options.proxy_protocol {
+            Some(version) => {
+                let local_addr = stream.local_addr().map_err(TransportError::from)?;
+                Some(version.header(peer, local_addr))
+            }
+            None => None,
+        };
+        let hold = self.options.hold_on_reconnect.map(|duration| InletHold {
+            duration,
+            outlet_route: self.outlet_listener_route.clone(),
+        });
+
+        let portal = self.next_portal(&outlet_listener_route).await?;
+        // The connection is registered before the portal starts so that a drain
+        // started in the meantime waits for it
+        self.inlet_connections.opened();
+        let client = ClientConnection {
             stream,
             peer,
-            outlet_listener_route,
-            addresses,
-            self.options.incoming_access_control.clone(),
-        )
-        .await?;
+            proxy_protocol_header,
+            hold,
+            connections: self.inlet_connections.clone(),
+        };
+        match portal {
+            InletPortal::Prewarmed(prewarmed, internal) => {
+                prewarmed.attach(&internal, client);
+                ctx.send(route![internal], PortalInternalMessage::Attach)
+                    .await?;
+            }
+            InletPortal::New(connection_permit) => {
+                let addresses = Addresses::generate(PortalType::Inlet);
+                TcpInletOptions::setup_flow_control(
+                    ctx.flow_controls(),
+                    &addresses,
+                    outlet_listener_route.next()?,
+                );
+                let options = PortalOptions::default()
+                    .with_idle_timeout(self.options.idle_timeout)
+                    .with_connection_permit(connection_permit)
+                    .with_socks5(self.options.socks5)
+                    .with_buffer_size(self.options.buffer_size)
+                    .with_span(self.span.clone());
+                if let Err(e) = TcpPortalWorker::start_new_inlet(
+                    ctx,
+                    self.registry.clone(),
+                    client,
+                    outlet_listener_route,
+                    addresses,
+                    self.options.incoming_access_control.clone(),
+                    options,
+                )
+                .await
+                {
+                    self.inlet_connections.closed();
+                    return Err(e);
+                }
+            }
+        }
+        // A new portal replaces the prewarmed portal used by the connection
+        self.prewarm(ctx).await?;
 
         Ok(true)
     }
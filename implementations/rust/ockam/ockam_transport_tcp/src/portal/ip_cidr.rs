@@ -0,0 +1,188 @@
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+use ockam_core::compat::net::IpAddr;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+
+/// Range of IP addresses in the CIDR notation, for example `10.0.0.0/8` or `2001:db8::/32`.
+///
+/// It is used by an inlet to only accept the client connections coming from some source addresses.
+/// A single IP address, without a prefix length, is the range containing only that address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpCidr {
+    address: IpAddr,
+    prefix_length: u8,
+}
+
+impl IpCidr {
+    /// Create a new range of addresses.
+    /// Return an error if the prefix length is larger than the number of bits of the address
+    pub fn new(address: IpAddr, prefix_length: u8) -> Result<Self> {
+        let max_prefix_length = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_length > max_prefix_length {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                format!("invalid prefix length for {address}: {prefix_length}. It must be at most {max_prefix_length}"),
+            ));
+        }
+        Ok(Self {
+            address,
+            prefix_length,
+        })
+    }
+
+    /// Return true if the address is in this range.
+    /// IPv4 addresses mapped to IPv6 addresses, as returned by a dual-stack socket,
+    /// are compared as IPv4 addresses
+    pub fn contains(&self, address: &IpAddr) -> bool {
+        match (self.address, to_canonical(*address)) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = mask_u32(self.prefix_length);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = mask_u128(self.prefix_length);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+
+    /// Return true if the address is in one of the ranges.
+    /// An empty list of ranges contains all the addresses
+    pub fn any_contains(ranges: &[IpCidr], address: &IpAddr) -> bool {
+        ranges.is_empty() || ranges.iter().any(|range| range.contains(address))
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                format!("invalid CIDR: {s}. Expected <IP>/<PREFIX LENGTH>, for example 10.0.0.0/8"),
+            )
+        };
+        let (address, prefix_length) = match s.split_once('/') {
+            Some((address, prefix_length)) => {
+                let address = IpAddr::from_str(address).map_err(|_| invalid())?;
+                let prefix_length = u8::from_str(prefix_length).map_err(|_| invalid())?;
+                (address, prefix_length)
+            }
+            None => {
+                let address = IpAddr::from_str(s).map_err(|_| invalid())?;
+                let prefix_length = if address.is_ipv4() { 32 } else { 128 };
+                (address, prefix_length)
+            }
+        };
+        Self::new(address, prefix_length)
+    }
+}
+
+impl Display for IpCidr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix_length)
+    }
+}
+
+/// Return an IPv4 address for an IPv4 address mapped to an IPv6 address
+fn to_canonical(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => IpAddr::V6(v6),
+        },
+        _ => address,
+    }
+}
+
+/// Return a mask with the `prefix_length` most significant bits set
+fn mask_u32(prefix_length: u8) -> u32 {
+    u32::MAX
+        .checked_shl(u32::BITS - prefix_length as u32)
+        .unwrap_or(0)
+}
+
+/// Return a mask with the `prefix_length` most significant bits set
+fn mask_u128(prefix_length: u8) -> u128 {
+    u128::MAX
+        .checked_shl(u128::BITS - prefix_length as u32)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use ockam_core::compat::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_cidr() {
+        let cidr = IpCidr::from_str("10.0.0.0/8").unwrap();
+        assert_eq!(
+            cidr,
+            IpCidr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8).unwrap()
+        );
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+
+        let cidr = IpCidr::from_str("2001:db8::/32").unwrap();
+        assert_eq!(cidr.to_string(), "2001:db8::/32");
+
+        // a single address is a range containing only that address
+        let cidr = IpCidr::from_str("192.168.1.10").unwrap();
+        assert_eq!(cidr.to_string(), "192.168.1.10/32");
+
+        assert!(IpCidr::from_str("10.0.0.0/33").is_err());
+        assert!(IpCidr::from_str("10.0.0.0/").is_err());
+        assert!(IpCidr::from_str("localhost/8").is_err());
+    }
+
+    #[test]
+    fn test_contains() {
+        let cidr = IpCidr::from_str("10.0.0.0/8").unwrap();
+
+        // allowed sources
+        assert!(cidr.contains(&IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))));
+        assert!(cidr.contains(&IpAddr::V4(Ipv4Addr::new(10, 255, 255, 255))));
+        assert!(cidr.contains(&IpAddr::V6(Ipv4Addr::new(10, 1, 2, 3).to_ipv6_mapped())));
+
+        // rejected sources
+        assert!(!cidr.contains(&IpAddr::V4(Ipv4Addr::new(11, 0, 0, 1))));
+        assert!(!cidr.contains(&IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10))));
+        assert!(!cidr.contains(&IpAddr::V6(Ipv6Addr::LOCALHOST)));
+
+        // the prefix lengths 0 and 32 contain all the addresses and a single address
+        let all = IpCidr::from_str("0.0.0.0/0").unwrap();
+        assert!(all.contains(&IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10))));
+        let single = IpCidr::from_str("127.0.0.1/32").unwrap();
+        assert!(single.contains(&IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert!(!single.contains(&IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2))));
+
+        let cidr = IpCidr::from_str("2001:db8::/32").unwrap();
+        assert!(cidr.contains(&IpAddr::from_str("2001:db8::1").unwrap()));
+        assert!(!cidr.contains(&IpAddr::from_str("2001:db9::1").unwrap()));
+    }
+
+    #[test]
+    fn test_any_contains() {
+        let address = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+        assert!(IpCidr::any_contains(&[], &address));
+
+        let ranges = vec![
+            IpCidr::from_str("10.0.0.0/8").unwrap(),
+            IpCidr::from_str("192.168.0.0/16").unwrap(),
+        ];
+        assert!(IpCidr::any_contains(&ranges, &address));
+        assert!(!IpCidr::any_contains(
+            &ranges,
+            &IpAddr::V4(Ipv4Addr::new(172, 16, 0, 1))
+        ));
+    }
+}
@@ -1,13 +1,27 @@
 mod addresses;
+mod drain;
+mod hold;
+mod idle;
 mod inlet_listener;
+mod ip_cidr;
 pub mod options;
 mod outlet_listener;
 mod portal_message;
 mod portal_receiver;
 mod portal_worker;
+mod prewarm;
+mod proxy_protocol;
+mod socks5;
 
+pub(crate) use drain::*;
+pub(crate) use hold::*;
+pub(crate) use idle::*;
 pub(crate) use inlet_listener::*;
+pub use ip_cidr::*;
 pub(crate) use outlet_listener::*;
 pub use portal_message::*;
 pub(crate) use portal_receiver::*;
 pub(crate) use portal_worker::*;
+pub(crate) use prewarm::*;
+pub use proxy_protocol::*;
+pub use socks5::*;
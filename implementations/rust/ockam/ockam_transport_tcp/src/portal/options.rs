@@ -1,12 +1,44 @@
 use crate::portal::addresses::Addresses;
+use crate::portal::{IpCidr, TargetAuthorization, MAX_PAYLOAD_SIZE};
+use crate::{ProxyProtocolVersion, TcpKeepaliveOptions};
+use core::time::Duration;
+use ockam_core::compat::string::String;
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
 use ockam_core::flow_control::{FlowControlId, FlowControls};
 use ockam_core::{Address, AllowAll, IncomingAccessControl};
+use tokio::sync::Notify;
+use tracing::{trace_span, Span};
+
+/// Default size of the buffer used to relay the data of an inlet connection
+pub const DEFAULT_INLET_BUFFER_SIZE: usize = MAX_PAYLOAD_SIZE;
+
+/// Minimum size of the buffer used to relay the data of an inlet connection
+pub const MIN_INLET_BUFFER_SIZE: usize = 1024;
+
+/// Maximum size of the buffer used to relay the data of an inlet connection
+pub const MAX_INLET_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+/// Name of the span entered by the workers of an inlet created with an alias.
+/// The span has an `alias` field, so that the logs of a given inlet can be enabled
+/// with a filter directive like `[inlet{alias=my-inlet}]=debug`
+pub const INLET_SPAN_NAME: &str = "inlet";
 
 /// Trust Options for an Inlet
 #[derive(Debug)]
 pub struct TcpInletOptions {
     pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(super) proxy_protocol: Option<ProxyProtocolVersion>,
+    pub(super) hold_on_reconnect: Option<Duration>,
+    pub(super) idle_timeout: Option<Duration>,
+    pub(super) keepalive: Option<TcpKeepaliveOptions>,
+    pub(super) allowed_sources: Vec<IpCidr>,
+    pub(super) socks5: bool,
+    pub(super) buffer_size: usize,
+    pub(super) alias: Option<String>,
+    pub(super) prewarm: usize,
+    pub(super) max_connections: Option<usize>,
+    pub(super) held_connection_notify: Option<Arc<Notify>>,
 }
 
 impl TcpInletOptions {
@@ -14,6 +46,17 @@ impl TcpInletOptions {
     pub fn new() -> Self {
         Self {
             incoming_access_control: Arc::new(AllowAll),
+            proxy_protocol: None,
+            hold_on_reconnect: None,
+            idle_timeout: None,
+            keepalive: None,
+            allowed_sources: vec![],
+            socks5: false,
+            buffer_size: DEFAULT_INLET_BUFFER_SIZE,
+            alias: None,
+            prewarm: 0,
+            max_connections: None,
+            held_connection_notify: None,
         }
     }
 
@@ -35,8 +78,122 @@ impl TcpInletOptions {
         self
     }
 
+    /// Notify `notify` every time a client connection is accepted while the inlet is held.
+    /// This can be used to only establish the route to the outlet of an inlet created with
+    /// [`TcpTransport::create_held_inlet`](crate::TcpTransport::create_held_inlet)
+    /// once a client needs it
+    pub fn with_held_connection_notify(mut self, notify: Arc<Notify>) -> Self {
+        self.held_connection_notify = Some(notify);
+        self
+    }
+
+    /// Only accept the client connections coming from an address in one of these ranges.
+    /// The other connections are closed as soon as they are accepted.
+    /// All the connections are accepted when the list is empty
+    pub fn with_allowed_sources(mut self, allowed_sources: Vec<IpCidr>) -> Self {
+        self.allowed_sources = allowed_sources;
+        self
+    }
+
+    /// Send a PROXY protocol header with the address of the client
+    /// at the beginning of each connection
+    pub fn with_proxy_protocol(mut self, version: ProxyProtocolVersion) -> Self {
+        self.proxy_protocol = Some(version);
+        self
+    }
+
+    /// Keep the client connections open while the inlet is held with
+    /// [`TcpTransport::hold_inlet`](crate::TcpTransport::hold_inlet), for at most `duration`.
+    /// The connections are reconnected to a new outlet when the inlet is resumed with
+    /// [`TcpTransport::resume_inlet`](crate::TcpTransport::resume_inlet), and closed
+    /// if the inlet is not resumed in time
+    pub fn with_hold_on_reconnect(mut self, duration: Duration) -> Self {
+        self.hold_on_reconnect = Some(duration);
+        self
+    }
+
+    /// Close the client connections when no data is sent or received for `duration`.
+    /// A zero duration keeps the connections open until they are closed by one of their ends
+    pub fn with_idle_timeout(mut self, duration: Duration) -> Self {
+        self.idle_timeout = Some(duration).filter(|d| !d.is_zero());
+        self
+    }
+
+    /// Set the keepalive configuration of the client sockets accepted by the inlet.
+    /// The OS defaults are used otherwise
+    pub fn with_keepalive(mut self, keepalive: TcpKeepaliveOptions) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Act as a SOCKS5 proxy: the target of each client connection is requested by the client
+    /// and sent to the outlet, which connects to it if the target is authorized.
+    /// See [`TcpOutletOptions::with_target_authorization`]
+    pub fn with_socks5(mut self) -> Self {
+        self.socks5 = true;
+        self
+    }
+
+    /// Set the size of the buffer used to relay the data of each client connection.
+    /// The data read from the client is read with a buffer of that size, and the send and receive
+    /// buffers of the client socket are set to that size when it is not the default size.
+    /// The size is clamped between [`MIN_INLET_BUFFER_SIZE`] and [`MAX_INLET_BUFFER_SIZE`]
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size.clamp(MIN_INLET_BUFFER_SIZE, MAX_INLET_BUFFER_SIZE);
+        self
+    }
+
+    /// Return the size of the buffer used to relay the data of each client connection
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Record this alias in the [`INLET_SPAN_NAME`] span entered by the listener of the inlet
+    /// and by the portal of each client connection
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    /// Return the span entered by the workers of the inlet.
+    /// There is no span when the inlet has no alias
+    pub(super) fn span(&self) -> Span {
+        match &self.alias {
+            // the span name must be a literal, it is the same as INLET_SPAN_NAME
+            Some(alias) => trace_span!("inlet", alias = alias.as_str()),
+            None => Span::none(),
+        }
+    }
+
+    /// Keep `size` portals connected to the outlet before any client connects, so that a new
+    /// client doesn't wait for the connection to the outlet. A new portal is prewarmed every
+    /// time a client connection uses one of them
+    pub fn with_prewarm(mut self, size: usize) -> Self {
+        self.prewarm = size;
+        self
+    }
+
+    /// Serve at most `max_connections` client connections at the same time, the prewarmed
+    /// portals included. The other client connections wait until a connection is closed
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Number of portals prewarmed by the inlet, within the limit of its maximum number
+    /// of connections. A SOCKS5 inlet doesn't prewarm portals, since the target of
+    /// a connection is only known once its client is connected
+    pub fn prewarm_pool_size(&self) -> usize {
+        if self.socks5 {
+            return 0;
+        }
+        match self.max_connections {
+            Some(max_connections) => self.prewarm.min(max_connections),
+            None => self.prewarm,
+        }
+    }
+
     pub(super) fn setup_flow_control(
-        &self,
         flow_controls: &FlowControls,
         addresses: &Addresses,
         next: &Address,
@@ -62,6 +219,7 @@ impl Default for TcpInletOptions {
 pub struct TcpOutletOptions {
     pub(super) consumer: Vec<FlowControlId>,
     pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(super) target_authorization: Option<Arc<dyn TargetAuthorization>>,
 }
 
 impl TcpOutletOptions {
@@ -70,6 +228,7 @@ impl TcpOutletOptions {
         Self {
             consumer: vec![],
             incoming_access_control: Arc::new(AllowAll),
+            target_authorization: None,
         }
     }
 
@@ -91,6 +250,16 @@ impl TcpOutletOptions {
         self
     }
 
+    /// Accept the targets requested by the clients of SOCKS5 inlets, when they are authorized.
+    /// The connections of SOCKS5 inlets are refused otherwise
+    pub fn with_target_authorization(
+        mut self,
+        target_authorization: Arc<dyn TargetAuthorization>,
+    ) -> Self {
+        self.target_authorization = Some(target_authorization);
+        self
+    }
+
     /// Mark that this Outlet listener is a Consumer for to the given [`FlowControlId`]
     /// Also, in this case spawned Outlets will be marked as Consumers with [`FlowControlId`]
     /// of the message that was used to create the Outlet
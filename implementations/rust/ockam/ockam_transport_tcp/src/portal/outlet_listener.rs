@@ -1,10 +1,14 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalWorker, PortalMessage, TcpOutletOptions, TcpRegistry};
+use crate::{
+    portal::{Socks5Target, TcpPortalWorker},
+    PortalMessage, TcpOutletOptions, TcpRegistry,
+};
 use ockam_core::{async_trait, Address, DenyAll, Result, Routed, Worker};
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_core::TransportError;
 use std::net::SocketAddr;
-use tracing::debug;
+use tokio::net::lookup_host;
+use tracing::{debug, warn};
 
 /// A TCP Portal Outlet listen worker
 ///
@@ -48,6 +52,56 @@ impl TcpOutletListenWorker {
 
         Ok(())
     }
+
+    /// Return the address of a target requested by the client of a SOCKS5 inlet
+    /// if the outlet is allowed to connect to it.
+    ///
+    /// The target is resolved first and every address it resolves to must be authorized,
+    /// so that a host name can't be used to reach an address refused by the policy.
+    /// The returned address is one of the authorized addresses, it is not resolved again
+    async fn authorize_target(&self, target: &Socks5Target) -> Option<SocketAddr> {
+        let Some(target_authorization) = &self.options.target_authorization else {
+            debug!("The target {} is not authorized", target);
+            return None;
+        };
+
+        let addresses: Vec<SocketAddr> =
+            match lookup_host((target.host.as_str(), target.port)).await {
+                Ok(addresses) => addresses.collect(),
+                Err(e) => {
+                    debug!("Failed to resolve the target {}: {}", target, e);
+                    return None;
+                }
+            };
+        if addresses.is_empty() {
+            debug!("The target {} could not be resolved", target);
+            return None;
+        }
+
+        for address in &addresses {
+            let is_authorized = target_authorization
+                .is_authorized(target, address.ip())
+                .await
+                .unwrap_or_else(|e| {
+                    warn!(
+                        "Failed to authorize the target {} ({}): {}",
+                        target, address, e
+                    );
+                    false
+                });
+            if !is_authorized {
+                debug!("The target {} ({}) is not authorized", target, address);
+                return None;
+            }
+        }
+
+        // Prefer ip4, like the outlet peers
+        addresses
+            .iter()
+            .find(|a| a.is_ipv4())
+            .or_else(|| addresses.first())
+            .copied()
+    }
 }
 
 #[async_trait]
@@ -75,25 +129,42 @@ impl Worker for TcpOutletListenWorker {
         let return_route = msg.return_route();
         let src_addr = msg.src_addr();
 
-        if let PortalMessage::Ping = msg.body() {
-        } else {
-            return Err(TransportError::Protocol.into());
-        }
+        // The peer is either fixed, or requested by the client of a SOCKS5 inlet
+        let peer = match msg.body() {
+            PortalMessage::Ping => Some(self.peer),
+            PortalMessage::PingTarget(target) => self.authorize_target(&target).await,
+            _ => return Err(TransportError::Protocol.into()),
+        };
 
         let addresses = Addresses::generate(PortalType::Outlet);
 
         self.options
             .setup_flow_control_for_outlet(ctx.flow_controls(), &addresses, &src_addr);
 
-        TcpPortalWorker::start_new_outlet(
-            ctx,
-            self.registry.clone(),
-            self.peer,
-            return_route.clone(),
-            addresses.clone(),
-            self.options.incoming_access_control.clone(),
-        )
-        .await?;
+        match peer {
+            Some(peer) => {
+                TcpPortalWorker::start_new_outlet(
+                    ctx,
+                    self.registry.clone(),
+                    peer,
+                    return_route.clone(),
+                    addresses.clone(),
+                    self.options.incoming_access_control.clone(),
+                )
+                .await?
+            }
+            None => {
+                return TcpPortalWorker::start_refused_outlet(
+                    ctx,
+                    self.registry.clone(),
+                    self.peer,
+                    return_route.clone(),
+                    addresses,
+                    self.options.incoming_access_control.clone(),
+                )
+                .await
+            }
+        }
 
         debug!("Created Tcp Outlet at {}", addresses.remote);
 
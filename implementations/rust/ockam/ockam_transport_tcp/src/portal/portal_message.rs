@@ -1,4 +1,5 @@
-use ockam_core::Message;
+use crate::portal::Socks5Target;
+use ockam_core::{Message, Route};
 use serde::{Deserialize, Serialize};
 
 /// A command message type for a Portal
@@ -13,6 +14,9 @@ pub enum PortalMessage {
     Disconnect,
     /// Message with binary payload
     Payload(Vec<u8>),
+    /// First message that an Inlet in SOCKS5 mode sends to the Outlet,
+    /// with the target requested by its client
+    PingTarget(Socks5Target),
 }
 
 /// An internal message type for a Portal
@@ -20,6 +24,12 @@ pub enum PortalMessage {
 pub enum PortalInternalMessage {
     /// Connection was dropped
     Disconnect,
+    /// The inlet is held, the connection must not be used until the portal is reconnected
+    Hold,
+    /// The portal must reconnect using a new route to the outlet listener
+    Reconnect(Route),
+    /// A client connection was attached to the prewarmed portal by its inlet listener
+    Attach,
 }
 
 ///Maximum allowed size for a payload
@@ -1,11 +1,12 @@
 use crate::portal::portal_message::MAX_PAYLOAD_SIZE;
-use crate::{PortalInternalMessage, PortalMessage, TcpRegistry};
+use crate::portal::{CloseReceiver, HoldEvent, IdleTimeout, ReceiverHold};
+use crate::{PortalInternalMessage, PortalMessage, TcpRegistry, DEFAULT_INLET_BUFFER_SIZE};
 use ockam_core::compat::vec::Vec;
 use ockam_core::{async_trait, Encodable, LocalMessage, Route, TransportMessage};
 use ockam_core::{route, Address, Processor, Result};
 use ockam_node::Context;
 use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
-use tracing::{error, warn};
+use tracing::{debug, error, warn};
 
 /// A TCP Portal receiving message processor
 ///
@@ -18,6 +19,54 @@ pub(crate) struct TcpPortalRecvProcessor {
     read_half: OwnedReadHalf,
     sender_address: Address,
     onward_route: Route,
+    hold: Option<ReceiverHold>,
+    idle_timeout: Option<IdleTimeout>,
+    close: Option<CloseReceiver>,
+}
+
+/// Options of a `TcpPortalRecvProcessor`, in addition to its connection and its routes
+pub(crate) struct ReceiverOptions {
+    hold: Option<ReceiverHold>,
+    idle_timeout: Option<IdleTimeout>,
+    close: Option<CloseReceiver>,
+    buffer_size: usize,
+}
+
+impl Default for ReceiverOptions {
+    fn default() -> Self {
+        Self {
+            hold: None,
+            idle_timeout: None,
+            close: None,
+            buffer_size: DEFAULT_INLET_BUFFER_SIZE,
+        }
+    }
+}
+
+impl ReceiverOptions {
+    /// Keep the connection open while the portal reconnects to a new outlet
+    pub(crate) fn with_hold(mut self, hold: Option<ReceiverHold>) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    /// Close the connection when no data is exchanged for the idle timeout
+    pub(crate) fn with_idle_timeout(mut self, idle_timeout: Option<IdleTimeout>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Close the connection when its inlet closes all its connections
+    pub(crate) fn with_close(mut self, close: Option<CloseReceiver>) -> Self {
+        self.close = close;
+        self
+    }
+
+    /// Size of the buffer used to read the data of the connection
+    pub(crate) fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
 }
 
 impl TcpPortalRecvProcessor {
@@ -27,15 +76,55 @@ impl TcpPortalRecvProcessor {
         read_half: OwnedReadHalf,
         sender_address: Address,
         onward_route: Route,
+        options: ReceiverOptions,
     ) -> Self {
         Self {
             registry,
-            buf: Vec::with_capacity(MAX_PAYLOAD_SIZE),
+            buf: Vec::with_capacity(options.buffer_size),
             read_half,
             sender_address,
             onward_route,
+            hold: options.hold,
+            idle_timeout: options.idle_timeout,
+            close: options.close,
         }
     }
+
+    /// Notify the Sender about a change of the connection
+    async fn notify_sender(&self, ctx: &Context, msg: PortalInternalMessage) -> Result<()> {
+        ctx.send(route![self.sender_address.clone()], msg).await
+    }
+
+    /// Handle a change of the connection while it is held.
+    /// Return false if the connection must be closed
+    async fn handle_hold_event(&mut self, ctx: &Context, event: HoldEvent) -> Result<bool> {
+        match event {
+            HoldEvent::Held => self.notify_sender(ctx, PortalInternalMessage::Hold).await?,
+            HoldEvent::Reconnecting(outlet_route) => {
+                self.notify_sender(ctx, PortalInternalMessage::Reconnect(outlet_route))
+                    .await?
+            }
+            HoldEvent::Reconnected(onward_route) => self.onward_route = onward_route,
+            HoldEvent::Expired => {
+                debug!(
+                    "Tcp Portal connection was not reconnected in time for {}",
+                    self.sender_address
+                );
+                if let Err(err) = self
+                    .notify_sender(ctx, PortalInternalMessage::Disconnect)
+                    .await
+                {
+                    warn!(
+                        "Error notifying Tcp Portal Sender about dropped connection {}",
+                        err
+                    );
+                }
+                return Ok(false);
+            }
+            HoldEvent::Unchanged => {}
+        }
+        Ok(true)
+    }
 }
 
 #[async_trait]
@@ -58,7 +147,35 @@ impl Processor for TcpPortalRecvProcessor {
     async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
         self.buf.clear();
 
-        let _len = match self.read_half.read_buf(&mut self.buf).await {
+        // Nothing is read from the connection while it is held
+        let is_held = self.hold.as_ref().map_or(false, |hold| hold.is_held());
+        let read = tokio::select! {
+            biased;
+            event = next_hold_event(self.hold.as_mut()) => {
+                return self.handle_hold_event(ctx, event).await;
+            }
+            is_idle = wait_until_idle(self.idle_timeout.as_mut()), if !is_held => {
+                if !is_idle {
+                    return Ok(true);
+                }
+                debug!(
+                    "Tcp Portal connection was idle for too long for {}",
+                    self.sender_address
+                );
+                // An idle connection is closed like a connection closed by the client
+                Ok(0)
+            }
+            _ = wait_until_closed(self.close.as_mut()) => {
+                debug!(
+                    "Tcp Portal connection was still open at the end of the inlet drain for {}",
+                    self.sender_address
+                );
+                Ok(0)
+            }
+            read = self.read_half.read_buf(&mut self.buf), if !is_held => read,
+        };
+
+        let _len = match read {
             Ok(len) => len,
             Err(err) => {
                 error!("Tcp Portal connection read failed with error: {}", err);
@@ -91,7 +208,7 @@ impl Processor for TcpPortalRecvProcessor {
             return Ok(false);
         }
 
-        // Loop just in case buf was extended (should not happen though)
+        // The buffer can be larger than the maximum payload size
         for chunk in self.buf.chunks(MAX_PAYLOAD_SIZE) {
             let msg = TransportMessage::v1(
                 self.onward_route.clone(),
@@ -104,3 +221,29 @@ impl Processor for TcpPortalRecvProcessor {
         Ok(true)
     }
 }
+
+/// Wait for the next hold event, if the connection can be held
+async fn next_hold_event(hold: Option<&mut ReceiverHold>) -> HoldEvent {
+    match hold {
+        Some(hold) => hold.next_event().await,
+        None => core::future::pending().await,
+    }
+}
+
+/// Wait until the connection is idle or active, if it has an idle timeout
+async fn wait_until_idle(idle_timeout: Option<&mut IdleTimeout>) -> bool {
+    match idle_timeout {
+        Some(idle_timeout) => idle_timeout.wait_until_idle().await,
+        None => core::future::pending().await,
+    }
+}
+
+/// Wait until the connection must be closed, if it belongs to an inlet being drained
+async fn wait_until_closed(close: Option<&mut CloseReceiver>) {
+    if let Some(close) = close {
+        if close.wait_for(|close| *close).await.is_ok() {
+            return;
+        }
+    }
+    core::future::pending().await
+}
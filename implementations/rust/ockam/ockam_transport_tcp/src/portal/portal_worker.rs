@@ -1,10 +1,18 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalRecvProcessor, PortalInternalMessage, PortalMessage, TcpRegistry};
+use crate::portal::{
+    accept_connect_request, write_reply, ActivitySender, AllowPortalOnwardRoute, ClientConnection,
+    ConnectionPermit, IdleTimeout, InletConnections, InletHold, PrewarmedPortals, ReceiverHold,
+    ReceiverOptions, Socks5Reply, Socks5Target,
+};
+use crate::{
+    portal::TcpPortalRecvProcessor, PortalInternalMessage, PortalMessage, TcpInletOptions,
+    TcpRegistry, DEFAULT_INLET_BUFFER_SIZE,
+};
 use core::time::Duration;
-use ockam_core::compat::{boxed::Box, net::SocketAddr, sync::Arc};
+use ockam_core::compat::{boxed::Box, net::SocketAddr, sync::Arc, vec::Vec};
 use ockam_core::{
-    async_trait, AllowAll, AllowOnwardAddresses, AllowSourceAddress, Decodable, DenyAll,
-    IncomingAccessControl, Mailbox, Mailboxes,
+    async_trait, AllowAll, AllowOnwardAddresses, AllowSourceAddress, AllowSourceAddresses,
+    Decodable, DenyAll, IncomingAccessControl, Mailbox, Mailboxes, OutgoingAccessControl,
 };
 use ockam_core::{Any, Result, Route, Routed, Worker};
 use ockam_node::{Context, ProcessorBuilder, WorkerBuilder};
@@ -12,19 +20,30 @@ use ockam_transport_core::TransportError;
 use tokio::io::AsyncWriteExt;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
-use tracing::{debug, info, trace, warn};
+use tokio::sync::watch;
+use tokio::time::timeout;
+use tracing::{debug, info, trace, warn, Instrument, Span};
+
+/// Maximum duration of the SOCKS5 handshake with the client of an inlet
+const SOCKS5_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Enumerate all `TcpPortalWorker` states
 ///
 /// Possible state transitions are:
 ///
 /// `Outlet`: `SendPong` -> `Initialized`
+/// `Outlet` refusing the target of a SOCKS5 inlet: `SendDisconnect`
 /// `Inlet`: `SendPing` -> `ReceivePong` -> `Initialized`
+/// Prewarmed `Inlet`: `SendPing` -> `ReceivePong` -> `Prewarmed` -> `Initialized`
+///
+/// An `Inlet` holding its connection goes back to `ReceivePong` when it reconnects to a new outlet
 #[derive(Clone)]
 enum State {
     SendPing { ping_route: Route },
     SendPong { pong_route: Route },
+    SendDisconnect { route: Route },
     ReceivePong,
+    Prewarmed,
     Initialized,
 }
 
@@ -44,29 +63,144 @@ pub(crate) struct TcpPortalWorker {
     remote_route: Option<Route>,
     is_disconnecting: bool,
     portal_type: PortalType,
+    proxy_protocol_header: Option<Vec<u8>>,
+    hold: Option<InletHold>,
+    onward_route: Option<watch::Sender<Route>>,
+    idle_timeout: Option<Duration>,
+    activity: Option<ActivitySender>,
+    connection_permit: Option<ConnectionPermit>,
+    prewarmed: Option<PrewarmedPortals>,
+    prewarmed_payloads: Vec<Vec<u8>>,
+    connections: Option<Arc<InletConnections>>,
+    is_socks5: bool,
+    socks5_target: Option<Socks5Target>,
+    is_socks5_reply_pending: bool,
+    buffer_size: usize,
+    span: Span,
+}
+
+/// Options of a `TcpPortalWorker`, in addition to its addresses and its access control
+pub(crate) struct PortalOptions {
+    proxy_protocol_header: Option<Vec<u8>>,
+    hold: Option<InletHold>,
+    idle_timeout: Option<Duration>,
+    connection_permit: Option<ConnectionPermit>,
+    prewarmed: Option<PrewarmedPortals>,
+    connections: Option<Arc<InletConnections>>,
+    is_socks5: bool,
+    buffer_size: usize,
+    span: Span,
+}
+
+impl Default for PortalOptions {
+    fn default() -> Self {
+        Self {
+            proxy_protocol_header: None,
+            hold: None,
+            idle_timeout: None,
+            connection_permit: None,
+            prewarmed: None,
+            connections: None,
+            is_socks5: false,
+            buffer_size: DEFAULT_INLET_BUFFER_SIZE,
+            span: Span::none(),
+        }
+    }
+}
+
+impl PortalOptions {
+    /// Stop the portal when no data is exchanged for `idle_timeout`
+    pub(crate) fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Count the portal against the maximum number of connections of its inlet
+    pub(crate) fn with_connection_permit(
+        mut self,
+        connection_permit: Option<ConnectionPermit>,
+    ) -> Self {
+        self.connection_permit = connection_permit;
+        self
+    }
+
+    /// Pool of prewarmed portals of the inlet which the portal belongs to
+    pub(crate) fn with_prewarmed(mut self, prewarmed: PrewarmedPortals) -> Self {
+        self.prewarmed = Some(prewarmed);
+        self
+    }
+
+    /// If true, the client of the inlet first requests its target with a SOCKS5 handshake
+    pub(crate) fn with_socks5(mut self, is_socks5: bool) -> Self {
+        self.is_socks5 = is_socks5;
+        self
+    }
+
+    /// Size of the buffer used to read the data of the client connection
+    pub(crate) fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Span of the inlet, used by the portal for its logs
+    pub(crate) fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
 }
 
 impl TcpPortalWorker {
-    /// Start a new `TcpPortalWorker` of type [`TypeName::Inlet`]
+    /// Start a new `TcpPortalWorker` of type [`TypeName::Inlet`] for a client connection
     pub(super) async fn start_new_inlet(
         ctx: &Context,
         registry: TcpRegistry,
-        stream: TcpStream,
-        peer: SocketAddr,
+        client: ClientConnection,
         ping_route: Route,
         addresses: Addresses,
         access_control: Arc<dyn IncomingAccessControl>,
+        options: PortalOptions,
     ) -> Result<()> {
-        Self::start(
-            ctx,
+        let options = PortalOptions {
+            proxy_protocol_header: client.proxy_protocol_header,
+            hold: client.hold,
+            connections: Some(client.connections),
+            ..options
+        };
+        Self::new(
             registry,
-            peer,
+            client.peer,
             State::SendPing { ping_route },
-            Some(stream),
+            Some(client.stream),
             addresses,
             PortalType::Inlet,
-            access_control,
+            options,
+        )
+        .start(ctx, access_control)
+        .await
+    }
+
+    /// Start a new `TcpPortalWorker` of type [`TypeName::Inlet`] connected to its outlet
+    /// before any client connects. `listen_addr` is the address of the inlet until
+    /// a client connection is attached to the portal
+    pub(super) async fn start_prewarmed_inlet(
+        ctx: &Context,
+        registry: TcpRegistry,
+        listen_addr: SocketAddr,
+        ping_route: Route,
+        addresses: Addresses,
+        access_control: Arc<dyn IncomingAccessControl>,
+        options: PortalOptions,
+    ) -> Result<()> {
+        Self::new(
+            registry,
+            listen_addr,
+            State::SendPing { ping_route },
+            None,
+            addresses,
+            PortalType::Inlet,
+            options,
         )
+        .start(ctx, access_control)
         .await
     }
 
@@ -79,38 +213,52 @@ impl TcpPortalWorker {
         addresses: Addresses,
         access_control: Arc<dyn IncomingAccessControl>,
     ) -> Result<()> {
-        Self::start(
-            ctx,
+        Self::new(
             registry,
             peer,
             State::SendPong { pong_route },
             None,
             addresses,
             PortalType::Outlet,
-            access_control,
+            PortalOptions::default(),
         )
+        .start(ctx, access_control)
         .await
     }
 
-    /// Start a new `TcpPortalWorker`
-    #[allow(clippy::too_many_arguments)]
-    async fn start(
+    /// Start a new `TcpPortalWorker` of type [`TypeName::Outlet`] which refuses the target
+    /// requested by a SOCKS5 inlet: it disconnects the inlet and stops
+    pub(super) async fn start_refused_outlet(
         ctx: &Context,
         registry: TcpRegistry,
         peer: SocketAddr,
-        state: State,
-        stream: Option<TcpStream>,
+        route: Route,
         addresses: Addresses,
-        portal_type: PortalType,
         access_control: Arc<dyn IncomingAccessControl>,
     ) -> Result<()> {
-        info!(
-            "Creating new {:?} at internal: {}, remote: {}",
-            portal_type.str(),
-            addresses.internal,
-            addresses.remote
-        );
+        Self::new(
+            registry,
+            peer,
+            State::SendDisconnect { route },
+            None,
+            addresses,
+            PortalType::Outlet,
+            PortalOptions::default(),
+        )
+        .start(ctx, access_control)
+        .await
+    }
 
+    /// Create a new `TcpPortalWorker`
+    fn new(
+        registry: TcpRegistry,
+        peer: SocketAddr,
+        state: State,
+        stream: Option<TcpStream>,
+        addresses: Addresses,
+        portal_type: PortalType,
+        options: PortalOptions,
+    ) -> Self {
         let (rx, tx) = match stream {
             Some(s) => {
                 let (rx, tx) = s.into_split();
@@ -119,21 +267,58 @@ impl TcpPortalWorker {
             None => (None, None),
         };
 
-        let worker = Self {
+        Self {
             registry,
             state,
             write_half: tx,
             read_half: rx,
             peer,
-            addresses: addresses.clone(),
+            addresses,
             remote_route: None,
             is_disconnecting: false,
             portal_type,
-        };
+            proxy_protocol_header: options.proxy_protocol_header,
+            hold: options.hold,
+            onward_route: None,
+            idle_timeout: options.idle_timeout,
+            activity: None,
+            connection_permit: options.connection_permit,
+            prewarmed: options.prewarmed,
+            prewarmed_payloads: vec![],
+            connections: options.connections,
+            is_socks5: options.is_socks5,
+            socks5_target: None,
+            is_socks5_reply_pending: false,
+            buffer_size: options.buffer_size,
+            span: options.span,
+        }
+    }
 
+    /// Start the `TcpPortalWorker`
+    async fn start(
+        self,
+        ctx: &Context,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Result<()> {
+        let addresses = self.addresses.clone();
+        info!(
+            "Creating new {:?} at internal: {}, remote: {}",
+            self.portal_type.str(),
+            addresses.internal,
+            addresses.remote
+        );
+
+        // The inlet listener of a prewarmed portal attaches a client connection to it
+        let internal_access_control: Arc<dyn IncomingAccessControl> = match &self.prewarmed {
+            Some(prewarmed) => Arc::new(AllowSourceAddresses(vec![
+                addresses.receiver,
+                prewarmed.listener().clone(),
+            ])),
+            None => Arc::new(AllowSourceAddress(addresses.receiver)),
+        };
         let internal_mailbox = Mailbox::new(
             addresses.internal,
-            Arc::new(AllowSourceAddress(addresses.receiver)),
+            internal_access_control,
             Arc::new(DenyAll),
         );
 
@@ -144,7 +329,7 @@ impl TcpPortalWorker {
         );
 
         // start worker
-        WorkerBuilder::new(worker)
+        WorkerBuilder::new(self)
             .with_mailboxes(Mailboxes::new(internal_mailbox, vec![remote_mailbox]))
             .start(ctx)
             .await?;
@@ -168,19 +353,52 @@ impl TcpPortalWorker {
     async fn start_receiver(&mut self, ctx: &Context, onward_route: Route) -> Result<()> {
         if let Some(rx) = self.read_half.take() {
             let next_hop = onward_route.next()?.clone();
+
+            // When the connection is held, the onward route of the receiver is updated
+            // every time the portal is reconnected to a new outlet
+            let (outgoing_access_control, hold): (Arc<dyn OutgoingAccessControl>, _) =
+                match self.hold.take() {
+                    Some(hold) => {
+                        let (sender, receiver) = watch::channel(onward_route.clone());
+                        self.onward_route = Some(sender);
+                        (
+                            Arc::new(AllowPortalOnwardRoute::new(
+                                self.addresses.internal.clone(),
+                                receiver.clone(),
+                            )),
+                            Some(ReceiverHold::new(hold, receiver)),
+                        )
+                    }
+                    // Only sends messages to `onward_route` and Sender
+                    None => (
+                        Arc::new(AllowOnwardAddresses(vec![
+                            next_hop,
+                            self.addresses.internal.clone(),
+                        ])),
+                        None,
+                    ),
+                };
+            let idle_timeout = self.idle_timeout.map(|timeout| {
+                let (idle_timeout, activity) = IdleTimeout::new(timeout);
+                self.activity = Some(activity);
+                idle_timeout
+            });
+            let options = ReceiverOptions::default()
+                .with_hold(hold)
+                .with_idle_timeout(idle_timeout)
+                .with_close(self.connections.as_ref().map(|c| c.close_receiver()))
+                .with_buffer_size(self.buffer_size);
             let receiver = TcpPortalRecvProcessor::new(
                 self.registry.clone(),
                 rx,
                 self.addresses.internal.clone(),
                 onward_route,
+                options,
             );
 
             ProcessorBuilder::new(receiver)
                 .with_address(self.addresses.receiver.clone())
-                .with_outgoing_access_control(AllowOnwardAddresses(vec![
-                    next_hop,
-                    self.addresses.internal.clone(),
-                ])) // Only sends messages to `onward_route` and Sender
+                .with_outgoing_access_control_arc(outgoing_access_control)
                 .start(ctx)
                 .await?;
 
@@ -274,12 +492,12 @@ impl TcpPortalWorker {
 
     async fn handle_send_ping(&self, ctx: &Context, ping_route: Route) -> Result<State> {
         // Force creation of Outlet on the other side
-        ctx.send_from_address(
-            ping_route,
-            PortalMessage::Ping,
-            self.addresses.remote.clone(),
-        )
-        .await?;
+        let ping = match &self.socks5_target {
+            Some(target) => PortalMessage::PingTarget(target.clone()),
+            None => PortalMessage::Ping,
+        };
+        ctx.send_from_address(ping_route, ping, self.addresses.remote.clone())
+            .await?;
 
         debug!("Inlet at: {} sent ping", self.addresses.internal);
 
@@ -316,6 +534,163 @@ impl TcpPortalWorker {
         self.remote_route = Some(pong_route);
         Ok(State::Initialized)
     }
+
+    /// Run the SOCKS5 handshake with the client of an inlet to get the target of the connection
+    async fn accept_socks5_request(&mut self) -> Result<Socks5Target> {
+        match (&mut self.read_half, &mut self.write_half) {
+            (Some(rx), Some(tx)) => {
+                timeout(SOCKS5_HANDSHAKE_TIMEOUT, accept_connect_request(rx, tx))
+                    .await
+                    .map_err(|_| TransportError::ConnectionDrop)?
+            }
+            _ => Err(TransportError::PortalInvalidState.into()),
+        }
+    }
+
+    /// Send the result of the connection to the target to the client of a SOCKS5 inlet
+    async fn reply_to_socks5_client(&mut self, reply: Socks5Reply) -> Result<()> {
+        if !self.is_socks5_reply_pending {
+            return Ok(());
+        }
+        self.is_socks5_reply_pending = false;
+        match &mut self.write_half {
+            Some(tx) => write_reply(tx, reply).await,
+            None => Err(TransportError::PortalInvalidState.into()),
+        }
+    }
+
+    /// Return true if the portal is holding its connection until it is reconnected
+    fn is_held(&self) -> bool {
+        self.onward_route.is_some() && self.remote_route.is_none()
+    }
+
+    /// Return true if a message was sent by an outlet used before the portal was reconnected
+    fn is_from_previous_outlet(&self, return_route: &Route) -> bool {
+        self.onward_route.is_some() && self.remote_route.as_ref() != Some(return_route)
+    }
+
+    /// Stop using the current outlet until the portal is reconnected
+    async fn hold_connection(&mut self, ctx: &Context) {
+        // The previous outlet, if it can still be reached, must close its own connection
+        if let Some(remote_route) = self.remote_route.take() {
+            if let Err(e) = ctx
+                .send_from_address(
+                    remote_route,
+                    PortalMessage::Disconnect,
+                    self.addresses.remote.clone(),
+                )
+                .await
+            {
+                debug!(
+                    "Inlet at: {} could not notify the previous outlet: {}",
+                    self.addresses.internal, e
+                );
+            }
+        }
+        info!("Inlet at: {} holds its connection", self.addresses.internal);
+    }
+
+    /// Connect to a new outlet, using a new route to the outlet listener
+    async fn reconnect(&mut self, ctx: &Context, ping_route: Route) -> Result<()> {
+        self.hold_connection(ctx).await;
+        TcpInletOptions::setup_flow_control(
+            ctx.flow_controls(),
+            &self.addresses,
+            ping_route.next()?,
+        );
+        self.state = self.handle_send_ping(ctx, ping_route).await?;
+        Ok(())
+    }
+
+    /// Write a payload received from the other side to the Tcp stream
+    async fn write_payload(&mut self, ctx: &Context, payload: Vec<u8>) -> Result<()> {
+        if let Some(tx) = &mut self.write_half {
+            if let Err(err) = tx.write_all(&payload).await {
+                warn!(
+                    "Failed to send message to peer {} with error: {}",
+                    self.peer, err
+                );
+                self.start_disconnection(ctx, DisconnectionReason::FailedTx)
+                    .await?;
+            } else if let Some(activity) = &self.activity {
+                // The data written to the connection restarts its idle timeout
+                activity.send_replace(());
+            }
+            Ok(())
+        } else {
+            Err(TransportError::PortalInvalidState.into())
+        }
+    }
+
+    async fn handle_internal_message(
+        &mut self,
+        ctx: &Context,
+        msg: PortalInternalMessage,
+    ) -> Result<()> {
+        match msg {
+            PortalInternalMessage::Disconnect => {
+                info!(
+                    "Tcp stream was dropped for {:?} at: {}",
+                    self.portal_type.str(),
+                    self.addresses.internal
+                );
+                self.start_disconnection(ctx, DisconnectionReason::FailedRx)
+                    .await
+            }
+            PortalInternalMessage::Hold => {
+                self.hold_connection(ctx).await;
+                Ok(())
+            }
+            PortalInternalMessage::Reconnect(ping_route) => self.reconnect(ctx, ping_route).await,
+            PortalInternalMessage::Attach => self.attach(ctx).await,
+        }
+    }
+
+    /// Start using a prewarmed portal with the client connection attached to it
+    async fn attach(&mut self, ctx: &Context) -> Result<()> {
+        let client = match (&self.state, self.prewarmed.take()) {
+            (State::Prewarmed, Some(prewarmed)) => prewarmed.take_client(&self.addresses.internal),
+            _ => None,
+        };
+        let client = client.ok_or(TransportError::PortalInvalidState)?;
+        let remote_route = self
+            .remote_route
+            .clone()
+            .ok_or(TransportError::PortalInvalidState)?;
+
+        let (rx, tx) = client.stream.into_split();
+        self.read_half = Some(rx);
+        self.write_half = Some(tx);
+        self.peer = client.peer;
+        self.hold = client.hold;
+        self.connections = Some(client.connections);
+
+        // The PROXY protocol header must be received by the target before
+        // any data read from the client
+        if let Some(header) = client.proxy_protocol_header {
+            ctx.send_from_address(
+                remote_route.clone(),
+                PortalMessage::Payload(header),
+                self.addresses.remote.clone(),
+            )
+            .await?;
+        }
+        for payload in core::mem::take(&mut self.prewarmed_payloads) {
+            self.write_payload(ctx, payload).await?;
+        }
+        if self.is_disconnecting {
+            return Ok(());
+        }
+        self.start_receiver(ctx, remote_route).await?;
+
+        debug!(
+            "Inlet at: {} attached a client connection from {}",
+            self.addresses.internal, self.peer
+        );
+
+        self.state = State::Initialized;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -324,16 +699,73 @@ impl Worker for TcpPortalWorker {
     type Message = Any;
 
     async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        let span = self.span.clone();
+        self.initialize_portal(ctx).instrument(span).await
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        self.registry.remove_portal_worker(&self.addresses.remote);
+        if let Some(prewarmed) = self.prewarmed.take() {
+            prewarmed.release(&self.addresses.internal);
+        }
+        // the connection doesn't count against the maximum number of connections of the inlet
+        self.connection_permit.take();
+        if let Some(connections) = &self.connections {
+            connections.closed();
+        }
+
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let span = self.span.clone();
+        self.handle_portal_message(ctx, msg).instrument(span).await
+    }
+}
+
+/// The messages of an inlet portal are handled in the span of the inlet
+impl TcpPortalWorker {
+    async fn initialize_portal(&mut self, ctx: &mut Context) -> Result<()> {
         let state = self.clone_state();
 
         match state {
             State::SendPing { ping_route } => {
+                if self.is_socks5 {
+                    match self.accept_socks5_request().await {
+                        Ok(target) => {
+                            debug!(
+                                "Inlet at: {} received a SOCKS5 request for {}",
+                                self.addresses.internal, target
+                            );
+                            self.socks5_target = Some(target);
+                            self.is_socks5_reply_pending = true;
+                        }
+                        Err(e) => {
+                            debug!(
+                                "Inlet at: {} failed the SOCKS5 handshake: {}",
+                                self.addresses.internal, e
+                            );
+                            self.registry.add_portal_worker(&self.addresses.remote);
+                            return ctx.stop_worker(self.addresses.internal.clone()).await;
+                        }
+                    }
+                }
                 self.state = self.handle_send_ping(ctx, ping_route.clone()).await?;
             }
             State::SendPong { pong_route } => {
                 self.state = self.handle_send_pong(ctx, pong_route.clone()).await?;
             }
-            State::ReceivePong | State::Initialized { .. } => {
+            State::SendDisconnect { route } => {
+                ctx.send_from_address(
+                    route,
+                    PortalMessage::Disconnect,
+                    self.addresses.remote.clone(),
+                )
+                .await?;
+                self.registry.add_portal_worker(&self.addresses.remote);
+                return ctx.stop_worker(self.addresses.internal.clone()).await;
+            }
+            State::ReceivePong | State::Prewarmed | State::Initialized { .. } => {
                 return Err(TransportError::PortalInvalidState.into())
             }
         }
@@ -343,15 +775,9 @@ impl Worker for TcpPortalWorker {
         Ok(())
     }
 
-    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
-        self.registry.remove_portal_worker(&self.addresses.remote);
-
-        Ok(())
-    }
-
     // TcpSendWorker will receive messages from the TcpRouter to send
     // across the TcpStream to our friend
-    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+    async fn handle_portal_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
         if self.is_disconnecting {
             return Ok(());
         }
@@ -367,22 +793,74 @@ impl Worker for TcpPortalWorker {
             return Err(TransportError::UnknownRoute.into());
         }
 
+        if recipient == self.addresses.internal {
+            trace!(
+                "{:?} at: {} received internal tcp packet",
+                self.portal_type.str(),
+                self.addresses.internal
+            );
+
+            let msg = PortalInternalMessage::decode(msg.payload())?;
+            return self.handle_internal_message(ctx, msg).await;
+        }
+
         let state = self.clone_state();
 
         match state {
             State::ReceivePong => {
-                if recipient == self.addresses.internal {
-                    return Err(TransportError::PortalInvalidState.into());
+                let msg = PortalMessage::decode(msg.payload())?;
+
+                match msg {
+                    PortalMessage::Pong => {
+                        self.reply_to_socks5_client(Socks5Reply::Succeeded).await?;
+                    }
+                    // While reconnecting, the previous outlet may still send some messages
+                    PortalMessage::Payload(payload) if self.is_held() => {
+                        return self.write_payload(ctx, payload).await;
+                    }
+                    PortalMessage::Disconnect if self.is_from_previous_outlet(&return_route) => {
+                        return Ok(())
+                    }
+                    // The outlet refused the target requested by the SOCKS5 client
+                    PortalMessage::Disconnect if self.is_socks5_reply_pending => {
+                        self.reply_to_socks5_client(Socks5Reply::ConnectionNotAllowed)
+                            .await?;
+                        // The receiver is not started yet, so the worker can be stopped directly
+                        self.is_disconnecting = true;
+                        return ctx.stop_worker(self.addresses.internal.clone()).await;
+                    }
+                    _ => return Err(TransportError::Protocol.into()),
                 }
 
-                let msg = PortalMessage::decode(msg.payload())?;
+                // A prewarmed portal waits for a client connection
+                if let (Some(prewarmed), None) = (&self.prewarmed, &self.write_half) {
+                    prewarmed.set_ready(&self.addresses.internal);
+                    debug!("Inlet at: {} is prewarmed", self.addresses.internal);
 
-                if let PortalMessage::Pong = msg {
-                } else {
-                    return Err(TransportError::Protocol.into());
+                    self.remote_route = Some(return_route);
+                    self.state = State::Prewarmed;
+                    return Ok(());
                 }
 
-                self.start_receiver(ctx, return_route.clone()).await?;
+                // The PROXY protocol header must be received by the target before
+                // any data read from the client
+                if let Some(header) = &self.proxy_protocol_header {
+                    ctx.send_from_address(
+                        return_route.clone(),
+                        PortalMessage::Payload(header.clone()),
+                        self.addresses.remote.clone(),
+                    )
+                    .await?;
+                }
+
+                match &self.onward_route {
+                    // The receiver is already started when the portal is reconnected
+                    Some(onward_route) => {
+                        onward_route.send_replace(return_route.clone());
+                        info!("Inlet at: {} reconnected", self.addresses.internal);
+                    }
+                    None => self.start_receiver(ctx, return_route.clone()).await?,
+                }
 
                 debug!("Inlet at: {} received pong", self.addresses.internal);
 
@@ -390,68 +868,47 @@ impl Worker for TcpPortalWorker {
                 self.state = State::Initialized;
             }
             State::Initialized => {
-                if recipient == self.addresses.internal {
-                    trace!(
-                        "{:?} at: {} received internal tcp packet",
-                        self.portal_type.str(),
-                        self.addresses.internal
-                    );
-
-                    let msg = PortalInternalMessage::decode(msg.payload())?;
-
-                    match msg {
-                        PortalInternalMessage::Disconnect => {
-                            info!(
-                                "Tcp stream was dropped for {:?} at: {}",
-                                self.portal_type.str(),
-                                self.addresses.internal
-                            );
-                            self.start_disconnection(ctx, DisconnectionReason::FailedRx)
-                                .await?;
-                        }
+                trace!(
+                    "{:?} at: {} received remote tcp packet",
+                    self.portal_type.str(),
+                    self.addresses.internal
+                );
+
+                // Send to Tcp stream
+                let msg = PortalMessage::decode(msg.payload())?;
+
+                match msg {
+                    PortalMessage::Payload(payload) => {
+                        self.write_payload(ctx, payload).await?;
                     }
-                } else {
-                    trace!(
-                        "{:?} at: {} received remote tcp packet",
-                        self.portal_type.str(),
-                        self.addresses.internal
-                    );
-
-                    // Send to Tcp stream
-                    let msg = PortalMessage::decode(msg.payload())?;
-
-                    match msg {
-                        PortalMessage::Payload(payload) => {
-                            if let Some(tx) = &mut self.write_half {
-                                match tx.write_all(&payload).await {
-                                    Ok(()) => {}
-                                    Err(err) => {
-                                        warn!(
-                                            "Failed to send message to peer {} with error: {}",
-                                            self.peer, err
-                                        );
-                                        self.start_disconnection(
-                                            ctx,
-                                            DisconnectionReason::FailedTx,
-                                        )
-                                        .await?;
-                                    }
-                                }
-                            } else {
-                                return Err(TransportError::PortalInvalidState.into());
-                            }
-                        }
-                        PortalMessage::Disconnect => {
-                            self.start_disconnection(ctx, DisconnectionReason::Remote)
-                                .await?;
-                        }
-                        PortalMessage::Ping | PortalMessage::Pong => {
-                            return Err(TransportError::Protocol.into());
-                        }
+                    // A held connection is only closed if it is not reconnected in time
+                    PortalMessage::Disconnect if self.is_from_previous_outlet(&return_route) => {}
+                    PortalMessage::Disconnect => {
+                        self.start_disconnection(ctx, DisconnectionReason::Remote)
+                            .await?;
+                    }
+                    PortalMessage::Ping | PortalMessage::PingTarget(_) | PortalMessage::Pong => {
+                        return Err(TransportError::Protocol.into());
+                    }
+                }
+            }
+            State::Prewarmed => {
+                let msg = PortalMessage::decode(msg.payload())?;
+
+                match msg {
+                    // The data sent by the target before a client connects, a banner for
+                    // example, is written to the client connection once it is attached
+                    PortalMessage::Payload(payload) => self.prewarmed_payloads.push(payload),
+                    PortalMessage::Disconnect => {
+                        self.start_disconnection(ctx, DisconnectionReason::Remote)
+                            .await?;
+                    }
+                    PortalMessage::Ping | PortalMessage::PingTarget(_) | PortalMessage::Pong => {
+                        return Err(TransportError::Protocol.into());
                     }
                 }
             }
-            State::SendPing { .. } | State::SendPong { .. } => {
+            State::SendPing { .. } | State::SendPong { .. } | State::SendDisconnect { .. } => {
                 return Err(TransportError::PortalInvalidState.into())
             }
         };
@@ -0,0 +1,193 @@
+use crate::portal::{InletConnections, InletHold};
+use core::fmt::{Debug, Formatter};
+use ockam_core::compat::collections::{BTreeMap, VecDeque};
+use ockam_core::compat::net::SocketAddr;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::vec::Vec;
+use ockam_core::{async_trait, Address, OutgoingAccessControl, RelayMessage, Result, Route};
+use tokio::net::TcpStream;
+use tokio::sync::{Notify, OwnedSemaphorePermit};
+
+/// Client connection accepted by an inlet listener and attached to one of its prewarmed portals
+pub(crate) struct ClientConnection {
+    pub(crate) stream: TcpStream,
+    pub(crate) peer: SocketAddr,
+    pub(crate) proxy_protocol_header: Option<Vec<u8>>,
+    pub(crate) hold: Option<InletHold>,
+    pub(crate) connections: Arc<InletConnections>,
+}
+
+/// Counts a portal against the maximum number of connections of its inlet, until the portal
+/// is stopped
+pub(crate) struct ConnectionPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    prewarmed: Option<PrewarmedPortals>,
+}
+
+impl ConnectionPermit {
+    /// Create a permit for a portal of an inlet with a given pool of prewarmed portals
+    pub(crate) fn new(permit: OwnedSemaphorePermit, prewarmed: Option<PrewarmedPortals>) -> Self {
+        Self {
+            permit: Some(permit),
+            prewarmed,
+        }
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.permit.take();
+        // The connection can be replaced by a new prewarmed portal
+        if let Some(prewarmed) = &self.prewarmed {
+            prewarmed.changed.notify_one();
+        }
+    }
+}
+
+/// Pool of the portals started by an inlet before any client connects.
+///
+/// A prewarmed portal connects to its outlet, and the outlet to its target, as soon as it is
+/// started. The portal is ready once it has received the pong of its outlet, and then waits for
+/// the inlet listener to attach a client connection to it.
+#[derive(Clone)]
+pub(crate) struct PrewarmedPortals {
+    listener: Address,
+    size: usize,
+    state: Arc<Mutex<PoolState>>,
+    changed: Arc<Notify>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    /// Internal addresses of the portals without a client connection, with the route
+    /// to the outlet listener they were started with
+    started: BTreeMap<Address, Route>,
+    /// Started portals which are connected to their outlet
+    ready: VecDeque<Address>,
+    /// Client connections attached to a portal, until the portal picks them up
+    attached: BTreeMap<Address, ClientConnection>,
+}
+
+impl PrewarmedPortals {
+    /// Create a pool of `size` portals for the inlet listener at `listener`
+    pub(crate) fn new(listener: Address, size: usize) -> Self {
+        Self {
+            listener,
+            size,
+            state: Default::default(),
+            changed: Default::default(),
+        }
+    }
+
+    /// Address of the inlet listener attaching the client connections to the portals
+    pub(crate) fn listener(&self) -> &Address {
+        &self.listener
+    }
+
+    /// Number of portals which must be started to fill the pool
+    pub(crate) fn missing(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        self.size.saturating_sub(state.started.len())
+    }
+
+    /// Number of portals connected to their outlet and waiting for a client connection
+    pub(crate) fn ready(&self) -> usize {
+        self.state.lock().unwrap().ready.len()
+    }
+
+    /// Add a portal started with a given route to the outlet listener
+    pub(crate) fn add(&self, internal: &Address, outlet_listener_route: Route) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .started
+            .insert(internal.clone(), outlet_listener_route);
+    }
+
+    /// Mark a portal as connected to its outlet
+    pub(crate) fn set_ready(&self, internal: &Address) {
+        let mut state = self.state.lock().unwrap();
+        if state.started.contains_key(internal) {
+            state.ready.push_back(internal.clone());
+            self.changed.notify_one();
+        }
+    }
+
+    /// Take a ready portal connected with the current route to the outlet listener
+    pub(crate) fn take_ready(&self, outlet_listener_route: &Route) -> Option<Address> {
+        let mut state = self.state.lock().unwrap();
+        let position = state
+            .ready
+            .iter()
+            .position(|internal| state.started.get(internal) == Some(outlet_listener_route))?;
+        state.ready.remove(position)
+    }
+
+    /// Take the ready portals connected with a previous route to the outlet listener.
+    /// They stay in the pool until they are stopped
+    pub(crate) fn take_stale(&self, outlet_listener_route: &Route) -> Vec<Address> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let (stale, ready): (VecDeque<_>, VecDeque<_>) = state
+            .ready
+            .drain(..)
+            .partition(|internal| state.started.get(internal) != Some(outlet_listener_route));
+        state.ready = ready;
+        stale.into_iter().collect()
+    }
+
+    /// Return the portals without a client connection
+    pub(crate) fn unattached(&self) -> Vec<Address> {
+        self.state.lock().unwrap().started.keys().cloned().collect()
+    }
+
+    /// Attach a client connection to a portal taken from the pool
+    pub(crate) fn attach(&self, internal: &Address, client: ClientConnection) {
+        let mut state = self.state.lock().unwrap();
+        state.started.remove(internal);
+        state.attached.insert(internal.clone(), client);
+    }
+
+    /// Return the client connection attached to a portal
+    pub(crate) fn take_client(&self, internal: &Address) -> Option<ClientConnection> {
+        self.state.lock().unwrap().attached.remove(internal)
+    }
+
+    /// Remove a portal which is stopped before picking up a client connection
+    pub(crate) fn release(&self, internal: &Address) {
+        let mut state = self.state.lock().unwrap();
+        state.started.remove(internal);
+        state.ready.retain(|address| address != internal);
+        // the client connection, if any, is closed when it is dropped
+        if let Some(client) = state.attached.remove(internal) {
+            client.connections.closed();
+        }
+        self.changed.notify_one();
+    }
+
+    /// Wait until a portal is ready or removed from the pool
+    pub(crate) async fn changed(&self) {
+        self.changed.notified().await
+    }
+}
+
+impl Debug for PrewarmedPortals {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PrewarmedPortals")
+            .field("listener", &self.listener)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// The inlet listener only sends messages to the portals of its pool
+#[async_trait]
+impl OutgoingAccessControl for PrewarmedPortals {
+    async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
+        let next = relay_msg.onward_route().next()?;
+        let state = self.state.lock().unwrap();
+        if state.started.contains_key(next) || state.attached.contains_key(next) {
+            return ockam_core::allow();
+        }
+        ockam_core::deny()
+    }
+}
@@ -0,0 +1,203 @@
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+use ockam_core::compat::net::{IpAddr, Ipv6Addr, SocketAddr};
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+
+/// Signature starting a PROXY protocol v2 header
+const V2_SIGNATURE: [u8; 12] = [
+    0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+];
+
+/// Version 2 of the protocol, PROXY command
+const V2_VERSION_AND_COMMAND: u8 = 0x21;
+
+/// TCP over IPv4
+const V2_TCP4: u8 = 0x11;
+
+/// TCP over IPv6
+const V2_TCP6: u8 = 0x21;
+
+/// Version of the [PROXY protocol](https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt)
+/// used by an inlet to transmit the address of its clients to the target of the outlet.
+///
+/// When a version is set, the header is sent at the beginning of each relayed connection
+/// before any data sent by the client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProxyProtocolVersion {
+    /// Human-readable header
+    V1,
+    /// Binary header
+    V2,
+}
+
+impl ProxyProtocolVersion {
+    /// Return the header describing a TCP connection from `source` to `destination`
+    pub fn header(&self, source: SocketAddr, destination: SocketAddr) -> Vec<u8> {
+        // both addresses are sent as IPv6 addresses if their families differ
+        let (source_ip, destination_ip) = match (source.ip(), destination.ip()) {
+            (IpAddr::V4(s), IpAddr::V4(d)) => (IpAddr::V4(s), IpAddr::V4(d)),
+            (s, d) => (IpAddr::V6(to_ipv6(s)), IpAddr::V6(to_ipv6(d))),
+        };
+        match self {
+            ProxyProtocolVersion::V1 => {
+                let family = if source_ip.is_ipv4() { "TCP4" } else { "TCP6" };
+                format!(
+                    "PROXY {family} {source_ip} {destination_ip} {} {}\r\n",
+                    source.port(),
+                    destination.port()
+                )
+                .into_bytes()
+            }
+            ProxyProtocolVersion::V2 => {
+                let mut header = V2_SIGNATURE.to_vec();
+                header.push(V2_VERSION_AND_COMMAND);
+                let addresses = match (source_ip, destination_ip) {
+                    (IpAddr::V4(s), IpAddr::V4(d)) => {
+                        header.push(V2_TCP4);
+                        [s.octets().as_slice(), d.octets().as_slice()].concat()
+                    }
+                    (s, d) => {
+                        header.push(V2_TCP6);
+                        [to_ipv6(s).octets(), to_ipv6(d).octets()].concat()
+                    }
+                };
+                // the length covers the addresses and the ports
+                let length = (addresses.len() + 4) as u16;
+                header.extend_from_slice(&length.to_be_bytes());
+                header.extend_from_slice(&addresses);
+                header.extend_from_slice(&source.port().to_be_bytes());
+                header.extend_from_slice(&destination.port().to_be_bytes());
+                header
+            }
+        }
+    }
+
+    /// Return the version number
+    pub fn number(&self) -> u8 {
+        match self {
+            ProxyProtocolVersion::V1 => 1,
+            ProxyProtocolVersion::V2 => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for ProxyProtocolVersion {
+    type Error = Error;
+
+    fn try_from(number: u8) -> Result<Self> {
+        match number {
+            1 => Ok(ProxyProtocolVersion::V1),
+            2 => Ok(ProxyProtocolVersion::V2),
+            _ => Err(Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                format!("unknown PROXY protocol version: {number}"),
+            )),
+        }
+    }
+}
+
+impl FromStr for ProxyProtocolVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "v1" => Ok(ProxyProtocolVersion::V1),
+            "v2" => Ok(ProxyProtocolVersion::V2),
+            _ => Err(Error::new(
+                Origin::Transport,
+                Kind::Invalid,
+                format!("unknown PROXY protocol version: {s}. Expected v1 or v2"),
+            )),
+        }
+    }
+}
+
+impl Display for ProxyProtocolVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "v{}", self.number())
+    }
+}
+
+/// Return an IP address as an IPv6 address, IPv4 addresses are mapped to IPv6 addresses
+fn to_ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipv4_headers() {
+        let source: SocketAddr = "192.168.1.10:56324".parse().unwrap();
+        let destination: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+
+        let header = ProxyProtocolVersion::V1.header(source, destination);
+        assert_eq!(header, b"PROXY TCP4 192.168.1.10 10.0.0.1 56324 4000\r\n");
+
+        let header = ProxyProtocolVersion::V2.header(source, destination);
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            // signature
+            0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+            // version 2, PROXY command, TCP over IPv4
+            0x21, 0x11,
+            // length
+            0x00, 0x0C,
+            // source and destination addresses
+            192, 168, 1, 10,
+            10, 0, 0, 1,
+            // source and destination ports
+            0xDC, 0x04,
+            0x0F, 0xA0,
+        ];
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn test_ipv6_headers() {
+        let source: SocketAddr = "[2001:db8::1]:56324".parse().unwrap();
+        let destination: SocketAddr = "[::1]:4000".parse().unwrap();
+
+        let header = ProxyProtocolVersion::V1.header(source, destination);
+        assert_eq!(header, b"PROXY TCP6 2001:db8::1 ::1 56324 4000\r\n");
+
+        let header = ProxyProtocolVersion::V2.header(source, destination);
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            // signature
+            0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+            // version 2, PROXY command, TCP over IPv6
+            0x21, 0x21,
+            // length
+            0x00, 0x24,
+            // source and destination addresses
+            0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01,
+            // source and destination ports
+            0xDC, 0x04,
+            0x0F, 0xA0,
+        ];
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            ProxyProtocolVersion::from_str("v1").unwrap(),
+            ProxyProtocolVersion::V1
+        );
+        assert_eq!(
+            ProxyProtocolVersion::from_str("V2").unwrap(),
+            ProxyProtocolVersion::V2
+        );
+        assert!(ProxyProtocolVersion::from_str("v3").is_err());
+        assert_eq!(ProxyProtocolVersion::try_from(2).unwrap().to_string(), "v2");
+    }
+}
@@ -0,0 +1,285 @@
+use core::fmt::{Debug, Display, Formatter};
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
+use ockam_transport_core::TransportError;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Version of the SOCKS protocol
+const VERSION: u8 = 0x05;
+
+/// The only authentication method supported by the inlets
+const NO_AUTHENTICATION: u8 = 0x00;
+
+/// Sent to the client when it doesn't support any of our authentication methods
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+
+/// The only command supported by the inlets
+const CONNECT: u8 = 0x01;
+
+const ADDRESS_TYPE_IPV4: u8 = 0x01;
+const ADDRESS_TYPE_DOMAIN_NAME: u8 = 0x03;
+const ADDRESS_TYPE_IPV6: u8 = 0x04;
+
+/// Target requested by the client of an inlet in SOCKS5 mode.
+///
+/// The target is sent to the outlet, which connects to it if the target is authorized
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Socks5Target {
+    /// Domain name or IP address of the target
+    pub host: String,
+    /// Port of the target
+    pub port: u16,
+}
+
+impl Socks5Target {
+    /// Create a new target
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+}
+
+impl Display for Socks5Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Decides if an outlet can connect to a target requested by the client of a SOCKS5 inlet
+#[async_trait]
+pub trait TargetAuthorization: Debug + Send + Sync + 'static {
+    /// Return true if the outlet can connect to the target, at one of the addresses
+    /// its host resolves to. This is called for each resolved address
+    async fn is_authorized(&self, target: &Socks5Target, address: IpAddr) -> Result<bool>;
+}
+
+/// Reply sent to the client at the end of the SOCKS5 handshake
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Socks5Reply {
+    Succeeded = 0x00,
+    GeneralFailure = 0x01,
+    ConnectionNotAllowed = 0x02,
+    CommandNotSupported = 0x07,
+    AddressTypeNotSupported = 0x08,
+}
+
+/// Run the server side of the SOCKS5 handshake ([RFC 1928](https://www.rfc-editor.org/rfc/rfc1928)),
+/// until the client sends a CONNECT request, and return the requested target.
+///
+/// Only clients without authentication are accepted. The final reply must be sent
+/// with [`write_reply`] once the connection to the target is established, or has failed
+pub(crate) async fn accept_connect_request<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<Socks5Target>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let [version, methods_count] = read_array(reader).await?;
+    check_version(version)?;
+    let methods = read_vec(reader, methods_count as usize).await?;
+    if !methods.contains(&NO_AUTHENTICATION) {
+        write_all(writer, &[VERSION, NO_ACCEPTABLE_METHODS]).await?;
+        return Err(protocol_error(
+            "the SOCKS5 client doesn't support connecting without authentication",
+        ));
+    }
+    write_all(writer, &[VERSION, NO_AUTHENTICATION]).await?;
+
+    let [version, command, _reserved, address_type] = read_array(reader).await?;
+    check_version(version)?;
+    if command != CONNECT {
+        write_reply(writer, Socks5Reply::CommandNotSupported).await?;
+        return Err(protocol_error(format!(
+            "the SOCKS5 command {command} is not supported"
+        )));
+    }
+    let host = match address_type {
+        ADDRESS_TYPE_IPV4 => Ipv4Addr::from(read_array::<_, 4>(reader).await?).to_string(),
+        ADDRESS_TYPE_IPV6 => Ipv6Addr::from(read_array::<_, 16>(reader).await?).to_string(),
+        ADDRESS_TYPE_DOMAIN_NAME => {
+            let [length] = read_array(reader).await?;
+            let name = read_vec(reader, length as usize).await?;
+            match String::from_utf8(name) {
+                Ok(name) if !name.is_empty() => name,
+                _ => {
+                    write_reply(writer, Socks5Reply::GeneralFailure).await?;
+                    return Err(protocol_error("the SOCKS5 domain name is not valid"));
+                }
+            }
+        }
+        _ => {
+            write_reply(writer, Socks5Reply::AddressTypeNotSupported).await?;
+            return Err(protocol_error(format!(
+                "the SOCKS5 address type {address_type} is not supported"
+            )));
+        }
+    };
+    let port = u16::from_be_bytes(read_array(reader).await?);
+    Ok(Socks5Target::new(host, port))
+}
+
+/// Send the reply to the CONNECT request of the client.
+/// The bound address is not meaningful for a portal, so it is always sent as 0.0.0.0:0
+pub(crate) async fn write_reply<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    reply: Socks5Reply,
+) -> Result<()> {
+    let mut bytes = vec![VERSION, reply as u8, 0x00, ADDRESS_TYPE_IPV4];
+    // bound address and port
+    bytes.extend_from_slice(&[0; 6]);
+    write_all(writer, &bytes).await
+}
+
+fn check_version(version: u8) -> Result<()> {
+    if version != VERSION {
+        return Err(protocol_error(format!(
+            "the SOCKS version {version} is not supported"
+        )));
+    }
+    Ok(())
+}
+
+async fn read_array<R: AsyncRead + Unpin, const N: usize>(reader: &mut R) -> Result<[u8; N]> {
+    let mut buffer = [0u8; N];
+    reader
+        .read_exact(&mut buffer)
+        .await
+        .map_err(TransportError::from)?;
+    Ok(buffer)
+}
+
+async fn read_vec<R: AsyncRead + Unpin>(reader: &mut R, length: usize) -> Result<Vec<u8>> {
+    let mut buffer = vec![0u8; length];
+    reader
+        .read_exact(&mut buffer)
+        .await
+        .map_err(TransportError::from)?;
+    Ok(buffer)
+}
+
+async fn write_all<W: AsyncWrite + Unpin>(writer: &mut W, bytes: &[u8]) -> Result<()> {
+    writer
+        .write_all(bytes)
+        .await
+        .map_err(TransportError::from)?;
+    Ok(())
+}
+
+fn protocol_error(message: impl Into<String>) -> Error {
+    Error::new(Origin::Transport, Kind::Protocol, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    #[tokio::test]
+    async fn test_socks5_connect_handshake() -> Result<()> {
+        let requests = [
+            // IPv4 address
+            (
+                vec![0x01, 127, 0, 0, 1, 0x1F, 0x90],
+                Socks5Target::new("127.0.0.1", 8080),
+            ),
+            // domain name
+            (
+                [vec![0x03, 11], b"example.com".to_vec(), vec![0x01, 0xBB]].concat(),
+                Socks5Target::new("example.com", 443),
+            ),
+            // IPv6 address
+            (
+                [
+                    vec![0x04],
+                    Ipv6Addr::LOCALHOST.octets().to_vec(),
+                    vec![0x00, 0x16],
+                ]
+                .concat(),
+                Socks5Target::new("::1", 22),
+            ),
+        ];
+
+        for (address, expected) in requests {
+            let (mut client, server) = duplex(1024);
+            let (mut reader, mut writer) = tokio::io::split(server);
+
+            // greeting with the "username/password" and "no authentication" methods
+            client.write_all(&[0x05, 0x02, 0x02, 0x00]).await.unwrap();
+            // CONNECT request
+            client.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+            client.write_all(&address).await.unwrap();
+
+            let target = accept_connect_request(&mut reader, &mut writer).await?;
+            assert_eq!(target, expected);
+            write_reply(&mut writer, Socks5Reply::Succeeded).await?;
+
+            let mut method_selection = [0u8; 2];
+            client.read_exact(&mut method_selection).await.unwrap();
+            assert_eq!(method_selection, [0x05, 0x00]);
+            let mut reply = [0u8; 10];
+            client.read_exact(&mut reply).await.unwrap();
+            assert_eq!(reply[..2], [0x05, 0x00]);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_socks5_unsupported_requests() -> Result<()> {
+        // the client requires an authentication
+        let (mut client, server) = duplex(1024);
+        let (mut reader, mut writer) = tokio::io::split(server);
+        client.write_all(&[0x05, 0x01, 0x02]).await.unwrap();
+        assert!(accept_connect_request(&mut reader, &mut writer)
+            .await
+            .is_err());
+        let mut method_selection = [0u8; 2];
+        client.read_exact(&mut method_selection).await.unwrap();
+        assert_eq!(method_selection, [0x05, NO_ACCEPTABLE_METHODS]);
+
+        // the client sends a BIND command
+        let (mut client, server) = duplex(1024);
+        let (mut reader, mut writer) = tokio::io::split(server);
+        client.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+        client
+            .write_all(&[0x05, 0x02, 0x00, 0x01, 127, 0, 0, 1, 0x1F, 0x90])
+            .await
+            .unwrap();
+        assert!(accept_connect_request(&mut reader, &mut writer)
+            .await
+            .is_err());
+        let mut response = [0u8; 12];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(response[..4], [0x05, 0x00, 0x05, 0x07]);
+
+        // the client uses SOCKS4
+        let (mut client, server) = duplex(1024);
+        let (mut reader, mut writer) = tokio::io::split(server);
+        client.write_all(&[0x04, 0x01]).await.unwrap();
+        assert!(accept_connect_request(&mut reader, &mut writer)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_socks5_target_display() {
+        assert_eq!(
+            Socks5Target::new("example.com", 443).to_string(),
+            "example.com:443"
+        );
+        assert_eq!(Socks5Target::new("::1", 22).to_string(), "[::1]:22");
+    }
+}
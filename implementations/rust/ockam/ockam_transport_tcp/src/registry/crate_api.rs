@@ -1,5 +1,7 @@
+use crate::portal::{InletConnections, OutletRouteSender, PrewarmedPortals};
 use crate::{TcpListenerInfo, TcpReceiverInfo, TcpRegistry, TcpSenderInfo};
-use ockam_core::Address;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{Address, Route};
 
 impl TcpRegistry {
     pub(crate) fn add_portal_worker(&self, addr: &Address) {
@@ -32,6 +34,74 @@ impl TcpRegistry {
             lock.remove_inlet_listener_processor(addr);
         }
     }
+    pub(crate) fn add_inlet_outlet_route(&self, addr: &Address, route: OutletRouteSender) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.add_inlet_outlet_route(addr, route);
+        }
+    }
+    pub(crate) fn remove_inlet_outlet_route(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.remove_inlet_outlet_route(addr);
+        }
+    }
+    pub(crate) fn add_prewarmed_portals(&self, addr: &Address, portals: PrewarmedPortals) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.add_prewarmed_portals(addr, portals);
+        }
+    }
+    pub(crate) fn remove_prewarmed_portals(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.remove_prewarmed_portals(addr);
+        }
+    }
+    /// Return the number of ready prewarmed portals of an inlet, if it has a pool of
+    /// prewarmed portals
+    pub(crate) fn get_ready_prewarmed_portals(&self, addr: &Address) -> Option<usize> {
+        let lock = self.registry.read().ok()?;
+        lock.prewarmed_portals
+            .get(addr)
+            .map(|portals| portals.ready())
+    }
+    /// Set the route to the outlet listener of an inlet, or unset it to hold the inlet.
+    /// Return false if the inlet is not found
+    pub(crate) fn set_inlet_outlet_route(&self, addr: &Address, route: Option<Route>) -> bool {
+        let lock = match self.registry.read() {
+            Ok(lock) => lock,
+            Err(_) => return false,
+        };
+        match lock.inlet_outlet_routes.get(addr) {
+            Some(sender) => {
+                // holding an inlet which is already held doesn't need to be notified
+                sender.send_if_modified(|current| {
+                    if current.is_none() && route.is_none() {
+                        false
+                    } else {
+                        *current = route;
+                        true
+                    }
+                });
+                true
+            }
+            None => false,
+        }
+    }
+    pub(crate) fn add_inlet_connections(&self, addr: &Address, connections: Arc<InletConnections>) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.add_inlet_connections(addr, connections);
+        }
+    }
+    pub(crate) fn remove_inlet_connections(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.remove_inlet_connections(addr);
+        }
+    }
+    /// Return the connections of an inlet, if the inlet is found
+    pub(crate) fn get_inlet_connections(&self, addr: &Address) -> Option<Arc<InletConnections>> {
+        self.registry
+            .read()
+            .ok()
+            .and_then(|lock| lock.inlet_connections.get(addr).cloned())
+    }
     pub(crate) fn add_outlet_listener_worker(&self, addr: &Address) {
         if let Ok(mut lock) = self.registry.write() {
             lock.add_outlet_listener_worker(addr);
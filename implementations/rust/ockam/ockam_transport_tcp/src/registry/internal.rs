@@ -1,4 +1,7 @@
+use crate::portal::{InletConnections, OutletRouteSender, PrewarmedPortals};
 use crate::{TcpListenerInfo, TcpReceiverInfo, TcpSenderInfo};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::Arc;
 use ockam_core::Address;
 
 #[derive(Default)]
@@ -6,6 +9,9 @@ pub(super) struct InternalRegistry {
     pub(super) portal_workers: Vec<Address>,
     pub(super) portal_receiver_processors: Vec<Address>,
     pub(super) inlet_listener_processors: Vec<Address>,
+    pub(super) inlet_outlet_routes: BTreeMap<Address, OutletRouteSender>,
+    pub(super) inlet_connections: BTreeMap<Address, Arc<InletConnections>>,
+    pub(super) prewarmed_portals: BTreeMap<Address, PrewarmedPortals>,
     pub(super) outlet_listener_workers: Vec<Address>,
     pub(super) listener_processors: Vec<TcpListenerInfo>,
     pub(super) sender_workers: Vec<TcpSenderInfo>,
@@ -31,6 +37,28 @@ impl InternalRegistry {
     pub(super) fn remove_inlet_listener_processor(&mut self, addr: &Address) {
         self.inlet_listener_processors.retain(|x| x != addr);
     }
+    pub(super) fn add_inlet_outlet_route(&mut self, addr: &Address, route: OutletRouteSender) {
+        self.inlet_outlet_routes.insert(addr.clone(), route);
+    }
+    pub(super) fn remove_inlet_outlet_route(&mut self, addr: &Address) {
+        self.inlet_outlet_routes.remove(addr);
+    }
+    pub(super) fn add_inlet_connections(
+        &mut self,
+        addr: &Address,
+        connections: Arc<InletConnections>,
+    ) {
+        self.inlet_connections.insert(addr.clone(), connections);
+    }
+    pub(super) fn remove_inlet_connections(&mut self, addr: &Address) {
+        self.inlet_connections.remove(addr);
+    }
+    pub(super) fn add_prewarmed_portals(&mut self, addr: &Address, portals: PrewarmedPortals) {
+        self.prewarmed_portals.insert(addr.clone(), portals);
+    }
+    pub(super) fn remove_prewarmed_portals(&mut self, addr: &Address) {
+        self.prewarmed_portals.remove(addr);
+    }
     pub(super) fn add_outlet_listener_worker(&mut self, addr: &Address) {
         self.outlet_listener_workers.push(addr.clone())
     }
@@ -24,7 +24,8 @@ impl TcpTransport {
         // Resolve peer address
         let socket = resolve_peer(peer.into())?;
 
-        let (read_half, write_half) = TcpSendWorker::connect(socket).await?;
+        let (read_half, write_half) =
+            TcpSendWorker::connect(socket, &options.keepalive, options.bind_address).await?;
 
         let mode = TcpConnectionMode::Outgoing;
         let addresses = Addresses::generate(mode);
@@ -1,5 +1,5 @@
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{async_trait, Address, AsyncTryClone, Error, Result, TransportType};
+use ockam_core::{async_trait, Address, AsyncTryClone, Error, Result, Route, TransportType};
 use ockam_node::Context;
 use ockam_transport_core::Transport;
 use std::sync::Arc;
@@ -64,11 +64,26 @@ impl Transport for TcpTransport {
             ))
         }
     }
+
+    /// The route returned by `resolve_address` is the address of the sender worker of
+    /// a TCP connection. The connection is alive as long as that worker is running
+    async fn is_route_alive(&self, route: &Route) -> bool {
+        let sender_address = match route.next() {
+            Ok(address) if route.len() == 1 => address,
+            _ => return false,
+        };
+        match self.ctx.list_workers().await {
+            Ok(workers) => workers.contains(sender_address),
+            Err(_) => false,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::time::Duration;
+    use ockam_core::route;
     use ockam_transport_core::TransportError;
     use std::net::TcpListener;
 
@@ -100,6 +115,29 @@ mod tests {
         ctx.stop().await
     }
 
+    #[ockam_macros::test]
+    async fn test_reuse_a_resolved_route_while_it_is_alive(ctx: &mut Context) -> Result<()> {
+        let tcp = TcpTransport::create(ctx).await?;
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(TransportError::from)?;
+        let route = route![(TCP, listener.local_addr().unwrap().to_string())];
+
+        // the connection created by the first resolution is reused
+        let first = ctx.resolve_transport_route(route.clone()).await?;
+        let second = ctx.resolve_transport_route(route.clone()).await?;
+        assert_eq!(first, second);
+
+        // a new connection is created once the first one is closed
+        tcp.disconnect(first.next()?.clone()).await?;
+        while tcp.is_route_alive(&first).await {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let third = ctx.resolve_transport_route(route).await?;
+        assert_ne!(first, third);
+        assert!(tcp.is_route_alive(&third).await);
+
+        ctx.stop().await
+    }
+
     #[ockam_macros::test]
     async fn test_resolve_route_with_dns_address(ctx: &mut Context) -> Result<()> {
         let tcp = TcpTransport::create(ctx).await?;
@@ -1,8 +1,10 @@
 use crate::portal::TcpInletListenProcessor;
 use crate::transport::common::{parse_socket_addr, resolve_peer};
 use crate::{portal::TcpOutletListenWorker, TcpInletOptions, TcpOutletOptions, TcpTransport};
+use core::time::Duration;
 use ockam_core::compat::net::SocketAddr;
-use ockam_core::{Address, Result, Route};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, Error, Result, Route};
 
 impl TcpTransport {
     /// Create Tcp Inlet that listens on bind_addr, transforms Tcp stream into Ockam Routable
@@ -32,13 +34,37 @@ impl TcpTransport {
         TcpInletListenProcessor::start(
             &self.ctx,
             self.registry.clone(),
-            outlet_route.into(),
+            Some(outlet_route.into()),
             socket_addr,
             options,
         )
         .await
     }
 
+    /// Create a Tcp Inlet which is held until a route to its outlet is given with
+    /// [`TcpTransport::resume_inlet`]. The client connections accepted in the meantime wait
+    /// for the inlet to be resumed, see [`TcpInletOptions::with_held_connection_notify`].
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{AllowAll, Result, route};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx).await?;
+    /// let (_, inlet) = tcp.create_held_inlet("127.0.0.1:4000", TcpInletOptions::new()).await?;
+    /// tcp.resume_inlet(inlet, route!["outlet"])?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create_held_inlet(
+        &self,
+        bind_addr: impl Into<String>,
+        options: TcpInletOptions,
+    ) -> Result<(SocketAddr, Address)> {
+        let socket_addr = parse_socket_addr(&bind_addr.into())?;
+        TcpInletListenProcessor::start(&self.ctx, self.registry.clone(), None, socket_addr, options)
+            .await
+    }
+
     /// Stop inlet at addr
     ///
     /// ```rust
@@ -59,6 +85,106 @@ impl TcpTransport {
         Ok(())
     }
 
+    /// Drain an inlet: stop accepting new connections but let the existing connections finish,
+    /// for at most `drain_timeout`. The connections still open after that are closed.
+    /// Return the number of connections which had to be closed
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{AllowAll, Result, route};
+    /// # use std::time::Duration;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx).await?;
+    /// let (_, inlet) = tcp.create_inlet("127.0.0.1:4000", route!["outlet"], TcpInletOptions::new()).await?;
+    /// tcp.drain_inlet(inlet, Duration::from_secs(30)).await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn drain_inlet(
+        &self,
+        addr: impl Into<Address>,
+        drain_timeout: Duration,
+    ) -> Result<usize> {
+        let addr = addr.into();
+        let Some(connections) = self.registry.get_inlet_connections(&addr) else {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::NotFound,
+                format!("inlet {addr} not found"),
+            ));
+        };
+        // the listener is closed, so new connections are refused
+        self.ctx.stop_processor(addr).await?;
+        Ok(connections.drain(drain_timeout).await)
+    }
+
+    /// Hold an inlet while the route to its outlet is being re-established.
+    ///
+    /// The connections accepted while the inlet is held wait for the inlet to be resumed.
+    /// The existing connections of an inlet created with
+    /// [`TcpInletOptions::with_hold_on_reconnect`] stop sending data to their outlet, and are
+    /// closed if the inlet is not resumed before the end of the hold duration.
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{AllowAll, Result, route};
+    /// # use std::time::Duration;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let options = TcpInletOptions::new().with_hold_on_reconnect(Duration::from_secs(5));
+    ///
+    /// let tcp = TcpTransport::create(&ctx).await?;
+    /// let (_, inlet) = tcp.create_inlet("127.0.0.1:4000", route!["outlet"], options).await?;
+    /// tcp.hold_inlet(inlet.clone())?;
+    /// tcp.resume_inlet(inlet, route!["restarted_outlet"])?;
+    /// # Ok(()) }
+    /// ```
+    pub fn hold_inlet(&self, addr: impl Into<Address>) -> Result<()> {
+        self.set_inlet_outlet_route(addr.into(), None)
+    }
+
+    /// Resume an inlet with a new route to its outlet. The connections held by the inlet
+    /// are reconnected to a new outlet, using that route
+    pub fn resume_inlet(
+        &self,
+        addr: impl Into<Address>,
+        outlet_route: impl Into<Route>,
+    ) -> Result<()> {
+        self.set_inlet_outlet_route(addr.into(), Some(outlet_route.into()))
+    }
+
+    /// Return the number of portals of an inlet which are connected to the outlet and wait for
+    /// a client connection, or None if the inlet is not created with
+    /// [`TcpInletOptions::with_prewarm`]
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{AllowAll, Result, route};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let options = TcpInletOptions::new().with_prewarm(2);
+    ///
+    /// let tcp = TcpTransport::create(&ctx).await?;
+    /// let (_, inlet) = tcp.create_inlet("127.0.0.1:4000", route!["outlet"], options).await?;
+    /// let ready = tcp.prewarmed_portals(&inlet);
+    /// # Ok(()) }
+    /// ```
+    pub fn prewarmed_portals(&self, addr: &Address) -> Option<usize> {
+        self.registry.get_ready_prewarmed_portals(addr)
+    }
+
+    fn set_inlet_outlet_route(&self, addr: Address, outlet_route: Option<Route>) -> Result<()> {
+        if self.registry.set_inlet_outlet_route(&addr, outlet_route) {
+            Ok(())
+        } else {
+            Err(Error::new(
+                Origin::Transport,
+                Kind::NotFound,
+                format!("inlet {addr} not found"),
+            ))
+        }
+    }
+
     /// Create Tcp Outlet Listener at address, that connects to peer using Tcp, transforms Ockam Messages
     /// received from Inlet into stream and sends it to peer Tcp stream. Outlet is bidirectional:
     /// Tcp stream received from peer is transformed into Ockam Routable Messages and sent
@@ -1,7 +1,5 @@
 use crate::workers::Addresses;
-use crate::{TcpConnectionMode, TcpRegistry, TcpSenderInfo};
-use cfg_if::cfg_if;
-use core::time::Duration;
+use crate::{TcpConnectionMode, TcpKeepaliveOptions, TcpRegistry, TcpSenderInfo};
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::{
     async_trait,
@@ -15,10 +13,9 @@ use ockam_core::{
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_core::TransportError;
 use serde::{Deserialize, Serialize};
-use socket2::{SockRef, TcpKeepalive};
 use tokio::io::AsyncWriteExt;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::TcpStream;
+use tokio::net::{TcpSocket, TcpStream};
 use tracing::{debug, info, trace, warn};
 
 #[derive(Serialize, Deserialize, Message, Clone)]
@@ -119,11 +116,19 @@ impl TcpSendWorker {
         Ok(())
     }
 
+    /// Connect to a socket address.
+    /// If a bind address is given, the local end of the connection is bound to that address
     pub(crate) async fn connect(
         socket_address: SocketAddr,
+        keepalive: &TcpKeepaliveOptions,
+        bind_address: Option<SocketAddr>,
     ) -> Result<(OwnedReadHalf, OwnedWriteHalf)> {
-        debug!(addr = %socket_address, "Connecting");
-        let connection = match TcpStream::connect(socket_address).await {
+        debug!(addr = %socket_address, bind = ?bind_address, "Connecting");
+        let connection = match bind_address {
+            Some(bind_address) => Self::connect_from(socket_address, bind_address).await,
+            None => TcpStream::connect(socket_address).await,
+        };
+        let connection = match connection {
             Ok(c) => {
                 debug!(addr = %socket_address, "Connected");
                 c
@@ -134,21 +139,24 @@ impl TcpSendWorker {
             }
         };
 
-        let mut keepalive = TcpKeepalive::new()
-            .with_time(Duration::from_secs(300))
-            .with_interval(Duration::from_secs(75));
-
-        cfg_if! {
-            if #[cfg(unix)] {
-               keepalive = keepalive.with_retries(2);
-            }
-        }
-
-        let socket = SockRef::from(&connection);
-        socket.set_tcp_keepalive(&keepalive).unwrap();
+        keepalive.apply(&connection)?;
 
         Ok(connection.into_split())
     }
+
+    /// Connect to a socket address from a specific local address
+    async fn connect_from(
+        socket_address: SocketAddr,
+        bind_address: SocketAddr,
+    ) -> std::io::Result<TcpStream> {
+        let socket = if bind_address.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        socket.bind(bind_address)?;
+        socket.connect(socket_address).await
+    }
 }
 
 #[async_trait]
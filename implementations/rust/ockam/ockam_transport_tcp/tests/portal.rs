@@ -1,13 +1,17 @@
+use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
 
 use ockam_core::compat::rand::random;
 use ockam_core::{route, Result};
 use ockam_node::Context;
 use ockam_transport_tcp::{
-    TcpConnectionOptions, TcpInletOptions, TcpListenerOptions, TcpOutletOptions, TcpTransport,
+    IpCidr, Socks5Target, TargetAuthorization, TcpConnectionOptions, TcpInletOptions,
+    TcpListenerOptions, TcpOutletOptions, TcpTransport,
 };
 
 const LENGTH: usize = 32;
@@ -238,3 +242,673 @@ async fn portal__tcp_connection_with_invalid_message_flow__should_not_succeed(
 
     Ok(())
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__reconnection_within_hold_duration__should_keep_the_connection(
+    ctx: &mut Context,
+) -> Result<()> {
+    let payload1 = generate_binary();
+    let payload2 = generate_binary();
+    let payload3 = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address.clone(), TcpOutletOptions::new())
+        .await?;
+    tcp.create_outlet("restarted_outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    let (inlet_socket_addr, inlet_address) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_hold_on_reconnect(Duration::from_secs(5)),
+        )
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        // Connection created by the first outlet
+        let (mut stream1, _) = listener.accept().await.unwrap();
+        read_assert_binary(&mut stream1, payload1).await;
+
+        // Connection created by the restarted outlet
+        let (mut stream2, _) = listener.accept().await.unwrap();
+        read_assert_binary(&mut stream2, payload2).await;
+        write_binary(&mut stream2, payload3).await;
+    });
+
+    // Wait till listener is up
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    write_binary(&mut stream, payload1).await;
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    // The outlet is restarted before the end of the hold duration
+    tcp.hold_inlet(inlet_address.clone())?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    tcp.resume_inlet(inlet_address, route!["restarted_outlet"])?;
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    // The client connection is still open and now goes through the restarted outlet
+    write_binary(&mut stream, payload2).await;
+    read_assert_binary(&mut stream, payload3).await;
+
+    let res = handle.await;
+    assert!(res.is_ok());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__held_inlet__should_notify_and_keep_the_first_client_connection(
+    ctx: &mut Context,
+) -> Result<()> {
+    let payload1 = generate_binary();
+    let payload2 = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    let notify = Arc::new(Notify::new());
+    let (inlet_socket_addr, inlet_address) = tcp
+        .create_held_inlet(
+            "127.0.0.1:0",
+            TcpInletOptions::new().with_held_connection_notify(notify.clone()),
+        )
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        read_assert_binary(&mut stream, payload1).await;
+        write_binary(&mut stream, payload2).await;
+    });
+
+    // The inlet is only resumed once a client connects
+    let mut stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    write_binary(&mut stream, payload1).await;
+    notify.notified().await;
+    tcp.resume_inlet(inlet_address, route!["outlet"])?;
+
+    read_assert_binary(&mut stream, payload2).await;
+
+    let res = handle.await;
+    assert!(res.is_ok());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__no_reconnection_within_hold_duration__should_close_the_connection(
+    ctx: &mut Context,
+) -> Result<()> {
+    let payload = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    let (inlet_socket_addr, inlet_address) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_hold_on_reconnect(Duration::from_millis(500)),
+        )
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        read_assert_binary(&mut stream, payload).await;
+    });
+
+    // Wait till listener is up
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    write_binary(&mut stream, payload).await;
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    // The inlet is never resumed
+    tcp.hold_inlet(inlet_address)?;
+
+    // The client connection is closed once the hold duration has elapsed
+    let mut buffer = [0u8; LENGTH];
+    let length = stream.read(&mut buffer).await.unwrap();
+    assert_eq!(length, 0);
+
+    let res = handle.await;
+    assert!(res.is_ok());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__idle_timeout__should_only_close_idle_connections(ctx: &mut Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    let (inlet_socket_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_idle_timeout(Duration::from_secs(1)),
+        )
+        .await?;
+
+    // The target of the outlet echoes the data sent by each client
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buffer = [0u8; LENGTH];
+                while let Ok(length) = stream.read(&mut buffer).await {
+                    if length == 0 || stream.write_all(&buffer[..length]).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    // Wait till listener is up
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut idle_stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    let payload = generate_binary();
+    write_binary(&mut idle_stream, payload).await;
+    read_assert_binary(&mut idle_stream, payload).await;
+
+    // The active connection keeps exchanging data for longer than the idle timeout
+    let mut active_stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    for _ in 0..8 {
+        let payload = generate_binary();
+        write_binary(&mut active_stream, payload).await;
+        read_assert_binary(&mut active_stream, payload).await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+    }
+
+    // The idle connection has been closed
+    let mut buffer = [0u8; LENGTH];
+    let length = idle_stream.read(&mut buffer).await.unwrap();
+    assert_eq!(length, 0);
+
+    // The active connection is still open
+    let payload = generate_binary();
+    write_binary(&mut active_stream, payload).await;
+    read_assert_binary(&mut active_stream, payload).await;
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__allowed_sources__should_only_accept_allowed_clients(
+    ctx: &mut Context,
+) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    // The client connects from 127.0.0.1 which is only allowed by the first inlet
+    let (allowed_inlet_socket_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_allowed_sources(vec![
+                "10.0.0.0/8".parse::<IpCidr>()?,
+                "127.0.0.0/8".parse::<IpCidr>()?,
+            ]),
+        )
+        .await?;
+    let (rejected_inlet_socket_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_allowed_sources(vec!["10.0.0.0/8".parse::<IpCidr>()?]),
+        )
+        .await?;
+
+    // The target of the outlet echoes the data sent by each client
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buffer = [0u8; LENGTH];
+                while let Ok(length) = stream.read(&mut buffer).await {
+                    if length == 0 || stream.write_all(&buffer[..length]).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    // Wait till listener is up
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut allowed_stream = TcpStream::connect(allowed_inlet_socket_addr).await.unwrap();
+    let payload = generate_binary();
+    write_binary(&mut allowed_stream, payload).await;
+    read_assert_binary(&mut allowed_stream, payload).await;
+
+    // The rejected connection is closed right away
+    let mut rejected_stream = TcpStream::connect(rejected_inlet_socket_addr)
+        .await
+        .unwrap();
+    let mut buffer = [0u8; LENGTH];
+    let length = rejected_stream.read(&mut buffer).await.unwrap_or(0);
+    assert_eq!(length, 0);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+/// Start a target for an outlet which sends a banner to each connection, then echoes the data
+/// sent by the client. The number of accepted connections is sent on the returned channel
+fn start_target_with_banner(
+    listener: TcpListener,
+    banner: [u8; LENGTH],
+) -> (
+    tokio::sync::mpsc::UnboundedReceiver<usize>,
+    tokio::sync::mpsc::UnboundedReceiver<()>,
+) {
+    let (accepted_sender, accepted_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (closed_sender, closed_receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut accepted = 0;
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            accepted += 1;
+            let _ = accepted_sender.send(accepted);
+            let closed_sender = closed_sender.clone();
+            tokio::spawn(async move {
+                write_binary(&mut stream, banner).await;
+                let mut buffer = [0u8; LENGTH];
+                while let Ok(length) = stream.read(&mut buffer).await {
+                    if length == 0 || stream.write_all(&buffer[..length]).await.is_err() {
+                        break;
+                    }
+                }
+                let _ = closed_sender.send(());
+            });
+        }
+    });
+    (accepted_receiver, closed_receiver)
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__prewarm__should_connect_before_any_client(ctx: &mut Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+    let banner = generate_binary();
+    let (mut accepted, mut closed) = start_target_with_banner(listener, banner);
+
+    let (inlet_socket_addr, inlet) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_prewarm(2),
+        )
+        .await?;
+
+    // Two tunnels are established before any client connects
+    assert_eq!(accepted.recv().await, Some(1));
+    assert_eq!(accepted.recv().await, Some(2));
+    while tcp.prewarmed_portals(&inlet) != Some(2) {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // The client uses a prewarmed tunnel, and receives the data sent by the target before
+    // the client was connected
+    let mut stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    read_assert_binary(&mut stream, banner).await;
+    let payload = generate_binary();
+    write_binary(&mut stream, payload).await;
+    read_assert_binary(&mut stream, payload).await;
+
+    // A new tunnel replaces the one used by the client
+    assert_eq!(accepted.recv().await, Some(3));
+    while tcp.prewarmed_portals(&inlet) != Some(2) {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // The prewarmed tunnels are closed when the inlet is stopped,
+    // while the client connection is kept
+    tcp.stop_inlet(inlet).await?;
+    assert_eq!(closed.recv().await, Some(()));
+    assert_eq!(closed.recv().await, Some(()));
+    write_binary(&mut stream, payload).await;
+    read_assert_binary(&mut stream, payload).await;
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__prewarm_with_max_connections__should_not_exceed_the_max_connections(
+    ctx: &mut Context,
+) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+    let banner = generate_binary();
+    let (mut accepted, _) = start_target_with_banner(listener, banner);
+
+    let (inlet_socket_addr, inlet) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new()
+                .with_prewarm(3)
+                .with_max_connections(2),
+        )
+        .await?;
+
+    // Only two tunnels are prewarmed
+    assert_eq!(accepted.recv().await, Some(1));
+    assert_eq!(accepted.recv().await, Some(2));
+    while tcp.prewarmed_portals(&inlet) != Some(2) {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(accepted.try_recv().is_err());
+
+    // The clients use the prewarmed tunnels, and no new tunnel is prewarmed while
+    // the maximum number of connections is reached
+    let mut first_stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    read_assert_binary(&mut first_stream, banner).await;
+    let mut second_stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    read_assert_binary(&mut second_stream, banner).await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(accepted.try_recv().is_err());
+    assert_eq!(tcp.prewarmed_portals(&inlet), Some(0));
+
+    // A tunnel is prewarmed again once a connection is closed
+    drop(first_stream);
+    assert_eq!(accepted.recv().await, Some(3));
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 20000)]
+async fn portal__drain__should_refuse_new_connections_and_let_existing_ones_finish(
+    ctx: &mut Context,
+) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    let (finishing_inlet_socket_addr, finishing_inlet) = tcp
+        .create_inlet("127.0.0.1:0", route!["outlet"], TcpInletOptions::new())
+        .await?;
+    let (lingering_inlet_socket_addr, lingering_inlet) = tcp
+        .create_inlet("127.0.0.1:0", route!["outlet"], TcpInletOptions::new())
+        .await?;
+
+    // The target of the outlet echoes the data sent by each client
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buffer = [0u8; LENGTH];
+                while let Ok(length) = stream.read(&mut buffer).await {
+                    if length == 0 || stream.write_all(&buffer[..length]).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    // Wait till listener is up
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut stream = TcpStream::connect(finishing_inlet_socket_addr)
+        .await
+        .unwrap();
+    let payload = generate_binary();
+    write_binary(&mut stream, payload).await;
+    read_assert_binary(&mut stream, payload).await;
+
+    let drain = {
+        let tcp = tcp.clone();
+        tokio::spawn(async move {
+            tcp.drain_inlet(finishing_inlet, Duration::from_secs(10))
+                .await
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    // During the drain, new connections are refused
+    assert!(TcpStream::connect(finishing_inlet_socket_addr)
+        .await
+        .is_err());
+
+    // The existing connection keeps working until it is closed by its client
+    let payload = generate_binary();
+    write_binary(&mut stream, payload).await;
+    read_assert_binary(&mut stream, payload).await;
+    drop(stream);
+    assert_eq!(drain.await.unwrap()?, 0);
+
+    // A connection still open at the end of the drain timeout is closed
+    let mut stream = TcpStream::connect(lingering_inlet_socket_addr)
+        .await
+        .unwrap();
+    let payload = generate_binary();
+    write_binary(&mut stream, payload).await;
+    read_assert_binary(&mut stream, payload).await;
+
+    let closed = tcp
+        .drain_inlet(lingering_inlet, Duration::from_millis(500))
+        .await?;
+    assert_eq!(closed, 1);
+    let mut buffer = [0u8; LENGTH];
+    let length = stream.read(&mut buffer).await.unwrap_or(0);
+    assert_eq!(length, 0);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+/// Only authorize the targets on a given port
+#[derive(Debug)]
+struct AllowPort(u16);
+
+#[ockam_core::async_trait]
+impl TargetAuthorization for AllowPort {
+    async fn is_authorized(&self, target: &Socks5Target, _address: IpAddr) -> Result<bool> {
+        Ok(target.port == self.0)
+    }
+}
+
+/// Only authorize the targets which don't resolve to a loopback address
+#[derive(Debug)]
+struct RefuseLoopback;
+
+#[ockam_core::async_trait]
+impl TargetAuthorization for RefuseLoopback {
+    async fn is_authorized(&self, _target: &Socks5Target, address: IpAddr) -> Result<bool> {
+        Ok(!address.is_loopback())
+    }
+}
+
+/// Run the SOCKS5 handshake with a CONNECT request to 127.0.0.1:port and return the reply code
+async fn socks5_connect(stream: &mut TcpStream, port: u16) -> u8 {
+    socks5_send_request(stream, vec![0x05, 0x01, 0x00, 0x01, 127, 0, 0, 1], port).await
+}
+
+/// Run the SOCKS5 handshake with a CONNECT request to host:port and return the reply code
+async fn socks5_connect_to_host(stream: &mut TcpStream, host: &str, port: u16) -> u8 {
+    let mut request = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+    request.extend_from_slice(host.as_bytes());
+    socks5_send_request(stream, request, port).await
+}
+
+/// Send a SOCKS5 CONNECT request, without its port, and return the reply code
+async fn socks5_send_request(stream: &mut TcpStream, mut request: Vec<u8>, port: u16) -> u8 {
+    stream.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+    let mut method_selection = [0u8; 2];
+    stream.read_exact(&mut method_selection).await.unwrap();
+    assert_eq!(method_selection, [0x05, 0x00]);
+
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await.unwrap();
+    let mut reply = [0u8; 10];
+    stream.read_exact(&mut reply).await.unwrap();
+    reply[1]
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__socks5_connect__should_only_reach_authorized_targets(
+    ctx: &mut Context,
+) -> Result<()> {
+    let payload1 = generate_binary();
+    let payload2 = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_port = listener.local_addr().unwrap().port();
+
+    tcp.create_outlet(
+        "outlet",
+        "127.0.0.1:1",
+        TcpOutletOptions::new().with_target_authorization(Arc::new(AllowPort(target_port))),
+    )
+    .await?;
+    let (inlet_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_socks5(),
+        )
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        read_assert_binary(&mut stream, payload1).await;
+        write_binary(&mut stream, payload2).await;
+    });
+
+    // The target is authorized
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    assert_eq!(socks5_connect(&mut stream, target_port).await, 0x00);
+    write_binary(&mut stream, payload1).await;
+    read_assert_binary(&mut stream, payload2).await;
+
+    let res = handle.await;
+    assert!(res.is_ok());
+
+    // The target is not authorized
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    assert_eq!(socks5_connect(&mut stream, target_port + 1).await, 0x02);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__socks5_connect__should_authorize_the_resolved_address_of_a_host(
+    ctx: &mut Context,
+) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_port = listener.local_addr().unwrap().port();
+
+    tcp.create_outlet(
+        "outlet",
+        "127.0.0.1:1",
+        TcpOutletOptions::new().with_target_authorization(Arc::new(RefuseLoopback)),
+    )
+    .await?;
+    let (inlet_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_socks5(),
+        )
+        .await?;
+
+    // localhost resolves to a loopback address, which is refused
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    assert_eq!(
+        socks5_connect_to_host(&mut stream, "localhost", target_port).await,
+        0x02
+    );
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
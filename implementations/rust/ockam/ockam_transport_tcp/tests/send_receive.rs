@@ -52,3 +52,31 @@ async fn send_receive(ctx: &mut Context) -> Result<()> {
 
     Ok(())
 }
+
+#[ockam_macros::test]
+async fn connect_with_a_bind_address(ctx: &mut Context) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    // find a free local port to bind the outgoing connection to
+    let bind_address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let transport = TcpTransport::create(ctx).await?;
+    let options = TcpConnectionOptions::new().with_bind_address(bind_address);
+    let connection = transport
+        .connect(listener.local_addr().unwrap().to_string(), options)
+        .await;
+
+    // the connection is accepted from the requested source address
+    let (_stream, peer) = listener.accept().await.unwrap();
+    assert!(connection.is_ok());
+    assert_eq!(peer, bind_address);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
@@ -192,6 +192,28 @@ void reset_application_state(void);
  */
 void enroll_user(void);
 
+/**
+ * Starts the enrollment of a service account, with a token obtained beforehand instead of
+ * the browser flow. The token must be a JWT.
+ * Returns null if the enrollment started, otherwise returns an error message.
+ */
+const char *enroll_user_with_service_token(const char *token);
+
+/**
+ * Enroll the user with an enrollment bundle exported from an already enrolled environment.
+ * The bundle is read from the JSON file at the provided path, without any network call.
+ * Returns null if successful, otherwise returns an error message.
+ */
+const char *enroll_user_with_offline_bundle(const char *path);
+
+/**
+ * Override the text of an enrollment notification, for example to translate it.
+ * The message is identified by its name, for example `enrolled_successfully`.
+ * The text of the `enrollment_failed` message can refer to the error with an `{error}` placeholder.
+ * Returns null if successful, otherwise returns an error message.
+ */
+const char *set_message_text(const char *name, const char *title, const char *message);
+
 /**
  * This function retrieve the current version of the application state, for polling purposes.
  */